@@ -17,6 +17,8 @@ struct InstanceInput {
     @location(12) shadow_offset: vec2<f32>,
     @location(13) shadow_radius: f32,
     @location(14) shadow_color: vec4<f32>,
+    // x: aa_scale, y: corner_smoothing
+    @location(15) edge: vec2<f32>,
 };
 
 struct VertexOutput {
@@ -36,8 +38,16 @@ struct VertexOutput {
     @location(11) @interpolate(flat) glow_radius: f32,
     @location(12) @interpolate(flat) shadow_offset: vec2<f32>,
     @location(13) @interpolate(flat) shadow_radius: f32,
+    @location(14) @interpolate(flat) aa_scale: f32,
+    @location(15) @interpolate(flat) corner_smoothing: f32,
 };
 
+struct Screen {
+    matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> screen: Screen;
+
 @vertex
 fn vs_main(
     vertex: VertexInput,
@@ -45,7 +55,7 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    out.position = vec4<f32>(vertex.position, 1.0);
+    out.position = vec4<f32>((screen.matrix * vec4<f32>(vertex.position.xy, 0.0, 1.0)).xy, vertex.position.z, 1.0);
     out.fill_color = vertex.fill_color;
     out.border_color = vertex.border_color;
     out.rect_coord = vertex.rect_coord;
@@ -61,6 +71,8 @@ fn vs_main(
     out.shadow_offset = instance.shadow_offset;
     out.shadow_radius = instance.shadow_radius;
     out.shadow_color = instance.shadow_color;
+    out.aa_scale = instance.edge.x;
+    out.corner_smoothing = instance.edge.y;
 
     return out;
 }
@@ -70,20 +82,46 @@ var texture: texture_2d<f32>;
 @group(0) @binding(1)
 var texture_sampler: sampler;
 
-// Returns vec3(distanceX, distanceY, borderRadius)
+// Returns vec3(distanceX, distanceY, borderRadius). Distances are negative inside of box
 fn box2d(rect: vec4<f32>, border_radius: vec4<f32>, coord: vec2<f32>) -> vec3<f32> {
     let half_size = rect.zw / 2.0;
     let center = rect.xy + half_size;
 
     let radius = border_radius[u32(center.y - coord.y <= 0.0) * 2u + u32(center.x - coord.x <= 0.0)];
 
-    let dist = max(abs(center - coord) - half_size + radius, vec2<f32>(0.0, 0.0));
+    let dist = abs(center - coord) - half_size + radius;
 
     return vec3<f32>(dist, radius);
 }
 
-fn box_distance(box2d: vec3<f32>) -> f32 {
-    return sqrt(dot(box2d.xy, box2d.xy)) - box2d.z;
+// Signed distance to box edge, negative inside.
+// Corners blend from circular arc into superellipse by smoothing.
+fn box_distance(box2d: vec3<f32>, smoothing: f32) -> f32 {
+    let outside = max(box2d.xy, vec2<f32>(0.0, 0.0));
+    let inside = min(max(box2d.x, box2d.y), 0.0);
+
+    let circular = sqrt(dot(outside, outside)) + inside - box2d.z;
+    if (smoothing <= 0.0) {
+        return circular;
+    }
+
+    // Superellipse of exponent 4. Divided by gradient length to approximate euclidean distance, so aa and border width stay even.
+    let outside2 = outside * outside;
+    let outside3 = outside2 * outside;
+    let norm = sqrt(sqrt(dot(outside2, outside2)));
+    let gradient = select(1.0, sqrt(dot(outside3, outside3)) / (norm * norm * norm), norm > 0.0);
+    let superellipse = (norm - box2d.z) / gradient + inside;
+
+    return mix(circular, superellipse, smoothing);
+}
+
+// Coverage of edge at given signed distance, smoothed over aa_width
+fn edge_coverage(dist: f32, aa_width: f32) -> f32 {
+    if (aa_width <= 0.0) {
+        return f32(dist <= 0.0);
+    }
+
+    return clamp(0.5 - dist / aa_width, 0.0, 1.0);
 }
 
 fn blend(source: vec4<f32>, dest: vec4<f32>) -> vec4<f32> {
@@ -95,29 +133,41 @@ fn wrap_texture_coord(coord: f32, wrap_mode: u32) -> f32 {
         return clamp(coord, 0.0, 1.0);
     } else if (wrap_mode == 2u) {
         return fract(coord);
+    } else if (wrap_mode == 3u) {
+        return 1.0 - abs(fract(coord * 0.5) * 2.0 - 1.0);
     } else {
         return coord;
     }
 }
 
 fn mapped_texture_color(tex: texture_2d<f32>, tex_sampler: sampler, wrap_mode: vec2<u32>, tex_sub_rect: vec4<f32>, tex_coord: vec2<f32>) -> vec4<f32> {
+    // Whole texture view is wrapped by sampler addressing, coordinate is passed untouched
+    let sampler_addressed = all(wrap_mode == vec2<u32>(4u, 4u));
+
     let coord = tex_sub_rect.xy + vec2<f32>(wrap_texture_coord(tex_coord.x, wrap_mode.x), wrap_texture_coord(tex_coord.y, wrap_mode.y)) * tex_sub_rect.zw;
-    let tex_color = textureSample(tex, tex_sampler, coord);
+
+    // Keep sampling point half texel inside of sub rect so filtering doesn't bleed neighboring pixels
+    let half_texel = 0.5 / vec2<f32>(textureDimensions(tex));
+    let sub_rect_coord = clamp(coord, tex_sub_rect.xy + half_texel, tex_sub_rect.xy + tex_sub_rect.zw - half_texel);
+    let tex_color = textureSample(tex, tex_sampler, select(sub_rect_coord, tex_sub_rect.xy + tex_coord * tex_sub_rect.zw, sampler_addressed));
 
     return select(
         vec4<f32>(1.0, 1.0, 1.0, 1.0),
         tex_color,
-        coord.x >= tex_sub_rect.x && coord.y >= tex_sub_rect.y && coord.x <= tex_sub_rect.x + tex_sub_rect.z && coord.y <= tex_sub_rect.y + tex_sub_rect.w
+        sampler_addressed || (coord.x >= tex_sub_rect.x && coord.y >= tex_sub_rect.y && coord.x <= tex_sub_rect.x + tex_sub_rect.z && coord.y <= tex_sub_rect.y + tex_sub_rect.w)
     );
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let box = box2d(in.rect, in.border_radius, in.rect_coord);
-    let box_dist = box_distance(box);
+    let box_dist = box_distance(box, in.corner_smoothing);
 
     let shadow_box = box2d(in.rect, in.border_radius, in.rect_coord - in.shadow_offset);
-    let shadow_box_dist = box_distance(shadow_box);
+    let shadow_box_dist = box_distance(shadow_box, in.corner_smoothing);
+
+    // Distance change over one physical pixel, so edges stay about 1px wide regardless of scale
+    let aa_width = fwidth(box_dist) * in.aa_scale;
 
     var color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
 
@@ -136,14 +186,19 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     }
 
     // Fill Color
-    if (box_dist <= 0.0) {
-        color = blend(color, fill_color);
+    let fill_coverage = edge_coverage(box_dist, aa_width);
+    if (fill_coverage > 0.0) {
+        color = blend(color, vec4<f32>(fill_color.rgb, fill_color.a * fill_coverage));
     }
 
     // Border
-    if (box_dist < in.border_thickness + 1.0 && box_dist > max(-box.z, -1.0)) {
-        let t = max(abs(box_dist - in.border_thickness / 2.0) - in.border_thickness / 2.0, 0.0);
-        color = blend(color, in.border_color * (1.0 - t * t));
+    if (in.border_thickness > 0.0) {
+        let border_dist = abs(box_dist - in.border_thickness / 2.0) - in.border_thickness / 2.0;
+        let border_coverage = edge_coverage(border_dist, aa_width);
+
+        if (border_coverage > 0.0) {
+            color = blend(color, vec4<f32>(in.border_color.rgb, in.border_color.a * border_coverage));
+        }
     }
 
     return color;
@@ -3,20 +3,21 @@ use std::{borrow::Cow, sync::Arc};
 use bytemuck::{Pod, Zeroable};
 use storyboard_core::{
     color::ShapeColor,
-    euclid::{Point2D, Point3D, Rect, Transform3D, Vector2D},
-    math::RectExt,
+    euclid::{Angle, Point2D, Point3D, Rect, Transform3D, UnknownUnit, Vector2D},
+    math::{empty_bounds, rotation_transform, FiniteExt, RectExt},
     palette::LinSrgba,
     store::{Store, StoreResources},
-    unit::{LogicalPixelUnit, RenderUnit, TextureUnit},
+    unit::{LogicalPixelUnit, TextureUnit},
 };
 
 use storyboard_render::{
     buffer::stream::StreamRange,
     cache::shader::ShaderCache,
-    component::{Component, Drawable},
+    component::{Component, ComponentTag, Drawable},
     renderer::pass::StoryboardRenderPass,
     renderer::{
         context::{DrawContext, RenderContext},
+        screen::ScreenResources,
         ComponentQueue,
     },
     shared::RenderScopeContext,
@@ -40,6 +41,7 @@ use storyboard_texture::{
 #[derive(Debug)]
 pub struct Box2DResources {
     pub pipeline: RenderPipeline,
+    pub opaque_pipeline: RenderPipeline,
     pub box_index_buffer: Buffer,
 }
 
@@ -51,8 +53,11 @@ impl StoreResources<RenderScopeContext<'_>> for Box2DResources {
             .backend
             .get::<ShaderCache>()
             .get_or_create("box_2d_shader", || init_box_shader(ctx.backend.device()));
-        let pipeline_layout =
-            init_box_pipeline_layout(ctx.backend.device(), textures.bind_group_layout());
+        let pipeline_layout = init_box_pipeline_layout(
+            ctx.backend.device(),
+            textures.bind_group_layout(),
+            &ctx.backend.get::<ScreenResources>().bind_group_layout,
+        );
         let pipeline = init_box_pipeline(
             ctx.backend.device(),
             &pipeline_layout,
@@ -63,6 +68,22 @@ impl StoreResources<RenderScopeContext<'_>> for Box2DResources {
                 write_mask: ColorWrites::ALL,
             })],
             ctx.pipeline.depth_stencil_read_only(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
+        );
+
+        let opaque_pipeline = init_box_pipeline(
+            ctx.backend.device(),
+            &pipeline_layout,
+            &shader,
+            &[Some(ColorTargetState {
+                format: ctx.pipeline.texture_format,
+                blend: None,
+                write_mask: ColorWrites::COLOR,
+            })],
+            ctx.pipeline.depth_stencil.clone(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
         );
 
         let box_index_buffer = ctx
@@ -76,6 +97,7 @@ impl StoreResources<RenderScopeContext<'_>> for Box2DResources {
 
         Self {
             pipeline,
+            opaque_pipeline,
             box_index_buffer,
         }
     }
@@ -93,6 +115,26 @@ pub struct Box2D {
     pub style: Box2DStyle,
 
     pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+    /// Rotation around `rotation_anchor`, applied before `transform`
+    pub rotation: Angle<f32>,
+    /// Rotation anchor relative to bounds. `(0.5, 0.5)` is center of bounds.
+    pub rotation_anchor: Point2D<f32, UnknownUnit>,
+
+    /// Screen space rect box is clipped to. Not clipped if None.
+    pub clip: Option<Rect<f32, LogicalPixelUnit>>,
+
+    /// Draw in opaque pass for better depth culling. Use only for opaque box without rounded corners, glow and shadow.
+    /// Anti aliased edges and transparent pixels are written without blending.
+    pub force_opaque: bool,
+
+    pub tag: Option<ComponentTag>,
+}
+
+impl Box2D {
+    /// Rotation composed with transform
+    pub fn draw_transform(&self) -> Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit> {
+        rotation_transform(&self.bounds, self.rotation, self.rotation_anchor).then(&self.transform)
+    }
 }
 
 impl Drawable for Box2D {
@@ -104,7 +146,11 @@ impl Drawable for Box2D {
         depth: f32,
     ) {
         if let Some(component) = Box2DComponent::from_box2d(self, ctx, depth) {
-            component_queue.push_transparent(component);
+            if self.force_opaque {
+                component_queue.push_opaque(component);
+            } else {
+                component_queue.push_transparent(component);
+            }
         }
     }
 }
@@ -112,15 +158,25 @@ impl Drawable for Box2D {
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
 pub struct Box2DStyle {
+    /// Corner radius at edge of bounds
     pub border_radius: [f32; 4],
     pub border_thickness: f32,
+    pub border_align: BorderAlign,
 
     pub glow_radius: f32,
+    /// Glow color. Components above 1.0 are kept in float render target and bleed over neighbors with bloom.
     pub glow_color: LinSrgba,
 
     pub shadow_offset: Vector2D<f32, LogicalPixelUnit>,
     pub shadow_radius: f32,
     pub shadow_color: LinSrgba,
+
+    /// Multiplier of edge anti aliasing width. 1.0 smooths edges over about 1 physical pixel, 0.0 disables it.
+    pub aa_scale: f32,
+
+    /// Blend rounded corners from circular arc (0.0) into superellipse (1.0), like squircle.
+    /// Border, glow and shadow follow smoothed corners.
+    pub corner_smoothing: f32,
 }
 
 impl Default for Box2DStyle {
@@ -128,15 +184,58 @@ impl Default for Box2DStyle {
         Self {
             border_radius: Default::default(),
             border_thickness: Default::default(),
+            border_align: BorderAlign::default(),
             glow_radius: Default::default(),
             glow_color: LinSrgba::new(0.0, 0.0, 0.0, 0.0),
             shadow_offset: Default::default(),
             shadow_radius: Default::default(),
             shadow_color: LinSrgba::new(0.0, 0.0, 0.0, 0.0),
+            aa_scale: 1.0,
+            corner_smoothing: 0.0,
         }
     }
 }
 
+/// Placement of border relative to bounds of box, as fraction of border thickness inside of bounds
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct BorderAlign(f32);
+
+impl BorderAlign {
+    /// Border grows inward and outer edge of border matches bounds, like css border
+    pub const INSIDE: Self = Self(1.0);
+
+    /// Border is centered on edge of bounds
+    pub const CENTER: Self = Self(0.5);
+
+    /// Border grows outward and fill covers whole bounds
+    pub const OUTSIDE: Self = Self(0.0);
+
+    /// Distance fill edge is moved inward from bounds, clamped so fill doesn't go negative
+    pub fn inset(self, bounds: &Rect<f32, LogicalPixelUnit>, border_thickness: f32) -> f32 {
+        (border_thickness * self.0)
+            .min(bounds.size.width / 2.0)
+            .min(bounds.size.height / 2.0)
+    }
+
+    /// Bounds of fill. Border is drawn outside of it.
+    pub fn fill_bounds(
+        self,
+        bounds: &Rect<f32, LogicalPixelUnit>,
+        border_thickness: f32,
+    ) -> Rect<f32, LogicalPixelUnit> {
+        let inset = self.inset(bounds, border_thickness);
+
+        bounds.inflate(-inset, -inset)
+    }
+}
+
+impl Default for BorderAlign {
+    fn default() -> Self {
+        Self::OUTSIDE
+    }
+}
+
 #[derive(Debug)]
 pub struct Box2DComponent {
     texture: Option<Arc<RenderTexture2D>>,
@@ -145,16 +244,40 @@ pub struct Box2DComponent {
 
     vertices_slice: StreamRange,
     instance_slice: StreamRange,
+
+    bounds: Option<Rect<f32, LogicalPixelUnit>>,
+    clip: Option<Rect<f32, LogicalPixelUnit>>,
+
+    tag: Option<ComponentTag>,
 }
 
 impl Box2DComponent {
     pub fn from_box2d(box2d: &Box2D, ctx: &mut DrawContext, depth: f32) -> Option<Self> {
-        let border_bounds_inflation = box2d.style.border_thickness + 1.0;
+        if empty_bounds(&box2d.bounds) {
+            return None;
+        }
+
+        let transform = box2d.draw_transform();
+
+        // Snap box itself so border edges land on physical pixels
+        let outer_bounds = ctx.screen.snap_rect(box2d.bounds);
+
+        // Shader draws border outside of fill, so move fill edge inward for inside and centered borders
+        let inset = box2d
+            .style
+            .border_align
+            .inset(&outer_bounds, box2d.style.border_thickness);
+        let bounds = outer_bounds.inflate(-inset, -inset);
+        let border_radius = box2d
+            .style
+            .border_radius
+            .map(|radius| (radius - inset).max(0.0));
+
+        let border_bounds_inflation = box2d.style.border_thickness + box2d.style.aa_scale.max(1.0);
         let bounds_inflation = border_bounds_inflation + box2d.style.glow_radius;
-        let mut inflated_bounds = box2d.bounds.inflate(bounds_inflation, bounds_inflation);
+        let mut inflated_bounds = bounds.inflate(bounds_inflation, bounds_inflation);
 
-        let shadow_bounds = box2d
-            .bounds
+        let shadow_bounds = bounds
             .inflate(
                 border_bounds_inflation + box2d.style.shadow_radius,
                 border_bounds_inflation + box2d.style.shadow_radius,
@@ -169,60 +292,75 @@ impl Box2DComponent {
             inflated_bounds = inflated_bounds.union(&shadow_bounds);
         }
 
+        let mut component_bounds =
+            transform.outer_transformed_rect(&inflated_bounds.union(&shadow_bounds));
+        if let Some(clip) = &box2d.clip {
+            // Skip box clipped out entirely
+            if let Some(bounds) = component_bounds {
+                component_bounds = Some(bounds.intersection(clip)?);
+            }
+        }
+
         let texture_bounds = ComponentTexture::option_get_texture_bounds(
             box2d.texture.as_ref(),
-            box2d.bounds,
+            bounds,
             ctx.screen.get_logical_size(),
         );
 
-        let texture_coords = texture_bounds
-            .relative_in(&inflated_bounds)
-            .cast_unit()
-            .into_coords();
+        let texture_coords = ComponentTexture::option_transform_coords(
+            box2d.texture.as_ref(),
+            texture_bounds
+                .relative_in(&inflated_bounds)
+                .cast_unit()
+                .into_coords(),
+        );
 
         let vertices_slice = {
             let mut writer = ctx.vertex_stream.next_writer();
 
             let box_coords = inflated_bounds.into_coords();
 
+            let fill_colors = box2d.fill_color.vertex_colors(&bounds, &box_coords);
+            let border_colors = box2d.border_color.vertex_colors(&bounds, &box_coords);
+
             writer.write(bytemuck::bytes_of(&[
                 BoxVertex {
                     position: ctx
-                        .screen_matrix
-                        .transform_point2d(box2d.transform.transform_point2d(box_coords[0])?)?
+                        .to_screen_point(transform.transform_point2d(box_coords[0])?)
+                        .finite()?
                         .extend(depth),
-                    fill_color: box2d.fill_color[0],
-                    border_color: box2d.border_color[0],
+                    fill_color: fill_colors[0],
+                    border_color: border_colors[0],
                     rect_coord: box_coords[0],
                     texture_coord: texture_coords[0],
                 },
                 BoxVertex {
                     position: ctx
-                        .screen_matrix
-                        .transform_point2d(box2d.transform.transform_point2d(box_coords[1])?)?
+                        .to_screen_point(transform.transform_point2d(box_coords[1])?)
+                        .finite()?
                         .extend(depth),
-                    fill_color: box2d.fill_color[1],
-                    border_color: box2d.border_color[1],
+                    fill_color: fill_colors[1],
+                    border_color: border_colors[1],
                     rect_coord: box_coords[1],
                     texture_coord: texture_coords[1],
                 },
                 BoxVertex {
                     position: ctx
-                        .screen_matrix
-                        .transform_point2d(box2d.transform.transform_point2d(box_coords[2])?)?
+                        .to_screen_point(transform.transform_point2d(box_coords[2])?)
+                        .finite()?
                         .extend(depth),
-                    fill_color: box2d.fill_color[2],
-                    border_color: box2d.border_color[2],
+                    fill_color: fill_colors[2],
+                    border_color: border_colors[2],
                     rect_coord: box_coords[2],
                     texture_coord: texture_coords[2],
                 },
                 BoxVertex {
                     position: ctx
-                        .screen_matrix
-                        .transform_point2d(box2d.transform.transform_point2d(box_coords[3])?)?
+                        .to_screen_point(transform.transform_point2d(box_coords[3])?)
+                        .finite()?
                         .extend(depth),
-                    fill_color: box2d.fill_color[3],
-                    border_color: box2d.border_color[3],
+                    fill_color: fill_colors[3],
+                    border_color: border_colors[3],
                     rect_coord: box_coords[3],
                     texture_coord: texture_coords[3],
                 },
@@ -234,32 +372,32 @@ impl Box2DComponent {
                 writer.write(bytemuck::bytes_of(&[
                     BoxVertex {
                         position: ctx
-                            .screen_matrix
-                            .transform_point2d(box2d.transform.transform_point2d(shadow_coords[0])?)?
+                            .to_screen_point(transform.transform_point2d(shadow_coords[0])?)
+                            .finite()?
                             .extend(depth),
                         rect_coord: shadow_coords[0],
                         ..Default::default()
                     },
                     BoxVertex {
                         position: ctx
-                            .screen_matrix
-                            .transform_point2d(box2d.transform.transform_point2d(shadow_coords[1])?)?
+                            .to_screen_point(transform.transform_point2d(shadow_coords[1])?)
+                            .finite()?
                             .extend(depth),
                         rect_coord: shadow_coords[1],
                         ..Default::default()
                     },
                     BoxVertex {
                         position: ctx
-                            .screen_matrix
-                            .transform_point2d(box2d.transform.transform_point2d(shadow_coords[2])?)?
+                            .to_screen_point(transform.transform_point2d(shadow_coords[2])?)
+                            .finite()?
                             .extend(depth),
                         rect_coord: shadow_coords[2],
                         ..Default::default()
                     },
                     BoxVertex {
                         position: ctx
-                            .screen_matrix
-                            .transform_point2d(box2d.transform.transform_point2d(shadow_coords[3])?)?
+                            .to_screen_point(transform.transform_point2d(shadow_coords[3])?)
+                            .finite()?
                             .extend(depth),
                         rect_coord: shadow_coords[3],
                         ..Default::default()
@@ -271,18 +409,26 @@ impl Box2DComponent {
         };
 
         let texture_rect = ComponentTexture::option_view_texture_rect(box2d.texture.as_ref());
-        let texture_wrap = ComponentTexture::option_wrapping_mode(box2d.texture.as_ref());
+        let texture_wrap = ComponentTexture::option_shader_wrap_mode(box2d.texture.as_ref());
 
         let instance_slice = ctx
             .vertex_stream
             .write_slice(bytemuck::bytes_of(&BoxInstance {
-                rect: box2d.bounds,
+                rect: bounds,
 
                 texture_rect,
-                texture_wrap_mode_u: texture_wrap.0 as _,
-                texture_wrap_mode_v: texture_wrap.1 as _,
-
-                style: box2d.style,
+                texture_wrap_mode_u: texture_wrap[0],
+                texture_wrap_mode_v: texture_wrap[1],
+
+                border_radius,
+                border_thickness: box2d.style.border_thickness,
+                glow_radius: box2d.style.glow_radius,
+                glow_color: box2d.style.glow_color,
+                shadow_offset: box2d.style.shadow_offset,
+                shadow_radius: box2d.style.shadow_radius,
+                shadow_color: box2d.style.shadow_color,
+                aa_scale: box2d.style.aa_scale,
+                corner_smoothing: box2d.style.corner_smoothing.clamp(0.0, 1.0),
             }));
 
         Some(Self {
@@ -290,27 +436,25 @@ impl Box2DComponent {
             indices,
             vertices_slice,
             instance_slice,
+            bounds: component_bounds,
+            clip: box2d.clip,
+            tag: box2d.tag,
         })
     }
-}
-
-impl Component for Box2DComponent {
-    fn render_opaque<'rpass>(
-        &'rpass self,
-        _: &RenderContext<'rpass>,
-        _: &mut StoryboardRenderPass<'rpass>,
-    ) {
-        unreachable!()
-    }
 
-    fn render_transparent<'rpass>(
+    fn draw<'rpass>(
         &'rpass self,
         ctx: &RenderContext<'rpass>,
         pass: &mut StoryboardRenderPass<'rpass>,
+        pipeline: &'rpass RenderPipeline,
     ) {
         let box_resources = ctx.scope.get::<Box2DResources>();
 
-        pass.set_pipeline(&box_resources.pipeline);
+        if let Some(clip) = &self.clip {
+            pass.set_clip(&ctx.screen, clip);
+        }
+
+        pass.set_pipeline(pipeline);
 
         pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.vertices_slice.clone()));
         pass.set_vertex_buffer(1, ctx.vertex_stream.slice(self.instance_slice.clone()));
@@ -336,15 +480,50 @@ impl Component for Box2DComponent {
                 .bind_group(),
             &[],
         );
+        pass.set_bind_group(1, ctx.screen_bind_group, &[]);
 
         pass.draw_indexed(0..self.indices, 0, 0..1);
+
+        if self.clip.is_some() {
+            pass.reset_clip(&ctx.screen);
+        }
+    }
+}
+
+impl Component for Box2DComponent {
+    fn render_opaque<'rpass>(
+        &'rpass self,
+        ctx: &RenderContext<'rpass>,
+        pass: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        self.draw(
+            ctx,
+            pass,
+            &ctx.scope.get::<Box2DResources>().opaque_pipeline,
+        );
+    }
+
+    fn render_transparent<'rpass>(
+        &'rpass self,
+        ctx: &RenderContext<'rpass>,
+        pass: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        self.draw(ctx, pass, &ctx.scope.get::<Box2DResources>().pipeline);
+    }
+
+    fn tag(&self) -> Option<&ComponentTag> {
+        self.tag.as_ref()
+    }
+
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        self.bounds
     }
 }
 
 #[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
 pub struct BoxVertex {
-    pub position: Point3D<f32, RenderUnit>,
+    pub position: Point3D<f32, LogicalPixelUnit>,
 
     pub fill_color: LinSrgba<f32>,
     pub border_color: LinSrgba<f32>,
@@ -361,7 +540,15 @@ pub struct BoxInstance {
     pub texture_wrap_mode_u: u32,
     pub texture_wrap_mode_v: u32,
 
-    pub style: Box2DStyle,
+    pub border_radius: [f32; 4],
+    pub border_thickness: f32,
+    pub glow_radius: f32,
+    pub glow_color: LinSrgba,
+    pub shadow_offset: Vector2D<f32, LogicalPixelUnit>,
+    pub shadow_radius: f32,
+    pub shadow_color: LinSrgba,
+    pub aa_scale: f32,
+    pub corner_smoothing: f32,
 }
 
 pub fn init_box_shader(device: &Device) -> ShaderModule {
@@ -374,10 +561,11 @@ pub fn init_box_shader(device: &Device) -> ShaderModule {
 pub fn init_box_pipeline_layout(
     device: &Device,
     texture_bind_group_layout: &BindGroupLayout,
+    screen_bind_group_layout: &BindGroupLayout,
 ) -> PipelineLayout {
     device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("Box2D shader pipeline layout"),
-        bind_group_layouts: &[texture_bind_group_layout],
+        bind_group_layouts: &[texture_bind_group_layout, screen_bind_group_layout],
         push_constant_ranges: &[],
     })
 }
@@ -388,6 +576,7 @@ pub fn init_box_pipeline(
     shader: &ShaderModule,
     fragment_targets: &[Option<ColorTargetState>],
     depth_stencil: Option<DepthStencilState>,
+    primitive: PrimitiveState,
 ) -> RenderPipeline {
     device.create_render_pipeline(&RenderPipelineDescriptor {
         label: Some("Box2D pipeline"),
@@ -420,7 +609,9 @@ pub fn init_box_pipeline(
                         11 => Float32x4,
                         12 => Float32x2,
                         13 => Float32,
-                        14 => Float32x4
+                        14 => Float32x4,
+                        // aa_scale and corner_smoothing, sharing last location of default limit
+                        15 => Float32x2
                     ],
                 },
             ],
@@ -431,11 +622,44 @@ pub fn init_box_pipeline(
             targets: fragment_targets,
         }),
         depth_stencil,
-        primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
-            ..PrimitiveState::default()
-        },
+        primitive,
         multisample: MultisampleState::default(),
         multiview: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D};
+
+    use crate::BorderAlign;
+
+    #[test]
+    fn border_align_test() {
+        let bounds = Rect::new(Point2D::new(10.0, 10.0), Size2D::new(100.0, 50.0));
+
+        // Outer edge of inside border matches bounds
+        let fill = BorderAlign::INSIDE.fill_bounds(&bounds, 4.0);
+        assert_eq!(
+            fill,
+            Rect::new(Point2D::new(14.0, 14.0), Size2D::new(92.0, 42.0))
+        );
+        assert_eq!(fill.inflate(4.0, 4.0), bounds);
+
+        // Border is centered on edge of bounds
+        assert_eq!(
+            BorderAlign::CENTER
+                .fill_bounds(&bounds, 4.0)
+                .inflate(2.0, 2.0),
+            bounds
+        );
+
+        assert_eq!(BorderAlign::OUTSIDE.fill_bounds(&bounds, 4.0), bounds);
+
+        // Border thicker than box fills it
+        assert_eq!(
+            BorderAlign::INSIDE.fill_bounds(&bounds, 40.0).size.height,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use std::{iter, mem, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use parking_lot::Mutex;
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Angle, Point2D, Rect, Size2D, Transform3D},
+    store::{Store, StoreResources},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
+};
+use storyboard_primitive::{PrimitiveComponent, Rectangle};
+use storyboard_render::{
+    cache::shader::ShaderCache,
+    component::Drawable,
+    renderer::{context::DrawContext, ComponentQueue},
+    shared::{RenderScopeContext, RenderShared},
+    texture::SizedTextureView2D,
+    wgpu::{
+        BindGroupLayout, CommandEncoder, Device, RenderPipeline, ShaderModule,
+        ShaderModuleDescriptor, ShaderSource, TextureFormat,
+    },
+};
+use storyboard_texture::{
+    render::{data::TextureData, renderer::StoryboardTextureRenderer, RenderTexture2D},
+    ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap,
+};
+
+use crate::{
+    blur::{Blur, BlurResources, BlurTargets},
+    fullscreen::{
+        effect_shader_source, fullscreen_pass, init_fullscreen_pipeline,
+        init_uniform_bind_group_layout, UniformBinding,
+    },
+    offscreen_screen, OffscreenShared,
+};
+
+/// Bloom drawable.
+///
+/// Renders drawable into float texture, extracts bright area above threshold into downsampled texture,
+/// blurs it and adds back over tonemapped drawable.
+/// Box2D glow color brighter than 1.0 is kept in float texture, so it bleeds over neighbors.
+#[derive(Debug)]
+pub struct Bloom<T> {
+    pub drawable: T,
+
+    pub bounds: Rect<f32, LogicalPixelUnit>,
+    pub config: BloomConfig,
+
+    pub cached_data: Arc<CachedBloomData>,
+}
+
+impl<T> Bloom<T> {
+    /// Format of scene and bloom textures
+    pub const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomConfig {
+    /// Luminance above which pixels bloom
+    pub threshold: f32,
+    /// Multiplier of bloom added to scene
+    pub intensity: f32,
+    /// Bloom blur radius in logical pixels
+    pub radius: f32,
+}
+
+impl Default for BloomConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            intensity: 1.0,
+            radius: 16.0,
+        }
+    }
+}
+
+impl<T: Drawable> Drawable for Bloom<T> {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        encoder: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        let (bounds, screen) =
+            match offscreen_screen(&ctx.screen, self.bounds, ctx.screen.scale_factor) {
+                Some(screen) => screen,
+                None => return,
+            };
+
+        // Bright pass is always downsampled, more if radius is large
+        let texel_radius = self.config.radius.max(0.0) * ctx.screen.scale_factor;
+        let downsample = (texel_radius / Blur::DOWNSAMPLE_THRESHOLD).ceil().max(2.0);
+        let bloom_size = (screen.rect.size.to_f32() / downsample)
+            .ceil()
+            .max(Size2D::new(1.0, 1.0))
+            .cast::<u32>();
+
+        let device = ctx.scope.backend().device();
+        let textures = ctx.scope.backend().get::<TextureData>();
+        let texture_format = ctx.scope.pipeline().texture_format;
+
+        let mut cache = self.cached_data.inner.lock();
+        let cache = cache.get_or_insert_with(|| BloomCache {
+            shared: ctx.scope.get::<OffscreenShared>().get(Self::FORMAT),
+            renderer: StoryboardTextureRenderer::init(
+                device,
+                textures,
+                Self::FORMAT,
+                screen.rect.size,
+            ),
+            targets: None,
+        });
+
+        let float_scope = ctx.scope.backend().render_scope(&cache.shared);
+
+        cache.renderer.render(
+            float_scope,
+            screen,
+            textures,
+            iter::once(&self.drawable as _),
+            encoder,
+        );
+
+        let blur_resources = float_scope.get::<BlurResources>();
+        let resources = ctx.scope.get::<BloomResources>();
+
+        let targets = match &mut cache.targets {
+            Some(targets)
+                if targets.size == screen.rect.size
+                    && targets.bloom_size == bloom_size
+                    && targets.format == texture_format =>
+            {
+                targets
+            }
+
+            targets => targets.insert(BloomTargets::init(
+                device,
+                textures,
+                blur_resources,
+                resources,
+                texture_format,
+                screen.rect.size,
+                bloom_size,
+            )),
+        };
+
+        let queue = ctx.scope.backend().queue();
+
+        targets.uniform.write(
+            queue,
+            bytemuck::bytes_of(&BloomUniform {
+                threshold: self.config.threshold,
+                intensity: self.config.intensity,
+                _padding: [0.0; 2],
+            }),
+        );
+
+        fullscreen_pass(
+            encoder,
+            "Bloom bright render pass",
+            &resources.bright_pipeline,
+            &[
+                cache.renderer.render_texture().bind_group(),
+                &targets.uniform.bind_group,
+            ],
+            &targets.bright.0,
+        );
+
+        let blur_radius = texel_radius / downsample;
+
+        blur_resources.blur_pass(
+            queue,
+            encoder,
+            &targets.bright.1,
+            &targets.horizontal.0,
+            &targets.blur_uniforms[0],
+            [1.0 / bloom_size.width as f32, 0.0],
+            blur_radius,
+        );
+
+        blur_resources.blur_pass(
+            queue,
+            encoder,
+            &targets.horizontal.1,
+            &targets.blurred.0,
+            &targets.blur_uniforms[1],
+            [0.0, 1.0 / bloom_size.height as f32],
+            blur_radius,
+        );
+
+        fullscreen_pass(
+            encoder,
+            "Bloom composite render pass",
+            &resources.composite_pipeline,
+            &[
+                cache.renderer.render_texture().bind_group(),
+                &targets.uniform.bind_group,
+                targets.blurred.1.bind_group(),
+            ],
+            &targets.output.0,
+        );
+
+        if let Some(component) = PrimitiveComponent::from_rectangle(
+            &Rectangle {
+                bounds,
+                color: ShapeColor::WHITE,
+                texture: Some(ComponentTexture::new(
+                    targets.output.1.clone(),
+                    TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                    (TextureWrap::Clamp, TextureWrap::Clamp),
+                )),
+                texture_coord: None,
+                transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                alpha_cutoff: None,
+            },
+            ctx,
+            depth,
+        ) {
+            component_queue.push_transparent(component);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CachedBloomData {
+    inner: Mutex<Option<BloomCache>>,
+}
+
+impl CachedBloomData {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BloomCache {
+    /// Render data for pipelines created against float format
+    shared: Arc<RenderShared>,
+    renderer: StoryboardTextureRenderer,
+    targets: Option<BloomTargets>,
+}
+
+#[derive(Debug)]
+struct BloomTargets {
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    bloom_size: Size2D<u32, PhyiscalPixelUnit>,
+    format: TextureFormat,
+
+    bright: (SizedTextureView2D, Arc<RenderTexture2D>),
+    horizontal: (SizedTextureView2D, Arc<RenderTexture2D>),
+    blurred: (SizedTextureView2D, Arc<RenderTexture2D>),
+    output: (SizedTextureView2D, Arc<RenderTexture2D>),
+
+    /// Uniform of bright and composite pass
+    uniform: UniformBinding,
+    /// Uniforms of horizontal and vertical blur pass
+    blur_uniforms: [UniformBinding; 2],
+}
+
+impl BloomTargets {
+    pub fn init(
+        device: &Device,
+        textures: &TextureData,
+        blur_resources: &BlurResources,
+        resources: &BloomResources,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        bloom_size: Size2D<u32, PhyiscalPixelUnit>,
+    ) -> Self {
+        let float_format = Bloom::<()>::FORMAT;
+
+        Self {
+            size,
+            bloom_size,
+            format,
+
+            uniform: UniformBinding::init(
+                device,
+                "Bloom uniform",
+                &resources.uniform_bind_group_layout,
+                mem::size_of::<BloomUniform>() as u64,
+            ),
+            blur_uniforms: [
+                blur_resources.create_uniform_binding(device),
+                blur_resources.create_uniform_binding(device),
+            ],
+
+            bright: BlurTargets::create_target(device, textures, float_format, bloom_size, None),
+            horizontal: BlurTargets::create_target(
+                device,
+                textures,
+                float_format,
+                bloom_size,
+                None,
+            ),
+            // Blurred bloom is upscaled to scene size
+            blurred: BlurTargets::create_target(
+                device,
+                textures,
+                float_format,
+                bloom_size,
+                Some(textures.linear_sampler()),
+            ),
+            output: BlurTargets::create_target(device, textures, format, size, None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct BloomUniform {
+    threshold: f32,
+    intensity: f32,
+    _padding: [f32; 2],
+}
+
+#[derive(Debug)]
+pub struct BloomResources {
+    /// Extracts bright area into float texture
+    pub bright_pipeline: RenderPipeline,
+    /// Adds bloom over scene and tonemaps into target format
+    pub composite_pipeline: RenderPipeline,
+    pub uniform_bind_group_layout: BindGroupLayout,
+}
+
+impl StoreResources<RenderScopeContext<'_>> for BloomResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let device = ctx.backend.device();
+        let textures = ctx.backend.get::<TextureData>();
+
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("bloom_shader", || init_bloom_shader(device));
+
+        let uniform_bind_group_layout =
+            init_uniform_bind_group_layout(device, "Bloom uniform bind group layout");
+
+        let bright_pipeline = init_fullscreen_pipeline(
+            device,
+            "Bloom bright pipeline",
+            &shader,
+            &[textures.bind_group_layout(), &uniform_bind_group_layout],
+            "fs_bright",
+            Bloom::<()>::FORMAT,
+        );
+
+        let composite_pipeline = init_fullscreen_pipeline(
+            device,
+            "Bloom composite pipeline",
+            &shader,
+            &[
+                textures.bind_group_layout(),
+                &uniform_bind_group_layout,
+                textures.bind_group_layout(),
+            ],
+            "fs_composite",
+            ctx.pipeline.texture_format,
+        );
+
+        Self {
+            bright_pipeline,
+            composite_pipeline,
+            uniform_bind_group_layout,
+        }
+    }
+}
+
+pub fn init_bloom_shader(device: &Device) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Bloom shader"),
+        source: ShaderSource::Wgsl(effect_shader_source(include_str!("bloom.wgsl"))),
+    })
+}
@@ -0,0 +1,47 @@
+struct BloomUniform {
+    threshold: f32,
+    intensity: f32,
+};
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// ACES filmic curve approximation
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    return clamp(
+        (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0)
+    );
+}
+
+@group(1) @binding(0)
+var<uniform> bloom: BloomUniform;
+
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.texture_coord);
+
+    // Keep only amount above threshold so bloom fades in smoothly
+    let brightness = luminance(color.rgb);
+    let contribution = max(brightness - bloom.threshold, 0.0) / max(brightness, 0.0001);
+
+    return vec4<f32>(color.rgb * contribution, 1.0);
+}
+
+@group(2) @binding(0)
+var bloom_texture: texture_2d<f32>;
+@group(2) @binding(1)
+var bloom_sampler: sampler;
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.texture_coord);
+    let bloom_color = textureSample(bloom_texture, bloom_sampler, in.texture_coord).rgb * bloom.intensity;
+
+    // Bloom bleeds outside of drawn area, so it contributes to alpha too
+    let alpha = clamp(color.a + luminance(bloom_color), 0.0, 1.0);
+
+    return vec4<f32>(aces(color.rgb + bloom_color), alpha);
+}
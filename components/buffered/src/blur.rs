@@ -0,0 +1,441 @@
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bytemuck::{Pod, Zeroable};
+use parking_lot::Mutex;
+use storyboard_box2d::{Box2D, Box2DComponent, Box2DStyle};
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Angle, Point2D, Rect, Size2D, Transform3D},
+    store::{Store, StoreResources},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
+};
+use storyboard_render::{
+    cache::shader::ShaderCache,
+    component::{Component, Drawable},
+    renderer::{
+        context::{DrawContext, RenderContext},
+        pass::StoryboardRenderPass,
+        Backdrop, ComponentQueue,
+    },
+    shared::RenderScopeContext,
+    texture::{SizedTexture2D, SizedTextureView2D},
+    wgpu::{
+        BindGroupLayout, CommandEncoder, Device, Extent3d, ImageCopyTexture, Origin3d, Queue,
+        RenderPipeline, Sampler, ShaderModule, ShaderModuleDescriptor, ShaderSource, TextureAspect,
+        TextureFormat, TextureUsages,
+    },
+};
+use storyboard_texture::{
+    render::{data::TextureData, RenderTexture2D},
+    ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap,
+};
+
+use crate::{
+    fullscreen::{
+        effect_shader_source, fullscreen_pass, init_fullscreen_pipeline,
+        init_uniform_bind_group_layout, UniformBinding,
+    },
+    offscreen_screen,
+};
+
+/// Backdrop blur drawable.
+///
+/// Copies content drawn before it inside bounds from render target, blurs it using separable gaussian blur and composites it in rounded rect.
+/// Only drawn by renderers providing backdrop, see [storyboard_render::renderer::StoryboardRenderer::render_with_backdrop].
+#[derive(Debug)]
+pub struct Blur {
+    pub bounds: Rect<f32, LogicalPixelUnit>,
+    pub border_radius: [f32; 4],
+
+    /// Blur radius in logical pixels
+    pub radius: f32,
+    /// Color multiplied to blurred backdrop
+    pub tint: ShapeColor<4>,
+
+    pub cached_data: Arc<CachedBlurData>,
+}
+
+impl Blur {
+    /// Blur radius in texels above which backdrop is blurred in downsampled texture
+    pub const DOWNSAMPLE_THRESHOLD: f32 = 8.0;
+}
+
+impl Drawable for Blur {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        _: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        // Region of render target copied, in physical pixel
+        let (texture_rect, region) =
+            match offscreen_screen(&ctx.screen, self.bounds, ctx.screen.scale_factor) {
+                Some((texture_rect, screen)) => (texture_rect, screen.rect),
+                None => return,
+            };
+
+        // Blur large radius in lower resolution, as the detail is lost anyway
+        let texel_radius = self.radius.max(0.0) * ctx.screen.scale_factor;
+        let downsample = (texel_radius / Self::DOWNSAMPLE_THRESHOLD).ceil().max(1.0);
+        let size = (region.size.to_f32() / downsample)
+            .ceil()
+            .max(Size2D::new(1.0, 1.0))
+            .cast::<u32>();
+
+        let device = ctx.scope.backend().device();
+        let textures = ctx.scope.backend().get::<TextureData>();
+        let texture_format = ctx.scope.pipeline().texture_format;
+
+        let targets = {
+            let mut targets = self.cached_data.targets.lock();
+
+            match &*targets {
+                Some(targets)
+                    if targets.backdrop.0.size() == region.size
+                        && targets.size == size
+                        && targets.format == texture_format =>
+                {
+                    targets.clone()
+                }
+
+                _ => targets
+                    .insert(Arc::new(BlurTargets::init(
+                        device,
+                        textures,
+                        ctx.scope.get::<BlurResources>(),
+                        texture_format,
+                        region.size,
+                        size,
+                    )))
+                    .clone(),
+            }
+        };
+
+        if let Some(composite) = Box2DComponent::from_box2d(
+            &Box2D {
+                bounds: self.bounds,
+                texture: Some(ComponentTexture::new(
+                    targets.output.1.clone(),
+                    TextureLayout::Absolute(TextureLayoutStyle::Custom(texture_rect)),
+                    (TextureWrap::Clamp, TextureWrap::Clamp),
+                )),
+                fill_color: self.tint.clone(),
+                border_color: ShapeColor::TRANSPARENT,
+                style: Box2DStyle {
+                    border_radius: self.border_radius,
+                    ..Default::default()
+                },
+                transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                tag: None,
+            },
+            ctx,
+            depth,
+        ) {
+            component_queue.push_transparent(BlurComponent {
+                targets,
+                region,
+                radius: texel_radius / downsample,
+                composite,
+                copied: AtomicBool::new(false),
+            });
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CachedBlurData {
+    targets: Mutex<Option<Arc<BlurTargets>>>,
+}
+
+impl CachedBlurData {
+    pub fn new() -> Self {
+        Self {
+            targets: Mutex::new(None),
+        }
+    }
+}
+
+/// Blurs backdrop copied from render target when renderer reaches it, then draws composite of blurred texture
+#[derive(Debug)]
+struct BlurComponent {
+    targets: Arc<BlurTargets>,
+    /// Region of render target copied, in physical pixel
+    region: Rect<u32, PhyiscalPixelUnit>,
+    /// Blur radius in texels of blur targets
+    radius: f32,
+
+    composite: Box2DComponent,
+    /// Set after backdrop is blurred, composite is skipped otherwise
+    copied: AtomicBool,
+}
+
+impl Component for BlurComponent {
+    fn render_opaque<'rpass>(
+        &'rpass self,
+        _: &RenderContext<'rpass>,
+        _: &mut StoryboardRenderPass<'rpass>,
+    ) {
+    }
+
+    fn render_transparent<'rpass>(
+        &'rpass self,
+        ctx: &RenderContext<'rpass>,
+        pass: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        if self.copied.load(Ordering::Relaxed) {
+            self.composite.render_transparent(ctx, pass);
+        }
+    }
+
+    fn reads_backdrop(&self) -> bool {
+        true
+    }
+
+    fn encode_backdrop(
+        &self,
+        ctx: &RenderContext,
+        encoder: &mut CommandEncoder,
+        backdrop: &Backdrop,
+    ) {
+        let region = match self.region.intersection(&Rect::from_size(backdrop.size)) {
+            Some(region) if !region.is_empty() => region,
+            _ => return,
+        };
+
+        encoder.copy_texture_to_texture(
+            ImageCopyTexture {
+                texture: backdrop.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: region.origin.x,
+                    y: region.origin.y,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            ImageCopyTexture {
+                texture: self.targets.backdrop.0.inner(),
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            Extent3d {
+                width: region.size.width,
+                height: region.size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let queue = ctx.scope.backend().queue();
+        let resources = ctx.scope.get::<BlurResources>();
+        let size = self.targets.size.to_f32();
+
+        resources.blur_pass(
+            queue,
+            encoder,
+            &self.targets.backdrop.1,
+            &self.targets.horizontal.0,
+            &self.targets.uniforms[0],
+            [1.0 / size.width, 0.0],
+            self.radius,
+        );
+
+        resources.blur_pass(
+            queue,
+            encoder,
+            &self.targets.horizontal.1,
+            &self.targets.output.0,
+            &self.targets.uniforms[1],
+            [0.0, 1.0 / size.height],
+            self.radius,
+        );
+
+        self.copied.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Intermediate and output textures of blur passes, reused while size and format stay same
+#[derive(Debug)]
+pub(crate) struct BlurTargets {
+    /// Size of blur passes, downsampled from backdrop if radius is large
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    format: TextureFormat,
+
+    /// Copy of backdrop region in full resolution
+    backdrop: (SizedTexture2D, Arc<RenderTexture2D>),
+    horizontal: (SizedTextureView2D, Arc<RenderTexture2D>),
+    output: (SizedTextureView2D, Arc<RenderTexture2D>),
+
+    /// Uniforms of horizontal and vertical pass
+    uniforms: [UniformBinding; 2],
+}
+
+impl BlurTargets {
+    pub fn init(
+        device: &Device,
+        textures: &TextureData,
+        resources: &BlurResources,
+        format: TextureFormat,
+        backdrop_size: Size2D<u32, PhyiscalPixelUnit>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+    ) -> Self {
+        let backdrop = SizedTexture2D::init(
+            device,
+            Some("Blur backdrop texture"),
+            backdrop_size,
+            format,
+            TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+        );
+        // Backdrop is downsampled by horizontal pass
+        let backdrop_texture = Arc::new(textures.create_tracked_render_texture(
+            device,
+            &backdrop,
+            Some(textures.linear_sampler()),
+        ));
+
+        Self {
+            size,
+            format,
+
+            uniforms: [
+                resources.create_uniform_binding(device),
+                resources.create_uniform_binding(device),
+            ],
+
+            backdrop: (backdrop, backdrop_texture),
+            horizontal: Self::create_target(device, textures, format, size, None),
+            // Output is upscaled to bounds if downsampled
+            output: Self::create_target(
+                device,
+                textures,
+                format,
+                size,
+                Some(textures.linear_sampler()),
+            ),
+        }
+    }
+
+    pub(crate) fn create_target(
+        device: &Device,
+        textures: &TextureData,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        sampler: Option<&Sampler>,
+    ) -> (SizedTextureView2D, Arc<RenderTexture2D>) {
+        let texture = SizedTexture2D::init(
+            device,
+            Some("Blur target texture"),
+            size,
+            format,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+
+        (
+            texture.create_view_default(None),
+            Arc::new(textures.create_tracked_render_texture(device, &texture, sampler)),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct BlurUniform {
+    direction: [f32; 2],
+    radius: f32,
+    sigma: f32,
+}
+
+#[derive(Debug)]
+pub struct BlurResources {
+    pub pipeline: RenderPipeline,
+    pub uniform_bind_group_layout: BindGroupLayout,
+}
+
+impl BlurResources {
+    pub(crate) fn create_uniform_binding(&self, device: &Device) -> UniformBinding {
+        UniformBinding::init(
+            device,
+            "Blur uniform",
+            &self.uniform_bind_group_layout,
+            mem::size_of::<BlurUniform>() as u64,
+        )
+    }
+
+    /// Blur source texture in direction into target
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn blur_pass(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        source: &RenderTexture2D,
+        target: &SizedTextureView2D,
+        uniform: &UniformBinding,
+        direction: [f32; 2],
+        radius: f32,
+    ) {
+        uniform.write(
+            queue,
+            bytemuck::bytes_of(&BlurUniform {
+                direction,
+                radius,
+                // Kernel covers about 3 sigma
+                sigma: (radius / 3.0).max(0.5),
+            }),
+        );
+
+        fullscreen_pass(
+            encoder,
+            "Blur render pass",
+            &self.pipeline,
+            &[source.bind_group(), &uniform.bind_group],
+            target,
+        );
+    }
+}
+
+impl StoreResources<RenderScopeContext<'_>> for BlurResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let device = ctx.backend.device();
+        let textures = ctx.backend.get::<TextureData>();
+
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("blur_shader", || init_blur_shader(device));
+
+        let uniform_bind_group_layout =
+            init_uniform_bind_group_layout(device, "Blur uniform bind group layout");
+
+        let pipeline = init_fullscreen_pipeline(
+            device,
+            "Blur pipeline",
+            &shader,
+            &[textures.bind_group_layout(), &uniform_bind_group_layout],
+            "fs_main",
+            ctx.pipeline.texture_format,
+        );
+
+        Self {
+            pipeline,
+            uniform_bind_group_layout,
+        }
+    }
+}
+
+pub fn init_blur_shader(device: &Device) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Blur shader"),
+        source: ShaderSource::Wgsl(effect_shader_source(include_str!("blur.wgsl"))),
+    })
+}
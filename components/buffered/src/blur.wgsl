@@ -0,0 +1,28 @@
+struct BlurUniform {
+    direction: vec2<f32>,
+    radius: f32,
+    sigma: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> blur: BlurUniform;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(texture, texture_sampler, in.texture_coord);
+    var total = 1.0;
+
+    let samples = i32(ceil(blur.radius));
+    for (var i = 1; i <= samples; i = i + 1) {
+        let offset = f32(i);
+        let weight = exp(-(offset * offset) / (2.0 * blur.sigma * blur.sigma));
+
+        color = color + (
+            textureSample(texture, texture_sampler, in.texture_coord + blur.direction * offset)
+            + textureSample(texture, texture_sampler, in.texture_coord - blur.direction * offset)
+        ) * weight;
+        total = total + weight * 2.0;
+    }
+
+    return color / total;
+}
@@ -0,0 +1,47 @@
+use storyboard_core::{euclid::Rect, unit::LogicalPixelUnit};
+use storyboard_render::{
+    component::Drawable,
+    renderer::{context::DrawContext, ComponentQueue},
+    wgpu::CommandEncoder,
+};
+
+/// Clip content into rounded rect.
+///
+/// Children like images inside rounded card don't overflow its corners.
+/// Clip shape is written into stencil, see [ComponentQueue::push_rounded_clip].
+/// Content is drawn unclipped if depth format of render target has no stencil.
+#[derive(Debug)]
+pub struct RoundedClip<T> {
+    pub content: T,
+
+    pub bounds: Rect<f32, LogicalPixelUnit>,
+    pub radius: f32,
+}
+
+impl<T> RoundedClip<T> {
+    pub const fn new(content: T, bounds: Rect<f32, LogicalPixelUnit>, radius: f32) -> Self {
+        Self {
+            content,
+            bounds,
+            radius,
+        }
+    }
+}
+
+impl<T: Drawable> Drawable for RoundedClip<T> {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        encoder: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        let clipped = component_queue.push_rounded_clip(ctx, self.bounds, self.radius);
+
+        self.content.prepare(component_queue, ctx, encoder, depth);
+
+        if clipped {
+            component_queue.pop_clip();
+        }
+    }
+}
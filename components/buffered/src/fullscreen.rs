@@ -0,0 +1,144 @@
+//! Fullscreen triangle pass shared by post effects.
+//!
+//! Effect shaders are appended to `fullscreen.wgsl`, which declares `VertexOutput`, `vs_main`
+//! and source texture in bind group 0.
+
+use std::borrow::Cow;
+
+use storyboard_render::{
+    texture::SizedTextureView2D,
+    wgpu::{
+        BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+        BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor,
+        BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device, FragmentState,
+        LoadOp, MultisampleState, Operations, PipelineLayoutDescriptor, PrimitiveState,
+        PrimitiveTopology, Queue, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+        RenderPipelineDescriptor, ShaderModule, ShaderStages, TextureFormat, VertexState,
+    },
+};
+
+/// Shader source of post effect, prepended with fullscreen vertex stage
+pub(crate) fn effect_shader_source(source: &str) -> Cow<'static, str> {
+    Cow::Owned([include_str!("fullscreen.wgsl"), source].concat())
+}
+
+/// Bind group layout of single uniform buffer read in fragment stage
+pub(crate) fn init_uniform_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &[BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+/// Uniform buffer of one pass with its bind group, kept with pass targets and rewritten every frame.
+/// Writes are applied before commands of the frame run, so one binding holds one value per frame.
+#[derive(Debug)]
+pub(crate) struct UniformBinding {
+    buffer: Buffer,
+    pub bind_group: BindGroup,
+}
+
+impl UniformBinding {
+    pub fn init(device: &Device, label: &str, layout: &BindGroupLayout, size: u64) -> Self {
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self { buffer, bind_group }
+    }
+
+    pub fn write(&self, queue: &Queue, data: &[u8]) {
+        queue.write_buffer(&self.buffer, 0, data);
+    }
+}
+
+/// Pipeline drawing fullscreen triangle using fragment entry point of effect shader, without blending
+pub(crate) fn init_fullscreen_pipeline(
+    device: &Device,
+    label: &str,
+    shader: &ShaderModule,
+    bind_group_layouts: &[&BindGroupLayout],
+    fragment_entry_point: &str,
+    format: TextureFormat,
+) -> RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: fragment_entry_point,
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        depth_stencil: None,
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            ..PrimitiveState::default()
+        },
+        multisample: MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// Clear target and draw fullscreen triangle into it. Bind groups are set in order from index 0.
+pub(crate) fn fullscreen_pass(
+    encoder: &mut CommandEncoder,
+    label: &str,
+    pipeline: &RenderPipeline,
+    bind_groups: &[&BindGroup],
+    target: &SizedTextureView2D,
+) {
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: target.inner(),
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(Color::TRANSPARENT),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: None,
+    });
+
+    pass.set_pipeline(pipeline);
+    for (index, bind_group) in bind_groups.iter().enumerate() {
+        pass.set_bind_group(index as u32, bind_group, &[]);
+    }
+    pass.draw(0..3, 0..1);
+}
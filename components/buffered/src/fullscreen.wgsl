@@ -0,0 +1,24 @@
+// Fullscreen triangle sampling source texture, prepended to post effect shaders
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) texture_coord: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var texture: texture_2d<f32>;
+@group(0) @binding(1)
+var texture_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let coord = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.position = vec4<f32>(coord.x * 2.0 - 1.0, 1.0 - coord.y * 2.0, 0.0, 1.0);
+    out.texture_coord = coord;
+
+    return out;
+}
+
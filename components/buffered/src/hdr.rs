@@ -0,0 +1,290 @@
+use std::{iter, mem, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use parking_lot::Mutex;
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Angle, Point2D, Rect, Size2D, Transform3D},
+    store::{Store, StoreResources},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
+};
+use storyboard_primitive::{PrimitiveComponent, Rectangle};
+use storyboard_render::{
+    cache::shader::ShaderCache,
+    component::Drawable,
+    renderer::{context::DrawContext, ComponentQueue},
+    shared::{RenderScopeContext, RenderShared},
+    texture::{SizedTexture2D, SizedTextureView2D},
+    wgpu::{
+        BindGroupLayout, CommandEncoder, Device, Queue, RenderPipeline, ShaderModule,
+        ShaderModuleDescriptor, ShaderSource, TextureFormat, TextureUsages,
+    },
+};
+use storyboard_texture::{
+    render::{data::TextureData, renderer::StoryboardTextureRenderer, RenderTexture2D},
+    ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap,
+};
+
+use crate::{
+    fullscreen::{
+        effect_shader_source, fullscreen_pass, init_fullscreen_pipeline,
+        init_uniform_bind_group_layout, UniformBinding,
+    },
+    offscreen_screen, OffscreenShared,
+};
+
+/// HDR drawable.
+///
+/// Renders drawable into float texture, so colors brighter than 1.0 are kept,
+/// then tonemaps it into current target format.
+#[derive(Debug)]
+pub struct Hdr<T> {
+    pub drawable: T,
+
+    pub bounds: Rect<f32, LogicalPixelUnit>,
+
+    /// Format of offscreen texture
+    pub format: TextureFormat,
+    /// Exposure multiplied before tonemapping
+    pub exposure: f32,
+
+    pub cached_data: Arc<CachedHdrData>,
+}
+
+impl<T> Hdr<T> {
+    pub const DEFAULT_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+}
+
+impl<T: Drawable> Drawable for Hdr<T> {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        encoder: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        let (bounds, screen) =
+            match offscreen_screen(&ctx.screen, self.bounds, ctx.screen.scale_factor) {
+                Some(screen) => screen,
+                None => return,
+            };
+
+        let device = ctx.scope.backend().device();
+        let textures = ctx.scope.backend().get::<TextureData>();
+        let texture_format = ctx.scope.pipeline().texture_format;
+
+        let mut cache = self.cached_data.inner.lock();
+        let cache = match &mut *cache {
+            Some(cache) if cache.shared.pipeline().texture_format == self.format => cache,
+
+            cache => cache.insert(HdrCache {
+                shared: ctx.scope.get::<OffscreenShared>().get(self.format),
+                renderer: StoryboardTextureRenderer::init(
+                    device,
+                    textures,
+                    self.format,
+                    screen.rect.size,
+                ),
+                target: None,
+            }),
+        };
+
+        cache.renderer.render(
+            ctx.scope.backend().render_scope(&cache.shared),
+            screen,
+            textures,
+            iter::once(&self.drawable as _),
+            encoder,
+        );
+
+        let target = match &mut cache.target {
+            Some(target) if target.size == screen.rect.size && target.format == texture_format => {
+                target
+            }
+
+            target => target.insert(TonemapTarget::init(
+                device,
+                textures,
+                ctx.scope.get::<TonemapResources>(),
+                texture_format,
+                screen.rect.size,
+            )),
+        };
+
+        ctx.scope.get::<TonemapResources>().tonemap_pass(
+            ctx.scope.backend().queue(),
+            encoder,
+            &target.uniform,
+            cache.renderer.render_texture(),
+            &target.view,
+            self.exposure,
+        );
+
+        if let Some(component) = PrimitiveComponent::from_rectangle(
+            &Rectangle {
+                bounds,
+                color: ShapeColor::WHITE,
+                texture: Some(ComponentTexture::new(
+                    target.texture.clone(),
+                    TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                    (TextureWrap::Clamp, TextureWrap::Clamp),
+                )),
+                texture_coord: None,
+                transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                alpha_cutoff: None,
+            },
+            ctx,
+            depth,
+        ) {
+            component_queue.push_transparent(component);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CachedHdrData {
+    inner: Mutex<Option<HdrCache>>,
+}
+
+impl CachedHdrData {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HdrCache {
+    /// Render data for pipelines created against offscreen format
+    shared: Arc<RenderShared>,
+    renderer: StoryboardTextureRenderer,
+    target: Option<TonemapTarget>,
+}
+
+/// Tonemapped output texture in target format
+#[derive(Debug)]
+struct TonemapTarget {
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    format: TextureFormat,
+
+    view: SizedTextureView2D,
+    texture: Arc<RenderTexture2D>,
+
+    uniform: UniformBinding,
+}
+
+impl TonemapTarget {
+    pub fn init(
+        device: &Device,
+        textures: &TextureData,
+        resources: &TonemapResources,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+    ) -> Self {
+        let texture = SizedTexture2D::init(
+            device,
+            Some("Tonemap target texture"),
+            size,
+            format,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+
+        Self {
+            size,
+            format,
+
+            view: texture.create_view_default(None),
+            texture: Arc::new(textures.create_tracked_render_texture(device, &texture, None)),
+
+            uniform: UniformBinding::init(
+                device,
+                "Tonemap uniform",
+                &resources.uniform_bind_group_layout,
+                mem::size_of::<TonemapUniform>() as u64,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct TonemapUniform {
+    exposure: f32,
+    _padding: [f32; 3],
+}
+
+#[derive(Debug)]
+pub struct TonemapResources {
+    pub pipeline: RenderPipeline,
+    pub uniform_bind_group_layout: BindGroupLayout,
+}
+
+impl TonemapResources {
+    /// Tonemap source texture into target
+    fn tonemap_pass(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        uniform: &UniformBinding,
+        source: &RenderTexture2D,
+        target: &SizedTextureView2D,
+        exposure: f32,
+    ) {
+        uniform.write(
+            queue,
+            bytemuck::bytes_of(&TonemapUniform {
+                exposure,
+                _padding: [0.0; 3],
+            }),
+        );
+
+        fullscreen_pass(
+            encoder,
+            "Tonemap render pass",
+            &self.pipeline,
+            &[source.bind_group(), &uniform.bind_group],
+            target,
+        );
+    }
+}
+
+impl StoreResources<RenderScopeContext<'_>> for TonemapResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let device = ctx.backend.device();
+        let textures = ctx.backend.get::<TextureData>();
+
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("tonemap_shader", || init_tonemap_shader(device));
+
+        let uniform_bind_group_layout =
+            init_uniform_bind_group_layout(device, "Tonemap uniform bind group layout");
+
+        let pipeline = init_fullscreen_pipeline(
+            device,
+            "Tonemap pipeline",
+            &shader,
+            &[textures.bind_group_layout(), &uniform_bind_group_layout],
+            "fs_main",
+            ctx.pipeline.texture_format,
+        );
+
+        Self {
+            pipeline,
+            uniform_bind_group_layout,
+        }
+    }
+}
+
+pub fn init_tonemap_shader(device: &Device) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Tonemap shader"),
+        source: ShaderSource::Wgsl(effect_shader_source(include_str!("tonemap.wgsl"))),
+    })
+}
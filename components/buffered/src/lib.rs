@@ -1,27 +1,41 @@
-use std::{iter, sync::Arc};
+use std::{collections::HashMap, iter, sync::Arc};
 
 use parking_lot::Mutex;
 use renderer::StoryboardTextureRenderer;
 use storyboard_core::{
     color::ShapeColor,
-    euclid::{Point2D, Rect, Transform3D},
-    unit::LogicalPixelUnit,
+    euclid::{Angle, Point2D, Rect, Transform3D},
+    store::{Store, StoreResources},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
 };
 use storyboard_primitive::{PrimitiveComponent, Rectangle};
 use storyboard_render::{
     component::Drawable,
     renderer::{context::DrawContext, ComponentQueue},
-    wgpu::CommandEncoder,
+    shared::{RenderPipelineData, RenderScopeContext, RenderShared},
+    wgpu::{CommandEncoder, TextureFormat},
     ScreenRect,
 };
-use storyboard_texture::render::data::TextureData;
+use storyboard_texture::{
+    render::{data::TextureData, RenderTexture2D},
+    ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap,
+};
 
+pub mod bloom;
+pub mod blur;
+pub mod clip;
+mod fullscreen;
+pub mod hdr;
 pub mod renderer;
 
 pub trait Bufferable: Drawable {
     fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>>;
 }
 
+/// Drawable rendered into cached texture, which is drawn as rectangle.
+///
+/// Inner content is rendered into outer encoder during prepare, which completes before outer render pass samples the texture.
+/// Buffered drawables can be nested, innermost content is rendered first.
 #[derive(Debug)]
 pub struct BufferedDrawable<T> {
     pub drawable: T,
@@ -37,57 +51,34 @@ impl<T: Bufferable> Drawable for BufferedDrawable<T> {
         depth: f32,
     ) {
         let (logical_rect, physical_screen) = if let Some(rect) = self.drawable.bounds() {
-            if rect.area() <= 0.0 {
-                return;
+            match offscreen_screen(&ctx.screen, rect, ctx.screen.scale_factor) {
+                Some(screen) => screen,
+                None => return,
             }
-
-            let phyiscal_rect = ScreenRect::new(
-                Rect::new(rect.origin, rect.size * ctx.screen.scale_factor)
-                    .round_out()
-                    .cast()
-                    .cast_unit(),
-                ctx.screen.scale_factor,
-            );
-
-            (rect.round_out(), phyiscal_rect)
         } else {
             (ctx.screen.get_logical_rect(), ctx.screen)
         };
-        
-        let textures = ctx.scope.backend().get::<TextureData>();
-
-        let mut inner_renderer = self.cached_data.inner_renderer.lock();
-        if inner_renderer.is_none() {
-            *inner_renderer = Some(StoryboardTextureRenderer::init(
-                ctx.scope.backend().device(),
-                textures,
-                ctx.scope.pipeline().texture_format,
-                physical_screen.rect.size,
-            ));
-        }
-
-        let inner_renderer = inner_renderer.as_mut().unwrap();
 
-        inner_renderer.render(
-            ctx.scope,
-            physical_screen,
-            textures,
-            iter::once(&self.drawable as _),
-            encoder,
-        );
+        let texture = self
+            .cached_data
+            .render(ctx, physical_screen, &self.drawable, encoder);
 
         if let Some(component) = PrimitiveComponent::from_rectangle(
             &Rectangle {
                 bounds: logical_rect,
                 color: ShapeColor::WHITE,
-                texture: Some(inner_renderer.render_texture().clone()),
-                texture_coord: [
-                    Point2D::new(0.0, 0.0),
-                    Point2D::new(0.0, 1.0),
-                    Point2D::new(1.0, 1.0),
-                    Point2D::new(1.0, 0.0),
-                ],
+                texture: Some(ComponentTexture::new(
+                    texture,
+                    TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                    (TextureWrap::Clamp, TextureWrap::Clamp),
+                )),
+                texture_coord: None,
                 transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                alpha_cutoff: None,
             },
             ctx,
             depth,
@@ -97,6 +88,73 @@ impl<T: Bufferable> Drawable for BufferedDrawable<T> {
     }
 }
 
+impl<T: Bufferable> Bufferable for BufferedDrawable<T> {
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        self.drawable.bounds()
+    }
+}
+
+/// Screen of offscreen texture covering visible part of logical bounds, rendered in given scale factor.
+/// Returns logical rect covered by the texture, aligned to its texels, with the screen.
+/// Returns None if bounds are empty or outside of current screen.
+pub(crate) fn offscreen_screen(
+    screen: &ScreenRect,
+    bounds: Rect<f32, LogicalPixelUnit>,
+    scale_factor: f32,
+) -> Option<(Rect<f32, LogicalPixelUnit>, ScreenRect)> {
+    // Physical rect cannot have negative origin, so part outside of screen is dropped.
+    // It is not visible anyway and texture is not allocated for it.
+    let bounds = bounds.intersection(&screen.get_logical_rect())?;
+    if bounds.is_empty() {
+        return None;
+    }
+
+    // Origin and size are both scaled, so ortho matrix of offscreen screen maps logical bounds onto texture
+    let rect: Rect<u32, PhyiscalPixelUnit> =
+        (bounds * scale_factor).round_out().try_cast()?.cast_unit();
+    if rect.is_empty() {
+        return None;
+    }
+
+    let offscreen = ScreenRect {
+        rect,
+        scale_factor,
+        ..*screen
+    };
+
+    Some((offscreen.physical_rect_to_logical(rect), offscreen))
+}
+
+/// Render data of offscreen texture formats, like float format of HDR and bloom.
+/// Pipelines are created against each format once per render scope and shared by every drawable using it.
+#[derive(Debug)]
+pub struct OffscreenShared {
+    pipeline: RenderPipelineData,
+    formats: Mutex<HashMap<TextureFormat, Arc<RenderShared>>>,
+}
+
+impl OffscreenShared {
+    /// Get render data using given format, with other pipeline configuration of render scope
+    pub fn get(&self, format: TextureFormat) -> Arc<RenderShared> {
+        self.formats
+            .lock()
+            .entry(format)
+            .or_insert_with(|| {
+                Arc::new(RenderShared::new(self.pipeline.with_texture_format(format)))
+            })
+            .clone()
+    }
+}
+
+impl StoreResources<RenderScopeContext<'_>> for OffscreenShared {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        Self {
+            pipeline: ctx.pipeline.clone(),
+            formats: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct CachedBufferData {
     inner_renderer: Mutex<Option<StoryboardTextureRenderer>>,
@@ -108,4 +166,35 @@ impl CachedBufferData {
             inner_renderer: Mutex::new(None),
         }
     }
+
+    /// Render drawable into cached texture and return it.
+    /// Texture is overwritten on every call, so same data must not be shared between drawables drawn in same frame.
+    pub(crate) fn render(
+        &self,
+        ctx: &DrawContext,
+        screen: ScreenRect,
+        drawable: &dyn Drawable,
+        encoder: &mut CommandEncoder,
+    ) -> Arc<RenderTexture2D> {
+        let textures = ctx.scope.backend().get::<TextureData>();
+
+        let mut inner_renderer = self.inner_renderer.lock();
+        let inner_renderer = inner_renderer.get_or_insert_with(|| {
+            let mut renderer = StoryboardTextureRenderer::init(
+                ctx.scope.backend().device(),
+                textures,
+                ctx.scope.pipeline().texture_format,
+                screen.rect.size,
+            );
+
+            // Share depth texture with other buffered drawables of same size
+            renderer.renderer_mut().set_pooled_depth(true);
+
+            renderer
+        });
+
+        inner_renderer.render(ctx.scope, screen, textures, iter::once(drawable), encoder);
+
+        inner_renderer.render_texture().clone()
+    }
 }
@@ -0,0 +1,29 @@
+struct TonemapUniform {
+    exposure: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> tonemap: TonemapUniform;
+
+// ACES filmic curve approximation
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    return clamp(
+        (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0)
+    );
+}
+
+// Interleaved gradient noise, breaks up banding when quantized into 8 bit target
+fn dither(position: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(position, vec2<f32>(0.06711056, 0.00583715)))) - 0.5;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.texture_coord);
+
+    let mapped = aces(color.rgb * tonemap.exposure) + dither(in.position.xy) / 255.0;
+
+    return vec4<f32>(max(mapped, vec3<f32>(0.0)), clamp(color.a, 0.0, 1.0));
+}
@@ -0,0 +1,915 @@
+use std::{iter, num::NonZeroU32, sync::Arc};
+
+use storyboard_box2d::{Box2D, Box2DStyle};
+use storyboard_buffered::{
+    blur::{Blur, CachedBlurData},
+    clip::RoundedClip,
+    Bufferable, BufferedDrawable, CachedBufferData,
+};
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Angle, Point2D, Rect, Size2D, Transform3D, Vector2D},
+    math::RectExt,
+    palette::LinSrgba,
+    unit::LogicalPixelUnit,
+};
+use storyboard_primitive::{
+    icon::{Icon, IconBatch},
+    Rectangle, Triangle,
+};
+use storyboard_render::{
+    backend::StoryboardBackend,
+    buffer::stream::BufferStream,
+    component::Drawable,
+    renderer::{context::DrawContext, stack::ComponentStack, ComponentQueue, StoryboardRenderer},
+    shared::RenderScope,
+    texture::{SizedTexture2D, SizedTexture2DArray},
+    wgpu::{
+        BufferDescriptor, BufferUsages, Color, CommandEncoder, Extent3d, FilterMode,
+        ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Maintain, MapMode, Origin3d,
+        TextureAspect, TextureUsages,
+    },
+    ScreenRect,
+};
+use storyboard_test_util::{
+    create_encoder, full_rect, pixel, read_backdrop_pixels, read_pixels, read_screen_pixels,
+    rectangle, TestBackend, FORMAT, GREEN, RED, SIZE,
+};
+use storyboard_texture::{
+    render::{data::TextureData, renderer::StoryboardTextureRenderer},
+    ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap, SAMPLER_WRAP_MODE,
+};
+
+/// Rectangles drawn in order
+#[derive(Debug)]
+struct Pattern(Vec<Rectangle>);
+
+impl Drawable for Pattern {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        encoder: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        for rect in &self.0 {
+            rect.prepare(component_queue, ctx, encoder, depth)
+        }
+    }
+}
+
+impl Bufferable for Pattern {
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        self.0
+            .iter()
+            .map(|rect| rect.bounds)
+            .reduce(|bounds, rect| bounds.union(&rect))
+    }
+}
+
+/// Red top half and green bottom half
+fn top_bottom_pattern() -> Pattern {
+    let half = Size2D::new(SIZE as f32, SIZE as f32 / 2.0);
+
+    Pattern(vec![
+        rectangle(Rect::new(Point2D::zero(), half), ShapeColor::RED, None),
+        rectangle(
+            Rect::new(Point2D::new(0.0, half.height), half),
+            ShapeColor::GREEN,
+            None,
+        ),
+    ])
+}
+
+/// Render [top_bottom_pattern] to render texture
+fn render_pattern_texture(
+    backend: &StoryboardBackend,
+    scope: RenderScope,
+) -> StoryboardTextureRenderer {
+    render_texture(backend, scope, &top_bottom_pattern())
+}
+
+/// Render drawable to transparent render texture
+fn render_texture(
+    backend: &StoryboardBackend,
+    scope: RenderScope,
+    drawable: &dyn Drawable,
+) -> StoryboardTextureRenderer {
+    let textures = scope.backend().get::<TextureData>();
+    let mut texture_renderer = StoryboardTextureRenderer::init(
+        backend.device(),
+        textures,
+        FORMAT,
+        Size2D::new(SIZE, SIZE),
+    );
+
+    let mut encoder = create_encoder(backend);
+    texture_renderer.render(
+        scope,
+        ScreenRect::new(Rect::from_size(Size2D::new(SIZE, SIZE)), 1.0),
+        textures,
+        [drawable].into_iter(),
+        &mut encoder,
+    );
+    backend.queue().submit(Some(encoder.finish()));
+
+    texture_renderer
+}
+
+#[test]
+fn nested_buffered_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let outer_data = Arc::new(CachedBufferData::new());
+    let inner_data = Arc::new(CachedBufferData::new());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Cached textures are reused, so second frame must not show content of first frame
+    for (color, expected) in [(ShapeColor::RED, RED), (ShapeColor::GREEN, GREEN)] {
+        let drawable = BufferedDrawable {
+            drawable: BufferedDrawable {
+                drawable: Pattern(vec![rectangle(full_rect(), color, None)]),
+                cached_data: inner_data.clone(),
+            },
+            cached_data: outer_data.clone(),
+        };
+
+        let data = read_pixels(backend, scope, &mut renderer, &[&drawable]);
+
+        for y in [0, SIZE / 2, SIZE - 1] {
+            for x in [0, SIZE / 2, SIZE - 1] {
+                assert_eq!(pixel(&data, x, y), expected, "pixel at {}, {}", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn offset_scaled_buffered_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Physical origin (64, 64) on 2x scale, logical screen covers (32, 32) to (64, 64)
+    let screen = ScreenRect::new(
+        Rect::new(Point2D::new(SIZE, SIZE), Size2D::new(SIZE, SIZE)),
+        2.0,
+    );
+    let logical = screen.get_logical_rect();
+
+    // Red top half and green bottom half of logical screen
+    let half = Size2D::new(logical.width(), logical.height() / 2.0);
+    let pattern = || {
+        Pattern(vec![
+            rectangle(Rect::new(logical.origin, half), ShapeColor::RED, None),
+            rectangle(
+                Rect::new(logical.origin + Vector2D::new(0.0, half.height), half),
+                ShapeColor::GREEN,
+                None,
+            ),
+        ])
+    };
+
+    let buffered = BufferedDrawable {
+        drawable: pattern(),
+        cached_data: Arc::new(CachedBufferData::new()),
+    };
+    let clip = RoundedClip::new(pattern(), logical, 0.0);
+
+    for (name, drawable) in [
+        ("buffered", &buffered as &dyn Drawable),
+        ("rounded clip", &clip),
+    ] {
+        let data = read_screen_pixels(backend, scope, &mut renderer, screen, &[drawable]);
+
+        // Offset content would move the boundary between halves or leave edges empty
+        for x in [4, SIZE / 2, SIZE - 5] {
+            for y in [4, SIZE / 2 - 4] {
+                assert_eq!(pixel(&data, x, y), RED, "{} pixel at {}, {}", name, x, y);
+            }
+
+            for y in [SIZE / 2 + 4, SIZE - 5] {
+                assert_eq!(pixel(&data, x, y), GREEN, "{} pixel at {}, {}", name, x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn partially_offscreen_buffered_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Pattern moved half of its size past top left corner of screen, only its green bottom right quarter is visible
+    let offset = Vector2D::new(-(SIZE as f32) / 2.0, -(SIZE as f32) / 2.0);
+    let pattern = || {
+        Pattern(
+            top_bottom_pattern()
+                .0
+                .into_iter()
+                .map(|rect| Rectangle {
+                    bounds: rect.bounds.translate(offset),
+                    ..rect
+                })
+                .collect(),
+        )
+    };
+
+    let buffered = BufferedDrawable {
+        drawable: pattern(),
+        cached_data: Arc::new(CachedBufferData::new()),
+    };
+    let clip = RoundedClip::new(pattern(), full_rect().translate(offset), 0.0);
+
+    for (name, drawable) in [
+        ("buffered", &buffered as &dyn Drawable),
+        ("rounded clip", &clip),
+    ] {
+        let data = read_pixels(backend, scope, &mut renderer, &[drawable]);
+
+        assert_eq!(pixel(&data, 4, 4), GREEN, "{}", name);
+        assert_eq!(pixel(&data, SIZE / 2 - 5, SIZE / 2 - 5), GREEN, "{}", name);
+        assert_eq!(pixel(&data, SIZE - 1, SIZE - 1), [0; 4], "{}", name);
+    }
+}
+
+#[test]
+fn empty_bounds_prepare_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let zero_width = Rect::new(Point2D::zero(), Size2D::new(0.0, SIZE as f32));
+    let nan_origin = Rect::new(Point2D::new(f32::NAN, 0.0), full_rect().size);
+
+    let box2d = |bounds| Box2D {
+        bounds,
+        fill_color: ShapeColor::WHITE,
+        border_color: ShapeColor::WHITE,
+        texture: None,
+        style: Box2DStyle::default(),
+        transform: Transform3D::identity(),
+        rotation: Angle::zero(),
+        rotation_anchor: Point2D::zero(),
+        clip: None,
+        force_opaque: true,
+        tag: None,
+    };
+
+    let triangle = |bounds| Triangle {
+        bounds,
+        color: ShapeColor::WHITE,
+        texture: None,
+        texture_coord: None,
+        transform: Transform3D::identity(),
+        rotation: Angle::zero(),
+        rotation_anchor: Point2D::zero(),
+        force_opaque: false,
+        alpha_cutoff: None,
+    };
+
+    let drawables: [&dyn Drawable; 6] = [
+        &box2d(zero_width),
+        &box2d(nan_origin),
+        &rectangle(zero_width, ShapeColor::WHITE, None),
+        &rectangle(nan_origin, ShapeColor::WHITE, None),
+        &triangle(zero_width),
+        &triangle(nan_origin),
+    ];
+
+    let mut vertex_stream = BufferStream::new(None, BufferUsages::VERTEX);
+    let mut index_stream = BufferStream::new(None, BufferUsages::INDEX);
+    let mut uniform_stream = BufferStream::new(None, BufferUsages::UNIFORM);
+
+    let screen = ScreenRect::new(Rect::from_size(Size2D::new(SIZE, SIZE)), 1.0);
+    let mut ctx = DrawContext {
+        scope,
+        screen,
+        screen_matrix: screen.get_logical_ortho_matrix(),
+        vertex_stream: &mut vertex_stream,
+        index_stream: &mut index_stream,
+        uniform_stream: &mut uniform_stream,
+    };
+
+    let mut opaque = ComponentStack::new();
+    let mut transparent = ComponentStack::new();
+    let mut queue = ComponentQueue::new(&mut opaque, &mut transparent);
+
+    let mut encoder = create_encoder(backend);
+    for drawable in drawables {
+        drawable.prepare(&mut queue, &mut ctx, &mut encoder, 0.5);
+    }
+
+    assert!(opaque.is_empty());
+    assert!(transparent.is_empty());
+    assert_eq!(vertex_stream.written_size(), 0);
+    assert_eq!(index_stream.written_size(), 0);
+}
+
+#[test]
+fn rounded_clip_corner_test() {
+    let test = TestBackend::init(Some(StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT));
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    let background = rectangle(full_rect(), ShapeColor::RED, None);
+
+    // Opaque content covering whole card, overflowing corners if not clipped
+    let clip = RoundedClip::new(
+        Pattern(vec![rectangle(full_rect(), ShapeColor::GREEN, None)]),
+        full_rect(),
+        16.0,
+    );
+
+    // Drawn after leaving clip, so it's not clipped
+    let after = rectangle(
+        Rect::new(Point2D::zero(), Size2D::new(1.0, 1.0)),
+        ShapeColor::BLUE,
+        None,
+    );
+
+    let data = read_pixels(backend, scope, &mut renderer, &[&background, &clip, &after]);
+
+    // Outside of corner arcs, background is left untouched
+    for x in [1, 2, SIZE - 3, SIZE - 1] {
+        for y in [1, 2, SIZE - 3, SIZE - 1] {
+            assert_eq!(pixel(&data, x, y), RED, "pixel at {}, {}", x, y);
+        }
+    }
+
+    // Inside of arcs and along edges, content is drawn
+    for (x, y) in [(SIZE / 2, SIZE / 2), (SIZE / 2, 2), (2, SIZE / 2), (8, 8)] {
+        assert_eq!(pixel(&data, x, y), GREEN, "pixel at {}, {}", x, y);
+    }
+
+    assert_eq!(pixel(&data, 0, 0)[..3], [0, 0, 255]);
+
+    // Nested clip draws content inside of both clips only
+    let nested = RoundedClip::new(
+        RoundedClip::new(
+            Pattern(vec![rectangle(full_rect(), ShapeColor::GREEN, None)]),
+            Rect::new(Point2D::zero(), Size2D::new(SIZE as f32 / 2.0, SIZE as f32)),
+            0.0,
+        ),
+        full_rect(),
+        16.0,
+    );
+
+    let data = read_pixels(backend, scope, &mut renderer, &[&background, &nested]);
+
+    for (x, y) in [
+        (1, 1),
+        (1, SIZE - 2),
+        (SIZE / 2 + 4, SIZE / 2),
+        (SIZE - 4, 4),
+    ] {
+        assert_eq!(pixel(&data, x, y), RED, "pixel at {}, {}", x, y);
+    }
+
+    for (x, y) in [(SIZE / 4, SIZE / 2), (SIZE / 2 - 2, 2), (2, SIZE / 2)] {
+        assert_eq!(pixel(&data, x, y), GREEN, "pixel at {}, {}", x, y);
+    }
+}
+
+#[test]
+fn blur_backdrop_copy_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    let blur = Blur {
+        bounds: full_rect(),
+        border_radius: [0.0; 4],
+        radius: 4.0,
+        tint: ShapeColor::WHITE,
+        cached_data: Arc::new(CachedBlurData::new()),
+    };
+
+    // Drawn after blur in same frame, so it's not blurred
+    let after = rectangle(
+        Rect::new(Point2D::zero(), Size2D::new(4.0, 4.0)),
+        ShapeColor::BLUE,
+        None,
+    );
+
+    let data = read_backdrop_pixels(
+        backend,
+        scope,
+        &mut renderer,
+        &[&top_bottom_pattern(), &blur, &after],
+    );
+
+    // Backdrop drawn before blur in same frame is copied and blurred across the edge
+    let edge = pixel(&data, SIZE / 2, SIZE / 2 - 1);
+    assert!(edge[0] > 64 && edge[1] > 64, "edge {:?}", edge);
+
+    // Far from the edge, backdrop color is kept
+    assert_eq!(pixel(&data, SIZE / 2, 8), RED);
+    assert_eq!(pixel(&data, SIZE / 2, SIZE - 8), GREEN);
+
+    assert_eq!(pixel(&data, 1, 1)[..3], [0, 0, 255]);
+
+    // Without backdrop, blur is not drawn and edge stays sharp
+    let data = read_pixels(
+        backend,
+        scope,
+        &mut renderer,
+        &[&top_bottom_pattern(), &blur],
+    );
+
+    assert_eq!(pixel(&data, SIZE / 2, SIZE / 2 - 1), RED);
+    assert_eq!(pixel(&data, SIZE / 2, SIZE / 2), GREEN);
+}
+
+#[test]
+fn texture_array_layers_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let array = SizedTexture2DArray::init(
+        backend.device(),
+        Some("texture_array_layers_test array"),
+        Size2D::new(SIZE, SIZE),
+        2,
+        FORMAT,
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+    );
+    assert!(array.create_layer_view(2, None).is_none());
+
+    // Different content per layer, rendered by same renderer into one texture
+    let layers: [&dyn Drawable; 2] = [
+        &top_bottom_pattern(),
+        &rectangle(full_rect(), ShapeColor::GREEN, None),
+    ];
+
+    let mut renderer = StoryboardRenderer::new();
+    let mut encoder = create_encoder(backend);
+    for (layer, drawable) in layers.iter().enumerate() {
+        let view = array.create_layer_view(layer as u32, None).unwrap();
+
+        renderer
+            .render_to_view(
+                scope,
+                view.inner(),
+                FORMAT,
+                Size2D::new(SIZE, SIZE),
+                1.0,
+                Some(Color::TRANSPARENT),
+                iter::once(*drawable),
+                &mut encoder,
+            )
+            .unwrap();
+    }
+
+    let bytes_per_row = SIZE * 4;
+    let layer_size = (bytes_per_row * SIZE) as usize;
+    let readback = backend.device().create_buffer(&BufferDescriptor {
+        label: Some("texture_array_layers_test readback buffer"),
+        size: (layer_size * 2) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+            texture: array.inner(),
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        ImageCopyBuffer {
+            buffer: &readback,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: NonZeroU32::new(SIZE),
+            },
+        },
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 2,
+        },
+    );
+
+    backend.queue().submit(
+        renderer
+            .take_prepare_commands()
+            .into_iter()
+            .chain(iter::once(encoder.finish())),
+    );
+
+    let slice = readback.slice(..);
+    slice.map_async(MapMode::Read, |result| result.unwrap());
+    backend.poll(Maintain::Wait);
+
+    let data = slice.get_mapped_range().to_vec();
+    let (first, second) = data.split_at(layer_size);
+
+    // First layer keeps its pattern after second layer is rendered
+    assert_eq!(pixel(first, SIZE / 2, SIZE / 4), RED);
+    assert_eq!(pixel(first, SIZE / 2, SIZE * 3 / 4), GREEN);
+
+    assert_eq!(pixel(second, SIZE / 2, SIZE / 4), GREEN);
+    assert_eq!(pixel(second, SIZE / 2, SIZE * 3 / 4), GREEN);
+}
+
+#[test]
+fn render_texture_orientation_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Buffered content keeps orientation without flipping
+    let data = read_pixels(
+        backend,
+        scope,
+        &mut renderer,
+        &[&BufferedDrawable {
+            drawable: top_bottom_pattern(),
+            cached_data: Arc::new(CachedBufferData::new()),
+        }],
+    );
+    assert_eq!(pixel(&data, SIZE / 2, 0), RED);
+    assert_eq!(pixel(&data, SIZE / 2, SIZE - 1), GREEN);
+
+    // Sampling render texture with flip_y turns it upside down
+    let texture_renderer = render_pattern_texture(backend, scope);
+
+    for (flip_y, top, bottom) in [(false, RED, GREEN), (true, GREEN, RED)] {
+        let texture = ComponentTexture {
+            flip_y,
+            ..ComponentTexture::new(
+                texture_renderer.render_texture().clone(),
+                TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                (TextureWrap::Clamp, TextureWrap::Clamp),
+            )
+        };
+
+        let data = read_pixels(
+            backend,
+            scope,
+            &mut renderer,
+            &[&rectangle(full_rect(), ShapeColor::WHITE, Some(texture))],
+        );
+        assert_eq!(pixel(&data, SIZE / 2, 0), top, "flip_y: {}", flip_y);
+        assert_eq!(
+            pixel(&data, SIZE / 2, SIZE - 1),
+            bottom,
+            "flip_y: {}",
+            flip_y
+        );
+    }
+}
+
+#[test]
+fn force_opaque_texture_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+    let texture_renderer = render_pattern_texture(backend, scope);
+
+    let data = read_pixels(
+        backend,
+        scope,
+        &mut renderer,
+        &[&Rectangle {
+            force_opaque: true,
+            ..rectangle(
+                full_rect(),
+                ShapeColor::WHITE,
+                Some(ComponentTexture::new(
+                    texture_renderer.render_texture().clone(),
+                    TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                    (TextureWrap::Clamp, TextureWrap::Clamp),
+                )),
+            )
+        }],
+    );
+
+    // Opaque pass doesn't write alpha, so compare color only
+    assert_eq!(pixel(&data, SIZE / 2, 0)[..3], RED[..3]);
+    assert_eq!(pixel(&data, SIZE / 2, SIZE - 1)[..3], GREEN[..3]);
+}
+
+#[test]
+fn sampler_wrap_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let textures = scope.backend().get::<TextureData>();
+
+    // Red left texel and green right texel
+    let texture = SizedTexture2D::init(
+        backend.device(),
+        Some("sampler_wrap_test texture"),
+        Size2D::new(2, 1),
+        FORMAT,
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+    );
+    texture.write(backend.queue(), None, &[RED, GREEN].concat());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    for (wrap, expected) in [(TextureWrap::Repeat, RED), (TextureWrap::Clamp, GREEN)] {
+        let component_texture = textures.create_component_texture(
+            backend.device(),
+            texture.create_view_default(None).into(),
+            FilterMode::Nearest,
+            1,
+            TextureLayout::Relative(TextureLayoutStyle::Stretched),
+            (wrap, wrap),
+        );
+        assert_eq!(component_texture.shader_wrap_mode(), [SAMPLER_WRAP_MODE; 2]);
+
+        // Texture coordinates span texture twice horizontally
+        let rect = Rectangle {
+            texture_coord: Some(Rect::new(Point2D::zero(), Size2D::new(2.0, 1.0)).into_coords()),
+            ..rectangle(full_rect(), ShapeColor::WHITE, Some(component_texture))
+        };
+
+        let data = read_pixels(backend, scope, &mut renderer, &[&rect]);
+
+        assert_eq!(pixel(&data, SIZE / 8, SIZE / 2), RED, "{:?}", wrap);
+        // Coordinate past right edge samples left texel again only if repeated
+        assert_eq!(pixel(&data, SIZE * 5 / 8, SIZE / 2), expected, "{:?}", wrap);
+    }
+}
+
+#[test]
+fn alpha_cutoff_test() {
+    let test = TestBackend::init(Some(StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT));
+    let (backend, scope) = (&test.backend, test.scope());
+
+    // Transparent top half and opaque green bottom half
+    let half = Size2D::new(SIZE as f32, SIZE as f32 / 2.0);
+    let texture_renderer = render_texture(
+        backend,
+        scope,
+        &rectangle(
+            Rect::new(Point2D::new(0.0, half.height), half),
+            ShapeColor::GREEN,
+            None,
+        ),
+    );
+
+    let back = rectangle(full_rect(), ShapeColor::BLUE, None);
+    let cutout = Rectangle {
+        alpha_cutoff: Some(0.5),
+        ..rectangle(
+            full_rect(),
+            ShapeColor::WHITE,
+            Some(ComponentTexture::new(
+                texture_renderer.render_texture().clone(),
+                TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                (TextureWrap::Clamp, TextureWrap::Clamp),
+            )),
+        )
+    };
+
+    let data = read_pixels(
+        backend,
+        scope,
+        &mut StoryboardRenderer::new(),
+        &[&back, &cutout],
+    );
+
+    // Opaque pass draws front to back, so rect behind is drawn after cutout and only shows through discarded part
+    assert_eq!(pixel(&data, SIZE / 2, 0)[..3], [0, 0, 255]);
+    assert_eq!(pixel(&data, SIZE / 2, SIZE - 1)[..3], GREEN[..3]);
+}
+
+#[test]
+fn icon_batch_draw_call_test() {
+    const ICONS: u32 = 100;
+
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+    let textures = scope.backend().get::<TextureData>();
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // 10x10 grid of distinct solid color icons
+    let cell = SIZE as f32 / 10.0;
+    let icon_bounds = |i: u32| {
+        Rect::new(
+            Point2D::new((i % 10) as f32 * cell, (i / 10) as f32 * cell),
+            Size2D::new(cell, cell),
+        )
+    };
+    let texel = |i: u32| [i as u8, 255, 0, 255];
+
+    // One texture and bind group per icon
+    let rects = (0..ICONS)
+        .map(|i| {
+            let texture = SizedTexture2D::init(
+                backend.device(),
+                None,
+                Size2D::new(1, 1),
+                FORMAT,
+                TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            );
+            texture.write(backend.queue(), None, &texel(i));
+
+            rectangle(
+                icon_bounds(i),
+                ShapeColor::WHITE,
+                Some(ComponentTexture::new(
+                    Arc::new(textures.create_tracked_render_texture(
+                        backend.device(),
+                        &texture,
+                        None,
+                    )),
+                    TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                    (TextureWrap::Clamp, TextureWrap::Clamp),
+                )),
+            )
+        })
+        .collect::<Vec<_>>();
+    let drawables = rects
+        .iter()
+        .map(|rect| rect as &dyn Drawable)
+        .collect::<Vec<_>>();
+
+    let separate = read_pixels(backend, scope, &mut renderer, &drawables);
+    assert!(renderer.stats().draw_calls >= ICONS);
+
+    // One texture array layer per icon
+    let array = SizedTexture2DArray::init(
+        backend.device(),
+        None,
+        Size2D::new(1, 1),
+        ICONS,
+        FORMAT,
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+    );
+    for i in 0..ICONS {
+        array.write_layer(backend.queue(), i, None, &texel(i));
+    }
+
+    let batch = IconBatch {
+        texture: Arc::new(textures.create_tracked_render_texture_array(
+            backend.device(),
+            array,
+            None,
+        )),
+        icons: Arc::new(
+            (0..ICONS)
+                .map(|i| Icon {
+                    bounds: icon_bounds(i),
+                    layer: i,
+                    color: LinSrgba::new(1.0, 1.0, 1.0, 1.0),
+                })
+                .collect(),
+        ),
+        transform: Transform3D::identity(),
+    };
+
+    let batched = read_pixels(backend, scope, &mut renderer, &[&batch]);
+    assert_eq!(renderer.stats().draw_calls, 1);
+
+    for i in 0..ICONS {
+        let center = icon_bounds(i).center().cast::<u32>();
+
+        assert_eq!(pixel(&batched, center.x, center.y), texel(i), "icon {}", i);
+        assert_eq!(
+            pixel(&batched, center.x, center.y),
+            pixel(&separate, center.x, center.y),
+            "icon {}",
+            i
+        );
+    }
+}
+
+#[test]
+fn corner_smoothing_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    let mut render_box = |style: Box2DStyle| {
+        let box2d = Box2D {
+            bounds: full_rect(),
+            fill_color: ShapeColor::WHITE,
+            border_color: ShapeColor::TRANSPARENT,
+            texture: None,
+            style: Box2DStyle {
+                border_radius: [24.0; 4],
+                ..style
+            },
+            transform: Transform3D::identity(),
+            rotation: Angle::zero(),
+            rotation_anchor: Point2D::zero(),
+            clip: None,
+            force_opaque: false,
+            tag: None,
+        };
+
+        read_pixels(backend, scope, &mut renderer, &[&box2d])
+    };
+
+    // Zero smoothing draws circular corners
+    let circular = render_box(Box2DStyle {
+        corner_smoothing: 0.0,
+        ..Default::default()
+    });
+    let smoothed = render_box(Box2DStyle {
+        corner_smoothing: 1.0,
+        ..Default::default()
+    });
+    assert_ne!(smoothed, circular);
+
+    // Zero smoothing matches circular arc reference, except pixels within aa width of arc
+    let center = Point2D::new(24.0, 24.0);
+    for x in 0..24 {
+        for y in 0..24 {
+            let distance = (Point2D::new(x as f32 + 0.5, y as f32 + 0.5) - center).length();
+
+            if distance < 23.0 {
+                assert_eq!(pixel(&circular, x, y)[3], 255, "pixel at {}, {}", x, y);
+            } else if distance > 25.0 {
+                assert_eq!(pixel(&circular, x, y)[3], 0, "pixel at {}, {}", x, y);
+            }
+        }
+    }
+
+    // Corner pixel outside of circular arc but inside of superellipse
+    assert_eq!(pixel(&circular, 5, 5)[3], 0);
+    assert!(pixel(&smoothed, 5, 5)[3] > 0);
+
+    // Straight edges and center are unchanged
+    for (x, y) in [(SIZE / 2, 2), (2, SIZE / 2), (SIZE / 2, SIZE / 2)] {
+        assert_eq!(pixel(&smoothed, x, y), pixel(&circular, x, y));
+    }
+}
+
+#[test]
+fn box_edge_aa_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Right edge lands on same physical position at every scale factor
+    const EDGE: f32 = 40.3;
+
+    for scale_factor in [1.0, 3.0] {
+        let box2d = Box2D {
+            bounds: Rect::new(
+                Point2D::zero(),
+                Size2D::new(EDGE / scale_factor, SIZE as f32 / scale_factor),
+            ),
+            fill_color: ShapeColor::WHITE,
+            border_color: ShapeColor::TRANSPARENT,
+            texture: None,
+            style: Box2DStyle::default(),
+            transform: Transform3D::identity(),
+            rotation: Angle::zero(),
+            rotation_anchor: Point2D::zero(),
+            clip: None,
+            force_opaque: false,
+            tag: None,
+        };
+
+        let data = read_screen_pixels(
+            backend,
+            scope,
+            &mut renderer,
+            ScreenRect::new(Rect::from_size(Size2D::new(SIZE, SIZE)), scale_factor),
+            &[&box2d],
+        );
+
+        let alpha = (0..SIZE)
+            .map(|x| pixel(&data, x, SIZE / 2)[3])
+            .collect::<Vec<_>>();
+
+        // Edge is smoothed over about one physical pixel, not scaled with logical pixel
+        let partial = alpha
+            .iter()
+            .filter(|alpha| **alpha > 0 && **alpha < 255)
+            .count();
+        assert!(
+            (1..=2).contains(&partial),
+            "scale factor {}, alpha {:?}",
+            scale_factor,
+            alpha
+        );
+
+        assert_eq!(
+            alpha[EDGE as usize - 2],
+            255,
+            "scale factor {}",
+            scale_factor
+        );
+        assert_eq!(alpha[EDGE as usize + 2], 0, "scale factor {}", scale_factor);
+    }
+}
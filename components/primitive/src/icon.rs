@@ -0,0 +1,259 @@
+//! Batched icons sampling layers of one texture array
+
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use storyboard_core::{
+    euclid::{Point2D, Rect, Transform3D},
+    math::{FiniteExt, RectExt},
+    palette::LinSrgba,
+    store::{Store, StoreResources},
+    unit::LogicalPixelUnit,
+};
+use storyboard_render::{
+    buffer::stream::StreamRange,
+    cache::shader::ShaderCache,
+    component::{Component, Drawable},
+    renderer::{
+        context::{DrawContext, RenderContext},
+        pass::StoryboardRenderPass,
+        screen::ScreenResources,
+        ComponentQueue,
+    },
+    shared::RenderScopeContext,
+    wgpu::{
+        vertex_attr_array, BindGroupLayout, BlendState, ColorTargetState, ColorWrites,
+        CommandEncoder, DepthStencilState, Device, FragmentState, MultisampleState, PipelineLayout,
+        PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, RenderPipeline,
+        RenderPipelineDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource,
+        VertexBufferLayout, VertexState, VertexStepMode,
+    },
+};
+use storyboard_texture::render::{data::TextureData, RenderTexture2DArray};
+
+#[derive(Debug)]
+pub struct IconResources {
+    pub pipeline: RenderPipeline,
+}
+
+impl StoreResources<RenderScopeContext<'_>> for IconResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("icon_shader", || init_icon_shader(ctx.backend.device()));
+
+        let pipeline_layout = init_icon_pipeline_layout(
+            ctx.backend.device(),
+            ctx.backend.get::<TextureData>().array_bind_group_layout(),
+            &ctx.backend.get::<ScreenResources>().bind_group_layout,
+        );
+
+        let pipeline = init_icon_pipeline(
+            ctx.backend.device(),
+            &pipeline_layout,
+            &shader,
+            &[Some(ColorTargetState {
+                format: ctx.pipeline.texture_format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            })],
+            ctx.pipeline.depth_stencil_read_only(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
+        );
+
+        Self { pipeline }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Icon {
+    pub bounds: Rect<f32, LogicalPixelUnit>,
+    /// Layer of texture array drawn
+    pub layer: u32,
+    /// Color multiplied to texture
+    pub color: LinSrgba<f32>,
+}
+
+/// Icons sampling layers of one texture array, drawn in one draw call using one bind group.
+/// Alternative of atlas packing without bleeding between neighbor icons.
+///
+/// Every icon is stretched to its bounds. Icons are drawn in transparent pass in order.
+#[derive(Debug, Clone)]
+pub struct IconBatch {
+    pub texture: Arc<RenderTexture2DArray>,
+    pub icons: Arc<Vec<Icon>>,
+    pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+}
+
+impl Drawable for IconBatch {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        _: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        if let Some(component) = IconBatchComponent::from_batch(self, ctx, depth) {
+            component_queue.push_transparent(component);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct IconBatchComponent {
+    texture: Arc<RenderTexture2DArray>,
+    instances_slice: StreamRange,
+    instances: u32,
+    bounds: Option<Rect<f32, LogicalPixelUnit>>,
+}
+
+impl IconBatchComponent {
+    /// Create component of icons. Icons with empty bounds, failed transform or out of range layer are skipped.
+    pub fn from_batch(batch: &IconBatch, ctx: &mut DrawContext, depth: f32) -> Option<Self> {
+        let layers = batch.texture.layers();
+
+        let instances = batch
+            .icons
+            .iter()
+            .filter_map(|icon| {
+                if icon.layer >= layers || icon.bounds.finite()?.area() <= 0.0 {
+                    return None;
+                }
+
+                let mut corners = [Point2D::zero(); 4];
+                for (corner, point) in corners.iter_mut().zip(icon.bounds.into_coords()) {
+                    *corner = ctx
+                        .to_screen_point(batch.transform.transform_point2d(point)?)
+                        .finite()?;
+                }
+
+                Some(IconInstance {
+                    corners,
+                    depth,
+                    layer: icon.layer,
+                    color: icon.color,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if instances.is_empty() {
+            return None;
+        }
+
+        let bounds = Rect::from_points(
+            instances
+                .iter()
+                .flat_map(|instance| instance.corners.iter()),
+        );
+
+        Some(Self {
+            texture: batch.texture.clone(),
+            instances_slice: ctx
+                .vertex_stream
+                .write_slice(bytemuck::cast_slice(&instances)),
+            instances: instances.len() as u32,
+            bounds: Some(bounds),
+        })
+    }
+}
+
+impl Component for IconBatchComponent {
+    fn render_opaque<'rpass>(
+        &'rpass self,
+        _: &RenderContext<'rpass>,
+        _: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        unreachable!()
+    }
+
+    fn render_transparent<'rpass>(
+        &'rpass self,
+        ctx: &RenderContext<'rpass>,
+        pass: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        pass.set_pipeline(&ctx.scope.get::<IconResources>().pipeline);
+
+        pass.set_bind_group(0, self.texture.bind_group(), &[]);
+        pass.set_bind_group(1, ctx.screen_bind_group, &[]);
+
+        pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.instances_slice.clone()));
+
+        pass.draw(0..6, 0..self.instances);
+    }
+
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        self.bounds
+    }
+}
+
+/// Per icon instance data. Quad vertices are generated in vertex shader.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct IconInstance {
+    /// Screen space corners in same order as [RectExt::into_coords]
+    pub corners: [Point2D<f32, LogicalPixelUnit>; 4],
+    pub depth: f32,
+    pub layer: u32,
+    pub color: LinSrgba<f32>,
+}
+
+pub fn init_icon_shader(device: &Device) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Icon shader"),
+        source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("icon.wgsl"))),
+    })
+}
+
+pub fn init_icon_pipeline_layout(
+    device: &Device,
+    texture_array_bind_group_layout: &BindGroupLayout,
+    screen_bind_group_layout: &BindGroupLayout,
+) -> PipelineLayout {
+    device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Icon shader pipeline layout"),
+        bind_group_layouts: &[texture_array_bind_group_layout, screen_bind_group_layout],
+        push_constant_ranges: &[],
+    })
+}
+
+pub fn init_icon_pipeline(
+    device: &Device,
+    pipeline_layout: &PipelineLayout,
+    shader: &ShaderModule,
+    fragment_targets: &[Option<ColorTargetState>],
+    depth_stencil: Option<DepthStencilState>,
+    primitive: PrimitiveState,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Icon pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[VertexBufferLayout {
+                array_stride: std::mem::size_of::<IconInstance>() as u64,
+                step_mode: VertexStepMode::Instance,
+                attributes: &vertex_attr_array![
+                    0 => Float32x2,
+                    1 => Float32x2,
+                    2 => Float32x2,
+                    3 => Float32x2,
+                    4 => Float32,
+                    5 => Uint32,
+                    6 => Float32x4
+                ],
+            }],
+        },
+        primitive,
+        depth_stencil,
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: fragment_targets,
+        }),
+        multiview: None,
+    })
+}
@@ -0,0 +1,65 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) texture_coord: vec2<f32>,
+    @location(2) @interpolate(flat) layer: u32,
+};
+
+struct Screen {
+    matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> screen: Screen;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) corner0: vec2<f32>,
+    @location(1) corner1: vec2<f32>,
+    @location(2) corner2: vec2<f32>,
+    @location(3) corner3: vec2<f32>,
+    @location(4) depth: f32,
+    @location(5) layer: u32,
+    @location(6) color: vec4<f32>,
+) -> VertexOutput {
+    // Two triangles of quad, corners in top left, bottom left, bottom right, top right order
+    var corner_indices = array<u32, 6>(0u, 1u, 2u, 0u, 2u, 3u);
+
+    var position: vec2<f32>;
+    var texture_coord: vec2<f32>;
+    switch (corner_indices[vertex_index]) {
+        case 0u: {
+            position = corner0;
+            texture_coord = vec2<f32>(0.0, 0.0);
+        }
+        case 1u: {
+            position = corner1;
+            texture_coord = vec2<f32>(0.0, 1.0);
+        }
+        case 2u: {
+            position = corner2;
+            texture_coord = vec2<f32>(1.0, 1.0);
+        }
+        default: {
+            position = corner3;
+            texture_coord = vec2<f32>(1.0, 0.0);
+        }
+    }
+
+    var out: VertexOutput;
+
+    out.position = vec4<f32>((screen.matrix * vec4<f32>(position, 0.0, 1.0)).xy, depth, 1.0);
+    out.color = color;
+    out.texture_coord = texture_coord;
+    out.layer = layer;
+
+    return out;
+}
+
+@group(0) @binding(0) var texture: texture_2d_array<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color * textureSample(texture, texture_sampler, in.texture_coord, i32(in.layer));
+}
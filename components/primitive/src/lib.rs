@@ -1,22 +1,26 @@
-use std::{borrow::Cow, sync::Arc};
+pub mod icon;
+
+use std::{borrow::Cow, num::NonZeroU32, sync::Arc};
 
 use bytemuck::{Pod, Zeroable};
 use storyboard_core::{
+    camera::Camera2D,
     color::ShapeColor,
-    euclid::{Point2D, Point3D, Rect, Transform3D},
-    math::RectExt,
+    euclid::{Angle, Point2D, Point3D, Rect, Size2D, Transform3D, UnknownUnit},
+    math::{empty_bounds, rotation_transform, FiniteExt, RectExt},
     palette::LinSrgba,
     store::{Store, StoreResources},
-    unit::{LogicalPixelUnit, RenderUnit, TextureUnit},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit, TextureUnit},
 };
 
 use storyboard_render::{
-    buffer::stream::StreamRange,
+    buffer::stream::{BufferStream, StreamRange},
     cache::shader::ShaderCache,
     component::{Component, Drawable},
     renderer::pass::StoryboardRenderPass,
     renderer::{
         context::{DrawContext, RenderContext},
+        screen::ScreenResources,
         ComponentQueue,
     },
     shared::RenderScopeContext,
@@ -31,10 +35,13 @@ use storyboard_render::{
 };
 use storyboard_texture::render::data::EmptyTextureResources;
 use storyboard_texture::render::{data::TextureData, RenderTexture2D};
+use storyboard_texture::{ComponentTexture, TextureWrap};
 
 #[derive(Debug)]
 pub struct PrimitiveResources {
     pub opaque_pipeline: RenderPipeline,
+    /// Opaque pipeline discarding fragments below alpha cutoff
+    pub cutout_pipeline: RenderPipeline,
     pub transparent_pipeline: RenderPipeline,
     pub quad_index_buffer: Buffer,
 }
@@ -49,31 +56,53 @@ impl StoreResources<RenderScopeContext<'_>> for PrimitiveResources {
                 init_primitive_shader(ctx.backend.device())
             });
 
-        let pipeline_layout =
-            init_primitive_pipeline_layout(ctx.backend.device(), textures.bind_group_layout());
+        let pipeline_layout = init_primitive_pipeline_layout(
+            ctx.backend.device(),
+            textures.bind_group_layout(),
+            &ctx.backend.get::<ScreenResources>().bind_group_layout,
+        );
+
+        let opaque_targets = [Some(ColorTargetState {
+            format: ctx.pipeline.texture_format,
+            blend: None,
+            write_mask: ColorWrites::COLOR,
+        })];
 
         let opaque_pipeline = init_primitive_pipeline(
             ctx.backend.device(),
             &pipeline_layout,
             &shader,
-            &[Some(ColorTargetState {
-                format: ctx.pipeline.texture_format,
-                blend: None,
-                write_mask: ColorWrites::COLOR,
-            })],
+            "fs_main",
+            &opaque_targets,
+            ctx.pipeline.depth_stencil.clone(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
+        );
+
+        let cutout_pipeline = init_primitive_pipeline(
+            ctx.backend.device(),
+            &pipeline_layout,
+            &shader,
+            "fs_cutout",
+            &opaque_targets,
             ctx.pipeline.depth_stencil.clone(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
         );
 
         let transparent_pipeline = init_primitive_pipeline(
             ctx.backend.device(),
             &pipeline_layout,
             &shader,
+            "fs_main",
             &[Some(ColorTargetState {
                 format: ctx.pipeline.texture_format,
                 blend: Some(BlendState::ALPHA_BLENDING),
                 write_mask: ColorWrites::ALL,
             })],
             ctx.pipeline.depth_stencil_read_only(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
         );
 
         let quad_index_buffer = ctx
@@ -87,6 +116,7 @@ impl StoreResources<RenderScopeContext<'_>> for PrimitiveResources {
 
         Self {
             opaque_pipeline,
+            cutout_pipeline,
             transparent_pipeline,
             quad_index_buffer,
         }
@@ -97,9 +127,28 @@ impl StoreResources<RenderScopeContext<'_>> for PrimitiveResources {
 pub struct Triangle {
     pub bounds: Rect<f32, LogicalPixelUnit>,
     pub color: ShapeColor<3>,
-    pub texture: Option<Arc<RenderTexture2D>>,
-    pub texture_coord: [Point2D<f32, TextureUnit>; 3],
+    pub texture: Option<ComponentTexture>,
+    /// Raw texture coordinates relative to texture view, used instead of texture layout if set.
+    /// Texture uv transform is still applied.
+    pub texture_coord: Option<[Point2D<f32, TextureUnit>; 3]>,
     pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+    /// Rotation around `rotation_anchor`, applied before `transform`
+    pub rotation: Angle<f32>,
+    /// Rotation anchor relative to bounds. `(0.5, 0.5)` is center of bounds.
+    pub rotation_anchor: Point2D<f32, UnknownUnit>,
+    /// Draw in opaque pass even if texture or color may be transparent, for better depth culling.
+    /// Use only if texture is known to be opaque. Transparent pixels are written without blending.
+    pub force_opaque: bool,
+    /// Discard fragments with alpha below cutoff and draw rest in opaque pass, writing depth along silhouette.
+    /// Used for cutout sprites like foliage. Remaining fragments are drawn without blending.
+    pub alpha_cutoff: Option<f32>,
+}
+
+impl Triangle {
+    /// Rotation composed with transform
+    pub fn draw_transform(&self) -> Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit> {
+        rotation_transform(&self.bounds, self.rotation, self.rotation_anchor).then(&self.transform)
+    }
 }
 
 impl Drawable for Triangle {
@@ -111,7 +160,10 @@ impl Drawable for Triangle {
         depth: f32,
     ) {
         if let Some(component) = PrimitiveComponent::from_triangle(self, ctx, depth) {
-            if self.texture.is_none() && self.color.opaque() {
+            if self.force_opaque
+                || self.alpha_cutoff.is_some()
+                || (self.texture.is_none() && self.color.opaque())
+            {
                 component_queue.push_opaque(component);
             } else {
                 component_queue.push_transparent(component);
@@ -124,9 +176,30 @@ impl Drawable for Triangle {
 pub struct Rectangle {
     pub bounds: Rect<f32, LogicalPixelUnit>,
     pub color: ShapeColor<4>,
-    pub texture: Option<Arc<RenderTexture2D>>,
-    pub texture_coord: [Point2D<f32, TextureUnit>; 4],
+    pub texture: Option<ComponentTexture>,
+    /// Raw texture coordinates relative to texture view, used instead of texture layout if set.
+    /// Texture uv transform is still applied.
+    pub texture_coord: Option<[Point2D<f32, TextureUnit>; 4]>,
     pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+    /// Rotation around `rotation_anchor`, applied before `transform`
+    pub rotation: Angle<f32>,
+    /// Rotation anchor relative to bounds. `(0.5, 0.5)` is center of bounds.
+    pub rotation_anchor: Point2D<f32, UnknownUnit>,
+    /// Screen space rect rectangle is clipped to. Not clipped if None.
+    pub clip: Option<Rect<f32, LogicalPixelUnit>>,
+    /// Draw in opaque pass even if texture or color may be transparent, for better depth culling.
+    /// Use only if texture is known to be opaque. Transparent pixels are written without blending.
+    pub force_opaque: bool,
+    /// Discard fragments with alpha below cutoff and draw rest in opaque pass, writing depth along silhouette.
+    /// Used for cutout sprites like foliage. Remaining fragments are drawn without blending.
+    pub alpha_cutoff: Option<f32>,
+}
+
+impl Rectangle {
+    /// Rotation composed with transform
+    pub fn draw_transform(&self) -> Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit> {
+        rotation_transform(&self.bounds, self.rotation, self.rotation_anchor).then(&self.transform)
+    }
 }
 
 impl Drawable for Rectangle {
@@ -138,7 +211,10 @@ impl Drawable for Rectangle {
         depth: f32,
     ) {
         if let Some(component) = PrimitiveComponent::from_rectangle(self, ctx, depth) {
-            if self.texture.is_none() && self.color.opaque() {
+            if self.force_opaque
+                || self.alpha_cutoff.is_some()
+                || (self.texture.is_none() && self.color.opaque())
+            {
                 component_queue.push_opaque(component);
             } else {
                 component_queue.push_transparent(component);
@@ -147,99 +223,618 @@ impl Drawable for Rectangle {
     }
 }
 
+/// How [Background] texture is fitted to screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundFit {
+    /// Fill screen ignoring aspect ratio
+    Stretch,
+
+    /// Fill screen preserving aspect ratio, cropping overflowing part of texture
+    Cover,
+
+    /// Fit inside screen preserving aspect ratio, leaving letterbox uncovered
+    Contain,
+
+    /// Repeat texture in its pixel size from top left of screen
+    Tile,
+}
+
+impl BackgroundFit {
+    /// Compute destination rect on screen and texture coordinates rect relative to texture view
+    pub fn layout(
+        &self,
+        screen: Rect<f32, LogicalPixelUnit>,
+        texture_size: Size2D<f32, PhyiscalPixelUnit>,
+    ) -> (Rect<f32, LogicalPixelUnit>, Rect<f32, TextureUnit>) {
+        let full = Rect::new(Point2D::zero(), Size2D::new(1.0, 1.0));
+
+        match self {
+            BackgroundFit::Stretch => (screen, full),
+
+            BackgroundFit::Cover => {
+                let scale = (screen.size.width / texture_size.width)
+                    .max(screen.size.height / texture_size.height);
+
+                let size = Size2D::new(
+                    screen.size.width / (texture_size.width * scale),
+                    screen.size.height / (texture_size.height * scale),
+                );
+
+                (
+                    screen,
+                    Rect::new(
+                        Point2D::new((1.0 - size.width) / 2.0, (1.0 - size.height) / 2.0),
+                        size,
+                    ),
+                )
+            }
+
+            BackgroundFit::Contain => {
+                let scale = (screen.size.width / texture_size.width)
+                    .min(screen.size.height / texture_size.height);
+
+                let size = Size2D::new(texture_size.width * scale, texture_size.height * scale);
+
+                (
+                    Rect::new(
+                        screen.origin
+                            + Size2D::new(
+                                (screen.size.width - size.width) / 2.0,
+                                (screen.size.height - size.height) / 2.0,
+                            )
+                            .to_vector(),
+                        size,
+                    ),
+                    full,
+                )
+            }
+
+            BackgroundFit::Tile => (
+                screen,
+                Rect::new(
+                    Point2D::zero(),
+                    Size2D::new(
+                        screen.size.width / texture_size.width,
+                        screen.size.height / texture_size.height,
+                    ),
+                ),
+            ),
+        }
+    }
+}
+
+/// Opaque textured quad covering whole screen.
+/// Drawn behind every other drawables if renderer uses depth texture, otherwise draw it first.
+///
+/// Layout and wrapping mode of texture are ignored. Animate uv transform to scroll background.
+#[derive(Debug, Clone)]
+pub struct Background {
+    pub texture: ComponentTexture,
+    pub fit: BackgroundFit,
+}
+
+impl Background {
+    /// Farthest depth still passing depth test against cleared depth texture
+    const DEPTH: f32 = 1.0 - f32::EPSILON / 2.0;
+}
+
+impl Drawable for Background {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        _: &mut CommandEncoder,
+        _: f32,
+    ) {
+        let (bounds, texture_rect) = self.fit.layout(
+            ctx.screen.get_logical_rect(),
+            self.texture.inner.view().size().cast(),
+        );
+
+        let wrap = match self.fit {
+            BackgroundFit::Tile => TextureWrap::Repeat,
+            _ => TextureWrap::Clamp,
+        };
+
+        let rect = Rectangle {
+            bounds,
+            color: ShapeColor::WHITE,
+            texture: Some(ComponentTexture {
+                wrapping_mode: (wrap, wrap),
+                ..self.texture.clone()
+            }),
+            texture_coord: Some(texture_rect.into_coords()),
+            transform: Transform3D::identity(),
+            rotation: Angle::zero(),
+            rotation_anchor: Point2D::zero(),
+            clip: None,
+            force_opaque: true,
+            alpha_cutoff: None,
+        };
+
+        if let Some(component) = PrimitiveComponent::from_rectangle(&rect, ctx, Self::DEPTH) {
+            component_queue.push_opaque(component);
+        }
+    }
+}
+
+/// Triangle list mesh of arbitrary size, like tessellated path.
+/// Uses 32 bit indices if points cannot be addressed with 16 bit indices.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub points: Arc<Vec<Point2D<f32, LogicalPixelUnit>>>,
+    pub indices: Arc<Vec<u32>>,
+    pub color: ShapeColor<1>,
+    pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+}
+
+impl Polygon {
+    /// Create triangle fan indices for convex outline with given number of points
+    pub fn fan_indices(points: usize) -> Vec<u32> {
+        let mut indices = Vec::with_capacity(points.saturating_sub(2) * 3);
+
+        for i in 1..points.saturating_sub(1) as u32 {
+            indices.extend_from_slice(&[0, i, i + 1]);
+        }
+
+        indices
+    }
+}
+
+impl Drawable for Polygon {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        _: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        if let Some(component) = PrimitiveComponent::from_polygon(self, ctx, depth) {
+            if self.color.opaque() {
+                component_queue.push_opaque(component);
+            } else {
+                component_queue.push_transparent(component);
+            }
+        }
+    }
+}
+
+/// Grid lines covering whole screen, for canvas editors and debugging layouts.
+/// Lines are placed in world space of `camera` if set, otherwise in screen space.
+#[derive(Debug, Clone)]
+pub struct DebugGrid {
+    /// Size of one cell in world logical pixels
+    pub cell_size: f32,
+    /// World point lines pass through
+    pub origin: Point2D<f32, LogicalPixelUnit>,
+    pub color: ShapeColor<1>,
+    /// Line width in screen logical pixels, kept regardless of zoom
+    pub line_width: f32,
+
+    /// Draw every nth cell line with `major_color`
+    pub major_every: Option<NonZeroU32>,
+    pub major_color: ShapeColor<1>,
+
+    /// Minimum screen space distance between lines.
+    /// Lines are skipped in power of two steps while zoomed out, so they don't become a solid mass.
+    pub min_spacing: f32,
+
+    pub camera: Option<Camera2D>,
+}
+
+impl DebugGrid {
+    /// Upper bound of lines drawn in one direction
+    pub const MAX_LINES: u32 = 4096;
+
+    pub fn new(cell_size: f32, color: ShapeColor<1>) -> Self {
+        Self {
+            cell_size,
+            origin: Point2D::zero(),
+            color: color.clone(),
+            line_width: 1.0,
+
+            major_every: None,
+            major_color: color,
+
+            min_spacing: 8.0,
+
+            camera: None,
+        }
+    }
+
+    /// Number of cells between drawn lines at given zoom.
+    /// Returns None if grid cannot be drawn, like zero cell size or zoom.
+    pub fn cells_per_line(&self, zoom: f32) -> Option<u32> {
+        let spacing = self.cell_size * zoom.abs();
+        if !spacing.is_finite() || spacing <= 0.0 {
+            return None;
+        }
+
+        let mut cells = 1_u32;
+        while spacing * (cells as f32) < self.min_spacing {
+            cells = cells.checked_mul(2)?;
+        }
+
+        Some(cells)
+    }
+
+    /// Create minor and major line meshes in world space covering given screen rect
+    fn line_meshes(&self, screen: Rect<f32, LogicalPixelUnit>) -> Option<[GridMesh; 2]> {
+        let zoom = self.camera.map_or(1.0, |camera| camera.zoom);
+        let cells = self.cells_per_line(zoom)?;
+
+        let visible = match &self.camera {
+            Some(camera) => {
+                let mut corners = [Point2D::zero(); 4];
+                for (corner, point) in corners.iter_mut().zip(screen.into_coords()) {
+                    *corner = camera.screen_to_world(point)?;
+                }
+
+                Rect::from_points(corners)
+            }
+
+            None => screen,
+        };
+
+        let step = self.cell_size * cells as f32;
+        let width = self.line_width / zoom.abs();
+
+        let mut meshes = [GridMesh::default(), GridMesh::default()];
+
+        for vertical in [true, false] {
+            let (min, max, origin) = if vertical {
+                (visible.min_x(), visible.max_x(), self.origin.x)
+            } else {
+                (visible.min_y(), visible.max_y(), self.origin.y)
+            };
+
+            let first = ((min - origin) / step).floor() as i64;
+            let last = ((max - origin) / step).ceil() as i64;
+            if last - first > Self::MAX_LINES as i64 {
+                return None;
+            }
+
+            for index in first..=last {
+                let position = origin + index as f32 * step - width / 2.0;
+
+                let major = self.major_every.map_or(false, |major_every| {
+                    (index * cells as i64).rem_euclid(major_every.get() as i64) == 0
+                });
+
+                meshes[major as usize].push_rect(if vertical {
+                    Rect::new(
+                        Point2D::new(position, visible.min_y()),
+                        Size2D::new(width, visible.height()),
+                    )
+                } else {
+                    Rect::new(
+                        Point2D::new(visible.min_x(), position),
+                        Size2D::new(visible.width(), width),
+                    )
+                });
+            }
+        }
+
+        Some(meshes)
+    }
+}
+
+impl Drawable for DebugGrid {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        encoder: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        let [minor, major] = match self.line_meshes(ctx.screen.get_logical_rect()) {
+            Some(meshes) => meshes,
+            None => return,
+        };
+
+        let transform = self
+            .camera
+            .map_or_else(Transform3D::identity, |camera| camera.matrix());
+
+        // Major lines are pushed last, so they cover crossing minor lines
+        for (mesh, color) in [(minor, &self.color), (major, &self.major_color)] {
+            if mesh.indices.is_empty() {
+                continue;
+            }
+
+            Polygon {
+                points: Arc::new(mesh.points),
+                indices: Arc::new(mesh.indices),
+                color: color.clone(),
+                transform,
+            }
+            .prepare(component_queue, ctx, encoder, depth);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct GridMesh {
+    points: Vec<Point2D<f32, LogicalPixelUnit>>,
+    indices: Vec<u32>,
+}
+
+impl GridMesh {
+    fn push_rect(&mut self, rect: Rect<f32, LogicalPixelUnit>) {
+        let base = self.points.len() as u32;
+
+        self.points.extend_from_slice(&rect.into_coords());
+        self.indices.extend(
+            Polygon::fan_indices(4)
+                .into_iter()
+                .map(|index| base + index),
+        );
+    }
+}
+
+/// Smallest index format which can address given number of vertices
+pub const fn index_format_for(vertices: usize) -> IndexFormat {
+    if vertices <= u16::MAX as usize {
+        IndexFormat::Uint16
+    } else {
+        IndexFormat::Uint32
+    }
+}
+
+/// Write indices to index stream using given format
+pub fn write_indices(
+    stream: &mut BufferStream,
+    indices: &[u32],
+    format: IndexFormat,
+) -> StreamRange {
+    // Index buffer offset must be aligned to index size
+    match format {
+        IndexFormat::Uint16 => {
+            let indices = indices
+                .iter()
+                .map(|&index| index as u16)
+                .collect::<Vec<_>>();
+
+            stream.write_aligned_slice(bytemuck::cast_slice(&indices), 4)
+        }
+
+        IndexFormat::Uint32 => stream.write_aligned_slice(bytemuck::cast_slice(indices), 4),
+    }
+}
+
 #[derive(Debug)]
 pub struct PrimitiveComponent {
     primitive_type: PrimitiveType,
     texture: Option<Arc<RenderTexture2D>>,
     vertices_slice: StreamRange,
+    bounds: Option<Rect<f32, LogicalPixelUnit>>,
+    clip: Option<Rect<f32, LogicalPixelUnit>>,
+    /// Draw using cutout pipeline in opaque pass
+    cutout: bool,
 }
 
 #[derive(Debug)]
 pub enum PrimitiveType {
     Triangle,
     Quad,
+    Mesh {
+        indices_slice: StreamRange,
+        index_format: IndexFormat,
+        indices: u32,
+    },
 }
 
 impl PrimitiveComponent {
     pub fn from_triangle(triangle: &Triangle, ctx: &mut DrawContext, depth: f32) -> Option<Self> {
+        if empty_bounds(&triangle.bounds) {
+            return None;
+        }
+
+        let transform = triangle.draw_transform();
+
         let coords = triangle.bounds.into_coords();
+        let points = [
+            (coords[0] + coords[3].to_vector()) / 2.0,
+            coords[1],
+            coords[2],
+        ];
+
+        let colors = triangle.color.vertex_colors(&triangle.bounds, &points);
+
+        let texture_coord = ComponentTexture::option_transform_coords(
+            triangle.texture.as_ref(),
+            triangle.texture_coord.unwrap_or_else(|| {
+                layout_texture_coords(triangle.texture.as_ref(), triangle.bounds, &points, ctx)
+            }),
+        );
+        let texture_rect = ComponentTexture::option_view_texture_rect(triangle.texture.as_ref());
+        let texture_wrap_mode = wrap_mode_of(triangle.texture.as_ref());
+        let alpha_cutoff = triangle.alpha_cutoff.unwrap_or(0.0);
 
         let vertices_slice = ctx.vertex_stream.write_slice(bytemuck::bytes_of(&[
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(triangle.transform.transform_point2d((coords[0] + coords[3].to_vector()) / 2.0)?)?
+                    .to_screen_point(transform.transform_point2d(points[0])?)
+                    .finite()?
                     .extend(depth),
-                color: triangle.color[0],
-                texture_coord: triangle.texture_coord[0],
+                color: colors[0],
+                texture_coord: texture_coord[0],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(triangle.transform.transform_point2d(coords[1])?)?
+                    .to_screen_point(transform.transform_point2d(points[1])?)
+                    .finite()?
                     .extend(depth),
-                color: triangle.color[1],
-                texture_coord: triangle.texture_coord[1],
+                color: colors[1],
+                texture_coord: texture_coord[1],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(triangle.transform.transform_point2d(coords[2])?)?
+                    .to_screen_point(transform.transform_point2d(points[2])?)
+                    .finite()?
                     .extend(depth),
-                color: triangle.color[2],
-                texture_coord: triangle.texture_coord[2],
+                color: colors[2],
+                texture_coord: texture_coord[2],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
         ]));
 
         Some(Self {
             primitive_type: PrimitiveType::Triangle,
-            texture: triangle.texture.clone(),
+            texture: triangle
+                .texture
+                .as_ref()
+                .map(|texture| texture.inner.clone()),
+            vertices_slice,
+            bounds: transform.outer_transformed_rect(&triangle.bounds),
+            clip: None,
+            cutout: triangle.alpha_cutoff.is_some(),
+        })
+    }
+
+    pub fn from_polygon(polygon: &Polygon, ctx: &mut DrawContext, depth: f32) -> Option<Self> {
+        let points = polygon.points.len();
+        if polygon.indices.is_empty()
+            || polygon.indices.len() % 3 != 0
+            || polygon
+                .indices
+                .iter()
+                .any(|&index| index as usize >= points)
+        {
+            return None;
+        }
+
+        let bounds = Rect::from_points(polygon.points.iter());
+
+        let mut vertices = Vec::with_capacity(points);
+        for point in polygon.points.iter() {
+            vertices.push(PrimitiveVertex {
+                position: ctx
+                    .to_screen_point(polygon.transform.transform_point2d(*point)?)
+                    .finite()?
+                    .extend(depth),
+                color: polygon.color.color_at(0, &bounds, *point),
+                texture_coord: Point2D::zero(),
+                texture_rect: Default::default(),
+                texture_wrap_mode: [TextureWrap::None as _; 2],
+                alpha_cutoff: 0.0,
+            });
+        }
+
+        let vertices_slice = ctx
+            .vertex_stream
+            .write_slice(bytemuck::cast_slice(&vertices));
+
+        let index_format = index_format_for(points);
+        let indices_slice = write_indices(ctx.index_stream, &polygon.indices, index_format);
+
+        Some(Self {
+            primitive_type: PrimitiveType::Mesh {
+                indices_slice,
+                index_format,
+                indices: polygon.indices.len() as u32,
+            },
+            texture: None,
             vertices_slice,
+            bounds: Some(Rect::from_points(
+                vertices.iter().map(|vertex| vertex.position.xy()),
+            )),
+            clip: None,
+            cutout: false,
         })
     }
 
     pub fn from_rectangle(rect: &Rectangle, ctx: &mut DrawContext, depth: f32) -> Option<Self> {
+        if empty_bounds(&rect.bounds) {
+            return None;
+        }
+
+        let transform = rect.draw_transform();
+
+        let mut bounds = transform.outer_transformed_rect(&rect.bounds);
+        if let Some(clip) = &rect.clip {
+            // Skip rectangle clipped out entirely
+            if let Some(rect_bounds) = bounds {
+                bounds = Some(rect_bounds.intersection(clip)?);
+            }
+        }
+
         let coords = rect.bounds.into_coords();
 
+        let colors = rect.color.vertex_colors(&rect.bounds, &coords);
+
+        let texture_coord = ComponentTexture::option_transform_coords(
+            rect.texture.as_ref(),
+            rect.texture_coord.unwrap_or_else(|| {
+                layout_texture_coords(rect.texture.as_ref(), rect.bounds, &coords, ctx)
+            }),
+        );
+        let texture_rect = ComponentTexture::option_view_texture_rect(rect.texture.as_ref());
+        let texture_wrap_mode = wrap_mode_of(rect.texture.as_ref());
+        let alpha_cutoff = rect.alpha_cutoff.unwrap_or(0.0);
+
         let vertices_slice = ctx.vertex_stream.write_slice(bytemuck::bytes_of(&[
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(rect.transform.transform_point2d(coords[0])?)?
+                    .to_screen_point(transform.transform_point2d(coords[0])?)
+                    .finite()?
                     .extend(depth),
-                color: rect.color[0],
-                texture_coord: rect.texture_coord[0],
+                color: colors[0],
+                texture_coord: texture_coord[0],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(rect.transform.transform_point2d(coords[1])?)?
+                    .to_screen_point(transform.transform_point2d(coords[1])?)
+                    .finite()?
                     .extend(depth),
-                color: rect.color[1],
-                texture_coord: rect.texture_coord[1],
+                color: colors[1],
+                texture_coord: texture_coord[1],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(rect.transform.transform_point2d(coords[2])?)?
+                    .to_screen_point(transform.transform_point2d(coords[2])?)
+                    .finite()?
                     .extend(depth),
-                color: rect.color[2],
-                texture_coord: rect.texture_coord[2],
+                color: colors[2],
+                texture_coord: texture_coord[2],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
             PrimitiveVertex {
                 position: ctx
-                    .screen_matrix
-                    .transform_point2d(rect.transform.transform_point2d(coords[3])?)?
+                    .to_screen_point(transform.transform_point2d(coords[3])?)
+                    .finite()?
                     .extend(depth),
-                color: rect.color[3],
-                texture_coord: rect.texture_coord[3],
+                color: colors[3],
+                texture_coord: texture_coord[3],
+                texture_rect,
+                texture_wrap_mode,
+                alpha_cutoff,
             },
         ]));
 
         Some(Self {
             primitive_type: PrimitiveType::Quad,
-            texture: rect.texture.clone(),
+            texture: rect.texture.as_ref().map(|texture| texture.inner.clone()),
             vertices_slice,
+            bounds,
+            clip: rect.clip,
+            cutout: rect.alpha_cutoff.is_some(),
         })
     }
 }
@@ -252,7 +847,15 @@ impl Component for PrimitiveComponent {
     ) {
         let resources = ctx.scope.get::<PrimitiveResources>();
 
-        pass.set_pipeline(&resources.opaque_pipeline);
+        if let Some(clip) = &self.clip {
+            pass.set_clip(&ctx.screen, clip);
+        }
+
+        pass.set_pipeline(if self.cutout {
+            &resources.cutout_pipeline
+        } else {
+            &resources.opaque_pipeline
+        });
 
         pass.set_bind_group(
             0,
@@ -270,6 +873,7 @@ impl Component for PrimitiveComponent {
                 .bind_group(),
             &[],
         );
+        pass.set_bind_group(1, ctx.screen_bind_group, &[]);
 
         pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.vertices_slice.clone()));
 
@@ -283,6 +887,20 @@ impl Component for PrimitiveComponent {
 
                 pass.draw_indexed(0..6, 0, 0..1);
             }
+
+            PrimitiveType::Mesh {
+                ref indices_slice,
+                index_format,
+                indices,
+            } => {
+                pass.set_index_buffer(ctx.index_stream.slice(indices_slice.clone()), index_format);
+
+                pass.draw_indexed(0..indices, 0, 0..1);
+            }
+        }
+
+        if self.clip.is_some() {
+            pass.reset_clip(&ctx.screen);
         }
     }
 
@@ -293,6 +911,10 @@ impl Component for PrimitiveComponent {
     ) {
         let resources = ctx.scope.get::<PrimitiveResources>();
 
+        if let Some(clip) = &self.clip {
+            pass.set_clip(&ctx.screen, clip);
+        }
+
         pass.set_pipeline(&resources.transparent_pipeline);
 
         pass.set_bind_group(
@@ -311,6 +933,7 @@ impl Component for PrimitiveComponent {
                 .bind_group(),
             &[],
         );
+        pass.set_bind_group(1, ctx.screen_bind_group, &[]);
 
         pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.vertices_slice.clone()));
 
@@ -324,16 +947,60 @@ impl Component for PrimitiveComponent {
 
                 pass.draw_indexed(0..6, 0, 0..1);
             }
+
+            PrimitiveType::Mesh {
+                ref indices_slice,
+                index_format,
+                indices,
+            } => {
+                pass.set_index_buffer(ctx.index_stream.slice(indices_slice.clone()), index_format);
+
+                pass.draw_indexed(0..indices, 0, 0..1);
+            }
+        }
+
+        if self.clip.is_some() {
+            pass.reset_clip(&ctx.screen);
         }
     }
+
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        self.bounds
+    }
 }
 
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
 pub struct PrimitiveVertex {
-    pub position: Point3D<f32, RenderUnit>,
+    pub position: Point3D<f32, LogicalPixelUnit>,
     pub color: LinSrgba<f32>,
     pub texture_coord: Point2D<f32, TextureUnit>,
+    pub texture_rect: Rect<f32, TextureUnit>,
+    pub texture_wrap_mode: [u32; 2],
+    /// Fragments with lower alpha are discarded by cutout pipeline
+    pub alpha_cutoff: f32,
+}
+
+/// Map points into texture coordinates using texture layout of given bounds
+fn layout_texture_coords<const N: usize>(
+    texture: Option<&ComponentTexture>,
+    bounds: Rect<f32, LogicalPixelUnit>,
+    points: &[Point2D<f32, LogicalPixelUnit>; N],
+    ctx: &DrawContext,
+) -> [Point2D<f32, TextureUnit>; N] {
+    let texture_bounds =
+        ComponentTexture::option_get_texture_bounds(texture, bounds, ctx.screen.get_logical_size());
+
+    points.map(|point| {
+        Point2D::new(
+            (point.x - texture_bounds.origin.x) / texture_bounds.size.width,
+            (point.y - texture_bounds.origin.y) / texture_bounds.size.height,
+        )
+    })
+}
+
+fn wrap_mode_of(texture: Option<&ComponentTexture>) -> [u32; 2] {
+    ComponentTexture::option_shader_wrap_mode(texture)
 }
 
 pub fn init_primitive_shader(device: &Device) -> ShaderModule {
@@ -346,10 +1013,11 @@ pub fn init_primitive_shader(device: &Device) -> ShaderModule {
 pub fn init_primitive_pipeline_layout(
     device: &Device,
     texture_bind_group_layout: &BindGroupLayout,
+    screen_bind_group_layout: &BindGroupLayout,
 ) -> PipelineLayout {
     device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("Primitive shader pipeline layout"),
-        bind_group_layouts: &[texture_bind_group_layout],
+        bind_group_layouts: &[texture_bind_group_layout, screen_bind_group_layout],
         push_constant_ranges: &[],
     })
 }
@@ -358,8 +1026,10 @@ pub fn init_primitive_pipeline(
     device: &Device,
     pipeline_layout: &PipelineLayout,
     shader: &ShaderModule,
+    fragment_entry_point: &str,
     fragment_targets: &[Option<ColorTargetState>],
     depth_stencil: Option<DepthStencilState>,
+    primitive: PrimitiveState,
 ) -> RenderPipeline {
     device.create_render_pipeline(&RenderPipelineDescriptor {
         label: Some("Primitive pipeline"),
@@ -370,20 +1040,144 @@ pub fn init_primitive_pipeline(
             buffers: &[VertexBufferLayout {
                 array_stride: std::mem::size_of::<PrimitiveVertex>() as u64,
                 step_mode: VertexStepMode::Vertex,
-                attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x4, 2 => Float32x2],
+                attributes: &vertex_attr_array![
+                    0 => Float32x3,
+                    1 => Float32x4,
+                    2 => Float32x2,
+                    3 => Float32x4,
+                    4 => Uint32x2,
+                    5 => Float32
+                ],
             }],
         },
-        primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
-            ..PrimitiveState::default()
-        },
+        primitive,
         depth_stencil,
         multisample: MultisampleState::default(),
         fragment: Some(FragmentState {
             module: shader,
-            entry_point: "fs_main",
+            entry_point: fragment_entry_point,
             targets: fragment_targets,
         }),
         multiview: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::{euclid::Size2D, unit::RenderUnit};
+    use storyboard_render::{
+        shared::RenderPipelineData,
+        wgpu::{BufferUsages, Face, FrontFace, TextureFormat},
+    };
+
+    use super::*;
+
+    /// Winding of triangle in render space, where y axis points up
+    fn render_winding(points: [Point2D<f32, LogicalPixelUnit>; 3]) -> FrontFace {
+        let [a, b, c] = points.map(|point| Point2D::<f32, RenderUnit>::new(point.x, -point.y));
+
+        if (b - a).cross(c - a) > 0.0 {
+            FrontFace::Ccw
+        } else {
+            FrontFace::Cw
+        }
+    }
+
+    #[test]
+    fn mirrored_cull_test() {
+        let coords = Rect::new(Point2D::new(10.0, 10.0), Size2D::new(50.0, 50.0)).into_coords();
+        let points = [
+            (coords[0] + coords[3].to_vector()) / 2.0,
+            coords[1],
+            coords[2],
+        ];
+
+        let mirrored =
+            Transform3D::<f32, LogicalPixelUnit, LogicalPixelUnit>::scale(-1.0, 1.0, 1.0);
+        let mirrored_points = points.map(|point| mirrored.transform_point2d(point).unwrap());
+
+        let pipeline = RenderPipelineData::new(TextureFormat::Bgra8Unorm, None, None);
+        assert_eq!(
+            pipeline
+                .primitive_state(PrimitiveTopology::TriangleList)
+                .cull_mode,
+            None
+        );
+
+        let state = pipeline
+            .with_cull_mode(Some(Face::Back), FrontFace::Ccw)
+            .primitive_state(PrimitiveTopology::TriangleList);
+        assert_eq!(state.cull_mode, Some(Face::Back));
+
+        // Unmirrored triangle is front facing and kept, mirrored one is back facing and culled
+        assert_eq!(render_winding(points), state.front_face);
+        assert_ne!(render_winding(mirrored_points), state.front_face);
+    }
+
+    #[test]
+    fn background_fit_test() {
+        let screen = Rect::new(Point2D::zero(), Size2D::new(200.0, 100.0));
+        let texture_size = Size2D::new(100.0, 100.0);
+
+        let (bounds, coords) = BackgroundFit::Cover.layout(screen, texture_size);
+        assert_eq!(bounds, screen);
+        assert_eq!(
+            coords,
+            Rect::new(Point2D::new(0.0, 0.25), Size2D::new(1.0, 0.5))
+        );
+
+        let (bounds, coords) = BackgroundFit::Contain.layout(screen, texture_size);
+        assert_eq!(
+            bounds,
+            Rect::new(Point2D::new(50.0, 0.0), Size2D::new(100.0, 100.0))
+        );
+        assert_eq!(coords, Rect::new(Point2D::zero(), Size2D::new(1.0, 1.0)));
+
+        let (_, coords) = BackgroundFit::Tile.layout(screen, texture_size);
+        assert_eq!(coords.size, Size2D::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn large_polygon_index_test() {
+        let points = 70000;
+        let indices = Polygon::fan_indices(points);
+
+        assert_eq!(indices.len(), (points - 2) * 3);
+        assert_eq!(index_format_for(u16::MAX as usize), IndexFormat::Uint16);
+        assert_eq!(index_format_for(points), IndexFormat::Uint32);
+
+        let mut stream = BufferStream::new(None, BufferUsages::INDEX);
+        stream.write_slice(&[0]);
+
+        let range = write_indices(&mut stream, &indices, index_format_for(points));
+        assert_eq!(range.start % 4, 0);
+        assert_eq!(range.end - range.start, indices.len() as u64 * 4);
+    }
+    #[test]
+    fn debug_grid_density_test() {
+        let mut grid = DebugGrid::new(10.0, ShapeColor::WHITE);
+        grid.major_every = NonZeroU32::new(4);
+
+        assert_eq!(grid.cells_per_line(1.0), Some(1));
+        // 10 * 0.1 = 1px spacing, skipped until 8px
+        assert_eq!(grid.cells_per_line(0.1), Some(8));
+        assert_eq!(grid.cells_per_line(0.0), None);
+
+        let screen = Rect::new(Point2D::zero(), Size2D::new(100.0, 50.0));
+        let [minor, major] = grid.line_meshes(screen).unwrap();
+
+        // 11 vertical and 6 horizontal lines, every 4th one major
+        assert_eq!((minor.points.len() + major.points.len()) / 4, 17);
+        assert_eq!(major.points.len() / 4, 3 + 2);
+
+        grid.camera = Some(Camera2D {
+            zoom: 0.1,
+            ..Camera2D::default()
+        });
+        let [minor, major] = grid.line_meshes(screen).unwrap();
+
+        // Every drawn line is 8 cells apart, so all of them are major lines
+        assert!(minor.points.is_empty());
+        assert!(!major.points.is_empty());
+    }
+}
@@ -2,19 +2,34 @@ struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) texture_coord: vec2<f32>,
+    @location(2) @interpolate(flat) texture_rect: vec4<f32>,
+    @location(3) @interpolate(flat) texture_wrap_mode: vec2<u32>,
+    @location(4) @interpolate(flat) alpha_cutoff: f32,
 };
 
+struct Screen {
+    matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> screen: Screen;
+
 @vertex
 fn vs_main(
     @location(0) position: vec3<f32>,
     @location(1) color: vec4<f32>,
     @location(2) texture_coord: vec2<f32>,
+    @location(3) texture_rect: vec4<f32>,
+    @location(4) texture_wrap_mode: vec2<u32>,
+    @location(5) alpha_cutoff: f32,
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    out.position = vec4<f32>(position, 1.0);
+    out.position = vec4<f32>((screen.matrix * vec4<f32>(position.xy, 0.0, 1.0)).xy, position.z, 1.0);
     out.color = color;
     out.texture_coord = texture_coord;
+    out.texture_rect = texture_rect;
+    out.texture_wrap_mode = texture_wrap_mode;
+    out.alpha_cutoff = alpha_cutoff;
 
     return out;
 }
@@ -22,8 +37,53 @@ fn vs_main(
 @group(0) @binding(0) var texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 
+fn wrap_texture_coord(coord: f32, wrap_mode: u32) -> f32 {
+    if (wrap_mode == 1u) {
+        return clamp(coord, 0.0, 1.0);
+    } else if (wrap_mode == 2u) {
+        return fract(coord);
+    } else if (wrap_mode == 3u) {
+        return 1.0 - abs(fract(coord * 0.5) * 2.0 - 1.0);
+    } else {
+        return coord;
+    }
+}
+
+fn mapped_texture_color(tex: texture_2d<f32>, tex_sampler: sampler, wrap_mode: vec2<u32>, tex_sub_rect: vec4<f32>, tex_coord: vec2<f32>) -> vec4<f32> {
+    // Whole texture view is wrapped by sampler addressing, coordinate is passed untouched
+    let sampler_addressed = all(wrap_mode == vec2<u32>(4u, 4u));
+
+    let coord = tex_sub_rect.xy + vec2<f32>(wrap_texture_coord(tex_coord.x, wrap_mode.x), wrap_texture_coord(tex_coord.y, wrap_mode.y)) * tex_sub_rect.zw;
+
+    // Keep sampling point half texel inside of sub rect so filtering doesn't bleed neighboring pixels
+    let half_texel = 0.5 / vec2<f32>(textureDimensions(tex));
+    let sub_rect_coord = clamp(coord, tex_sub_rect.xy + half_texel, tex_sub_rect.xy + tex_sub_rect.zw - half_texel);
+    let tex_color = textureSample(tex, tex_sampler, select(sub_rect_coord, tex_sub_rect.xy + tex_coord * tex_sub_rect.zw, sampler_addressed));
+
+    return select(
+        vec4<f32>(1.0, 1.0, 1.0, 1.0),
+        tex_color,
+        sampler_addressed || (coord.x >= tex_sub_rect.x && coord.y >= tex_sub_rect.y && coord.x <= tex_sub_rect.x + tex_sub_rect.z && coord.y <= tex_sub_rect.y + tex_sub_rect.w)
+    );
+}
+
+fn fragment_color(in: VertexOutput) -> vec4<f32> {
+    return in.color * mapped_texture_color(texture, texture_sampler, in.texture_wrap_mode, in.texture_rect, in.texture_coord);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color = in.color * textureSample(texture, texture_sampler, in.texture_coord);
+    return fragment_color(in);
+}
+
+@fragment
+fn fs_cutout(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = fragment_color(in);
+
+    // Discarded fragments don't write depth, so only silhouette occludes drawables behind
+    if (color.a < in.alpha_cutoff) {
+        discard;
+    }
+
     return color;
 }
@@ -0,0 +1,104 @@
+use std::{f32::consts::TAU, sync::Arc};
+
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Point2D, Transform3D},
+    unit::LogicalPixelUnit,
+};
+use storyboard_primitive::Polygon;
+use storyboard_render::renderer::StoryboardRenderer;
+use storyboard_test_util::{pixel, read_pixels, TestBackend, GREEN, RED, SIZE};
+
+fn polygon(
+    points: Vec<Point2D<f32, LogicalPixelUnit>>,
+    indices: Vec<u32>,
+    color: ShapeColor<1>,
+) -> Polygon {
+    Polygon {
+        points: Arc::new(points),
+        indices: Arc::new(indices),
+        color,
+        transform: Transform3D::identity(),
+    }
+}
+
+#[test]
+fn odd_u16_indices_test() {
+    let test = TestBackend::init(None);
+
+    // Three u16 indices leave index stream unaligned to copy alignment
+    let triangle = polygon(
+        vec![
+            Point2D::zero(),
+            Point2D::new(SIZE as f32, 0.0),
+            Point2D::new(0.0, SIZE as f32),
+        ],
+        vec![0, 1, 2],
+        ShapeColor::RED,
+    );
+
+    let data = read_pixels(
+        &test.backend,
+        test.scope(),
+        &mut StoryboardRenderer::new(),
+        &[&triangle],
+    );
+
+    assert_eq!(pixel(&data, 4, 4), RED);
+    assert_eq!(pixel(&data, SIZE - 4, SIZE - 4), [0; 4]);
+}
+
+#[test]
+fn large_polygon_render_test() {
+    const POINTS: usize = 70_000;
+
+    let test = TestBackend::init(None);
+
+    // Circle with more vertices than 16 bit indices can address
+    let center = Point2D::new(SIZE as f32 / 2.0, SIZE as f32 / 2.0);
+    let radius = SIZE as f32 / 4.0;
+    let circle = polygon(
+        (0..POINTS)
+            .map(|i| {
+                let angle = i as f32 / POINTS as f32 * TAU;
+                center + Point2D::new(angle.cos(), angle.sin()).to_vector() * radius
+            })
+            .collect(),
+        Polygon::fan_indices(POINTS),
+        ShapeColor::GREEN,
+    );
+
+    // Drawn before, so 32 bit indices are written after odd number of 16 bit indices
+    let triangle = polygon(
+        vec![
+            Point2D::zero(),
+            Point2D::new(8.0, 0.0),
+            Point2D::new(0.0, 8.0),
+        ],
+        vec![0, 1, 2],
+        ShapeColor::RED,
+    );
+
+    let data = read_pixels(
+        &test.backend,
+        test.scope(),
+        &mut StoryboardRenderer::new(),
+        &[&triangle, &circle],
+    );
+
+    assert_eq!(pixel(&data, 1, 1), RED);
+
+    // Points near end of outline are drawn too
+    for (x, y) in [
+        (SIZE / 2, SIZE / 2),
+        (SIZE / 2 + 14, SIZE / 2),
+        (SIZE / 2 + 14, SIZE / 2 - 2),
+        (SIZE / 2, SIZE / 2 - 14),
+    ] {
+        assert_eq!(pixel(&data, x, y), GREEN, "pixel at {}, {}", x, y);
+    }
+
+    for (x, y) in [(SIZE - 4, SIZE - 4), (SIZE / 2, 4), (4, SIZE / 2)] {
+        assert_eq!(pixel(&data, x, y), [0; 4], "pixel at {}, {}", x, y);
+    }
+}
@@ -1,4 +1,11 @@
-use std::{collections::HashMap, fmt::Debug, iter::Peekable};
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+    iter::{self, Peekable},
+};
 
 use rect_packer::DensePacker;
 use ringbuffer::{ConstGenericRingBuffer, RingBufferExt, RingBufferWrite};
@@ -8,7 +15,14 @@ use storyboard_core::{
 };
 use storyboard_render::{
     texture::{SizedTexture2D, SizedTextureView2D, TextureView2D},
-    wgpu::{Device, Queue, TextureFormat, TextureUsages},
+    wgpu::{
+        CommandEncoderDescriptor, Device, Extent3d, ImageCopyTexture, Origin3d, Queue,
+        TextureAspect, TextureFormat, TextureUsages,
+    },
+};
+use storyboard_texture::render::{
+    data::TextureData,
+    memory::{mipmapped_texture_memory_size, TextureAllocation},
 };
 
 use crate::{
@@ -16,8 +30,13 @@ use crate::{
     rasterizer::{GlyphData, GlyphRasterizer},
 };
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct GlyphCache {
+    page_size: u32,
+    format: TextureFormat,
+    lcd: bool,
+    mipmaps: bool,
+
     pages: ConstGenericRingBuffer<GlyphAtlasMap, { Self::PAGES }>,
     colored_pages: ConstGenericRingBuffer<GlyphAtlasMap, { Self::PAGES }>,
 }
@@ -26,29 +45,119 @@ impl GlyphCache {
     pub const PAGES: usize = 8;
     pub const PAGE_SIZE_LIMIT: u32 = 256;
 
+    pub const DEFAULT_PAGE_SIZE: u32 = 1024;
+    pub const DEFAULT_FORMAT: TextureFormat = TextureFormat::R8Unorm;
+
+    /// Mip levels of glyph pages if mipmaps are enabled, down to eighth of rasterized size
+    pub const MIP_LEVELS: u32 = 4;
+
     pub fn new() -> Self {
+        Self::new_unchecked(Self::DEFAULT_PAGE_SIZE, Self::DEFAULT_FORMAT)
+    }
+
+    /// Create cache with given atlas page size and glyph page format.
+    ///
+    /// Larger page fits more glyphs in single draw call. [TextureFormat::R8Unorm] uses quarter of memory compared to 4 channel formats.
+    /// Glyph coverage is written to every channels of multi channel formats, so glyph shader reads it from same channel.
+    /// Coverage is encoded for sRGB formats, so sampled coverage is same as other formats.
+    /// Colored glyphs like emoji are kept in separate [TextureFormat::Rgba8Unorm] pages, created only when text has them.
+    ///
+    /// Only 8 bit unorm formats are supported, see [coverage_channels].
+    pub fn new_with(page_size: u32, format: TextureFormat) -> Result<Self, GlyphFormatError> {
+        if coverage_channels(format).is_none() {
+            return Err(GlyphFormatError(format));
+        }
+
+        Ok(Self::new_unchecked(page_size, format))
+    }
+
+    fn new_unchecked(page_size: u32, format: TextureFormat) -> Self {
         Self {
+            page_size: page_size.max(1),
+            format,
+            lcd: false,
+            mipmaps: false,
+
             pages: ConstGenericRingBuffer::new(),
             colored_pages: ConstGenericRingBuffer::new(),
         }
     }
 
+    /// Create cache rasterizing glyphs with subpixel coverage for LCD displays with horizontal RGB stripes.
+    ///
+    /// Text using the cache is drawn in three passes writing each color channel, so it needs no dual source blending.
+    /// Limitations:
+    /// * Alpha of render target is not written, so text must be drawn over opaque background.
+    /// * Subpixel layout is horizontal RGB. Rotated or skewed text falls back to grayscale coverage.
+    /// * Draws three times per glyph batch and pages use four times of memory of grayscale [GlyphCache::new].
+    pub fn new_lcd() -> Self {
+        Self::new_lcd_with(Self::DEFAULT_PAGE_SIZE)
+    }
+
+    /// Create subpixel glyph cache with given atlas page size
+    pub fn new_lcd_with(page_size: u32) -> Self {
+        Self {
+            lcd: true,
+            ..Self::new_unchecked(page_size, TextureFormat::Rgba8Unorm)
+        }
+    }
+
+    /// Generate mipmaps for glyph pages, so text minified by zooming out stays legible instead of aliasing.
+    /// Sample glyphs using linear filtered sampler to blend between levels.
+    ///
+    /// Mip levels are downsampled on cpu around each uploaded glyph, keeping copy of page in memory.
+    /// Glyphs are packed with padding so lower levels don't bleed neighboring glyphs.
+    /// Fixed size text doesn't need it.
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub const fn mipmaps(&self) -> bool {
+        self.mipmaps
+    }
+
+    const fn page_mip_levels(&self) -> u32 {
+        if self.mipmaps {
+            Self::MIP_LEVELS
+        } else {
+            1
+        }
+    }
+
+    /// Glyphs are rasterized with subpixel coverage
+    pub const fn lcd(&self) -> bool {
+        self.lcd
+    }
+
+    pub const fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    pub const fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Rasterize glyphs into pages and batch them while they are in same page.
+    /// Memory of created pages is tracked in [TextureData::memory_usage].
     pub fn batch(
         &mut self,
         device: &Device,
         queue: &Queue,
+        textures: &TextureData,
         font: &Font,
         indices: &mut Peekable<impl Iterator<Item = u16>>,
         size_px: u32,
     ) -> Option<GlyphBatch> {
-        self.batch_glyph(device, queue, font, indices, size_px)
-            .or_else(|| self.batch_image(device, queue, font, indices, size_px))
+        self.batch_glyph(device, queue, textures, font, indices, size_px)
+            .or_else(|| self.batch_image(device, queue, textures, font, indices, size_px))
     }
 
     pub fn batch_image(
         &mut self,
         device: &Device,
         queue: &Queue,
+        textures: &TextureData,
         font: &Font,
         indices: &mut Peekable<impl Iterator<Item = u16>>,
         size_px: u32,
@@ -70,7 +179,14 @@ impl GlyphCache {
                     let mut rasterizer = GlyphRasterizer::new(font);
 
                     if let Some(glyph) = rasterizer.rasterize_image(*index, size_px as f32) {
-                        if let Some(rect) = page.pack(queue, key, &glyph) {
+                        // Repacking moves glyphs, so only do it before any rect of this page is used
+                        let packed = if rects.is_empty() {
+                            page.pack_or_repack(device, queue, key, &glyph)
+                        } else {
+                            page.pack(queue, key, &glyph)
+                        };
+
+                        if let Some(rect) = packed {
                             rects.push(rect);
                         } else {
                             break;
@@ -79,6 +195,7 @@ impl GlyphCache {
                         return Some(GlyphBatch {
                             view: page.create_view().into(),
                             rects,
+                            colored: true,
                         });
                     } else {
                         return None;
@@ -92,16 +209,22 @@ impl GlyphCache {
                 return Some(GlyphBatch {
                     view: page.create_view().into(),
                     rects,
+                    colored: true,
                 });
             }
         }
 
         if indices.peek().is_some() {
-            let atlas =
-                GlyphAtlasMap::init(device, Size2D::new(1024, 1024), TextureFormat::Rgba8Unorm);
+            let atlas = GlyphAtlasMap::init_with_mips(
+                device,
+                textures,
+                Size2D::new(self.page_size, self.page_size),
+                TextureFormat::Rgba8Unorm,
+                self.page_mip_levels(),
+            );
             self.colored_pages.push(atlas);
 
-            return self.batch_image(device, queue, font, indices, size_px);
+            return self.batch_image(device, queue, textures, font, indices, size_px);
         }
 
         None
@@ -111,6 +234,7 @@ impl GlyphCache {
         &mut self,
         device: &Device,
         queue: &Queue,
+        textures: &TextureData,
         font: &Font,
         glyph_indices: &mut Peekable<impl Iterator<Item = u16>>,
         size_px: u32,
@@ -131,8 +255,21 @@ impl GlyphCache {
                 } else {
                     let rasterizer = GlyphRasterizer::new(font);
 
-                    if let Some(glyph) = rasterizer.rasterize_glyph(*index, size_px as f32) {
-                        if let Some(rect) = page.pack(queue, key, &glyph) {
+                    let glyph = if self.lcd {
+                        rasterizer.rasterize_lcd_glyph(*index, size_px as f32)
+                    } else {
+                        rasterizer.rasterize_glyph(*index, size_px as f32)
+                    };
+
+                    if let Some(glyph) = glyph {
+                        // Repacking moves glyphs, so only do it before any rect of this page is used
+                        let packed = if rects.is_empty() {
+                            page.pack_or_repack(device, queue, key, &glyph)
+                        } else {
+                            page.pack(queue, key, &glyph)
+                        };
+
+                        if let Some(rect) = packed {
                             rects.push(rect);
                         } else {
                             break;
@@ -141,6 +278,7 @@ impl GlyphCache {
                         return Some(GlyphBatch {
                             view: page.create_view().into(),
                             rects,
+                            colored: false,
                         });
                     } else {
                         return None;
@@ -154,16 +292,22 @@ impl GlyphCache {
                 return Some(GlyphBatch {
                     view: page.create_view().into(),
                     rects,
+                    colored: false,
                 });
             }
         }
 
         if glyph_indices.peek().is_some() {
-            let atlas =
-                GlyphAtlasMap::init(device, Size2D::new(1024, 1024), TextureFormat::R8Unorm);
+            let atlas = GlyphAtlasMap::init_with_mips(
+                device,
+                textures,
+                Size2D::new(self.page_size, self.page_size),
+                self.format,
+                self.page_mip_levels(),
+            );
             self.pages.push(atlas);
 
-            return self.batch_glyph(device, queue, font, glyph_indices, size_px);
+            return self.batch_glyph(device, queue, textures, font, glyph_indices, size_px);
         }
 
         None
@@ -174,6 +318,8 @@ impl GlyphCache {
 pub struct GlyphBatch {
     pub view: TextureView2D,
     pub rects: Vec<GlyphTextureRect>,
+    /// Glyphs are color images from colored page, drawn with their own colors instead of as coverage
+    pub colored: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -197,27 +343,228 @@ pub struct GlyphAtlasMap {
     texture: SizedTexture2D,
     packer: DensePacker,
     map: HashMap<GlyphKey, GlyphTextureRect>,
+    used_area: u32,
+    /// Repacking again without new glyph wouldn't free more space
+    packed_since_repack: bool,
+
+    mips: Option<AtlasMipChain>,
+
+    /// Memory of texture. Size and levels are kept on repack, so it's tracked once.
+    allocation: TextureAllocation,
 }
 
 impl GlyphAtlasMap {
+    /// Ratio of used area under which full page is considered fragmented
+    pub const REPACK_THRESHOLD: f32 = 0.6;
+
     pub fn init(
         device: &Device,
+        textures: &TextureData,
         size: Size2D<u32, PhyiscalPixelUnit>,
         format: TextureFormat,
     ) -> Self {
-        let texture = SizedTexture2D::init(
+        Self::init_with_mips(device, textures, size, format, 1)
+    }
+
+    /// Create atlas with mip levels, updated around glyphs packed into it
+    pub fn init_with_mips(
+        device: &Device,
+        textures: &TextureData,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: TextureFormat,
+        mip_levels: u32,
+    ) -> Self {
+        let mip_levels =
+            mip_levels.clamp(1, u32::BITS - size.width.max(size.height).leading_zeros());
+
+        Self {
+            texture: Self::create_texture(device, size, format, mip_levels),
+            packer: DensePacker::new(size.width as i32, size.height as i32),
+            map: HashMap::new(),
+            used_area: 0,
+            packed_since_repack: false,
+            mips: (mip_levels > 1).then(|| {
+                AtlasMipChain::new(size, format.describe().block_size as usize, mip_levels)
+            }),
+
+            allocation: textures
+                .memory()
+                .track(mipmapped_texture_memory_size(size, format, mip_levels)),
+        }
+    }
+
+    fn create_texture(
+        device: &Device,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: TextureFormat,
+        mip_levels: u32,
+    ) -> SizedTexture2D {
+        SizedTexture2D::init_mipmapped(
             device,
             Some("GlyphAtlasTexture texture"),
             size,
+            mip_levels,
             format,
-            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-        );
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::COPY_SRC,
+        )
+    }
 
-        Self {
-            texture,
-            packer: DensePacker::new(size.width as i32, size.height as i32),
-            map: HashMap::new(),
+    pub fn mip_levels(&self) -> u32 {
+        self.mips
+            .as_ref()
+            .map_or(1, |mips| mips.levels.len() as u32)
+    }
+
+    /// Space kept between packed glyphs so lowest mip level doesn't mix neighboring glyphs
+    fn padding(&self) -> u32 {
+        (1 << self.mip_levels()) >> 1
+    }
+
+    /// Cpu copy of mip level data. None if atlas has no mipmaps.
+    pub fn mip_data(&self, level: u32) -> Option<&[u8]> {
+        Some(&self.mips.as_ref()?.levels.get(level as usize)?.1)
+    }
+
+    /// Area of packed glyphs
+    pub const fn used_area(&self) -> u32 {
+        self.used_area
+    }
+
+    pub fn fragmented(&self) -> bool {
+        (self.used_area as f32) < self.texture.size().area() as f32 * Self::REPACK_THRESHOLD
+    }
+
+    /// Lay out packed glyphs again into fresh texture, tallest first so packer wastes less space.
+    /// Glyphs are copied on gpu and stored rects are updated.
+    ///
+    /// Batches created before keep view of previous texture, which stays alive until they drop.
+    /// Returns false if glyphs didn't fit, atlas is unchanged in that case.
+    pub fn repack(&mut self, device: &Device, queue: &Queue) -> bool {
+        let size = self.texture.size();
+        let padding = self.padding();
+        let mut packer = DensePacker::new(size.width as i32, size.height as i32);
+
+        let mut entries = self
+            .map
+            .iter()
+            .map(|(key, rect)| (*key, *rect))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, rect)| Reverse(rect.tex_rect.size.height));
+
+        let mut map = HashMap::with_capacity(entries.len());
+        let mut copies = Vec::new();
+        for (key, rect) in entries {
+            let tex_rect = if !rect.tex_rect.is_empty() {
+                let packed = match packer.pack(
+                    (rect.tex_rect.size.width + padding) as i32,
+                    (rect.tex_rect.size.height + padding) as i32,
+                    false,
+                ) {
+                    Some(packed) => packed,
+                    None => return false,
+                };
+
+                let tex_rect = Rect::new(
+                    Point2D::new(packed.x as u32, packed.y as u32),
+                    rect.tex_rect.size,
+                );
+                copies.push((rect.tex_rect, tex_rect.origin));
+
+                tex_rect
+            } else {
+                Rect::zero()
+            };
+
+            map.insert(
+                key,
+                GlyphTextureRect {
+                    glyph_offset: rect.glyph_offset,
+                    tex_rect,
+                },
+            );
+        }
+
+        let texture = Self::create_texture(device, size, self.texture.format(), self.mip_levels());
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("GlyphAtlasMap repack command encoder"),
+        });
+
+        for &(from, to) in &copies {
+            encoder.copy_texture_to_texture(
+                ImageCopyTexture {
+                    texture: self.texture.inner(),
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: from.origin.x,
+                        y: from.origin.y,
+                        z: 0,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                ImageCopyTexture {
+                    texture: texture.inner(),
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: to.x,
+                        y: to.y,
+                        z: 0,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                Extent3d {
+                    width: from.size.width,
+                    height: from.size.height,
+                    depth_or_array_layers: 1,
+                },
+            );
         }
+
+        // Pending glyph writes to previous texture run before this submission
+        queue.submit(iter::once(encoder.finish()));
+
+        if let Some(mips) = &mut self.mips {
+            mips.move_rects(&copies);
+
+            for (level, rect, data) in mips.downsample(Rect::from_size(size)) {
+                texture.write_mip(queue, level, Some(rect), &data);
+            }
+        }
+
+        self.texture = texture;
+        self.packer = packer;
+        self.map = map;
+        self.packed_since_repack = false;
+
+        true
+    }
+
+    /// Pack glyph, repacking atlas first if it doesn't fit because of fragmentation.
+    /// Rects returned before from this atlas are invalidated if repacked.
+    pub fn pack_or_repack(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        key: GlyphKey,
+        glyph: &GlyphData,
+    ) -> Option<GlyphTextureRect> {
+        if let Some(rect) = self.pack(queue, key, glyph) {
+            return Some(rect);
+        }
+
+        if self.packed_since_repack && self.fragmented() && self.repack(device, queue) {
+            self.pack(queue, key, glyph)
+        } else {
+            None
+        }
+    }
+
+    pub const fn texture(&self) -> &SizedTexture2D {
+        &self.texture
+    }
+
+    pub const fn allocation(&self) -> &TextureAllocation {
+        &self.allocation
     }
 
     pub fn create_view(&self) -> SizedTextureView2D {
@@ -228,26 +575,71 @@ impl GlyphAtlasMap {
         Some(*self.map.get(key)?)
     }
 
+    /// Convert single channel glyph coverage into texels of atlas format.
+    /// Data which isn't single channel coverage, like colored glyphs, or format without coverage layout is written as is.
+    fn expand_coverage<'a>(&self, glyph: &'a GlyphData) -> Cow<'a, [u8]> {
+        let format = self.texture.format();
+
+        let channels = match coverage_channels(format) {
+            Some(channels) if glyph.data.len() == glyph.size.area() as usize => channels,
+            _ => return Cow::Borrowed(&glyph.data),
+        };
+
+        let srgb = format.describe().srgb;
+        if channels == 1 && !srgb {
+            return Cow::Borrowed(&glyph.data);
+        }
+
+        Cow::Owned(
+            glyph
+                .data
+                .iter()
+                .map(|&coverage| {
+                    if srgb {
+                        encode_srgb(coverage)
+                    } else {
+                        coverage
+                    }
+                })
+                .flat_map(|coverage| iter::repeat(coverage).take(channels))
+                .collect(),
+        )
+    }
+
     pub fn pack(
         &mut self,
         queue: &Queue,
         key: GlyphKey,
         glyph: &GlyphData,
     ) -> Option<GlyphTextureRect> {
+        let padding = self.padding();
         let tex_rect = if !glyph.data.is_empty() {
             self.packer
-                .pack(glyph.size.width as i32, glyph.size.height as i32, false)
-                .map(|rect| {
-                    Rect::new(
-                        Point2D::new(rect.x as u32, rect.y as u32),
-                        Size2D::new(rect.width as u32, rect.height as u32),
-                    )
-                })?
+                .pack(
+                    (glyph.size.width + padding) as i32,
+                    (glyph.size.height + padding) as i32,
+                    false,
+                )
+                .map(|rect| Rect::new(Point2D::new(rect.x as u32, rect.y as u32), glyph.size))?
         } else {
             Rect::zero()
         };
-        
-        self.texture.write(queue, Some(tex_rect), &glyph.data);
+
+        let data = self.expand_coverage(glyph);
+        self.texture.write(queue, Some(tex_rect), &data);
+
+        if let Some(mips) = &mut self.mips {
+            if !tex_rect.is_empty() {
+                mips.write(tex_rect, &data);
+
+                for (level, rect, data) in mips.downsample(tex_rect) {
+                    self.texture.write_mip(queue, level, Some(rect), &data);
+                }
+            }
+        }
+
+        self.used_area += tex_rect.area();
+        self.packed_since_repack = true;
         self.map.insert(
             key,
             GlyphTextureRect {
@@ -260,15 +652,175 @@ impl GlyphAtlasMap {
     }
 }
 
+impl Default for GlyphCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Channels of glyph page format coverage is written to.
+/// None if format cannot store coverage as 8 bit unorm channels.
+pub const fn coverage_channels(format: TextureFormat) -> Option<usize> {
+    match format {
+        TextureFormat::R8Unorm => Some(1),
+        TextureFormat::Rg8Unorm => Some(2),
+        TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Bgra8UnormSrgb => Some(4),
+        _ => None,
+    }
+}
+
+/// Encode linear coverage, so sampling sRGB texture decodes it back
+fn encode_srgb(coverage: u8) -> u8 {
+    let linear = coverage as f32 / 255.0;
+
+    let encoded = if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
+/// Glyph page format is not supported. See [coverage_channels].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphFormatError(pub TextureFormat);
+
+impl Display for GlyphFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unsupported glyph page format: {:?}", self.0)
+    }
+}
+
+impl Error for GlyphFormatError {}
+
 impl Debug for GlyphAtlasMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GlyphAtlasMap")
             .field("texture", &self.texture)
             .field("map", &self.map)
+            .field("used_area", &self.used_area)
             .finish_non_exhaustive()
     }
 }
 
+/// Cpu copy of atlas mip levels, so levels can be downsampled around updated region only
+#[derive(Debug)]
+struct AtlasMipChain {
+    block_size: usize,
+    levels: Vec<(Size2D<u32, PhyiscalPixelUnit>, Vec<u8>)>,
+}
+
+impl AtlasMipChain {
+    pub fn new(size: Size2D<u32, PhyiscalPixelUnit>, block_size: usize, mip_levels: u32) -> Self {
+        let levels = (0..mip_levels)
+            .map(|level| {
+                let size = Size2D::new((size.width >> level).max(1), (size.height >> level).max(1));
+
+                (size, vec![0; size.area() as usize * block_size])
+            })
+            .collect();
+
+        Self { block_size, levels }
+    }
+
+    /// Write data into region of first level
+    pub fn write(&mut self, rect: Rect<u32, PhyiscalPixelUnit>, data: &[u8]) {
+        let (size, level) = &mut self.levels[0];
+        let row_size = rect.size.width as usize * self.block_size;
+
+        for (y, row) in data.chunks_exact(row_size).enumerate() {
+            let start = ((rect.origin.y as usize + y) * size.width as usize
+                + rect.origin.x as usize)
+                * self.block_size;
+
+            level[start..start + row_size].copy_from_slice(row);
+        }
+    }
+
+    /// Move regions of first level, like GPU copies done by repacking
+    pub fn move_rects(
+        &mut self,
+        copies: &[(
+            Rect<u32, PhyiscalPixelUnit>,
+            Point2D<u32, PhyiscalPixelUnit>,
+        )],
+    ) {
+        let (size, level) = &self.levels[0];
+        let row_size = size.width as usize * self.block_size;
+
+        let mut moved = vec![0; level.len()];
+        for (from, to) in copies {
+            let copy_size = from.size.width as usize * self.block_size;
+
+            for y in 0..from.size.height as usize {
+                let src = (from.origin.y as usize + y) * row_size
+                    + from.origin.x as usize * self.block_size;
+                let dst = (to.y as usize + y) * row_size + to.x as usize * self.block_size;
+
+                moved[dst..dst + copy_size].copy_from_slice(&level[src..src + copy_size]);
+            }
+        }
+
+        self.levels[0].1 = moved;
+    }
+
+    /// Downsample lower levels covering region of first level.
+    /// Returns level, region in the level and its data to upload.
+    pub fn downsample(
+        &mut self,
+        rect: Rect<u32, PhyiscalPixelUnit>,
+    ) -> Vec<(u32, Rect<u32, PhyiscalPixelUnit>, Vec<u8>)> {
+        let block_size = self.block_size;
+
+        let mut updates = Vec::with_capacity(self.levels.len() - 1);
+        let (mut min, mut max) = (rect.min(), rect.max());
+
+        for level in 1..self.levels.len() {
+            let (upper, lower) = self.levels.split_at_mut(level);
+            let (src_size, src) = &upper[level - 1];
+            let (dst_size, dst) = &mut lower[0];
+
+            min = Point2D::new(min.x / 2, min.y / 2);
+            max = Point2D::new(
+                ((max.x + 1) / 2).min(dst_size.width),
+                ((max.y + 1) / 2).min(dst_size.height),
+            );
+
+            let region = Rect::new(min, Size2D::new(max.x - min.x, max.y - min.y));
+            let mut data = Vec::with_capacity(region.area() as usize * block_size);
+
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    for channel in 0..block_size {
+                        let mut sum = 0_u32;
+
+                        for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                            let sx = (x * 2 + sx).min(src_size.width - 1) as usize;
+                            let sy = (y * 2 + sy).min(src_size.height - 1) as usize;
+
+                            sum += src[(sy * src_size.width as usize + sx) * block_size + channel]
+                                as u32;
+                        }
+
+                        let value = ((sum + 2) / 4) as u8;
+                        dst[(y as usize * dst_size.width as usize + x as usize) * block_size
+                            + channel] = value;
+                        data.push(value);
+                    }
+                }
+            }
+
+            updates.push((level as u32, region, data));
+        }
+
+        updates
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct GlyphTextureRect {
     pub glyph_offset: Vector2D<f32, PhyiscalPixelUnit>,
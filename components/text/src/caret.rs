@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Angle, Point2D, Rect, Size2D, Transform3D},
+    palette::LinSrgba,
+    unit::LogicalPixelUnit,
+};
+use storyboard_primitive::{PrimitiveComponent, Rectangle};
+use storyboard_render::{
+    component::Drawable,
+    renderer::{context::DrawContext, ComponentQueue},
+    wgpu::CommandEncoder,
+};
+
+use crate::Text;
+
+/// Blinking text caret drawable with optional selection highlight
+#[derive(Debug, Clone)]
+pub struct Caret {
+    /// Caret rect from [Text::caret_rect]. Caret is not drawn if None.
+    pub rect: Option<Rect<f32, LogicalPixelUnit>>,
+    pub width: f32,
+    pub color: ShapeColor<4>,
+
+    /// Selection highlight rects from [Text::selection_rects]
+    pub selection: Vec<Rect<f32, LogicalPixelUnit>>,
+    pub selection_color: ShapeColor<4>,
+
+    pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+
+    /// Duration caret stays visible or hidden. Caret doesn't blink if zero.
+    pub blink_interval: Duration,
+    elapsed: Duration,
+}
+
+impl Caret {
+    pub const DEFAULT_BLINK_INTERVAL: Duration = Duration::from_millis(530);
+
+    pub fn new(color: ShapeColor<4>, selection_color: ShapeColor<4>) -> Self {
+        Self {
+            rect: None,
+            width: 1.0,
+            color,
+
+            selection: Vec::new(),
+            selection_color,
+
+            transform: Transform3D::identity(),
+
+            blink_interval: Self::DEFAULT_BLINK_INTERVAL,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Move caret to byte offset of text and highlight selection byte range if any.
+    /// Blink is restarted so caret is visible right after moving.
+    pub fn set_from_text(&mut self, text: &Text, offset: usize, selection: Option<(usize, usize)>) {
+        self.rect = text.caret_rect(offset);
+        self.transform = text.transform;
+
+        self.selection = match selection {
+            Some((start, end)) => text.selection_rects(start, end),
+            None => Vec::new(),
+        };
+
+        self.reset_blink();
+    }
+
+    /// Advance blink timer
+    pub fn update(&mut self, elapsed: Duration) {
+        self.elapsed += elapsed;
+
+        if !self.blink_interval.is_zero() {
+            // Keep timer in single blink period
+            while self.elapsed >= self.blink_interval * 2 {
+                self.elapsed -= self.blink_interval * 2;
+            }
+        }
+    }
+
+    pub fn reset_blink(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn visible(&self) -> bool {
+        self.blink_interval.is_zero() || self.elapsed < self.blink_interval
+    }
+
+    fn rectangle(&self, bounds: Rect<f32, LogicalPixelUnit>, color: &ShapeColor<4>) -> Rectangle {
+        Rectangle {
+            bounds,
+            color: color.clone(),
+            texture: None,
+            texture_coord: None,
+            transform: self.transform,
+            rotation: Angle::zero(),
+            rotation_anchor: Point2D::zero(),
+            clip: None,
+            force_opaque: false,
+            alpha_cutoff: None,
+        }
+    }
+}
+
+impl Default for Caret {
+    fn default() -> Self {
+        Self::new(
+            LinSrgba::new(0.0, 0.0, 0.0, 1.0).into(),
+            LinSrgba::new(0.2, 0.4, 1.0, 0.4).into(),
+        )
+    }
+}
+
+impl Drawable for Caret {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        _: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        for rect in &self.selection {
+            if let Some(component) = PrimitiveComponent::from_rectangle(
+                &self.rectangle(*rect, &self.selection_color),
+                ctx,
+                depth,
+            ) {
+                component_queue.push_transparent(component);
+            }
+        }
+
+        if let Some(rect) = self.rect.filter(|_| self.visible()) {
+            let bar = Rect::new(rect.origin, Size2D::new(self.width, rect.size.height));
+
+            if let Some(component) =
+                PrimitiveComponent::from_rectangle(&self.rectangle(bar, &self.color), ctx, depth)
+            {
+                component_queue.push_transparent(component);
+            }
+        }
+    }
+}
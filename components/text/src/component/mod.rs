@@ -1,13 +1,14 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, ops::Range, sync::Arc};
 
 use bytemuck::{Pod, Zeroable};
+use parking_lot::Mutex;
 use storyboard_core::{
     color::ShapeColor,
     euclid::{Point2D, Point3D, Rect, Transform3D},
-    math::RectExt,
+    math::{FiniteExt, RectExt},
     palette::LinSrgba,
     store::{Store, StoreResources},
-    unit::{LogicalPixelUnit, RenderUnit, TextureUnit},
+    unit::{LogicalPixelUnit, TextureUnit},
 };
 use storyboard_render::{
     buffer::stream::StreamRange,
@@ -16,6 +17,7 @@ use storyboard_render::{
     renderer::{
         context::{DrawContext, RenderContext},
         pass::StoryboardRenderPass,
+        screen::ScreenResources,
         ComponentQueue,
     },
     wgpu::{
@@ -28,9 +30,13 @@ use storyboard_render::{
 };
 use storyboard_texture::render::{data::TextureData, RenderTexture2D};
 
+/// Glyph pipeline outputs straight alpha color.
+/// Render into sRGB format target to blend glyph edges in linear space.
 #[derive(Debug)]
 pub struct TextResources {
     pub pipeline: RenderPipeline,
+    /// Pipeline drawing colored glyphs like emoji with their own colors
+    pub colored_pipeline: RenderPipeline,
 }
 
 impl StoreResources<RenderScopeContext<'_>> for TextResources {
@@ -40,21 +46,93 @@ impl StoreResources<RenderScopeContext<'_>> for TextResources {
         let shader = ctx.backend
             .get::<ShaderCache>()
             .get_or_create("glyph_shader", || init_glyph_shader(ctx.backend.device()));
-        let pipeline_layout = init_glyph_pipeline_layout(ctx.backend.device(), textures.bind_group_layout());
+        let pipeline_layout = init_glyph_pipeline_layout(
+            ctx.backend.device(),
+            textures.bind_group_layout(),
+            &ctx.backend.get::<ScreenResources>().bind_group_layout,
+        );
+
+        let targets = [Some(ColorTargetState {
+            format: ctx.pipeline.texture_format,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::ALL,
+        })];
 
         let pipeline = init_glyph_pipeline(
             ctx.backend.device(),
             &pipeline_layout,
             &shader,
-            &[Some(ColorTargetState {
-                format: ctx.pipeline.texture_format,
-                blend: Some(BlendState::ALPHA_BLENDING),
-                write_mask: ColorWrites::ALL,
-            })],
+            &targets,
             ctx.pipeline.depth_stencil_read_only(),
+            ctx.pipeline.primitive_state(PrimitiveTopology::TriangleList),
+        );
+
+        let colored_pipeline = init_glyph_pipeline_with_entry(
+            ctx.backend.device(),
+            &pipeline_layout,
+            &shader,
+            "fs_colored",
+            &targets,
+            ctx.pipeline.depth_stencil_read_only(),
+            ctx.pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
+        );
+
+        Self {
+            pipeline,
+            colored_pipeline,
+        }
+    }
+}
+
+/// Pipelines for glyphs rasterized with subpixel coverage
+#[derive(Debug)]
+pub struct LcdTextResources {
+    /// Pipelines writing red, green and blue channel
+    pub channel_pipelines: [RenderPipeline; 3],
+    /// Grayscale fallback for transformed glyphs
+    pub gray_pipeline: RenderPipeline,
+}
+
+impl StoreResources<RenderScopeContext<'_>> for LcdTextResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let textures = ctx.backend.get::<TextureData>();
+
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("glyph_shader", || init_glyph_shader(ctx.backend.device()));
+        let pipeline_layout = init_glyph_pipeline_layout(
+            ctx.backend.device(),
+            textures.bind_group_layout(),
+            &ctx.backend.get::<ScreenResources>().bind_group_layout,
         );
 
-        Self { pipeline }
+        let pipeline = |fragment_entry: &str, write_mask: ColorWrites| {
+            init_glyph_pipeline_with_entry(
+                ctx.backend.device(),
+                &pipeline_layout,
+                &shader,
+                fragment_entry,
+                &[Some(ColorTargetState {
+                    format: ctx.pipeline.texture_format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask,
+                })],
+                ctx.pipeline.depth_stencil_read_only(),
+                ctx.pipeline
+                    .primitive_state(PrimitiveTopology::TriangleList),
+            )
+        };
+
+        Self {
+            channel_pipelines: [
+                pipeline("fs_lcd_red", ColorWrites::RED),
+                pipeline("fs_lcd_green", ColorWrites::GREEN),
+                pipeline("fs_lcd_blue", ColorWrites::BLUE),
+            ],
+            gray_pipeline: pipeline("fs_lcd_gray", ColorWrites::ALL),
+        }
     }
 }
 
@@ -62,12 +140,45 @@ impl StoreResources<RenderScopeContext<'_>> for TextResources {
 pub struct TextRenderBatch {
     pub texture: Arc<RenderTexture2D>,
     pub rects: Vec<GlyphRect>,
+    /// Texture holds subpixel coverage from [crate::cache::GlyphCache::new_lcd]
+    pub lcd: bool,
+    /// Texture holds color glyph images, see [crate::cache::GlyphBatch::colored]
+    pub colored: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct GlyphRect {
     pub rect: Rect<f32, LogicalPixelUnit>,
     pub texture_rect: Rect<f32, TextureUnit>,
+    /// Byte offset of glyph cluster in text
+    pub cluster: u32,
+}
+
+/// Color override for byte range of text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub range: Range<usize>,
+    pub color: ShapeColor<4>,
+}
+
+impl TextSpan {
+    pub const fn new(range: Range<usize>, color: ShapeColor<4>) -> Self {
+        Self { range, color }
+    }
+
+    /// Find color of glyph cluster. Later span overrides former one.
+    pub fn color_for<'a>(
+        spans: &'a [TextSpan],
+        cluster: u32,
+        default: &'a ShapeColor<4>,
+    ) -> &'a ShapeColor<4> {
+        spans
+            .iter()
+            .rev()
+            .find(|span| span.range.contains(&(cluster as usize)))
+            .map(|span| &span.color)
+            .unwrap_or(default)
+    }
 }
 
 #[derive(Debug)]
@@ -75,6 +186,9 @@ pub struct TextDrawable {
     pub batches: Arc<Vec<TextRenderBatch>>,
     pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
     pub color: ShapeColor<4>,
+    pub spans: Arc<Vec<TextSpan>>,
+    /// Screen space rect text is clipped to. Not clipped if None.
+    pub clip: Option<Rect<f32, LogicalPixelUnit>>,
 }
 
 impl Drawable for TextDrawable {
@@ -86,9 +200,24 @@ impl Drawable for TextDrawable {
         depth: f32,
     ) {
         for batch in self.batches.iter() {
-            if let Some(component) = GlyphComponent::from_batch(batch, &self.transform, ctx, depth)
-            {
-                component_queue.push_transparent(component);
+            if let Some(component) = GlyphComponent::from_batch(
+                batch,
+                &self.transform,
+                &self.color,
+                &self.spans,
+                self.clip,
+                ctx,
+                depth,
+            ) {
+                // Merge into previous glyph draw sharing same atlas texture, even if it is from other text
+                if let Some(last) = component_queue.last_batch::<GlyphBatch>() {
+                    if last.merge(&component.batch) {
+                        continue;
+                    }
+                }
+
+                let batch = component.batch.clone();
+                component_queue.push_transparent_batch(component, batch);
             }
         }
     }
@@ -96,62 +225,121 @@ impl Drawable for TextDrawable {
 
 #[derive(Debug)]
 pub struct GlyphComponent {
+    batch: Arc<GlyphBatch>,
+}
+
+/// Glyph vertices drawn in single draw call
+#[derive(Debug)]
+pub struct GlyphBatch {
     texture: Arc<RenderTexture2D>,
+    coverage: GlyphCoverage,
+    clip: Option<Rect<f32, LogicalPixelUnit>>,
+    draw: Mutex<GlyphDraw>,
+}
+
+/// How glyph texture coverage is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GlyphCoverage {
+    Grayscale,
+    Subpixel,
+    /// Subpixel texture drawn in grayscale, because transform breaks subpixel layout
+    SubpixelFallback,
+    /// Color glyph image, only its alpha is multiplied by text color alpha
+    Colored,
+}
+
+#[derive(Debug, Clone)]
+struct GlyphDraw {
     vertices: u32,
     vertices_slice: StreamRange,
+    bounds: Rect<f32, LogicalPixelUnit>,
+}
+
+impl GlyphBatch {
+    /// Merge other batch if it uses same texture and clip, and its vertices follow this batch in stream
+    pub fn merge(&self, other: &GlyphBatch) -> bool {
+        if !Arc::ptr_eq(&self.texture, &other.texture)
+            || self.coverage != other.coverage
+            || self.clip != other.clip
+        {
+            return false;
+        }
+
+        let mut draw = self.draw.lock();
+        let other = other.draw.lock();
+
+        if draw.vertices_slice.end != other.vertices_slice.start {
+            return false;
+        }
+
+        draw.vertices += other.vertices;
+        draw.vertices_slice.end = other.vertices_slice.end;
+        draw.bounds = draw.bounds.union(&other.bounds);
+
+        true
+    }
 }
 
 impl GlyphComponent {
     pub fn from_batch(
         batch: &TextRenderBatch,
         transform: &Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+        color: &ShapeColor<4>,
+        spans: &[TextSpan],
+        clip: Option<Rect<f32, LogicalPixelUnit>>,
         ctx: &mut DrawContext,
         depth: f32,
     ) -> Option<Self> {
         let mut writer = ctx.vertex_stream.next_writer();
 
         let mut vertices = 0;
+        let mut bounds = Rect::zero();
         for rect in &batch.rects {
             if rect.texture_rect.area() <= 0.0 {
                 continue;
             }
 
-            let coords = transform.outer_transformed_rect(&rect.rect)?.into_coords();
+            // Skip glyph with degenerate transform, so it doesn't write invalid vertices
+            let coords = match transform
+                .outer_transformed_rect(&rect.rect)
+                .and_then(FiniteExt::finite)
+            {
+                // Snap glyph origin only, glyph size is already in whole physical pixels
+                Some(transformed) if transformed.area() > 0.0 => {
+                    let rect =
+                        Rect::new(ctx.screen.snap_point(transformed.origin), transformed.size);
+                    bounds = bounds.union(&rect);
+
+                    rect.into_coords()
+                }
+                _ => continue,
+            };
+
             let tex_coords = rect.texture_rect.into_coords();
+            let color = TextSpan::color_for(spans, rect.cluster, color)
+                .vertex_colors(&rect.rect, &rect.rect.into_coords());
 
             let left_top = GlyphVertex {
-                position: ctx
-                    .screen_matrix
-                    .transform_point2d(coords[0])?
-                    .extend(depth),
-                color: ShapeColor::WHITE.into(),
+                position: coords[0].extend(depth),
+                color: color[0],
                 texture_coord: tex_coords[0],
             };
 
             let left_bottom = GlyphVertex {
-                position: ctx
-                    .screen_matrix
-                    .transform_point2d(coords[1])?
-                    .extend(depth),
-                color: ShapeColor::WHITE.into(),
+                position: coords[1].extend(depth),
+                color: color[1],
                 texture_coord: tex_coords[1],
             };
 
             let right_bottom = GlyphVertex {
-                position: ctx
-                    .screen_matrix
-                    .transform_point2d(coords[2])?
-                    .extend(depth),
-                color: ShapeColor::WHITE.into(),
+                position: coords[2].extend(depth),
+                color: color[2],
                 texture_coord: tex_coords[2],
             };
 
             let right_top = GlyphVertex {
-                position: ctx
-                    .screen_matrix
-                    .transform_point2d(coords[3])?
-                    .extend(depth),
-                color: ShapeColor::WHITE.into(),
+                position: coords[3].extend(depth),
+                color: color[3],
                 texture_coord: tex_coords[3],
             };
 
@@ -171,12 +359,34 @@ impl GlyphComponent {
             return None;
         }
 
+        // Skip batch clipped out entirely
+        if let Some(clip) = &clip {
+            bounds = bounds.intersection(clip)?;
+        }
+
         let vertices_slice = writer.finish();
 
+        let coverage = if batch.colored {
+            GlyphCoverage::Colored
+        } else if !batch.lcd {
+            GlyphCoverage::Grayscale
+        } else if transform.m12 == 0.0 && transform.m21 == 0.0 {
+            GlyphCoverage::Subpixel
+        } else {
+            GlyphCoverage::SubpixelFallback
+        };
+
         Some(Self {
-            texture: batch.texture.clone(),
-            vertices,
-            vertices_slice,
+            batch: Arc::new(GlyphBatch {
+                texture: batch.texture.clone(),
+                coverage,
+                clip,
+                draw: Mutex::new(GlyphDraw {
+                    vertices,
+                    vertices_slice,
+                    bounds,
+                }),
+            }),
         })
     }
 }
@@ -195,19 +405,50 @@ impl Component for GlyphComponent {
         ctx: &RenderContext<'rpass>,
         pass: &mut StoryboardRenderPass<'rpass>,
     ) {
-        let text_resources = ctx.scope.get::<TextResources>();
+        let draw = self.batch.draw.lock().clone();
+
+        let pipelines: &[RenderPipeline] = match self.batch.coverage {
+            GlyphCoverage::Grayscale => {
+                std::slice::from_ref(&ctx.scope.get::<TextResources>().pipeline)
+            }
+
+            GlyphCoverage::Subpixel => &ctx.scope.get::<LcdTextResources>().channel_pipelines,
 
-        pass.set_pipeline(&text_resources.pipeline);
-        pass.set_bind_group(0, self.texture.bind_group(), &[]);
-        pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.vertices_slice.clone()));
-        pass.draw(0..self.vertices, 0..1);
+            GlyphCoverage::SubpixelFallback => {
+                std::slice::from_ref(&ctx.scope.get::<LcdTextResources>().gray_pipeline)
+            }
+
+            GlyphCoverage::Colored => {
+                std::slice::from_ref(&ctx.scope.get::<TextResources>().colored_pipeline)
+            }
+        };
+
+        if let Some(clip) = &self.batch.clip {
+            pass.set_clip(&ctx.screen, clip);
+        }
+
+        for pipeline in pipelines {
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, self.batch.texture.bind_group(), &[]);
+            pass.set_bind_group(1, ctx.screen_bind_group, &[]);
+            pass.set_vertex_buffer(0, ctx.vertex_stream.slice(draw.vertices_slice.clone()));
+            pass.draw(0..draw.vertices, 0..1);
+        }
+
+        if self.batch.clip.is_some() {
+            pass.reset_clip(&ctx.screen);
+        }
+    }
+
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        Some(self.batch.draw.lock().bounds)
     }
 }
 
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
 pub struct GlyphVertex {
-    pub position: Point3D<f32, RenderUnit>,
+    pub position: Point3D<f32, LogicalPixelUnit>,
     pub color: LinSrgba<f32>,
     pub texture_coord: Point2D<f32, TextureUnit>,
 }
@@ -222,10 +463,11 @@ pub fn init_glyph_shader(device: &Device) -> ShaderModule {
 pub fn init_glyph_pipeline_layout(
     device: &Device,
     texture_bind_group_layout: &BindGroupLayout,
+    screen_bind_group_layout: &BindGroupLayout,
 ) -> PipelineLayout {
     device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("Glyph shader pipeline layout"),
-        bind_group_layouts: &[texture_bind_group_layout],
+        bind_group_layouts: &[texture_bind_group_layout, screen_bind_group_layout],
         push_constant_ranges: &[],
     })
 }
@@ -236,6 +478,28 @@ pub fn init_glyph_pipeline(
     shader: &ShaderModule,
     fragment_targets: &[Option<ColorTargetState>],
     depth_stencil: Option<DepthStencilState>,
+    primitive: PrimitiveState,
+) -> RenderPipeline {
+    init_glyph_pipeline_with_entry(
+        device,
+        pipeline_layout,
+        shader,
+        "fs_main",
+        fragment_targets,
+        depth_stencil,
+        primitive,
+    )
+}
+
+/// Create glyph pipeline using given fragment entry point of glyph shader
+pub fn init_glyph_pipeline_with_entry(
+    device: &Device,
+    pipeline_layout: &PipelineLayout,
+    shader: &ShaderModule,
+    fragment_entry: &str,
+    fragment_targets: &[Option<ColorTargetState>],
+    depth_stencil: Option<DepthStencilState>,
+    primitive: PrimitiveState,
 ) -> RenderPipeline {
     device.create_render_pipeline(&RenderPipelineDescriptor {
         label: Some("Glyph pipeline"),
@@ -249,15 +513,12 @@ pub fn init_glyph_pipeline(
                 attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x4, 2 => Float32x2],
             }],
         },
-        primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
-            ..PrimitiveState::default()
-        },
+        primitive,
         depth_stencil,
         multisample: MultisampleState::default(),
         fragment: Some(FragmentState {
             module: shader,
-            entry_point: "fs_main",
+            entry_point: fragment_entry,
             targets: fragment_targets,
         }),
         multiview: None,
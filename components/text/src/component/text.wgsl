@@ -4,6 +4,12 @@ struct VertexOutput {
     @location(1) texture_coord: vec2<f32>,
 };
 
+struct Screen {
+    matrix: mat4x4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> screen: Screen;
+
 @vertex
 fn vs_main(
     @location(0) position: vec3<f32>,
@@ -12,7 +18,7 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    out.position = vec4<f32>(position, 1.0);
+    out.position = vec4<f32>((screen.matrix * vec4<f32>(position.xy, 0.0, 1.0)).xy, position.z, 1.0);
     out.color = color;
     out.texture_coord = texture_coord;
 
@@ -22,8 +28,44 @@ fn vs_main(
 @group(0) @binding(0) var texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 
+// Coverage only scales alpha. Color is multiplied by alpha in blending,
+// so multiplying it here too darkens antialiased edges.
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color = in.color * textureSample(texture, texture_sampler, in.texture_coord).r;
-    return color;
+    let coverage = textureSample(texture, texture_sampler, in.texture_coord).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
+
+// Color glyph image keeps its own color, text color only fades it
+@fragment
+fn fs_colored(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.texture_coord);
+    return vec4<f32>(color.rgb, color.a * in.color.a);
+}
+
+// Subpixel coverage is blended per channel in separate passes writing single channel,
+// as blending each channel with its own alpha needs dual source blending otherwise.
+@fragment
+fn fs_lcd_red(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(texture, texture_sampler, in.texture_coord);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage.r);
+}
+
+@fragment
+fn fs_lcd_green(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(texture, texture_sampler, in.texture_coord);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage.g);
+}
+
+@fragment
+fn fs_lcd_blue(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(texture, texture_sampler, in.texture_coord);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage.b);
+}
+
+// Grayscale fallback using average coverage stored in alpha
+@fragment
+fn fs_lcd_gray(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(texture, texture_sampler, in.texture_coord).a;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
 }
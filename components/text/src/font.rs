@@ -9,19 +9,26 @@ use std::{
     sync::Arc,
 };
 
-use ttf_parser::{Face, FaceParsingError};
+use ttf_parser::{Face, FaceParsingError, Tag, VariationAxis};
 
 #[derive(Clone)]
 pub struct Font {
     font_hash: u64,
-    face: Arc<(Pin<Cow<'static, [u8]>>, Face<'static>)>,
+    /// Hash of font data, without variations
+    data_hash: u64,
+
+    variations: Vec<([u8; 4], f32)>,
+
+    // Face borrows data, so face must be dropped first
+    face: Face<'static>,
+    data: Arc<Pin<Cow<'static, [u8]>>>,
 }
 
 impl Font {
     pub fn new(data: Cow<'static, [u8]>, index: u32) -> Result<Self, FaceParsingError> {
-        let data = Pin::new(data);
+        let data = Arc::new(Pin::new(data));
 
-        // data is pinned and the reference of data never move
+        // data is pinned and the reference of data never move while Arc is alive
         let face = Face::from_slice(
             unsafe { slice::from_raw_parts(data.as_ptr(), data.len()) },
             index,
@@ -30,31 +37,122 @@ impl Font {
         let mut hasher = DefaultHasher::new();
         index.hash(&mut hasher);
         data.hash(&mut hasher);
+        let data_hash = hasher.finish();
 
         Ok(Self {
-            face: Arc::new((data, face)),
-            font_hash: hasher.finish(),
+            font_hash: data_hash,
+            data_hash,
+            variations: Vec::new(),
+            face,
+            data,
         })
     }
 
     pub const fn font_hash(this: &Self) -> u64 {
         this.font_hash
     }
+
+    /// Variation axes of variable font. Empty if font is not variable.
+    pub fn variation_axes(&self) -> Vec<VariationAxis> {
+        self.face.variation_axes().into_iter().collect()
+    }
+
+    /// Variation values set on font
+    pub fn variations(&self) -> &[([u8; 4], f32)] {
+        &self.variations
+    }
+
+    /// Set variation axis value like `*b"wght"` of variable font.
+    /// Glyphs are cached per variation instance, so text using the font is rasterized again.
+    /// Returns None if font doesn't have the axis.
+    pub fn set_variation(&mut self, axis_tag: [u8; 4], value: f32) -> Option<()> {
+        self.face.set_variation(Tag::from_bytes(&axis_tag), value)?;
+
+        match self.variations.iter_mut().find(|(tag, _)| *tag == axis_tag) {
+            Some((_, current)) => *current = value,
+            None => self.variations.push((axis_tag, value)),
+        }
+
+        let mut hasher = DefaultHasher::new();
+        self.data_hash.hash(&mut hasher);
+        for (tag, value) in &self.variations {
+            tag.hash(&mut hasher);
+            value.to_bits().hash(&mut hasher);
+        }
+        self.font_hash = hasher.finish();
+
+        Some(())
+    }
 }
 
 impl Deref for Font {
     type Target = Face<'static>;
 
     fn deref(&self) -> &Self::Target {
-        &self.face.1
+        &self.face
     }
 }
 
 impl Debug for Font {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Font")
-            .field("face", &self.face.1)
+            .field("face", &self.face)
             .field("file_hash", &self.font_hash)
+            .field("variations", &self.variations)
             .finish_non_exhaustive()
     }
 }
+
+/// Ordered list of fonts.
+/// Glyphs missing in former font falls back to next font which has it.
+#[derive(Debug, Clone)]
+pub struct FontStack {
+    fonts: Vec<Font>,
+}
+
+impl FontStack {
+    pub fn new(primary: Font) -> Self {
+        Self {
+            fonts: vec![primary],
+        }
+    }
+
+    pub fn with_fallback(mut self, font: Font) -> Self {
+        self.push(font);
+        self
+    }
+
+    pub fn push(&mut self, font: Font) {
+        self.fonts.push(font);
+    }
+
+    pub fn primary(&self) -> &Font {
+        &self.fonts[0]
+    }
+
+    /// Fonts after primary font, in fallback order
+    pub fn fallbacks(&self) -> &[Font] {
+        &self.fonts[1..]
+    }
+
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// Set variation axis value on every font having the axis.
+    /// Returns true if any font has the axis.
+    pub fn set_variation(&mut self, axis_tag: [u8; 4], value: f32) -> bool {
+        let mut found = false;
+        for font in &mut self.fonts {
+            found |= font.set_variation(axis_tag, value).is_some();
+        }
+
+        found
+    }
+}
+
+impl From<Font> for FontStack {
+    fn from(font: Font) -> Self {
+        Self::new(font)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     fmt::Debug,
-    iter::{Enumerate, Peekable, Zip},
+    iter::{self, Enumerate, Peekable, Zip},
     ops::{Deref, Range},
     slice::Iter,
     str::CharIndices,
@@ -12,7 +12,7 @@ use ttf_parser::Face;
 
 #[derive(Debug)]
 pub struct TextLayout<'a> {
-    face: &'a Face<'a>,
+    faces: Vec<&'a Face<'a>>,
 
     ascender: i16,
     descender: i16,
@@ -26,6 +26,23 @@ pub struct TextLayout<'a> {
 
 impl<'a> TextLayout<'a> {
     pub fn new(face: &'a Face, text: &'a str) -> Self {
+        Self::with_primary(face, iter::empty(), text)
+    }
+
+    /// Create layout using first face as primary and others as fallback faces for missing glyphs.
+    /// Returns None if faces is empty.
+    pub fn with_fallback(faces: Vec<&'a Face<'a>>, text: &'a str) -> Option<Self> {
+        let (face, fallback) = faces.split_first()?;
+
+        Some(Self::with_primary(face, fallback.iter().copied(), text))
+    }
+
+    /// Create layout using primary face and fallback faces for glyphs missing in primary face
+    pub fn with_primary(
+        face: &'a Face<'a>,
+        fallback: impl IntoIterator<Item = &'a Face<'a>>,
+        text: &'a str,
+    ) -> Self {
         let units_per_em = face.units_per_em();
 
         let ascender = face.ascender();
@@ -37,7 +54,7 @@ impl<'a> TextLayout<'a> {
             .unwrap_or_default();
 
         Self {
-            face,
+            faces: iter::once(face).chain(fallback).collect(),
 
             ascender,
             descender,
@@ -50,9 +67,23 @@ impl<'a> TextLayout<'a> {
         }
     }
 
+    /// Faces used for layout. Primary face comes first.
+    pub fn faces(&self) -> &[&'a Face<'a>] {
+        &self.faces
+    }
+
     pub fn iter(&self, tab_size: u32, size_px: f32) -> TextLayoutIter<'a> {
         TextLayoutIter {
-            face: rustybuzz::Face::from_face(self.face.clone()).unwrap(),
+            faces: self
+                .faces
+                .iter()
+                .map(|face| rustybuzz::Face::from_face((*face).clone()).unwrap())
+                .collect(),
+            scales: self
+                .faces
+                .iter()
+                .map(|face| size_px / face.units_per_em() as f32)
+                .collect(),
 
             current_position: Vector2D::zero(),
 
@@ -69,10 +100,32 @@ impl<'a> TextLayout<'a> {
             shape_buffer: None,
         }
     }
+
+    /// Start layout from byte offset with given position.
+    /// Offset must be start of line, where layout state doesn't depend on preceding text.
+    pub fn iter_from(
+        &self,
+        tab_size: u32,
+        size_px: f32,
+        offset: usize,
+        position: Vector2D<f32, PhyiscalPixelUnit>,
+    ) -> TextLayoutIter<'a> {
+        let mut iter = self.iter(tab_size, size_px);
+        iter.current_position = position;
+
+        while iter
+            .text_iter
+            .next_if(|(_, (char_offset, _))| *char_offset < offset)
+            .is_some()
+        {}
+
+        iter
+    }
 }
 
 pub struct TextLayoutIter<'a> {
-    face: rustybuzz::Face<'a>,
+    faces: Vec<rustybuzz::Face<'a>>,
+    scales: Vec<f32>,
 
     current_position: Vector2D<f32, PhyiscalPixelUnit>,
 
@@ -102,6 +155,19 @@ impl<'a> TextLayoutIter<'a> {
         self.tab_width as f32 * self.scale
     }
 
+    /// Position next span starts from
+    pub const fn current_position(&self) -> Vector2D<f32, PhyiscalPixelUnit> {
+        self.current_position
+    }
+
+    /// Byte offset of next character to layout
+    pub fn offset(&mut self) -> usize {
+        self.text_iter
+            .peek()
+            .map(|(_, (offset, _))| *offset)
+            .unwrap_or(self.text.len())
+    }
+
     fn get_placement_for(&self, ch: char) -> Option<TextPlacement> {
         match ch {
             '\n' => Some(TextPlacement::Set(Vector2D::new(
@@ -129,19 +195,37 @@ impl<'a> TextLayoutIter<'a> {
         }
     }
 
+    /// Index of first face containing glyph for the character. Returns primary face if no face has it.
+    fn face_index_for(&self, ch: char) -> usize {
+        self.faces
+            .iter()
+            .position(|face| face.glyph_index(ch).is_some())
+            .unwrap_or(0)
+    }
+
     fn next_text_slice(&mut self) -> Option<TextSlice> {
         let (cluster_offset, (start_offset, start_ch)) = *self.text_iter.peek()?;
 
+        let face_index = self.face_index_for(start_ch);
+
         let mut next_placement = TextPlacement::default();
 
         let mut end_offset = start_offset + start_ch.len_utf8();
 
-        while let Some((_, (start_pos, ch))) = self.text_iter.next() {
+        while let Some(&(_, (start_pos, ch))) = self.text_iter.peek() {
             if let Some(placement) = self.get_placement_for(ch) {
+                self.text_iter.next();
+
                 end_offset = start_pos;
                 next_placement = placement;
                 break;
+            } else if start_pos != start_offset && self.face_index_for(ch) != face_index {
+                // Split run so it can be shaped using fallback face
+                end_offset = start_pos;
+                break;
             } else {
+                self.text_iter.next();
+
                 end_offset = start_pos + ch.len_utf8();
             }
         }
@@ -149,25 +233,37 @@ impl<'a> TextLayoutIter<'a> {
         Some(TextSlice {
             range: start_offset..end_offset,
             cluster_offset,
+            face_index,
             next_placement,
         })
     }
 
-    fn shape_text(&mut self, mut shape_buffer: UnicodeBuffer, text: &str) -> GlyphBuffer {
+    fn shape_text(
+        &mut self,
+        mut shape_buffer: UnicodeBuffer,
+        face_index: usize,
+        text: &str,
+    ) -> GlyphBuffer {
         shape_buffer.push_str(text);
         shape_buffer.guess_segment_properties();
 
-        rustybuzz::shape(&self.face, &[], shape_buffer)
+        rustybuzz::shape(&self.faces[face_index], &[], shape_buffer)
     }
 
     pub fn next<'iter>(&'iter mut self) -> Option<SpanLayoutRef<'iter, 'a>> {
         let slice = self.next_text_slice()?;
 
         let shape_buffer = self.shape_buffer.take().unwrap_or_default();
-        let shape_buffer = self.shape_text(shape_buffer, &self.text[slice.range]);
+        let shape_buffer = self.shape_text(
+            shape_buffer,
+            slice.face_index,
+            &self.text[slice.range.clone()],
+        );
 
         let line_layout = SpanLayout {
-            scale: self.scale,
+            scale: self.scales[slice.face_index],
+            face_index: slice.face_index,
+            text_offset: slice.range.start,
             current_position: self.current_position,
             buffer: shape_buffer,
         };
@@ -245,12 +341,17 @@ impl TextPlacement {
 pub struct TextSlice {
     pub range: Range<usize>,
     pub cluster_offset: usize,
+    pub face_index: usize,
     pub next_placement: TextPlacement,
 }
 
 #[derive(Debug)]
 pub struct SpanLayout {
     scale: f32,
+    /// Index of face used for shaping span
+    pub face_index: usize,
+    /// Byte offset of span in source text
+    pub text_offset: usize,
     pub current_position: Vector2D<f32, PhyiscalPixelUnit>,
     buffer: GlyphBuffer,
 }
@@ -270,6 +371,8 @@ impl SpanLayout {
 
         Self {
             scale,
+            face_index: 0,
+            text_offset: 0,
             current_position: Vector2D::zero(),
 
             buffer,
@@ -287,6 +390,8 @@ impl SpanLayout {
 
         Self {
             scale,
+            face_index: 0,
+            text_offset: 0,
             current_position: Vector2D::zero(),
 
             buffer,
@@ -316,7 +421,7 @@ impl SpanLayout {
     pub fn iter(&self) -> SpanLayoutIter {
         SpanLayoutIter {
             scale: self.scale,
-            cluster_offset: 0,
+            cluster_offset: self.text_offset as u32,
             current_position: self.current_position,
             iter: self
                 .buffer
@@ -345,6 +450,11 @@ impl<'a> Iterator for SpanLayoutIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         let (info, pos) = self.iter.next()?;
 
+        let advance = Vector2D::new(
+            pos.x_advance as f32 * self.scale,
+            pos.y_advance as f32 * self.scale,
+        );
+
         let glyph_info = GlyphInfo {
             glyph_id: info.glyph_id as u16,
             cluster: self.cluster_offset + info.cluster,
@@ -353,21 +463,19 @@ impl<'a> Iterator for SpanLayoutIter<'a> {
                     pos.x_offset as f32 * self.scale,
                     pos.y_offset as f32 * self.scale,
                 ),
+            advance,
         };
 
-        let advance = Vector2D::new(
-            pos.x_advance as f32 * self.scale,
-            pos.y_advance as f32 * self.scale,
-        );
         self.current_position += advance;
 
         Some(glyph_info)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct GlyphInfo {
     pub glyph_id: u16,
     pub cluster: u32,
     pub position: Vector2D<f32, PhyiscalPixelUnit>,
+    pub advance: Vector2D<f32, PhyiscalPixelUnit>,
 }
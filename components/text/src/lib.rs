@@ -2,30 +2,49 @@ pub use rustybuzz;
 pub use ttf_parser;
 
 pub mod cache;
+pub mod caret;
 pub mod component;
 pub mod font;
 pub mod layout;
 pub mod rasterizer;
+#[cfg(feature = "system-font")]
+pub mod system_font;
+pub mod truncate;
 
 #[derive(Debug, Clone, Copy)]
 pub struct FontUnit;
 
-use std::{borrow::Cow, fmt::Debug, sync::Arc};
+use std::{borrow::Cow, fmt::Debug, iter, sync::Arc};
 
 use layout::TextLayout;
 use storyboard_core::{
     color::ShapeColor,
-    euclid::{Box2D, Point2D, Rect, Vector2D, Transform3D},
+    euclid::{Angle, Box2D, Point2D, Rect, Size2D, Transform3D, Vector2D},
+    math::RectExt,
     observable::Observable,
-    unit::LogicalPixelUnit,
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
+};
+use storyboard_primitive::Rectangle;
+use storyboard_render::{
+    component::Drawable,
+    shared::RenderScope,
+    wgpu::{AddressMode, CommandEncoder, Device, FilterMode, Queue},
+    ScreenRect,
+};
+use storyboard_texture::{
+    render::{
+        data::{SamplerKey, TextureData},
+        renderer::StoryboardTextureRenderer,
+        RenderTexture2D,
+    },
+    ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap,
 };
-use storyboard_render::wgpu::{Device, Queue};
-use storyboard_texture::render::{data::TextureData, RenderTexture2D};
 
 use crate::{
     cache::GlyphCache,
-    component::{GlyphRect, TextDrawable, TextRenderBatch},
-    font::Font,
+    component::{GlyphRect, TextDrawable, TextRenderBatch, TextSpan},
+    font::{Font, FontStack},
+    truncate::{Ellipsis, ShapedSpan, Truncate},
 };
 
 pub struct Text {
@@ -34,12 +53,37 @@ pub struct Text {
 
     pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
 
+    /// Screen space rect text is clipped to, like overflowing label. Not clipped if None.
+    pub clip: Option<Rect<f32, LogicalPixelUnit>>,
+
     text: Observable<Cow<'static, str>>,
-    font: Observable<Font>,
+    font: Observable<FontStack>,
+
+    spans: Arc<Vec<TextSpan>>,
+
+    truncate: Observable<Option<Truncate>>,
+    truncated: bool,
+
+    vertical_anchor: Observable<VerticalAnchor>,
+
+    /// Text and top left origin of last layout
+    layout_text: String,
+    layout_origin: Point2D<f32, LogicalPixelUnit>,
+    checkpoints: Vec<LineCheckpoint>,
+    /// Lines laid out on last layout, excluding reused lines before changed text
+    relaid_lines: usize,
 
     bounding_box: Box2D<f32, LogicalPixelUnit>,
+    glyph_bounds: Vec<GlyphBounds>,
+
+    /// Scale factor glyphs were rasterized at
+    scale_factor: f32,
 
     batches: Arc<Vec<TextRenderBatch>>,
+
+    baked: Option<BakedText>,
+    /// Layout or spans changed since last bake
+    bake_invalidated: bool,
 }
 
 impl Text {
@@ -54,21 +98,55 @@ impl Text {
             position,
             size_px,
             transform,
-            font: font.into(),
+            clip: None,
+            font: FontStack::new(font).into(),
             text: text.into(),
 
+            spans: Arc::new(Vec::new()),
+
+            truncate: None.into(),
+            truncated: false,
+
+            vertical_anchor: VerticalAnchor::Top.into(),
+
+            layout_text: String::new(),
+            layout_origin: position,
+            checkpoints: Vec::new(),
+            relaid_lines: 0,
+
             bounding_box: Box2D::zero(),
+            glyph_bounds: Vec::new(),
+
+            scale_factor: 0.0,
 
             batches: Arc::new(Vec::new()),
+
+            baked: None,
+            bake_invalidated: true,
         }
     }
 
     pub fn font(&self) -> &Font {
-        &self.font
+        self.font.primary()
     }
 
     pub fn set_font(&mut self, font: Font) {
-        self.font = font.into();
+        self.font = FontStack::new(font).into();
+    }
+
+    pub fn font_stack(&self) -> &FontStack {
+        &self.font
+    }
+
+    /// Set font stack. Runs of text missing in primary font are rendered using fallback fonts.
+    pub fn set_font_stack(&mut self, font_stack: FontStack) {
+        self.font = font_stack.into();
+    }
+
+    /// Set variation axis value like `*b"wght"` on variable fonts of font stack.
+    /// Text is laid out and rasterized again on next update.
+    pub fn set_font_variation(&mut self, axis_tag: [u8; 4], value: f32) -> bool {
+        self.font.set_variation(axis_tag, value)
     }
 
     pub fn text(&self) -> &str {
@@ -79,10 +157,106 @@ impl Text {
         self.text = text.into();
     }
 
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    /// Set color spans of text. Spans doesn't require relayout.
+    pub fn set_spans(&mut self, spans: Vec<TextSpan>) {
+        self.spans = Arc::new(spans);
+        self.bake_invalidated = true;
+    }
+
+    pub fn truncate(&self) -> Option<Truncate> {
+        *self.truncate
+    }
+
+    /// Set truncation of lines overflowing max width. Text is not truncated if None.
+    pub fn set_truncate(&mut self, truncate: Option<Truncate>) {
+        self.truncate = truncate.into();
+    }
+
+    pub fn vertical_anchor(&self) -> VerticalAnchor {
+        *self.vertical_anchor
+    }
+
+    /// Set which vertical point of text block [Text::position] refers to
+    pub fn set_vertical_anchor(&mut self, vertical_anchor: VerticalAnchor) {
+        self.vertical_anchor = vertical_anchor.into();
+    }
+
+    /// Returns true if text was truncated on last layout
+    pub const fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Lines laid out on last layout. Lines before changed part of text are reused if only text changed.
+    pub const fn relaid_lines(&self) -> usize {
+        self.relaid_lines
+    }
+
+    /// Scale factor used on last rasterization
+    pub const fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
     pub const fn bounding_box(&self) -> Box2D<f32, LogicalPixelUnit> {
         self.bounding_box
     }
 
+    /// Returns rects covering glyphs in byte range, one rect per line.
+    /// Available after [Text::update].
+    pub fn selection_rects(&self, start: usize, end: usize) -> Vec<Rect<f32, LogicalPixelUnit>> {
+        let mut rects: Vec<Rect<f32, LogicalPixelUnit>> = Vec::new();
+
+        if start >= end {
+            return rects;
+        }
+
+        for bounds in self
+            .glyph_bounds
+            .iter()
+            .filter(|bounds| (start..end).contains(&(bounds.cluster as usize)))
+        {
+            match rects.last_mut() {
+                Some(last) if last.origin.y == bounds.rect.origin.y => {
+                    *last = last.union(&bounds.rect);
+                }
+
+                _ => rects.push(bounds.rect),
+            }
+        }
+
+        rects
+    }
+
+    /// Returns zero width rect of caret placed before glyph at byte offset, or after last glyph if offset is past it.
+    /// Available after [Text::update], returns None if there is no glyph.
+    pub fn caret_rect(&self, offset: usize) -> Option<Rect<f32, LogicalPixelUnit>> {
+        let next_index = self
+            .glyph_bounds
+            .iter()
+            .position(|bounds| bounds.cluster as usize >= offset)
+            .unwrap_or(self.glyph_bounds.len());
+
+        let next = self.glyph_bounds.get(next_index);
+        let prev = next_index
+            .checked_sub(1)
+            .and_then(|index| self.glyph_bounds.get(index));
+
+        let rect = match (prev, next) {
+            (_, Some(next)) if next.cluster as usize == offset => next.rect,
+            (Some(prev), _) => prev
+                .rect
+                .translate(Vector2D::new(prev.rect.size.width, 0.0)),
+            (None, Some(next)) => next.rect,
+            (None, None) => return None,
+        };
+
+        Some(Rect::new(rect.origin, Size2D::new(0.0, rect.size.height)))
+    }
+
+    /// Layout and rasterize text if text, font, truncation, anchor or scale factor changed since last update
     pub fn update(
         &mut self,
         device: &Device,
@@ -93,70 +267,239 @@ impl Text {
     ) {
         let font_invalidated = Observable::invalidate(&mut self.font);
         let text_invalidated = Observable::invalidate(&mut self.text);
+        let truncate_invalidated = Observable::invalidate(&mut self.truncate);
+        let anchor_invalidated = Observable::invalidate(&mut self.vertical_anchor);
+
+        // Rasterize again in new physical size, like when window moves to other monitor
+        let scale_changed = self.scale_factor != scale_factor;
+
+        if !(font_invalidated
+            || text_invalidated
+            || truncate_invalidated
+            || anchor_invalidated
+            || scale_changed)
+        {
+            return;
+        }
+
+        self.bake_invalidated = true;
+
+        let origin = {
+            let font = self.font.primary();
+            let scale = self.size_px as f32 / font.units_per_em() as f32;
+
+            let ascender = font.ascender() as f32 * scale;
+            let line_height = ascender - font.descender() as f32 * scale;
+
+            let mut lines = self.text.matches('\n').count() + 1;
+            if let Some(max_lines) = self.truncate.and_then(|truncate| truncate.max_lines) {
+                lines = lines.min(max_lines);
+            }
+
+            self.position
+                - Vector2D::new(
+                    0.0,
+                    self.vertical_anchor.offset(ascender, line_height, lines),
+                )
+        };
+
+        // Only text changed, so lines before changed part can be reused.
+        // Truncation may change every lines, always relayout whole text for it.
+        let incremental = !(font_invalidated || truncate_invalidated || scale_changed)
+            && self.truncate.is_none()
+            && self.layout_origin == origin;
+
+        let start = if incremental {
+            let unchanged = common_prefix_len(&self.layout_text, &self.text);
+
+            self.checkpoints
+                .iter()
+                .rev()
+                .find(|checkpoint| checkpoint.offset <= unchanged)
+                .copied()
+        } else {
+            None
+        }
+        .unwrap_or_else(|| LineCheckpoint::new(Box2D::new(origin, origin)));
+
+        self.scale_factor = scale_factor;
+        self.layout_origin = origin;
+        self.layout_text.clear();
+        self.layout_text.push_str(&self.text);
+
+        self.checkpoints
+            .retain(|checkpoint| checkpoint.offset <= start.offset);
+        self.glyph_bounds.truncate(start.glyph_bounds);
+        self.bounding_box = start.bounding_box;
+
+        let batches = Arc::make_mut(&mut self.batches);
+        batches.truncate(start.batches);
+
+        let scaled_size = (self.size_px as f32 * scale_factor).ceil() as u32;
+
+        let layout = TextLayout::with_primary(
+            &**self.font.primary(),
+            self.font.fallbacks().iter().map(|font| &**font),
+            &self.text,
+        );
+        let mut layout_iter =
+            layout.iter_from(8, self.size_px as f32, start.offset, start.position);
+
+        let ascender = layout_iter.ascender();
+        let line_height = ascender - layout_iter.descender();
+
+        // Line starts after line feed, with index of span starting there
+        let mut line_starts = Vec::new();
+
+        let mut lines: Vec<Vec<ShapedSpan>> = Vec::new();
+        let mut span_count = 0;
+        loop {
+            let offset = layout_iter.offset();
+            if offset > start.offset && self.text[..offset].ends_with('\n') {
+                line_starts.push((span_count, offset, layout_iter.current_position()));
+            }
+
+            let line_layout = match layout_iter.next() {
+                Some(line_layout) => line_layout,
+                None => break,
+            };
+
+            let span = ShapedSpan {
+                face_index: line_layout.face_index,
+                y: line_layout.current_position.y,
+                glyphs: line_layout.iter().collect(),
+                ellipsis: false,
+            };
+            span_count += 1;
 
-        if font_invalidated || text_invalidated {
-            self.bounding_box = Box2D::new(self.position, self.position);
-
-            let scaled_size = (self.size_px as f32 * scale_factor).ceil() as u32;
-
-            let layout = TextLayout::new(&self.font, &self.text);
-            let mut layout_iter = layout.iter(8, self.size_px as f32);
-
-            let mut batches = Vec::new();
-
-            let ascender = layout_iter.ascender();
-
-            while let Some(line_layout) = layout_iter.next() {
-                let mut span_iter = line_layout.iter();
-                let mut glyph_id_iter = line_layout.glyph_id_iter().peekable();
-
-                while glyph_id_iter.peek().is_some() {
-                    if let Some(view_batch) =
-                        cache.batch(device, queue, &self.font, &mut glyph_id_iter, scaled_size)
-                    {
-                        let texture = Arc::new(RenderTexture2D::init(
-                            device,
-                            view_batch.view,
-                            textures.bind_group_layout(),
-                            textures.nearest_sampler(),
-                        ));
-                        let mut rects = Vec::new();
-
-                        for (texture_rect, info) in view_batch.rects.iter().zip(&mut span_iter) {
-                            let position = self.position
-                                + info.position.cast_unit()
-                                + Vector2D::new(
-                                    0.0,
-                                    ascender
-                                        - texture_rect.tex_rect.size.height as f32 / scale_factor,
-                                )
-                                + (texture_rect.glyph_offset / scale_factor).cast_unit();
-
-                            let size =
-                                (texture_rect.tex_rect.size.cast() / scale_factor).cast_unit();
-
-                            rects.push(GlyphRect {
-                                rect: Rect::new(position, size),
-                                texture_rect: texture.view().to_texture_rect(texture_rect.tex_rect),
-                            });
-
-                            self.bounding_box = Box2D::from_points(&[
-                                self.bounding_box.min,
-                                self.bounding_box.max,
-                                position,
-                                position + size,
-                            ]);
-                        }
-
-                        batches.push(TextRenderBatch { texture, rects });
-                    } else {
-                        glyph_id_iter.next();
-                        span_iter.next();
+            match lines.last_mut() {
+                Some(line) if line[0].y == span.y => line.push(span),
+
+                _ => lines.push(vec![span]),
+            }
+        }
+        self.relaid_lines = lines.len();
+
+        self.truncated = match *self.truncate {
+            Some(truncate) => {
+                let ellipsis = Ellipsis::new(self.font.primary(), self.size_px as f32);
+
+                truncate.apply(&mut lines, ellipsis.as_ref())
+            }
+
+            None => false,
+        };
+
+        // Blend between mip levels of minified glyphs
+        let mipmap_sampler = cache.mipmaps().then(|| {
+            textures.sampler(
+                device,
+                SamplerKey {
+                    filter: FilterMode::Linear,
+                    address_mode_u: AddressMode::ClampToEdge,
+                    address_mode_v: AddressMode::ClampToEdge,
+                    anisotropy: 1,
+                },
+            )
+        });
+
+        let mut line_starts = line_starts.into_iter().peekable();
+        for (index, span) in lines.iter().flatten().enumerate() {
+            while let Some((_, offset, position)) =
+                line_starts.next_if(|(span_index, _, _)| *span_index <= index)
+            {
+                self.checkpoints.push(LineCheckpoint {
+                    offset,
+                    position,
+                    batches: batches.len(),
+                    glyph_bounds: self.glyph_bounds.len(),
+                    bounding_box: self.bounding_box,
+                });
+            }
+
+            let font = &self.font.fonts()[span.face_index];
+
+            if !span.ellipsis {
+                for info in &span.glyphs {
+                    self.glyph_bounds.push(GlyphBounds {
+                        cluster: info.cluster,
+                        rect: Rect::new(
+                            origin + Vector2D::new(info.position.x, span.y),
+                            Size2D::new(info.advance.x, line_height),
+                        ),
+                    });
+                }
+            }
+
+            let mut span_iter = span.glyphs.iter();
+            let mut glyph_id_iter = span.glyphs.iter().map(|info| info.glyph_id).peekable();
+
+            while glyph_id_iter.peek().is_some() {
+                if let Some(view_batch) = cache.batch(
+                    device,
+                    queue,
+                    textures,
+                    font,
+                    &mut glyph_id_iter,
+                    scaled_size,
+                ) {
+                    let texture = Arc::new(RenderTexture2D::init(
+                        device,
+                        view_batch.view,
+                        textures.bind_group_layout(),
+                        mipmap_sampler
+                            .as_deref()
+                            .unwrap_or_else(|| textures.nearest_sampler()),
+                    ));
+                    let mut rects = Vec::new();
+
+                    for (texture_rect, info) in view_batch.rects.iter().zip(&mut span_iter) {
+                        let position = origin
+                            + info.position.cast_unit()
+                            + Vector2D::new(
+                                0.0,
+                                ascender - texture_rect.tex_rect.size.height as f32 / scale_factor,
+                            )
+                            + (texture_rect.glyph_offset / scale_factor).cast_unit();
+
+                        let size = (texture_rect.tex_rect.size.cast() / scale_factor).cast_unit();
+
+                        rects.push(GlyphRect {
+                            rect: Rect::new(position, size),
+                            texture_rect: texture.view().to_texture_rect(texture_rect.tex_rect),
+                            cluster: info.cluster,
+                        });
+
+                        self.bounding_box = Box2D::from_points(&[
+                            self.bounding_box.min,
+                            self.bounding_box.max,
+                            position,
+                            position + size,
+                        ]);
                     }
+
+                    batches.push(TextRenderBatch {
+                        texture,
+                        rects,
+                        lcd: cache.lcd() && !view_batch.colored,
+                        colored: view_batch.colored,
+                    });
+                } else {
+                    glyph_id_iter.next();
+                    span_iter.next();
                 }
             }
+        }
 
-            self.batches = Arc::new(batches);
+        // Line starts without glyph after it, like trailing line feed
+        for (_, offset, position) in line_starts {
+            self.checkpoints.push(LineCheckpoint {
+                offset,
+                position,
+                batches: batches.len(),
+                glyph_bounds: self.glyph_bounds.len(),
+                bounding_box: self.bounding_box,
+            });
         }
     }
 
@@ -165,10 +508,199 @@ impl Text {
             batches: self.batches.clone(),
             transform: self.transform,
             color: color.clone(),
+            spans: self.spans.clone(),
+            clip: self.clip,
+        }
+    }
+
+    /// Render text into tightly sized texture, so static text can be drawn as single rectangle using [BakedText::rectangle].
+    /// Texture covers every rasterized glyph rect and is rendered again only if layout, spans, color or scale factor changed since last bake.
+    ///
+    /// Call [Text::update] before baking. Commands are recorded into given encoder, which must be submitted before baked text is drawn.
+    /// Returns None if text has no glyph.
+    pub fn bake(
+        &mut self,
+        scope: RenderScope,
+        color: &ShapeColor<4>,
+        encoder: &mut CommandEncoder,
+    ) -> Option<&BakedText> {
+        let valid = !self.bake_invalidated
+            && self.baked.as_ref().map_or(false, |baked| {
+                baked.scale_factor == self.scale_factor && baked.color == *color
+            });
+
+        if !valid {
+            self.baked = self.bake_texture(scope, color, encoder);
+            self.bake_invalidated = false;
+        }
+
+        self.baked.as_ref()
+    }
+
+    fn bake_texture(
+        &self,
+        scope: RenderScope,
+        color: &ShapeColor<4>,
+        encoder: &mut CommandEncoder,
+    ) -> Option<BakedText> {
+        if self.scale_factor <= 0.0 {
+            return None;
+        }
+
+        let glyph_bounds = self
+            .batches
+            .iter()
+            .flat_map(|batch| batch.rects.iter())
+            .map(|rect| rect.rect)
+            .reduce(|bounds, rect| bounds.union(&rect))?;
+
+        // Align texture to physical pixels, so glyphs keep their pixel alignment
+        let physical_bounds = (glyph_bounds * self.scale_factor).round_out();
+        let size: Size2D<u32, PhyiscalPixelUnit> = physical_bounds.size.try_cast()?.cast_unit();
+        if size.is_empty() {
+            return None;
         }
+
+        let bounds = physical_bounds / self.scale_factor;
+
+        let drawable = TextDrawable {
+            batches: self.batches.clone(),
+            transform: Transform3D::translation(-bounds.origin.x, -bounds.origin.y, 0.0),
+            color: color.clone(),
+            spans: self.spans.clone(),
+            clip: None,
+        };
+
+        let textures = scope.backend().get::<TextureData>();
+
+        let mut renderer = StoryboardTextureRenderer::init(
+            scope.backend().device(),
+            textures,
+            scope.pipeline().texture_format,
+            size,
+        );
+        renderer.renderer_mut().set_pooled_depth(true);
+
+        renderer.render(
+            scope,
+            ScreenRect::new(Rect::from_size(size), self.scale_factor),
+            textures,
+            iter::once(&drawable as &dyn Drawable),
+            encoder,
+        );
+
+        Some(BakedText {
+            texture: renderer.render_texture().clone(),
+            bounds,
+            scale_factor: self.scale_factor,
+            color: color.clone(),
+        })
     }
 }
 
+/// Text rendered into texture by [Text::bake]
+#[derive(Debug, Clone)]
+pub struct BakedText {
+    pub texture: Arc<RenderTexture2D>,
+
+    /// Logical bounds of texture covering every glyph
+    pub bounds: Rect<f32, LogicalPixelUnit>,
+
+    scale_factor: f32,
+    color: ShapeColor<4>,
+}
+
+impl BakedText {
+    /// Rectangle drawing baked texture at original position of text, like [Text::transform]
+    pub fn rectangle(
+        &self,
+        transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+    ) -> Rectangle {
+        Rectangle {
+            bounds: self.bounds,
+            color: ShapeColor::WHITE,
+            texture: Some(ComponentTexture::new(
+                self.texture.clone(),
+                TextureLayout::Relative(TextureLayoutStyle::Stretched),
+                (TextureWrap::Clamp, TextureWrap::Clamp),
+            )),
+            texture_coord: Some(Rect::new(Point2D::zero(), Size2D::new(1.0, 1.0)).into_coords()),
+            transform,
+            rotation: Angle::zero(),
+            rotation_anchor: Point2D::zero(),
+            clip: None,
+            force_opaque: false,
+            alpha_cutoff: None,
+        }
+    }
+}
+
+/// Vertical point of text block placed at [Text::position]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAnchor {
+    /// Top of first line
+    Top,
+    /// Baseline of first line
+    Baseline,
+    /// Middle of whole lines
+    Middle,
+    /// Bottom of last line
+    Bottom,
+}
+
+impl VerticalAnchor {
+    /// Distance from top of text block to anchor
+    pub fn offset(&self, ascender: f32, line_height: f32, lines: usize) -> f32 {
+        match self {
+            VerticalAnchor::Top => 0.0,
+            VerticalAnchor::Baseline => ascender,
+            VerticalAnchor::Middle => line_height * lines as f32 / 2.0,
+            VerticalAnchor::Bottom => line_height * lines as f32,
+        }
+    }
+}
+
+impl Default for VerticalAnchor {
+    fn default() -> Self {
+        Self::Top
+    }
+}
+
+/// Advance box of glyph in line
+#[derive(Debug, Clone, Copy)]
+struct GlyphBounds {
+    cluster: u32,
+    rect: Rect<f32, LogicalPixelUnit>,
+}
+
+/// Layout state at start of line. Layout can resume from it if preceding text is unchanged.
+#[derive(Debug, Clone, Copy)]
+struct LineCheckpoint {
+    /// Byte offset of line start
+    offset: usize,
+    position: Vector2D<f32, PhyiscalPixelUnit>,
+
+    batches: usize,
+    glyph_bounds: usize,
+    bounding_box: Box2D<f32, LogicalPixelUnit>,
+}
+
+impl LineCheckpoint {
+    fn new(bounding_box: Box2D<f32, LogicalPixelUnit>) -> Self {
+        Self {
+            offset: 0,
+            position: Vector2D::zero(),
+            batches: 0,
+            glyph_bounds: 0,
+            bounding_box,
+        }
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count()
+}
+
 impl Debug for Text {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Text")
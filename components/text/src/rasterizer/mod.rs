@@ -6,6 +6,8 @@ use storyboard_core::{
 };
 use ttf_parser::{Face, GlyphId};
 
+use crate::layout::TextLayout;
+
 use self::outline::GlyphOutlineBuilder;
 
 pub struct GlyphRasterizer<'a> {
@@ -44,6 +46,28 @@ impl<'a> GlyphRasterizer<'a> {
         Some(builder.get_glyph_data())
     }
 
+    /// Rasterize glyph with subpixel coverage for horizontal RGB stripe displays.
+    /// Returned data is RGBA with coverage of each subpixel in color channels.
+    pub fn rasterize_lcd_glyph(&self, index: u16, size_px: f32) -> Option<GlyphData> {
+        let bounding_box = {
+            let bounding_box = self.face.glyph_bounding_box(GlyphId(index))?;
+
+            Rect::new(
+                Point2D::new(bounding_box.x_min, bounding_box.y_min),
+                Size2D::new(bounding_box.width(), bounding_box.height()),
+            )
+            .cast()
+        };
+
+        let scale = size_px as f32 / self.face.units_per_em() as f32;
+        let mut builder =
+            GlyphOutlineBuilder::new_scaled(bounding_box, Vector2D::new(scale * 3.0, scale));
+
+        self.face.outline_glyph(GlyphId(index), &mut builder)?;
+
+        Some(builder.get_lcd_glyph_data())
+    }
+
     pub fn rasterize(&mut self, index: u16, size_px: f32) -> Option<RasterizedGlyph> {
         self.rasterize_glyph(index, size_px)
             .map(RasterizedGlyph::Glyph)
@@ -66,3 +90,86 @@ pub struct GlyphData {
     pub size: Size2D<u32, PhyiscalPixelUnit>,
     pub data: Vec<u8>,
 }
+
+/// Rasterize glyph into grayscale coverage bitmap on CPU.
+/// Returns empty bitmap for glyph without outline, like space.
+pub fn rasterize_glyph(
+    face: &Face,
+    glyph_id: u16,
+    size_px: f32,
+) -> (Size2D<u32, PhyiscalPixelUnit>, Vec<u8>) {
+    match GlyphRasterizer::new(face).rasterize_glyph(glyph_id, size_px) {
+        Some(glyph) => (glyph.size, glyph.data),
+        None => (Size2D::zero(), Vec::new()),
+    }
+}
+
+/// Rasterize every glyph of layout and composite them into single grayscale coverage bitmap on CPU.
+///
+/// Top left of bitmap is layout origin, glyphs are placed on whole pixels like [crate::Text].
+/// Parts of glyphs left of or above origin are cut off. Returns empty bitmap if text has no visible glyph.
+pub fn rasterize_layout(
+    layout: &TextLayout,
+    tab_size: u32,
+    size_px: f32,
+) -> (Size2D<u32, PhyiscalPixelUnit>, Vec<u8>) {
+    let mut layout_iter = layout.iter(tab_size, size_px);
+    let ascender = layout_iter.ascender();
+
+    let mut glyphs = Vec::new();
+    let mut size = Size2D::<u32, PhyiscalPixelUnit>::zero();
+
+    while let Some(span) = layout_iter.next() {
+        let rasterizer = GlyphRasterizer::new(layout.faces()[span.face_index]);
+
+        for info in span.iter() {
+            let glyph = match rasterizer.rasterize_glyph(info.glyph_id, size_px) {
+                Some(glyph) if !glyph.size.is_empty() => glyph,
+                _ => continue,
+            };
+
+            let position = (info.position
+                + Vector2D::new(0.0, ascender - glyph.size.height as f32)
+                + glyph.origin)
+                .round()
+                .cast::<i32>();
+
+            size = size.max(Size2D::new(
+                (position.x + glyph.size.width as i32).max(0) as u32,
+                (position.y + glyph.size.height as i32).max(0) as u32,
+            ));
+
+            glyphs.push((position, glyph));
+        }
+    }
+
+    if size.is_empty() {
+        return (Size2D::zero(), Vec::new());
+    }
+
+    let mut data = vec![0_u8; size.area() as usize];
+
+    for (position, glyph) in glyphs {
+        for y in 0..glyph.size.height as i32 {
+            let target_y = position.y + y;
+            if target_y < 0 {
+                continue;
+            }
+
+            for x in 0..glyph.size.width as i32 {
+                let target_x = position.x + x;
+                if target_x < 0 {
+                    continue;
+                }
+
+                let coverage = glyph.data[(y * glyph.size.width as i32 + x) as usize];
+                let pixel = &mut data[(target_y * size.width as i32 + target_x) as usize];
+
+                // Overlapping glyphs accumulate coverage
+                *pixel = pixel.saturating_add(coverage);
+            }
+        }
+    }
+
+    (size, data)
+}
@@ -11,14 +11,22 @@ use crate::{rasterizer::GlyphData, FontUnit};
 pub struct GlyphOutlineBuilder {
     bounds: Rect<f32, PhyiscalPixelUnit>,
     rasterizer: Rasterizer,
-    scale: f32,
+    scale: Vector2D<f32, PhyiscalPixelUnit>,
     point: Vector2D<f32, FontUnit>,
     last_move_point: Option<Vector2D<f32, FontUnit>>,
 }
 
 impl GlyphOutlineBuilder {
     pub fn new(bounds: Rect<f32, FontUnit>, scale: f32) -> Self {
-        let mut bounds = bounds.scale(scale, scale).cast_unit();
+        Self::new_scaled(bounds, Vector2D::new(scale, scale))
+    }
+
+    /// Create builder with different horizontal and vertical scale, like triple horizontal resolution for subpixel rendering
+    pub fn new_scaled(
+        bounds: Rect<f32, FontUnit>,
+        scale: Vector2D<f32, PhyiscalPixelUnit>,
+    ) -> Self {
+        let mut bounds = bounds.scale(scale.x, scale.y).cast_unit();
         bounds.size.width = bounds.size.width.ceil();
         bounds.size.height = bounds.size.height.ceil();
 
@@ -34,8 +42,8 @@ impl GlyphOutlineBuilder {
     #[inline]
     fn to_point(&self, vec: Vector2D<f32, FontUnit>) -> Point {
         Point {
-            x: vec.x * self.scale - self.bounds.origin.x,
-            y: self.bounds.size.height - vec.y * self.scale + self.bounds.origin.y,
+            x: vec.x * self.scale.x - self.bounds.origin.x,
+            y: self.bounds.size.height - vec.y * self.scale.y + self.bounds.origin.y,
         }
     }
 
@@ -59,6 +67,62 @@ impl GlyphOutlineBuilder {
             data,
         }
     }
+
+    /// Get subpixel glyph data from coverage rasterized in triple horizontal resolution.
+    /// Coverage is filtered to reduce color fringes and written as RGBA, alpha being average coverage.
+    pub fn get_lcd_glyph_data(&self) -> GlyphData {
+        // FreeType default LCD filter
+        const FILTER: [u32; 5] = [0x08, 0x4D, 0x56, 0x4D, 0x08];
+
+        let (width, height) = self.rasterizer.dimensions();
+
+        let mut coverage = vec![0_u8; width * height];
+        self.rasterizer
+            .for_each_pixel(|i, alpha| coverage[i] = (alpha * 255.0) as u8);
+
+        // Filter spreads coverage two subpixels to both sides
+        let subpixels = width + 4;
+        let pixel_width = (subpixels + 2) / 3;
+
+        let mut data = vec![0_u8; pixel_width * height * 4];
+        for y in 0..height {
+            let row = &coverage[y * width..(y + 1) * width];
+
+            for x in 0..pixel_width {
+                let mut pixel = [0_u8; 4];
+
+                for channel in 0..3 {
+                    let subpixel = (x * 3 + channel) as isize - 2;
+
+                    let filtered = FILTER
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(tap, weight)| {
+                            let index = subpixel + tap as isize - 2;
+
+                            (0..width as isize)
+                                .contains(&index)
+                                .then(|| row[index as usize] as u32 * weight)
+                        })
+                        .sum::<u32>();
+
+                    pixel[channel] = (filtered / 0x100).min(255) as u8;
+                }
+                pixel[3] = ((pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32) / 3) as u8;
+
+                let offset = (y * pixel_width + x) * 4;
+                data[offset..offset + 4].copy_from_slice(&pixel);
+            }
+        }
+
+        let origin = Vector2D::new((self.bounds.origin.x - 2.0) / 3.0, -self.bounds.origin.y);
+
+        GlyphData {
+            origin,
+            size: Size2D::new(pixel_width as u32, height as u32),
+            data,
+        }
+    }
 }
 
 impl OutlineBuilder for GlyphOutlineBuilder {
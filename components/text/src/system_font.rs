@@ -0,0 +1,111 @@
+//! Font loading from fonts installed on system
+
+use std::{borrow::Cow, error::Error, fmt::Display};
+
+use fontdb::{Database, Family, Query, Stretch};
+use ttf_parser::FaceParsingError;
+
+pub use fontdb::{Style, Weight};
+
+use crate::font::Font;
+
+/// Fonts installed on system.
+/// Scanning system fonts is slow, so keep it to load multiple fonts.
+#[derive(Debug)]
+pub struct SystemFonts {
+    db: Database,
+}
+
+impl SystemFonts {
+    /// Scan fonts installed on system
+    pub fn load() -> Self {
+        let mut db = Database::new();
+        db.load_system_fonts();
+
+        Self { db }
+    }
+
+    pub fn len(&self) -> usize {
+        self.db.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
+    /// Load font of family name closest to given weight and style
+    pub fn query(
+        &self,
+        family: &str,
+        weight: Weight,
+        style: Style,
+    ) -> Result<Font, SystemFontError> {
+        let id = self
+            .db
+            .query(&Query {
+                families: &[Family::Name(family)],
+                weight,
+                stretch: Stretch::Normal,
+                style,
+            })
+            .ok_or_else(|| SystemFontError::NotFound(family.to_string()))?;
+
+        let (data, index) = self
+            .db
+            .with_face_data(id, |data, index| (data.to_vec(), index))
+            .ok_or_else(|| SystemFontError::NotFound(family.to_string()))?;
+
+        Ok(Font::new(Cow::Owned(data), index)?)
+    }
+}
+
+impl Font {
+    /// Load regular font of family name installed on system
+    pub fn from_system(family: &str) -> Result<Self, SystemFontError> {
+        Self::from_system_with(family, Weight::NORMAL, Style::Normal)
+    }
+
+    /// Load font of family name installed on system closest to given weight and style
+    pub fn from_system_with(
+        family: &str,
+        weight: Weight,
+        style: Style,
+    ) -> Result<Self, SystemFontError> {
+        SystemFonts::load().query(family, weight, style)
+    }
+}
+
+#[derive(Debug)]
+pub enum SystemFontError {
+    /// No installed font has the family name
+    NotFound(String),
+
+    /// Font file is found but cannot be parsed
+    Parse(FaceParsingError),
+}
+
+impl Display for SystemFontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SystemFontError::NotFound(family) => write!(f, "Font family {} not found", family),
+            SystemFontError::Parse(err) => write!(f, "Font parsing failed: {}", err),
+        }
+    }
+}
+
+impl Error for SystemFontError {}
+
+impl From<FaceParsingError> for SystemFontError {
+    fn from(err: FaceParsingError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn missing_family_test() {
+    assert!(matches!(
+        Font::from_system("Storyboard Missing Family"),
+        Err(SystemFontError::NotFound(_))
+    ));
+}
@@ -0,0 +1,185 @@
+use storyboard_core::euclid::Vector2D;
+
+use crate::{font::Font, layout::GlyphInfo};
+
+/// Truncation of lines overflowing max width.
+/// Overflowing lines are cut and ended with ellipsis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Truncate {
+    /// Maximum width of line in logical pixel
+    pub max_width: f32,
+
+    /// Maximum number of lines. Each line is truncated independently if None.
+    pub max_lines: Option<usize>,
+}
+
+impl Truncate {
+    pub const fn new(max_width: f32, max_lines: Option<usize>) -> Self {
+        Self {
+            max_width,
+            max_lines,
+        }
+    }
+
+    /// Truncate text into single line
+    pub const fn single_line(max_width: f32) -> Self {
+        Self::new(max_width, Some(1))
+    }
+
+    /// Truncate every line independently
+    pub const fn per_line(max_width: f32) -> Self {
+        Self::new(max_width, None)
+    }
+
+    /// Truncate lines in place, returns true if any glyph or line was removed
+    pub(crate) fn apply(
+        &self,
+        lines: &mut Vec<Vec<ShapedSpan>>,
+        ellipsis: Option<&Ellipsis>,
+    ) -> bool {
+        let lines_dropped = match self.max_lines {
+            Some(max_lines) if lines.len() > max_lines => {
+                lines.truncate(max_lines);
+                true
+            }
+
+            _ => false,
+        };
+
+        let last_line = lines.len().checked_sub(1);
+
+        let mut truncated = lines_dropped;
+        for (index, line) in lines.iter_mut().enumerate() {
+            let forced = lines_dropped && Some(index) == last_line;
+
+            truncated |= self.truncate_line(line, ellipsis, forced);
+        }
+
+        truncated
+    }
+
+    fn truncate_line(
+        &self,
+        line: &mut Vec<ShapedSpan>,
+        ellipsis: Option<&Ellipsis>,
+        forced: bool,
+    ) -> bool {
+        if !forced && line_width(line) <= self.max_width {
+            return false;
+        }
+
+        let y = match line.first() {
+            Some(span) => span.y,
+            None => return forced,
+        };
+
+        // Clip without ellipsis if even ellipsis doesn't fit
+        let ellipsis = ellipsis.filter(|ellipsis| ellipsis.width <= self.max_width);
+        let limit = self.max_width - ellipsis.map_or(0.0, |ellipsis| ellipsis.width);
+
+        let mut cut_cluster = None;
+        for span in line.iter_mut() {
+            if let Some(index) = span
+                .glyphs
+                .iter()
+                .position(|info| info.position.x + info.advance.x > limit)
+            {
+                cut_cluster = cut_cluster.or(Some(span.glyphs[index].cluster));
+                span.glyphs.truncate(index);
+            }
+        }
+        line.retain(|span| !span.glyphs.is_empty());
+
+        if let Some(ellipsis) = ellipsis {
+            let cluster = cut_cluster
+                .or_else(|| {
+                    line.iter()
+                        .flat_map(|span| &span.glyphs)
+                        .last()
+                        .map(|info| info.cluster)
+                })
+                .unwrap_or(0);
+
+            line.push(ellipsis.span(line_width(line), y, cluster));
+        }
+
+        true
+    }
+}
+
+/// Glyphs of one layout span
+#[derive(Debug)]
+pub(crate) struct ShapedSpan {
+    pub face_index: usize,
+    pub y: f32,
+    pub glyphs: Vec<GlyphInfo>,
+
+    /// Span is ellipsis inserted by truncation
+    pub ellipsis: bool,
+}
+
+fn line_width(line: &[ShapedSpan]) -> f32 {
+    line.iter()
+        .flat_map(|span| &span.glyphs)
+        .map(|info| info.position.x + info.advance.x)
+        .fold(0.0, f32::max)
+}
+
+/// Ellipsis glyphs of primary font
+#[derive(Debug)]
+pub(crate) struct Ellipsis {
+    glyphs: Vec<(u16, f32)>,
+    width: f32,
+}
+
+impl Ellipsis {
+    /// Use '…' glyph or three '.' glyphs if font doesn't have it
+    pub fn new(font: &Font, size_px: f32) -> Option<Self> {
+        let scale = size_px / font.units_per_em() as f32;
+
+        let glyph = |c: char| {
+            let id = font.glyph_index(c)?;
+
+            Some((id.0, font.glyph_hor_advance(id)? as f32 * scale))
+        };
+
+        let glyphs = match glyph('…') {
+            Some(glyph) => vec![glyph],
+            None => vec![glyph('.')?; 3],
+        };
+
+        let width = glyphs.iter().map(|(_, advance)| advance).sum();
+
+        Some(Self { glyphs, width })
+    }
+
+    fn span(&self, x: f32, y: f32, cluster: u32) -> ShapedSpan {
+        let mut position = Vector2D::new(x, y);
+
+        let glyphs = self
+            .glyphs
+            .iter()
+            .map(|&(glyph_id, advance)| {
+                let advance = Vector2D::new(advance, 0.0);
+
+                let info = GlyphInfo {
+                    glyph_id,
+                    cluster,
+                    position,
+                    advance,
+                };
+
+                position += advance;
+
+                info
+            })
+            .collect();
+
+        ShapedSpan {
+            face_index: 0,
+            y,
+            glyphs,
+            ellipsis: true,
+        }
+    }
+}
@@ -1,8 +1,21 @@
-use std::{error::Error, borrow::Cow};
+use std::{borrow::Cow, error::Error, num::NonZeroU32};
 
 use rustybuzz::{Face, UnicodeBuffer};
-use storyboard_render::{backend::{BackendOptions, StoryboardBackend}, wgpu::{Backends, Instance}};
-use storyboard_text::{cache::GlyphCache, font::Font};
+use storyboard_render::{
+    backend::{BackendOptions, StoryboardBackend},
+    wgpu::{
+        Backends, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d,
+        ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Instance, Maintain, MapMode, Origin3d,
+        TextureAspect, TextureFormat,
+    },
+};
+use storyboard_core::euclid::{Rect, Size2D, Vector2D};
+use storyboard_text::{
+    cache::{GlyphAtlasMap, GlyphCache, GlyphFormatError, GlyphKey},
+    font::Font,
+    rasterizer::GlyphData,
+};
+use storyboard_texture::render::data::TextureData;
 
 pub static FONT: &[u8] = include_bytes!("./NotoSansCJKkr-Regular.otf");
 
@@ -24,12 +37,269 @@ fn layout_test() -> Result<(), Box<dyn Error>> {
 
     let buffer = rustybuzz::shape(&Face::from_slice(FONT, 0).unwrap(), &[], buffer);
     
+    let textures = TextureData::init(backend.device());
     let mut cache = GlyphCache::new();
 
     let mut indices_iter = buffer.glyph_infos().iter().map(|info| info.glyph_id as u16).peekable();
-    while let Some(batch) = cache.batch(backend.device(), backend.queue(), &font, &mut indices_iter, 16) {
+    while let Some(batch) = cache.batch(backend.device(), backend.queue(), &textures, &font, &mut indices_iter, 16) {
         println!("batch: {:?}\n", batch);
+
+        // Outline glyphs go to grayscale pages
+        assert!(!batch.colored);
+    }
+
+    // Grayscale R8 page is tracked
+    assert!(textures.memory_usage() >= 1024 * 1024);
+
+    drop(cache);
+    assert_eq!(textures.memory_usage(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn page_format_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None
+    ))
+    .unwrap();
+
+    let font = Font::new(Cow::Borrowed(FONT), 0)?;
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str("Hello world");
+
+    let buffer = rustybuzz::shape(&Face::from_slice(FONT, 0).unwrap(), &[], buffer);
+
+    let textures = TextureData::init(backend.device());
+    let mut cache = GlyphCache::new_with(2048, TextureFormat::Rgba8Unorm)?;
+
+    let mut indices_iter = buffer.glyph_infos().iter().map(|info| info.glyph_id as u16).peekable();
+    while let Some(batch) = cache.batch(backend.device(), backend.queue(), &textures, &font, &mut indices_iter, 16) {
+        assert_eq!(batch.view.inner().size().width, 2048);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn repack_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut atlas = GlyphAtlasMap::init(backend.device(), &textures, Size2D::new(64, 64), TextureFormat::R8Unorm);
+
+    let sizes = [(10, 4), (6, 20), (16, 8), (4, 4), (12, 16), (8, 10)];
+    for (index, (width, height)) in sizes.iter().enumerate() {
+        let glyph = GlyphData {
+            origin: Vector2D::zero(),
+            size: Size2D::new(*width, *height),
+            data: vec![255; (width * height) as usize],
+        };
+
+        atlas.pack(backend.queue(), GlyphKey::new(0, index as u16, 16), &glyph).unwrap();
+    }
+
+    let used_area = atlas.used_area();
+    assert!(atlas.repack(backend.device(), backend.queue()));
+    assert_eq!(atlas.used_area(), used_area);
+
+    // Repacked texture has same size, tracked memory doesn't change
+    assert_eq!(textures.memory_usage(), 64 * 64);
+
+    let rects = (0..sizes.len())
+        .map(|index| atlas.get_rect(&GlyphKey::new(0, index as u16, 16)).unwrap().tex_rect)
+        .collect::<Vec<_>>();
+
+    for (i, rect) in rects.iter().enumerate() {
+        assert_eq!((rect.size.width, rect.size.height), sizes[i]);
+        assert!(rect.max_x() <= 64 && rect.max_y() <= 64);
+
+        for other in &rects[i + 1..] {
+            assert!(!rect.intersects(other));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn mipmap_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut atlas = GlyphAtlasMap::init_with_mips(backend.device(), &textures, Size2D::new(64, 64), TextureFormat::R8Unorm, 3);
+    assert_eq!(atlas.mip_levels(), 3);
+    assert_eq!(atlas.allocation().bytes(), 64 * 64 + 32 * 32 + 16 * 16);
+
+    for index in 0..4 {
+        let glyph = GlyphData {
+            origin: Vector2D::zero(),
+            size: Size2D::new(8, 8),
+            data: vec![255; 64],
+        };
+
+        atlas.pack(backend.queue(), GlyphKey::new(0, index, 16), &glyph).unwrap();
+    }
+
+    let rects = (0..4)
+        .map(|index| atlas.get_rect(&GlyphKey::new(0, index, 16)).unwrap().tex_rect)
+        .collect::<Vec<_>>();
+
+    // Glyphs are padded so smallest level doesn't mix neighbors
+    for (i, rect) in rects.iter().enumerate() {
+        for other in &rects[i + 1..] {
+            let padded = Rect::new(rect.origin, rect.size + Size2D::new(4, 4));
+            assert!(!padded.intersects(other));
+        }
+    }
+
+    // Fully covered glyph stays fully covered in lower levels
+    let rect = rects[0];
+    let level = atlas.mip_data(2).unwrap();
+    assert_eq!(level[(rect.origin.y as usize / 4) * 16 + rect.origin.x as usize / 4], 255);
+
+    Ok(())
+}
+
+#[test]
+fn r8_write_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None
+    ))
+    .unwrap();
+
+    // Width of 256 single channel pixels keeps rows aligned for buffer copy
+    let textures = TextureData::init(backend.device());
+    let mut atlas = GlyphAtlasMap::init(backend.device(), &textures, Size2D::new(256, 8), TextureFormat::R8Unorm);
+
+    // Odd glyph width, rows must not be padded to 4 channels
+    let glyph = GlyphData {
+        origin: Vector2D::zero(),
+        size: Size2D::new(7, 5),
+        data: (1..=35).collect(),
+    };
+    let rect = atlas.pack(backend.queue(), GlyphKey::new(0, 0, 16), &glyph).unwrap().tex_rect;
+
+    let data = read_atlas(&backend, &atlas, 256);
+    for y in 0..rect.size.height as usize {
+        let start = (rect.origin.y as usize + y) * 256 + rect.origin.x as usize;
+
+        assert_eq!(
+            &data[start..start + rect.size.width as usize],
+            &glyph.data[y * 7..(y + 1) * 7]
+        );
     }
 
     Ok(())
 }
+
+#[test]
+fn unsupported_format_test() {
+    assert!(GlyphCache::new_with(1024, TextureFormat::R8Unorm).is_ok());
+    assert!(GlyphCache::new_with(1024, TextureFormat::Bgra8UnormSrgb).is_ok());
+
+    for format in [TextureFormat::R16Float, TextureFormat::Rgba32Float, TextureFormat::R8Snorm] {
+        assert_eq!(GlyphCache::new_with(1024, format).unwrap_err(), GlyphFormatError(format));
+    }
+}
+
+#[test]
+fn srgb_write_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None
+    ))
+    .unwrap();
+
+    // Width of 64 four channel pixels keeps rows aligned for buffer copy
+    let textures = TextureData::init(backend.device());
+    let mut atlas = GlyphAtlasMap::init(backend.device(), &textures, Size2D::new(64, 4), TextureFormat::Rgba8UnormSrgb);
+
+    let glyph = GlyphData {
+        origin: Vector2D::zero(),
+        size: Size2D::new(3, 1),
+        data: vec![0, 128, 255],
+    };
+    let rect = atlas.pack(backend.queue(), GlyphKey::new(0, 0, 16), &glyph).unwrap().tex_rect;
+
+    let data = read_atlas(&backend, &atlas, 64 * 4);
+    let start = (rect.origin.y as usize * 64 + rect.origin.x as usize) * 4;
+
+    // Coverage is encoded, so sampler decodes it back into linear coverage. 0.5 encodes to about 0.735.
+    assert_eq!(&data[start..start + 4], &[0; 4]);
+    assert_eq!(&data[start + 4..start + 8], &[188; 4]);
+    assert_eq!(&data[start + 8..start + 12], &[255; 4]);
+
+    Ok(())
+}
+
+/// Read back first mip level of atlas having rows of given bytes
+fn read_atlas(backend: &StoryboardBackend, atlas: &GlyphAtlasMap, bytes_per_row: u32) -> Vec<u8> {
+    let size = atlas.texture().size();
+
+    let readback = backend.device().create_buffer(&BufferDescriptor {
+        label: None,
+        size: (bytes_per_row * size.height) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = backend.device().create_command_encoder(&CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+            texture: atlas.texture().inner(),
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        ImageCopyBuffer {
+            buffer: &readback,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+    );
+    backend.queue().submit(Some(encoder.finish()));
+
+    let slice = readback.slice(..);
+    slice.map_async(MapMode::Read, |result| result.unwrap());
+    backend.poll(Maintain::Wait);
+
+    let data = slice.get_mapped_range().to_vec();
+    readback.unmap();
+
+    data
+}
@@ -1,9 +1,10 @@
 use std::error::Error;
 
 use rustybuzz::{Face, UnicodeBuffer};
-use storyboard_text::layout::SpanLayout;
+use storyboard_text::layout::{SpanLayout, TextLayout};
 
-pub static FONT: &[u8] = include_bytes!("./NotoSansCjkKr-Regular.otf");
+pub static FONT: &[u8] = include_bytes!("./NotoSansCJKkr-Regular.otf");
+pub static LATIN_FONT: &[u8] = include_bytes!("./DejaVuSansMono.ttf");
 
 #[test]
 fn layout_test() -> Result<(), Box<dyn Error>> {
@@ -20,3 +21,42 @@ fn layout_test() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn layout_cluster_test() {
+    let face = Face::from_slice(FONT, 0).unwrap();
+
+    let layout = TextLayout::new(&face, "ab\ncd");
+    let mut layout_iter = layout.iter(8, 16.0);
+
+    let mut clusters = Vec::new();
+    while let Some(span) = layout_iter.next() {
+        clusters.extend(span.iter().map(|info| info.cluster));
+    }
+
+    assert_eq!(clusters, vec![0, 1, 3, 4]);
+}
+
+#[test]
+fn layout_fallback_test() {
+    let latin_face = Face::from_slice(LATIN_FONT, 0).unwrap();
+    let face = Face::from_slice(FONT, 0).unwrap();
+
+    // Primary face doesn't contain hangul glyphs
+    let layout = TextLayout::with_fallback(vec![&*latin_face, &*face], "ab한글cd").unwrap();
+    let mut layout_iter = layout.iter(8, 16.0);
+
+    let mut spans = Vec::new();
+    while let Some(span) = layout_iter.next() {
+        spans.push((span.face_index, span.text_offset));
+
+        assert!(span.glyph_id_iter().all(|id| id != 0));
+    }
+
+    assert_eq!(spans, vec![(0, 0), (1, 2), (0, 8)]);
+}
+
+#[test]
+fn layout_empty_fallback_test() {
+    assert!(TextLayout::with_fallback(Vec::new(), "ab").is_none());
+}
@@ -1,6 +1,9 @@
 use std::error::Error;
 
-use storyboard_text::rasterizer::GlyphRasterizer;
+use storyboard_text::{
+    layout::TextLayout,
+    rasterizer::{rasterize_glyph, rasterize_layout, GlyphRasterizer},
+};
 use ttf_parser::Face;
 
 pub static FONT: &[u8] = include_bytes!("./NotoSansCJKkr-Regular.otf");
@@ -17,4 +20,33 @@ fn rasterizer_test() -> Result<(), Box<dyn Error>> {
     println!("{rasterized:?}");
 
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn cpu_rasterize_test() -> Result<(), Box<dyn Error>> {
+    let face = Face::from_slice(FONT, 0)?;
+
+    let (glyph_size, data) = rasterize_glyph(&face, face.glyph_index('a').unwrap().0, 16.0);
+    assert!(!glyph_size.is_empty());
+    assert_eq!(data.len(), glyph_size.area() as usize);
+    assert!(data.iter().any(|&coverage| coverage > 0));
+
+    // Space has no outline
+    let (size, data) = rasterize_glyph(&face, face.glyph_index(' ').unwrap().0, 16.0);
+    assert!(size.is_empty());
+    assert!(data.is_empty());
+
+    let layout = TextLayout::new(&face, "aa a");
+    let (size, data) = rasterize_layout(&layout, 8, 16.0);
+    assert_eq!(data.len(), size.area() as usize);
+    assert!(size.width > glyph_size.width * 3 && size.height >= glyph_size.height);
+    assert!(data.iter().any(|&coverage| coverage > 0));
+
+    // Deterministic without device
+    assert_eq!(rasterize_layout(&layout, 8, 16.0), (size, data));
+
+    let (size, data) = rasterize_layout(&TextLayout::new(&face, "  \n "), 8, 16.0);
+    assert!(size.is_empty());
+    assert!(data.is_empty());
+
+    Ok(())
+}
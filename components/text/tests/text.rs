@@ -0,0 +1,490 @@
+use std::{borrow::Cow, error::Error};
+
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Point2D, Size2D, Transform3D},
+};
+use storyboard_render::{
+    backend::{BackendOptions, StoryboardBackend},
+    component::Drawable,
+    renderer::StoryboardRenderer,
+    shared::{BackendScopeContext, BackendShared, RenderShared},
+    texture::SizedTexture2D,
+    wgpu::{Backends, Color, CommandEncoderDescriptor, Instance, TextureFormat, TextureUsages},
+};
+use storyboard_text::{
+    cache::{GlyphCache, GlyphKey},
+    font::Font,
+    rasterizer::GlyphRasterizer,
+    truncate::Truncate,
+    Text, VerticalAnchor,
+};
+use storyboard_texture::render::data::TextureData;
+
+pub static FONT: &[u8] = include_bytes!("./NotoSansCJKkr-Regular.otf");
+pub static MONO_FONT: &[u8] = include_bytes!("./DejaVuSansMono.ttf");
+/// Single stem glyph mapped to 'l', widened along wght axis from 100 to 900
+pub static VARIABLE_FONT: &[u8] = include_bytes!("./VariableStem.ttf");
+
+#[test]
+fn scale_factor_reraster_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut cache = GlyphCache::new();
+
+    let mut text = Text::new(
+        Point2D::zero(),
+        16,
+        Transform3D::identity(),
+        Font::new(Cow::Borrowed(FONT), 0)?,
+        Cow::Borrowed("A"),
+    );
+
+    text.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+    let first = text.draw(&ShapeColor::WHITE).batches[0].rects[0].texture_rect;
+
+    text.update(
+        backend.device(),
+        backend.queue(),
+        2.0,
+        &textures,
+        &mut cache,
+    );
+    assert_eq!(text.scale_factor(), 2.0);
+
+    let second = text.draw(&ShapeColor::WHITE).batches[0].rects[0].texture_rect;
+    assert!(second.size.width > first.size.width);
+    assert!(second.size.height > first.size.height);
+
+    Ok(())
+}
+
+#[test]
+fn truncate_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut cache = GlyphCache::new();
+
+    let mut text = Text::new(
+        Point2D::zero(),
+        16,
+        Transform3D::identity(),
+        Font::new(Cow::Borrowed(FONT), 0)?,
+        Cow::Borrowed("Some long label"),
+    );
+
+    text.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+    assert!(!text.was_truncated());
+    let full_width = text.bounding_box().width();
+
+    text.set_truncate(Some(Truncate::single_line(60.0)));
+    text.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+    assert!(text.was_truncated());
+    // Glyph bitmap may overhang advance slightly
+    assert!(text.bounding_box().width() <= 61.0);
+    assert!(text.bounding_box().width() < full_width);
+
+    Ok(())
+}
+
+#[test]
+fn incremental_update_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut cache = GlyphCache::new();
+
+    let log = (0..99)
+        .map(|line| format!("[{}] log line\n", line))
+        .collect::<String>();
+
+    let mut text = Text::new(
+        Point2D::zero(),
+        16,
+        Transform3D::identity(),
+        Font::new(Cow::Borrowed(FONT), 0)?,
+        Cow::Owned(format!("{}frame 0", log)),
+    );
+    text.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+
+    // Every line is laid out first time
+    assert_eq!(text.relaid_lines(), 100);
+
+    for frame in 1..=60 {
+        text.set_text(Cow::Owned(format!("{}frame {}", log, frame)));
+        text.update(
+            backend.device(),
+            backend.queue(),
+            1.0,
+            &textures,
+            &mut cache,
+        );
+
+        // Only changed last line is laid out again
+        assert_eq!(text.relaid_lines(), 1);
+    }
+
+    let mut full = Text::new(
+        Point2D::zero(),
+        16,
+        Transform3D::identity(),
+        Font::new(Cow::Borrowed(FONT), 0)?,
+        Cow::Borrowed(""),
+    );
+
+    for frame in 1..=60 {
+        // Changing font forces full relayout
+        full.set_font(text.font().clone());
+        full.set_text(Cow::Owned(format!("{}frame {}", log, frame)));
+        full.update(
+            backend.device(),
+            backend.queue(),
+            1.0,
+            &textures,
+            &mut cache,
+        );
+
+        assert_eq!(full.relaid_lines(), 100);
+    }
+
+    // Changing earlier line relays out lines after it
+    text.set_text(Cow::Owned(format!(
+        "{}frame 60",
+        log.replacen("[97]", "[X]", 1)
+    )));
+    text.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+    assert_eq!(text.relaid_lines(), 3);
+
+    full.set_font(text.font().clone());
+    full.set_text(Cow::Owned(format!(
+        "{}frame 60",
+        log.replacen("[97]", "[X]", 1)
+    )));
+    full.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+
+    let rects = |text: &mut Text| {
+        text.draw(&ShapeColor::WHITE)
+            .batches
+            .iter()
+            .flat_map(|batch| batch.rects.iter().map(|rect| (rect.rect, rect.cluster)))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(rects(&mut text), rects(&mut full));
+    assert_eq!(text.bounding_box(), full.bounding_box());
+
+    Ok(())
+}
+
+#[test]
+fn baseline_anchor_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut cache = GlyphCache::new();
+
+    let baseline = Point2D::new(0.0, 100.0);
+
+    // Bottom of glyph sitting on baseline
+    let mut glyph_bottom = |font: &'static [u8]| -> Result<f32, Box<dyn Error>> {
+        let mut text = Text::new(
+            baseline,
+            32,
+            Transform3D::identity(),
+            Font::new(Cow::Borrowed(font), 0)?,
+            Cow::Borrowed("H"),
+        );
+        text.set_vertical_anchor(VerticalAnchor::Baseline);
+
+        text.update(
+            backend.device(),
+            backend.queue(),
+            1.0,
+            &textures,
+            &mut cache,
+        );
+
+        Ok(text.bounding_box().max.y)
+    };
+
+    let noto = glyph_bottom(FONT)?;
+    let mono = glyph_bottom(MONO_FONT)?;
+
+    // Allow rasterizer rounding
+    assert!((noto - baseline.y).abs() <= 1.0);
+    assert!((mono - baseline.y).abs() <= 1.0);
+
+    Ok(())
+}
+
+#[test]
+fn static_font_variation_test() -> Result<(), Box<dyn Error>> {
+    let mut font = Font::new(Cow::Borrowed(MONO_FONT), 0)?;
+    let hash = Font::font_hash(&font);
+
+    assert!(font.variation_axes().is_empty());
+    assert!(font.set_variation(*b"wght", 700.0).is_none());
+
+    // Glyph cache stays valid
+    assert_eq!(Font::font_hash(&font), hash);
+
+    Ok(())
+}
+
+#[test]
+fn font_variation_test() -> Result<(), Box<dyn Error>> {
+    let mut font = Font::new(Cow::Borrowed(VARIABLE_FONT), 0)?;
+    let index = font.glyph_index('l').unwrap().0;
+
+    let axes = font.variation_axes();
+    assert_eq!(axes.len(), 1);
+    assert_eq!(axes[0].tag.to_bytes(), *b"wght");
+
+    font.set_variation(*b"wght", 300.0).unwrap();
+    let light_key = GlyphKey::new(Font::font_hash(&font), index, 64);
+    let light = GlyphRasterizer::new(&font)
+        .rasterize_glyph(index, 64.0)
+        .unwrap();
+
+    font.set_variation(*b"wght", 700.0).unwrap();
+    let bold_key = GlyphKey::new(Font::font_hash(&font), index, 64);
+    let bold = GlyphRasterizer::new(&font)
+        .rasterize_glyph(index, 64.0)
+        .unwrap();
+
+    // Bold instance has wider stem and is cached separately
+    assert!(bold.size.width > light.size.width);
+    assert_eq!(bold.size.height, light.size.height);
+    assert_ne!(bold_key, light_key);
+
+    // Same variation gives same key back
+    font.set_variation(*b"wght", 300.0).unwrap();
+    assert_eq!(GlyphKey::new(Font::font_hash(&font), index, 64), light_key);
+
+    Ok(())
+}
+
+#[test]
+fn lcd_rasterize_test() -> Result<(), Box<dyn Error>> {
+    let font = Font::new(Cow::Borrowed(MONO_FONT), 0)?;
+    let index = font.glyph_index('H').unwrap().0;
+
+    let rasterizer = GlyphRasterizer::new(&font);
+    let gray = rasterizer.rasterize_glyph(index, 16.0).unwrap();
+    let lcd = rasterizer.rasterize_lcd_glyph(index, 16.0).unwrap();
+
+    assert_eq!(lcd.data.len(), lcd.size.area() as usize * 4);
+    assert_eq!(lcd.size.height, gray.size.height);
+    // Filter padding adds at most two pixels
+    assert!(lcd.size.width >= gray.size.width && lcd.size.width <= gray.size.width + 2);
+
+    // Vertical stem edges have different coverage per subpixel
+    assert!(lcd
+        .data
+        .chunks(4)
+        .any(|pixel| pixel[0] != pixel[1] || pixel[1] != pixel[2]));
+
+    Ok(())
+}
+
+#[test]
+fn label_batch_draw_call_test() -> Result<(), Box<dyn Error>> {
+    const LABELS: usize = 50;
+    const SIZE: u32 = 256;
+    const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let backend_shared = BackendShared::new();
+    let render_shared = RenderShared::new(StoryboardRenderer::create_renderer_pipeline_data(
+        FORMAT, None, None,
+    ));
+    let scope = backend_shared
+        .scope(BackendScopeContext {
+            device: backend.device(),
+            queue: backend.queue(),
+        })
+        .render_scope(&render_shared);
+    let textures = scope.backend().get::<TextureData>();
+
+    let font = Font::new(Cow::Borrowed(FONT), 0)?;
+    let mut cache = GlyphCache::new();
+
+    // Labels of same font and size share one atlas page
+    let mut drawables = Vec::new();
+    for i in 0..LABELS {
+        let mut text = Text::new(
+            Point2D::new(0.0, i as f32 * 5.0),
+            16,
+            Transform3D::identity(),
+            font.clone(),
+            Cow::Owned(format!("label {}", i)),
+        );
+        text.update(backend.device(), backend.queue(), 1.0, textures, &mut cache);
+
+        drawables.push(text.draw(&ShapeColor::WHITE));
+    }
+
+    let target = SizedTexture2D::init(
+        backend.device(),
+        None,
+        Size2D::new(SIZE, SIZE),
+        FORMAT,
+        TextureUsages::RENDER_ATTACHMENT,
+    );
+    let view = target.create_view_default(None);
+
+    let mut renderer = StoryboardRenderer::new();
+    let mut encoder = backend
+        .device()
+        .create_command_encoder(&CommandEncoderDescriptor { label: None });
+    renderer.render_to_view(
+        scope,
+        view.inner(),
+        FORMAT,
+        Size2D::new(SIZE, SIZE),
+        1.0,
+        Some(Color::TRANSPARENT),
+        drawables.iter().map(|drawable| drawable as &dyn Drawable),
+        &mut encoder,
+    )?;
+
+    let mut commands = renderer.take_prepare_commands();
+    commands.push(encoder.finish());
+    backend.queue().submit(commands);
+
+    // Every label is merged into single draw instead of one draw per label
+    assert_eq!(renderer.stats().draw_calls, 1);
+
+    Ok(())
+}
+
+#[test]
+fn selection_rects_test() -> Result<(), Box<dyn Error>> {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        storyboard_render::wgpu::Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    let mut cache = GlyphCache::new();
+
+    let mut text = Text::new(
+        Point2D::zero(),
+        16,
+        Transform3D::identity(),
+        Font::new(Cow::Borrowed(MONO_FONT), 0)?,
+        Cow::Borrowed("ab\ncd"),
+    );
+    text.update(
+        backend.device(),
+        backend.queue(),
+        1.0,
+        &textures,
+        &mut cache,
+    );
+
+    let caret_x = |offset: usize| text.caret_rect(offset).unwrap().origin.x;
+
+    // Empty and reversed range
+    assert!(text.selection_rects(2, 2).is_empty());
+    assert!(text.selection_rects(4, 1).is_empty());
+
+    // Single line
+    let rects = text.selection_rects(0, 2);
+    assert_eq!(rects.len(), 1);
+    assert_eq!(rects[0].min_x(), caret_x(0));
+    assert_eq!(rects[0].max_x(), caret_x(1) + rects[0].width() / 2.0);
+
+    // Selection start to line end, then line start to selection end
+    let rects = text.selection_rects(1, 4);
+    assert_eq!(rects.len(), 2);
+    assert!(rects[0].max_y() <= rects[1].min_y());
+    assert_eq!(rects[0].min_x(), caret_x(1));
+    assert_eq!(rects[1].min_x(), caret_x(3));
+    assert_eq!(rects[1].max_x(), caret_x(4));
+
+    // Whole text
+    let rects = text.selection_rects(0, 5);
+    assert_eq!(rects.len(), 2);
+    assert_eq!(rects[1].max_x(), caret_x(5));
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Anchoring boxes relative to parent rect, like screen
+
+use euclid::{Point2D, Rect, Size2D, UnknownUnit, Vector2D};
+
+use crate::{screen::ScreenRect, unit::LogicalPixelUnit};
+
+/// Position and size of box relative to parent rect.
+/// Relative values are fractions of parent size, fixed values are logical pixels added to them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    /// Point of parent box is placed at. `(0.5, 0.5)` is center of parent.
+    pub position: Point2D<f32, UnknownUnit>,
+    pub offset: Vector2D<f32, LogicalPixelUnit>,
+
+    /// Point of box placed at `position`. `(0.5, 0.5)` centers box.
+    pub origin: Point2D<f32, UnknownUnit>,
+
+    pub size: Size2D<f32, UnknownUnit>,
+    pub fixed_size: Size2D<f32, LogicalPixelUnit>,
+}
+
+impl Anchor {
+    /// Box filling parent
+    pub const FILL: Anchor = Anchor::relative(Point2D::new(0.0, 0.0), Size2D::new(1.0, 1.0));
+
+    /// Box with relative size, with its top left corner at relative position
+    pub const fn relative(
+        position: Point2D<f32, UnknownUnit>,
+        size: Size2D<f32, UnknownUnit>,
+    ) -> Self {
+        Self {
+            position,
+            offset: Vector2D::new(0.0, 0.0),
+            origin: Point2D::new(0.0, 0.0),
+            size,
+            fixed_size: Size2D::new(0.0, 0.0),
+        }
+    }
+
+    /// Box with relative size, centered at relative position
+    pub const fn centered(
+        position: Point2D<f32, UnknownUnit>,
+        size: Size2D<f32, UnknownUnit>,
+    ) -> Self {
+        Self {
+            origin: Point2D::new(0.5, 0.5),
+            ..Self::relative(position, size)
+        }
+    }
+
+    /// Box with fixed size, with its origin at relative position
+    pub const fn fixed(
+        position: Point2D<f32, UnknownUnit>,
+        origin: Point2D<f32, UnknownUnit>,
+        size: Size2D<f32, LogicalPixelUnit>,
+    ) -> Self {
+        Self {
+            position,
+            offset: Vector2D::new(0.0, 0.0),
+            origin,
+            size: Size2D::new(0.0, 0.0),
+            fixed_size: size,
+        }
+    }
+
+    /// Compute absolute logical rect of box in parent
+    pub fn resolve(&self, parent: Rect<f32, LogicalPixelUnit>) -> Rect<f32, LogicalPixelUnit> {
+        let size = Size2D::new(
+            self.size.width * parent.size.width,
+            self.size.height * parent.size.height,
+        ) + self.fixed_size;
+
+        let position = parent.origin
+            + Vector2D::new(
+                self.position.x * parent.size.width,
+                self.position.y * parent.size.height,
+            )
+            + self.offset;
+
+        Rect::new(
+            position - Vector2D::new(self.origin.x * size.width, self.origin.y * size.height),
+            size,
+        )
+    }
+
+    /// Compute absolute logical rect of box on screen
+    pub fn resolve_screen(&self, screen: &ScreenRect) -> Rect<f32, LogicalPixelUnit> {
+        self.resolve(screen.get_logical_rect())
+    }
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Self::FILL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::{Point2D, Rect, Size2D, Vector2D};
+
+    use crate::screen::ScreenRect;
+
+    use super::Anchor;
+
+    #[test]
+    fn anchor_test() {
+        // 800x600 logical screen
+        let screen = ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(1600, 1200)), 2.0);
+
+        // Center at 50%, size 50% of screen
+        assert_eq!(
+            Anchor::centered(Point2D::new(0.5, 0.5), Size2D::new(0.5, 0.5)).resolve_screen(&screen),
+            Rect::new(Point2D::new(200.0, 150.0), Size2D::new(400.0, 300.0))
+        );
+
+        assert_eq!(
+            Anchor::FILL.resolve_screen(&screen),
+            Rect::new(Point2D::zero(), Size2D::new(800.0, 600.0))
+        );
+
+        // 100x50 box at bottom right corner, 10px from edges
+        let anchor = Anchor {
+            offset: Vector2D::new(-10.0, -10.0),
+            ..Anchor::fixed(
+                Point2D::new(1.0, 1.0),
+                Point2D::new(1.0, 1.0),
+                Size2D::new(100.0, 50.0),
+            )
+        };
+        assert_eq!(
+            anchor.resolve_screen(&screen),
+            Rect::new(Point2D::new(690.0, 540.0), Size2D::new(100.0, 50.0))
+        );
+
+        // Relative to parent origin
+        assert_eq!(
+            Anchor::relative(Point2D::new(0.5, 0.0), Size2D::new(0.5, 1.0)).resolve(Rect::new(
+                Point2D::new(100.0, 100.0),
+                Size2D::new(200.0, 100.0)
+            )),
+            Rect::new(Point2D::new(200.0, 100.0), Size2D::new(100.0, 100.0))
+        );
+    }
+}
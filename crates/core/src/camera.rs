@@ -0,0 +1,98 @@
+//! 2D camera for pannable and zoomable canvas
+
+use euclid::{Angle, Point2D, Transform3D};
+
+use crate::unit::LogicalPixelUnit;
+
+/// Camera mapping world coordinates to screen coordinates.
+/// Use [Camera2D::matrix] as transform of drawables placed in world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    /// World point shown at `anchor`
+    pub position: Point2D<f32, LogicalPixelUnit>,
+
+    /// Screen point camera looks through, like center of screen
+    pub anchor: Point2D<f32, LogicalPixelUnit>,
+
+    pub zoom: f32,
+    pub rotation: Angle<f32>,
+}
+
+impl Camera2D {
+    pub const fn new(anchor: Point2D<f32, LogicalPixelUnit>) -> Self {
+        Self {
+            position: anchor,
+            anchor,
+            zoom: 1.0,
+            rotation: Angle { radians: 0.0 },
+        }
+    }
+
+    /// World to screen matrix
+    pub fn matrix(&self) -> Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit> {
+        Transform3D::translation(-self.position.x, -self.position.y, 0.0)
+            .then_scale(self.zoom, self.zoom, 1.0)
+            .then_rotate(0.0, 0.0, 1.0, self.rotation)
+            .then_translate(self.anchor.to_vector().to_3d())
+    }
+
+    /// Convert world point to screen point
+    pub fn world_to_screen(
+        &self,
+        point: Point2D<f32, LogicalPixelUnit>,
+    ) -> Option<Point2D<f32, LogicalPixelUnit>> {
+        self.matrix().transform_point2d(point)
+    }
+
+    /// Convert screen point, like cursor position, to world point for picking.
+    /// Returns None if camera matrix is not invertible, like zero zoom.
+    pub fn screen_to_world(
+        &self,
+        point: Point2D<f32, LogicalPixelUnit>,
+    ) -> Option<Point2D<f32, LogicalPixelUnit>> {
+        self.matrix().inverse()?.transform_point2d(point)
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new(Point2D::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::{Angle, Point2D};
+
+    use super::Camera2D;
+
+    #[test]
+    fn camera_round_trip_test() {
+        let camera = Camera2D {
+            position: Point2D::new(120.0, -40.0),
+            anchor: Point2D::new(400.0, 300.0),
+            zoom: 2.5,
+            rotation: Angle::degrees(30.0),
+        };
+
+        assert!(camera
+            .world_to_screen(camera.position)
+            .unwrap()
+            .approx_eq_eps(&camera.anchor, &Point2D::new(1e-3, 1e-3)));
+
+        let point = Point2D::new(37.0, 83.0);
+        let world = camera.screen_to_world(point).unwrap();
+
+        assert!(camera
+            .world_to_screen(world)
+            .unwrap()
+            .approx_eq_eps(&point, &Point2D::new(1e-3, 1e-3)));
+
+        // Zero zoom cannot be inverted
+        let camera = Camera2D {
+            zoom: 0.0,
+            ..camera
+        };
+        assert_eq!(camera.screen_to_world(point), None);
+    }
+}
@@ -1,13 +1,30 @@
-use std::{marker::PhantomData, ops::Index};
+use std::marker::PhantomData;
 
-use palette::rgb::{LinSrgba, Rgb};
+use euclid::{Angle, Point2D, Rect};
+use palette::{
+    rgb::{LinSrgba, Rgb},
+    Mix,
+};
 
 pub type Color = LinSrgba;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ShapeColor<const VERTICES: usize = 1> {
     Single(Color),
+
+    /// Color of each vertex
     Gradient([Color; VERTICES]),
+
+    /// Gradient from `start` to `end` across local bounds of shape.
+    /// Angle 0 goes from left to right and increases clockwise, so 90 degrees goes from top to bottom.
+    /// Corners of bounds lie on start and end like css `linear-gradient`.
+    ///
+    /// Gradient is linear, so it is evaluated on vertices and interpolated exactly by rasterizer.
+    LinearGradient {
+        start: Color,
+        end: Color,
+        angle: Angle<f32>,
+    },
 }
 
 impl<const VERTICES: usize> ShapeColor<VERTICES> {
@@ -73,8 +90,54 @@ impl<const VERTICES: usize> ShapeColor<VERTICES> {
         match self {
             ShapeColor::Single(color) => color.alpha >= 1.0,
             ShapeColor::Gradient(colors) => colors.iter().any(|color| color.alpha >= 1.0),
+            ShapeColor::LinearGradient { start, end, .. } => start.alpha >= 1.0 && end.alpha >= 1.0,
+        }
+    }
+
+    /// Color of vertex at `index` located at `point` of shape with local `bounds`
+    pub fn color_at<U>(
+        &self,
+        index: usize,
+        bounds: &Rect<f32, U>,
+        point: Point2D<f32, U>,
+    ) -> Color {
+        match self {
+            ShapeColor::Single(color) => *color,
+
+            ShapeColor::Gradient(gradient) => gradient[index],
+
+            ShapeColor::LinearGradient { start, end, angle } => {
+                let (sin, cos) = angle.sin_cos();
+
+                // Length of gradient line, which makes corners of bounds lie on start and end
+                let length = (bounds.size.width * cos).abs() + (bounds.size.height * sin).abs();
+                if length <= 0.0 {
+                    return *start;
+                }
+
+                let offset = point - bounds.center();
+                let t = (offset.x * cos + offset.y * sin) / length + 0.5;
+
+                start.mix(end, t)
+            }
         }
     }
+
+    /// Colors of vertices located at `points` of shape with local `bounds`
+    pub fn vertex_colors<U>(
+        &self,
+        bounds: &Rect<f32, U>,
+        points: &[Point2D<f32, U>; VERTICES],
+    ) -> [Color; VERTICES] {
+        let mut index = 0;
+
+        points.map(|point| {
+            let color = self.color_at(index, bounds, point);
+            index += 1;
+
+            color
+        })
+    }
 }
 
 impl<const VERTICES: usize> Default for ShapeColor<VERTICES> {
@@ -95,29 +158,43 @@ impl<const VERTICES: usize> From<[Color; VERTICES]> for ShapeColor<VERTICES> {
     }
 }
 
-impl<const VERTICES: usize> Index<usize> for ShapeColor<VERTICES> {
-    type Output = Color;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        match self {
-            ShapeColor::Single(color) => {
-                if index >= VERTICES {
-                    panic!("Index out of size. size = {}", VERTICES);
-                }
-
-                color
-            }
-
-            ShapeColor::Gradient(gradient) => &gradient[index],
-        }
-    }
-}
-
 impl From<ShapeColor<1>> for Color {
     fn from(color: ShapeColor<1>) -> Self {
         match color {
             ShapeColor::Single(color) => color,
             ShapeColor::Gradient(colors) => colors[0],
+            ShapeColor::LinearGradient { start, end, .. } => start.mix(&end, 0.5),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use euclid::{Angle, Point2D, Rect, Size2D, UnknownUnit};
+
+    use crate::math::RectExt;
+
+    use super::{Color, ShapeColor};
+
+    #[test]
+    fn linear_gradient_test() {
+        let bounds =
+            Rect::<f32, UnknownUnit>::new(Point2D::new(10.0, 10.0), Size2D::new(20.0, 20.0));
+        let color = ShapeColor::<4>::LinearGradient {
+            start: Color::new(0.0, 0.0, 0.0, 1.0),
+            end: Color::new(1.0, 1.0, 1.0, 1.0),
+            angle: Angle::degrees(45.0),
+        };
+
+        let [left_top, left_bottom, right_bottom, right_top] =
+            color.vertex_colors(&bounds, &bounds.into_coords());
+
+        assert!((left_top.red - 0.0).abs() < 1e-5);
+        assert!((right_bottom.red - 1.0).abs() < 1e-5);
+
+        // 45 degrees gradient is symmetric along diagonal
+        assert!((left_bottom.red - 0.5).abs() < 1e-5);
+        assert!((right_top.red - 0.5).abs() < 1e-5);
+        assert!((color.color_at(0, &bounds, bounds.center()).red - 0.5).abs() < 1e-5);
+    }
+}
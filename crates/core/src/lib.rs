@@ -1,11 +1,24 @@
+//! Core types of storyboard.
+//!
+//! Math, units, observables and screen types don't depend on graphics crates.
+//! Color types and bytemuck support of euclid types are available with `graphics` feature, enabled by default.
+//! Build with `default-features = false` to use the lightweight subset only.
+
+pub mod anchor;
+pub mod camera;
 pub mod math;
 pub mod observable;
+pub mod screen;
 pub mod store;
 pub mod tick_task;
 pub mod time_sampler;
 pub mod unit;
+
+#[cfg(feature = "graphics")]
 pub mod color;
 
 // Reexports
 pub use euclid;
+
+#[cfg(feature = "graphics")]
 pub use palette;
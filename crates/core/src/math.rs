@@ -2,7 +2,7 @@
 
 use std::ops::{Add, Div, Neg, Sub};
 
-use euclid::{Point2D, Rect, Size2D};
+use euclid::{Angle, Point2D, Point3D, Rect, Size2D, Transform3D, UnknownUnit};
 
 pub trait RectExt<T, U> {
     fn into_coords(self) -> [Point2D<T, U>; 4];
@@ -35,3 +35,135 @@ impl<T: Copy + Add<Output = T> + Sub<Output = T> + Neg<Output = T> + Div<Output
         )
     }
 }
+
+pub trait FiniteExt: Sized {
+    /// Returns None if any component is NaN or infinite
+    fn finite(self) -> Option<Self>;
+}
+
+impl<U> FiniteExt for Point2D<f32, U> {
+    fn finite(self) -> Option<Self> {
+        if self.x.is_finite() && self.y.is_finite() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl<U> FiniteExt for Point3D<f32, U> {
+    fn finite(self) -> Option<Self> {
+        if self.x.is_finite() && self.y.is_finite() && self.z.is_finite() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl<U> FiniteExt for Rect<f32, U> {
+    fn finite(self) -> Option<Self> {
+        if self.origin.finite().is_some()
+            && self.size.width.is_finite()
+            && self.size.height.is_finite()
+        {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns true if bounds has no area or non finite component, so nothing is drawn in it
+pub fn empty_bounds<U>(bounds: &Rect<f32, U>) -> bool {
+    bounds.finite().map_or(true, |bounds| {
+        !(bounds.size.width > 0.0 && bounds.size.height > 0.0)
+    })
+}
+
+/// Rotation of `bounds` by `angle` around anchor.
+/// Anchor is relative to bounds, `(0.5, 0.5)` is center of bounds.
+pub fn rotation_transform<U>(
+    bounds: &Rect<f32, U>,
+    angle: Angle<f32>,
+    anchor: Point2D<f32, UnknownUnit>,
+) -> Transform3D<f32, U, U> {
+    if angle.radians == 0.0 {
+        return Transform3D::identity();
+    }
+
+    let anchor =
+        bounds.origin + Size2D::new(bounds.size.width * anchor.x, bounds.size.height * anchor.y);
+
+    Transform3D::translation(-anchor.x, -anchor.y, 0.0)
+        .then_rotate(0.0, 0.0, 1.0, angle)
+        .then_translate(anchor.to_vector().to_3d())
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::{Angle, Point2D, Rect, Size2D, Transform3D, UnknownUnit};
+
+    use super::{empty_bounds, rotation_transform, FiniteExt};
+
+    #[test]
+    fn nan_offset_test() {
+        let transform = Transform3D::<f32, UnknownUnit, UnknownUnit>::translation(f32::NAN, 0.0, 0.0);
+
+        let point = transform.transform_point2d(Point2D::new(1.0, 1.0));
+
+        assert!(point.and_then(FiniteExt::finite).is_none());
+    }
+
+    #[test]
+    fn zero_scale_test() {
+        let transform = Transform3D::<f32, UnknownUnit, UnknownUnit>::scale(0.0, 0.0, 1.0);
+
+        let rect = transform
+            .outer_transformed_rect(&Rect::new(Point2D::new(10.0, 10.0), Size2D::new(50.0, 50.0)))
+            .and_then(FiniteExt::finite)
+            .unwrap();
+
+        assert_eq!(rect.area(), 0.0);
+    }
+
+    #[test]
+    fn empty_bounds_test() {
+        let rect = |x: f32, width: f32, height: f32| {
+            Rect::<f32, UnknownUnit>::new(Point2D::new(x, 0.0), Size2D::new(width, height))
+        };
+
+        assert!(!empty_bounds(&rect(0.0, 10.0, 10.0)));
+        assert!(empty_bounds(&rect(0.0, 0.0, 10.0)));
+        assert!(empty_bounds(&rect(0.0, -10.0, -10.0)));
+        assert!(empty_bounds(&rect(f32::NAN, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn rotation_transform_test() {
+        let bounds =
+            Rect::<f32, UnknownUnit>::new(Point2D::new(10.0, 20.0), Size2D::new(40.0, 20.0));
+        let angle = Angle::degrees(30.0);
+
+        let expected = Transform3D::translation(-30.0, -30.0, 0.0)
+            .then_rotate(0.0, 0.0, 1.0, angle)
+            .then_translate((30.0, 30.0, 0.0).into());
+
+        assert_eq!(
+            rotation_transform(&bounds, angle, Point2D::new(0.5, 0.5)),
+            expected
+        );
+
+        // Anchor stays in place
+        let top_left = rotation_transform(&bounds, angle, Point2D::zero());
+        assert!(top_left
+            .transform_point2d(bounds.origin)
+            .unwrap()
+            .approx_eq(&bounds.origin));
+
+        assert_eq!(
+            rotation_transform(&bounds, Angle::zero(), Point2D::new(0.5, 0.5)),
+            Transform3D::identity()
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+};
 
 /// Track changes of inner data
 #[derive(Debug, Clone, Default)]
@@ -77,6 +80,110 @@ impl<T> From<T> for Observable<T> {
     }
 }
 
+/// Id of subscription returned from [SubscribedObservable::subscribe]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// [Observable] notifying subscribers when its changes are invalidated.
+///
+/// Subscribers are called after [SubscribedObservable::set] and on [SubscribedObservable::invalidate] if data was changed.
+/// Polling using [Observable] api is still available through deref.
+///
+/// Callbacks receive shared reference of data and must not mutate same observable.
+/// Mutating it through shared handle like mutex in callback would deadlock.
+pub struct SubscribedObservable<T> {
+    inner: Observable<T>,
+
+    next_id: u64,
+    subscribers: Vec<(SubscriptionId, Box<dyn FnMut(&T) + Send>)>,
+}
+
+impl<T> SubscribedObservable<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            inner: Observable::new(data),
+            next_id: 0,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Add subscriber callback called after every change invalidation
+    pub fn subscribe(this: &mut Self, callback: impl FnMut(&T) + Send + 'static) -> SubscriptionId {
+        let id = SubscriptionId(this.next_id);
+        this.next_id += 1;
+
+        this.subscribers.push((id, Box::new(callback)));
+
+        id
+    }
+
+    /// Remove subscriber. Returns false if subscription doesn't exist.
+    pub fn unsubscribe(this: &mut Self, id: SubscriptionId) -> bool {
+        let len = this.subscribers.len();
+        this.subscribers
+            .retain(|(subscription, _)| *subscription != id);
+
+        this.subscribers.len() != len
+    }
+
+    /// Replace data and notify subscribers
+    pub fn set(this: &mut Self, data: T) {
+        *this.inner = data;
+        Self::invalidate(this);
+    }
+
+    /// Invalidate inner data change flag and notify subscribers if data was changed.
+    /// Return true if changes unmarked.
+    pub fn invalidate(this: &mut Self) -> bool {
+        if !Observable::invalidate(&mut this.inner) {
+            return false;
+        }
+
+        for (_, callback) in this.subscribers.iter_mut() {
+            callback(&*this.inner);
+        }
+
+        true
+    }
+
+    pub fn into_inner(this: Self) -> Observable<T> {
+        this.inner
+    }
+}
+
+impl<T> Deref for SubscribedObservable<T> {
+    type Target = Observable<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for SubscribedObservable<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T> From<Observable<T>> for SubscribedObservable<T> {
+    fn from(inner: Observable<T>) -> Self {
+        Self {
+            inner,
+            next_id: 0,
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for SubscribedObservable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubscribedObservable")
+            .field("inner", &self.inner)
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 #[test]
 pub fn observable_test() {
@@ -88,3 +195,36 @@ pub fn observable_test() {
 
     assert!(Observable::changed(&data));
 }
+
+#[cfg(test)]
+#[test]
+pub fn subscribed_observable_test() {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicU32::new(0));
+
+    let mut data = SubscribedObservable::new(1);
+    let id = SubscribedObservable::subscribe(&mut data, {
+        let calls = calls.clone();
+        move |_| {
+            calls.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    assert!(SubscribedObservable::invalidate(&mut data));
+    assert!(!SubscribedObservable::invalidate(&mut data));
+
+    // Mutating through deref notifies on next invalidate
+    **data = 3;
+    assert!(SubscribedObservable::invalidate(&mut data));
+
+    SubscribedObservable::set(&mut data, 2);
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+    assert!(SubscribedObservable::unsubscribe(&mut data, id));
+    SubscribedObservable::set(&mut data, 4);
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+}
@@ -0,0 +1,195 @@
+//! Screen space math shared by renderer and application logic
+
+use euclid::{Point2D, Rect, Size2D, Transform3D};
+
+use crate::unit::{LogicalPixelUnit, PhyiscalPixelUnit, RenderUnit};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenRect {
+    pub rect: Rect<u32, PhyiscalPixelUnit>,
+    pub scale_factor: f32,
+
+    /// Round vertex positions to nearest physical pixel for crisp edges and text.
+    /// Snapping and smooth sub-pixel animation are mutually exclusive, moving drawables will jitter by whole pixels.
+    pub pixel_snap: bool,
+}
+
+impl ScreenRect {
+    pub const fn new(rect: Rect<u32, PhyiscalPixelUnit>, scale_factor: f32) -> Self {
+        Self {
+            rect,
+            scale_factor,
+            pixel_snap: false,
+        }
+    }
+
+    pub fn get_logical_size(&self) -> Size2D<f32, LogicalPixelUnit> {
+        (self.rect.size.cast::<f32>() / self.scale_factor)
+            .ceil()
+            .cast_unit()
+    }
+
+    pub fn get_logical_origin(&self) -> Point2D<f32, LogicalPixelUnit> {
+        (self.rect.origin.cast::<f32>() / self.scale_factor).cast_unit()
+    }
+
+    pub fn get_logical_rect(&self) -> Rect<f32, LogicalPixelUnit> {
+        Rect::new(self.get_logical_origin(), self.get_logical_size())
+    }
+
+    /// Snap logical point to nearest physical pixel if [ScreenRect::pixel_snap] is enabled
+    pub fn snap_point(
+        &self,
+        point: Point2D<f32, LogicalPixelUnit>,
+    ) -> Point2D<f32, LogicalPixelUnit> {
+        if !self.pixel_snap {
+            return point;
+        }
+
+        let origin = self.get_logical_origin();
+
+        (((point - origin) * self.scale_factor).round() / self.scale_factor + origin.to_vector())
+            .to_point()
+    }
+
+    /// Snap corners of logical rect to nearest physical pixels if [ScreenRect::pixel_snap] is enabled
+    pub fn snap_rect(&self, rect: Rect<f32, LogicalPixelUnit>) -> Rect<f32, LogicalPixelUnit> {
+        if !self.pixel_snap {
+            return rect;
+        }
+
+        Rect::from_points([self.snap_point(rect.min()), self.snap_point(rect.max())])
+    }
+
+    /// Convert window relative physical point, like cursor position, to logical point
+    pub fn physical_to_logical(
+        &self,
+        point: Point2D<f64, PhyiscalPixelUnit>,
+    ) -> Point2D<f32, LogicalPixelUnit> {
+        (point / self.scale_factor as f64).cast().cast_unit()
+    }
+
+    /// Convert logical point to window relative physical point
+    pub fn logical_to_physical(
+        &self,
+        point: Point2D<f32, LogicalPixelUnit>,
+    ) -> Point2D<f64, PhyiscalPixelUnit> {
+        point.cast::<f64>().cast_unit() * self.scale_factor as f64
+    }
+
+    pub fn physical_rect_to_logical(
+        &self,
+        rect: Rect<u32, PhyiscalPixelUnit>,
+    ) -> Rect<f32, LogicalPixelUnit> {
+        (rect.cast::<f32>() / self.scale_factor).cast_unit()
+    }
+
+    /// Convert logical rect to smallest physical rect covering it.
+    /// Returns None if the rect is outside of physical coordinate range.
+    pub fn logical_rect_to_physical(
+        &self,
+        rect: Rect<f32, LogicalPixelUnit>,
+    ) -> Option<Rect<u32, PhyiscalPixelUnit>> {
+        (rect * self.scale_factor)
+            .round_out()
+            .try_cast()
+            .map(|rect| rect.cast_unit())
+    }
+
+    pub fn get_logical_ortho_matrix(&self) -> Transform3D<f32, LogicalPixelUnit, RenderUnit> {
+        let rect = self.get_logical_rect();
+
+        Transform3D::ortho(
+            rect.min_x(),
+            rect.min_x() + self.rect.size.width as f32 / self.scale_factor,
+            rect.min_y() + self.rect.size.height as f32 / self.scale_factor,
+            rect.min_y(),
+            0.0,
+            1.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use euclid::{Point2D, Rect, Size2D};
+
+    use super::ScreenRect;
+
+    #[test]
+    fn coordinate_conversion_test() {
+        let screen = ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(100, 100)), 1.25);
+
+        assert_eq!(
+            screen.physical_to_logical(Point2D::new(25.0, 50.0)),
+            Point2D::new(20.0, 40.0)
+        );
+        assert_eq!(
+            screen.logical_to_physical(Point2D::new(20.0, 40.0)),
+            Point2D::new(25.0, 50.0)
+        );
+
+        // Partially covered physical pixels are included
+        assert_eq!(
+            screen
+                .logical_rect_to_physical(Rect::new(Point2D::new(1.0, 1.0), Size2D::new(2.0, 2.0))),
+            Some(Rect::new(Point2D::new(1, 1), Size2D::new(3, 3)))
+        );
+        assert_eq!(
+            screen.logical_rect_to_physical(Rect::new(
+                Point2D::new(-10.0, 0.0),
+                Size2D::new(1.0, 1.0)
+            )),
+            None
+        );
+
+        let screen = ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(100, 100)), 2.0);
+
+        assert_eq!(
+            screen.physical_rect_to_logical(Rect::new(Point2D::new(10, 20), Size2D::new(30, 40))),
+            Rect::new(Point2D::new(5.0, 10.0), Size2D::new(15.0, 20.0))
+        );
+        assert_eq!(
+            screen.logical_to_physical(screen.physical_to_logical(Point2D::new(33.0, 17.0))),
+            Point2D::new(33.0, 17.0)
+        );
+    }
+
+    #[test]
+    fn offset_ortho_matrix_test() {
+        let screen = ScreenRect::new(
+            Rect::new(Point2D::new(100, 100), Size2D::new(200, 200)),
+            2.0,
+        );
+
+        let matrix = screen.get_logical_ortho_matrix();
+
+        assert_eq!(
+            matrix.transform_point2d(Point2D::new(50.0, 50.0)),
+            Some(Point2D::new(-1.0, 1.0))
+        );
+        assert_eq!(
+            matrix.transform_point2d(Point2D::new(100.0, 100.0)),
+            Some(Point2D::new(0.0, 0.0))
+        );
+        assert_eq!(
+            matrix.transform_point2d(Point2D::new(150.0, 150.0)),
+            Some(Point2D::new(1.0, -1.0))
+        );
+    }
+
+    #[test]
+    fn pixel_snap_test() {
+        let mut screen = ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(100, 100)), 2.0);
+
+        let rect = Rect::new(Point2D::new(10.3, 20.2), Size2D::new(30.0, 0.5));
+        assert_eq!(screen.snap_rect(rect), rect);
+
+        screen.pixel_snap = true;
+
+        // Half logical pixel border is exactly one physical row on 2x scale
+        let snapped = screen.snap_rect(rect);
+        assert_eq!(snapped.origin, Point2D::new(10.5, 20.0));
+        assert_eq!(snapped.size.height * screen.scale_factor, 1.0);
+    }
+}
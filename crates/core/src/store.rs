@@ -1,12 +1,19 @@
-use std::{any::TypeId, fmt::Debug, mem};
-
+use std::{
+    any::{type_name, TypeId},
+    fmt::Debug,
+    mem,
+    time::Duration,
+};
+
+use instant::Instant;
 use parking_lot::RwLock;
 use rustc_hash::FxHashMap;
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 /// Resource store for storing type erased local resource data
 pub struct Store {
     map: RwLock<FxHashMap<TypeId, *mut ()>>,
+    init_hook: RwLock<Option<Box<dyn Fn(&StoreInit) + Send + Sync>>>,
 }
 
 // SAFETY: Values in Store is Send
@@ -19,24 +26,62 @@ impl Store {
     pub fn new() -> Self {
         Self {
             map: RwLock::new(FxHashMap::default()),
+            init_hook: RwLock::new(None),
         }
     }
 
+    /// Set hook called after resource is initialized on first access, like when component pipeline is compiled.
+    /// Initializations are logged on debug level regardless of hook.
+    pub fn set_init_hook(&self, hook: Option<Box<dyn Fn(&StoreInit) + Send + Sync>>) {
+        *self.init_hook.write() = hook;
+    }
+
+    /// Initialize resources ahead of first use, so first frame using them doesn't stutter.
+    /// Takes tuple of resources like `(Box2DResources, GlyphResources)`.
+    pub fn precompile<T: StoreResourcesList<Context>, Context>(&self, ctx: &Context) {
+        T::precompile(self, ctx);
+    }
+
     pub fn get<'a, T: StoreResources<Context>, Context>(&'a self, ctx: &Context) -> &'a T {
         if let Some(item) = self.map.read().get(&TypeId::of::<T>()) {
             // SAFETY: Value was created with valid type and was type erased.
             return unsafe { &*(*item as *mut T) };
         }
 
+        let start = Instant::now();
         let item = Box::new(T::initialize(self, ctx));
+
+        let init = StoreInit {
+            type_name: type_name::<T>(),
+            elapsed: start.elapsed(),
+        };
+        log::debug!(
+            "Initialized {} in {} ms",
+            init.type_name,
+            init.elapsed.as_secs_f64() * 1000.0
+        );
+        if let Some(hook) = &*self.init_hook.read() {
+            hook(&init);
+        }
+
+        // Other thread may have initialized it meanwhile, like pipeline warm up running along render thread
         self.map
             .write()
-            .insert(TypeId::of::<T>(), Box::into_raw(item) as *mut ());
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::into_raw(item) as *mut ());
 
         self.get(ctx)
     }
 }
 
+impl Debug for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Store")
+            .field("map", &self.map)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Drop for Store {
     fn drop(&mut self) {
         // SAFETY: pointer created with [Box::into_raw]
@@ -52,6 +97,38 @@ pub trait StoreResources<Context>: 'static + Send + Sync {
     fn initialize(store: &Store, ctx: &Context) -> Self;
 }
 
+/// First time initialization of resource
+#[derive(Debug, Clone, Copy)]
+pub struct StoreInit {
+    pub type_name: &'static str,
+    /// Time taken by [StoreResources::initialize], including resources it initialized
+    pub elapsed: Duration,
+}
+
+/// Tuple of resources which can be initialized together
+pub trait StoreResourcesList<Context> {
+    fn precompile(store: &Store, ctx: &Context);
+}
+
+macro_rules! impl_resources_list {
+    ($($name: ident),+) => {
+        impl<$($name: StoreResources<Context>,)+ Context> StoreResourcesList<Context> for ($($name,)+) {
+            fn precompile(store: &Store, ctx: &Context) {
+                $(store.get::<$name, Context>(ctx);)+
+            }
+        }
+    };
+}
+
+impl_resources_list!(A);
+impl_resources_list!(A, B);
+impl_resources_list!(A, B, C);
+impl_resources_list!(A, B, C, D);
+impl_resources_list!(A, B, C, D, E);
+impl_resources_list!(A, B, C, D, E, F);
+impl_resources_list!(A, B, C, D, E, F, G);
+impl_resources_list!(A, B, C, D, E, F, G, H);
+
 #[cfg(test)]
 mod tests {
     use std::time::Instant;
@@ -96,4 +173,44 @@ mod tests {
 
         assert_eq!(store.get::<ResB, _>(&()).string, "test");
     }
+    #[test]
+    fn precompile_test() {
+        use std::sync::{Arc, Mutex};
+
+        struct ResA;
+
+        impl StoreResources<()> for ResA {
+            fn initialize(_: &Store, _: &()) -> Self {
+                ResA
+            }
+        }
+
+        struct ResB;
+
+        impl StoreResources<()> for ResB {
+            fn initialize(store: &Store, _: &()) -> Self {
+                store.get::<ResA, _>(&());
+
+                ResB
+            }
+        }
+
+        let store = Store::new();
+
+        let inits = Arc::new(Mutex::new(Vec::new()));
+        store.set_init_hook(Some(Box::new({
+            let inits = inits.clone();
+
+            move |init| inits.lock().unwrap().push(init.type_name)
+        })));
+
+        store.precompile::<(ResB, ResA), _>(&());
+        store.get::<ResA, _>(&());
+
+        // Dependency finishes first, every resource initialized once
+        let inits = inits.lock().unwrap();
+        assert_eq!(inits.len(), 2);
+        assert!(inits[0].ends_with("ResA"));
+        assert!(inits[1].ends_with("ResB"));
+    }
 }
@@ -6,6 +6,7 @@ use std::{
         Arc,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use parking_lot::Mutex;
@@ -130,6 +131,24 @@ impl<T: Send + 'static> IndependentTickTask<T> {
             TickTaskVariant::NonThreaded(task) => task.join(),
         }
     }
+
+    /// Join task if its thread finishes within timeout, returning task back otherwise.
+    /// Interrupt task and wake it up before calling, or thread doesn't finish.
+    pub fn join_timeout(self, timeout: Duration) -> Result<T, Self> {
+        if let TickTaskVariant::Threaded(task) = &self.0 {
+            let start = Instant::now();
+
+            while !task.finished() {
+                if start.elapsed() >= timeout {
+                    return Err(self);
+                }
+
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        Ok(self.join())
+    }
 }
 
 #[derive(Debug)]
@@ -153,6 +172,12 @@ impl<T> ThreadedTask<T> {
         self.interrupted.store(true, Ordering::Relaxed);
     }
 
+    pub fn finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .map_or(true, |handle| handle.is_finished())
+    }
+
     pub fn tick(&mut self) {
         if Arc::strong_count(&self.interrupted) < 2 && !self.interrupted() {
             panic::resume_unwind(self.handle.take().unwrap().join().err().unwrap());
@@ -205,3 +230,42 @@ struct TaskHandle<T> {
     item: T,
     handler: fn(&mut T),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc::{channel, Receiver},
+        time::{Duration, Instant},
+    };
+
+    use super::IndependentTickTask;
+
+    #[test]
+    fn join_timeout_test() {
+        // Task blocked waiting for frame signal, like render task
+        let (sender, receiver) = channel::<()>();
+        let task = IndependentTickTask::run_threaded(receiver, |receiver: &mut Receiver<()>| {
+            receiver.recv().ok();
+        });
+        assert!(task.threaded());
+
+        // Thread doesn't finish without interrupting, task is returned back
+        let mut task = match task.join_timeout(Duration::from_millis(50)) {
+            Ok(_) => panic!("task joined without interrupting"),
+            Err(task) => task,
+        };
+
+        // Interrupted and woken up task finishes within timeout
+        task.interrupt();
+        sender.send(()).unwrap();
+
+        let timeout = Duration::from_secs(1);
+        let start = Instant::now();
+        assert!(task.join_timeout(timeout).is_ok());
+        assert!(start.elapsed() < timeout);
+
+        // Non threaded task joins immediately
+        let task = IndependentTickTask::run_none_threaded(0, |count: &mut u32| *count += 1);
+        assert_eq!(task.join_timeout(Duration::ZERO).ok(), Some(0));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use instant::{Duration, Instant};
 
 #[derive(Debug)]
@@ -9,10 +11,17 @@ pub struct TimeSampler {
     total_elapsed: Duration,
     total_count: u32,
 
-    average_elapsed: Option<f64>
+    average_elapsed: Option<f64>,
+
+    samples: VecDeque<Duration>,
+    median: Option<Duration>,
+    dropped_frames: u64,
 }
 
 impl TimeSampler {
+    /// Number of recent samples used for percentile calculation
+    pub const WINDOW_SIZE: usize = 240;
+
     pub fn new(report_interval: Duration) -> Self {
         Self {
             report_rate: report_interval,
@@ -22,6 +31,10 @@ impl TimeSampler {
             total_count: 0,
 
             average_elapsed: None,
+
+            samples: VecDeque::with_capacity(Self::WINDOW_SIZE),
+            median: None,
+            dropped_frames: 0,
         }
     }
 
@@ -43,6 +56,8 @@ impl TimeSampler {
         self.total_elapsed += elapsed;
         self.total_count += 1;
 
+        self.push_sample(elapsed);
+
         if let Some(fps_sample_start) = self.fps_sample_start {
             if fps_sample_start.elapsed() >= self.report_rate {
                 self.fps_sample_start.take();
@@ -55,6 +70,7 @@ impl TimeSampler {
                 self.total_count = 0;
     
                 self.average_elapsed = Some(rate);
+                self.median = Some(self.percentile(0.5));
             }
         }
         
@@ -62,7 +78,98 @@ impl TimeSampler {
         elapsed
     }
 
+    fn push_sample(&mut self, elapsed: Duration) {
+        // Frame took more than twice of usual frame time
+        if let Some(median) = self.median {
+            if elapsed > median * 2 {
+                self.dropped_frames += 1;
+            }
+        }
+
+        if self.samples.len() >= Self::WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(elapsed);
+    }
+
     pub fn average_elapsed(&mut self) -> Option<f64> {
         self.average_elapsed.take()
     }
+
+    pub fn last_elapsed(&self) -> Duration {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    /// Frame count took more than twice of median frame time
+    pub const fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Calculate percentile (0.0 ~ 1.0) of recent samples
+    pub fn percentile(&self, percentile: f32) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let mut sorted = self.samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+
+        let index = ((sorted.len() - 1) as f32 * percentile.clamp(0.0, 1.0)).round() as usize;
+
+        sorted[index]
+    }
+
+    /// Calculate statistics of recent samples
+    pub fn stats(&self) -> FrameStats {
+        FrameStats {
+            last_frame: self.last_elapsed(),
+            p50: self.percentile(0.5),
+            p95: self.percentile(0.95),
+            p99: self.percentile(0.99),
+            dropped_frames: self.dropped_frames,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub last_frame: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub dropped_frames: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use instant::Duration;
+
+    use super::TimeSampler;
+
+    #[test]
+    fn percentile_test() {
+        let mut sampler = TimeSampler::new(Duration::from_secs(1));
+
+        for i in 1..=100 {
+            sampler.push_sample(Duration::from_millis(i));
+        }
+
+        assert_eq!(sampler.percentile(0.0), Duration::from_millis(1));
+        assert_eq!(sampler.percentile(0.5), Duration::from_millis(51));
+        assert_eq!(sampler.percentile(0.99), Duration::from_millis(99));
+        assert_eq!(sampler.percentile(1.0), Duration::from_millis(100));
+        assert_eq!(sampler.last_elapsed(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn dropped_frames_test() {
+        let mut sampler = TimeSampler::new(Duration::from_secs(1));
+        sampler.median = Some(Duration::from_millis(16));
+
+        sampler.push_sample(Duration::from_millis(16));
+        sampler.push_sample(Duration::from_millis(40));
+
+        assert_eq!(sampler.dropped_frames(), 1);
+    }
 }
@@ -1,7 +1,10 @@
+pub mod scene;
+
 use downcast::{downcast, Any};
 use rustc_hash::{FxHashSet, FxHasher};
 use std::{fmt::Debug, hash::BuildHasherDefault, marker::PhantomData};
-use storyboard_render::task::RenderTask;
+use storyboard_core::{euclid::Rect, unit::LogicalPixelUnit};
+use storyboard_render::{task::RenderTask, ScreenRect};
 
 use indexmap::IndexMap;
 
@@ -70,6 +73,26 @@ impl FrameContainer {
         Some(*boxed)
     }
 
+    /// Draw components visible on screen in insertion order.
+    /// Components without bounds are always drawn. Returns number of drawn components.
+    pub fn draw_all(&self, task: &mut RenderTask, screen: &ScreenRect) -> usize {
+        let screen_rect = Rect::from_size(screen.get_logical_size());
+
+        let mut drawn = 0;
+        for component in self.state_map.values() {
+            if let Some(bounds) = component.bounds() {
+                if !bounds.intersects(&screen_rect) {
+                    continue;
+                }
+            }
+
+            component.draw(task);
+            drawn += 1;
+        }
+
+        drawn
+    }
+
     pub fn update(&mut self) {
         self.dirty_list.retain(|id| {
             if let Some(state) = self.state_map.get_mut(id) {
@@ -92,6 +115,12 @@ pub trait FrameComponent: Any {
     fn update(&mut self) -> bool;
 
     fn draw(&self, task: &mut RenderTask);
+
+    /// Bounds covering everything component draws, including transform. Used for culling.
+    /// Component is never culled if None.
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        None
+    }
 }
 
 downcast!(dyn FrameComponent);
@@ -0,0 +1,368 @@
+//! Retained scene graph with parent-child transforms
+
+use std::{fmt::Debug, marker::PhantomData};
+
+use storyboard_core::{
+    color::Color,
+    euclid::{Rect, Transform3D},
+    observable::Observable,
+    palette::rgb::Rgb,
+    unit::LogicalPixelUnit,
+};
+use storyboard_render::task::RenderTask;
+
+use crate::FrameComponent;
+
+/// State of node composed with its ancestors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeState {
+    pub transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+
+    /// Color multiplied to content color
+    pub tint: Color,
+
+    /// Screen space rect content is clipped to. Not clipped if None.
+    pub clip: Option<Rect<f32, LogicalPixelUnit>>,
+}
+
+impl NodeState {
+    pub const IDENTITY: NodeState = NodeState {
+        transform: Transform3D::identity(),
+        tint: Color {
+            color: Rgb {
+                red: 1.0,
+                green: 1.0,
+                blue: 1.0,
+                standard: PhantomData,
+            },
+            alpha: 1.0,
+        },
+        clip: None,
+    };
+
+    /// Compose local transform, tint and clip of child node
+    pub fn compose(
+        &self,
+        transform: &Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+        tint: &Color,
+        clip: Option<&Rect<f32, LogicalPixelUnit>>,
+    ) -> Self {
+        let transform = transform.then(&self.transform);
+
+        let clip = match clip.map(|clip| transform.outer_transformed_rect(clip)) {
+            Some(Some(clip)) => Some(match self.clip {
+                Some(parent_clip) => parent_clip.intersection(&clip).unwrap_or_else(Rect::zero),
+                None => clip,
+            }),
+
+            // Clip rect cannot be projected on screen
+            Some(None) => Some(Rect::zero()),
+
+            None => self.clip,
+        };
+
+        Self {
+            transform,
+            tint: Color::new(
+                self.tint.red * tint.red,
+                self.tint.green * tint.green,
+                self.tint.blue * tint.blue,
+                self.tint.alpha * tint.alpha,
+            ),
+            clip,
+        }
+    }
+
+    /// Check if everything is clipped out
+    pub fn clipped(&self) -> bool {
+        match self.clip {
+            Some(clip) => clip.is_empty(),
+            None => false,
+        }
+    }
+}
+
+impl Default for NodeState {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Content drawn by node using composed node state
+pub trait NodeContent: 'static {
+    fn draw(&self, state: &NodeState, task: &mut RenderTask);
+
+    /// Local bounds of content. Content outside of clip is not drawn.
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        None
+    }
+}
+
+impl<F: Fn(&NodeState, &mut RenderTask) + 'static> NodeContent for F {
+    fn draw(&self, state: &NodeState, task: &mut RenderTask) {
+        self(state, task)
+    }
+}
+
+impl Debug for dyn NodeContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeContent").finish_non_exhaustive()
+    }
+}
+
+/// Scene graph node.
+///
+/// Call [Node::update] on root node after modifying tree to compose node states.
+/// Only changed nodes and their descendants are composed again.
+#[derive(Debug)]
+pub struct Node {
+    transform: Observable<Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>>,
+    tint: Observable<Color>,
+    clip: Observable<Option<Rect<f32, LogicalPixelUnit>>>,
+    visible: bool,
+
+    content: Option<Box<dyn NodeContent>>,
+
+    children: Vec<Node>,
+    children_changed: bool,
+
+    state: NodeState,
+}
+
+impl Node {
+    pub fn new() -> Self {
+        Self {
+            transform: Transform3D::identity().into(),
+            tint: NodeState::IDENTITY.tint.into(),
+            clip: None.into(),
+            visible: true,
+
+            content: None,
+
+            children: Vec::new(),
+            children_changed: false,
+
+            state: NodeState::IDENTITY,
+        }
+    }
+
+    pub fn with_content(content: impl NodeContent) -> Self {
+        Self {
+            content: Some(Box::new(content)),
+            ..Self::new()
+        }
+    }
+
+    pub fn transform(&self) -> &Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit> {
+        &self.transform
+    }
+
+    /// Set transform relative to parent node
+    pub fn set_transform(
+        &mut self,
+        transform: Transform3D<f32, LogicalPixelUnit, LogicalPixelUnit>,
+    ) {
+        if self.transform.ne(&transform) {
+            *self.transform = transform;
+        }
+    }
+
+    pub fn tint(&self) -> &Color {
+        &self.tint
+    }
+
+    /// Set tint multiplied to parent tint
+    pub fn set_tint(&mut self, tint: Color) {
+        if self.tint.ne(&tint) {
+            *self.tint = tint;
+        }
+    }
+
+    pub fn clip(&self) -> Option<&Rect<f32, LogicalPixelUnit>> {
+        self.clip.as_ref()
+    }
+
+    /// Set clip rect in local space, intersected with parent clip
+    pub fn set_clip(&mut self, clip: Option<Rect<f32, LogicalPixelUnit>>) {
+        if self.clip.ne(&clip) {
+            *self.clip = clip;
+        }
+    }
+
+    pub const fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Hide node and its descendants
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn content(&self) -> Option<&dyn NodeContent> {
+        self.content.as_deref()
+    }
+
+    pub fn set_content(&mut self, content: Option<Box<dyn NodeContent>>) {
+        self.content = content;
+    }
+
+    /// Composed state of node. Up to date after [Node::update].
+    pub const fn state(&self) -> &NodeState {
+        &self.state
+    }
+
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    pub fn child_mut(&mut self, index: usize) -> Option<&mut Node> {
+        let child = self.children.get_mut(index)?;
+        self.children_changed = true;
+
+        Some(child)
+    }
+
+    /// Add child node drawn after existing children and return its index
+    pub fn push_child(&mut self, mut child: Node) -> usize {
+        child.mark_changed();
+        self.children.push(child);
+        self.children_changed = true;
+
+        self.children.len() - 1
+    }
+
+    pub fn remove_child(&mut self, index: usize) -> Option<Node> {
+        if index < self.children.len() {
+            Some(self.children.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Compose states of changed nodes in tree, treating this node as root.
+    /// Returns true if any node state changed.
+    pub fn update(&mut self) -> bool {
+        self.update_with(&NodeState::IDENTITY, false)
+    }
+
+    fn update_with(&mut self, parent: &NodeState, parent_changed: bool) -> bool {
+        let changed = Observable::invalidate(&mut self.transform)
+            | Observable::invalidate(&mut self.tint)
+            | Observable::invalidate(&mut self.clip)
+            || parent_changed;
+
+        if !changed && !self.children_changed {
+            return false;
+        }
+
+        if changed {
+            self.state = parent.compose(&self.transform, &self.tint, self.clip.as_ref());
+        }
+        self.children_changed = false;
+
+        let mut child_changed = false;
+        for child in &mut self.children {
+            child_changed |= child.update_with(&self.state, changed);
+        }
+
+        changed || child_changed
+    }
+
+    /// Draw contents of visible nodes in tree, parent first
+    pub fn draw(&self, task: &mut RenderTask) {
+        if !self.visible || self.state.clipped() {
+            return;
+        }
+
+        if let Some(content) = &self.content {
+            let visible = match (self.state.clip, content.bounds()) {
+                (Some(clip), Some(bounds)) => self
+                    .state
+                    .transform
+                    .outer_transformed_rect(&bounds)
+                    .map_or(true, |bounds| bounds.intersects(&clip)),
+
+                _ => true,
+            };
+
+            if visible {
+                content.draw(&self.state, task);
+            }
+        }
+
+        for child in &self.children {
+            child.draw(task);
+        }
+    }
+
+    fn mark_changed(&mut self) {
+        Observable::mark(&mut self.transform);
+    }
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameComponent for Node {
+    fn expired(&self) -> bool {
+        false
+    }
+
+    fn update(&mut self) -> bool {
+        Node::update(self);
+
+        false
+    }
+
+    fn draw(&self, task: &mut RenderTask) {
+        Node::draw(self, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D, Transform3D};
+
+    use super::Node;
+
+    #[test]
+    fn node_compose_test() {
+        let mut root = Node::new();
+        root.set_transform(Transform3D::translation(10.0, 20.0, 0.0));
+        root.set_clip(Some(Rect::new(Point2D::zero(), Size2D::new(100.0, 100.0))));
+
+        let mut child = Node::new();
+        child.set_transform(Transform3D::scale(2.0, 2.0, 1.0));
+        let index = root.push_child(child);
+
+        assert!(root.update());
+        assert!(!root.update());
+
+        let state = root.children()[index].state();
+        assert_eq!(
+            state.transform.transform_point2d(Point2D::new(1.0, 1.0)),
+            Some(Point2D::new(12.0, 22.0))
+        );
+        assert_eq!(
+            state.clip,
+            Some(Rect::new(
+                Point2D::new(10.0, 20.0),
+                Size2D::new(100.0, 100.0)
+            ))
+        );
+
+        // Parent change propagates to children
+        root.set_transform(Transform3D::identity());
+        assert!(root.update());
+        assert_eq!(
+            root.children()[index]
+                .state()
+                .transform
+                .transform_point2d(Point2D::new(1.0, 1.0)),
+            Some(Point2D::new(2.0, 2.0))
+        );
+    }
+}
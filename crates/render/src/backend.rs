@@ -1,8 +1,16 @@
-use std::{error::Error, fmt::Display, path::Path};
+use std::{
+    error::Error,
+    fmt::Display,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use wgpu::{
-    Adapter, Device, DeviceDescriptor, Features, Instance, Limits, PowerPreference, Queue,
-    RequestAdapterOptions, RequestDeviceError, Surface,
+    Adapter, Backend, Backends, Device, DeviceDescriptor, DownlevelFlags, Features, Instance,
+    Limits, Maintain, PowerPreference, Queue, RequestAdapterOptions, RequestDeviceError, Surface,
 };
 
 #[derive(Debug)]
@@ -23,14 +31,16 @@ impl StoryboardBackend {
         options: &BackendOptions,
         trace_path: Option<&Path>
     ) -> Result<Self, BackendInitError> {
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
+        let adapter = with_timeout(
+            instance.request_adapter(&RequestAdapterOptions {
                 power_preference: options.power_preference,
                 compatible_surface,
                 force_fallback_adapter: options.force_fallback_adapter,
-            })
-            .await
-            .ok_or(BackendInitError::NoSuitableAdapter)?;
+            }),
+            options.timeout,
+        )
+        .await?
+        .ok_or(BackendInitError::NoSuitableAdapter)?;
 
         let adapter_features = adapter.features();
 
@@ -40,16 +50,18 @@ impl StoryboardBackend {
             ));
         }
 
-        let (device, queue) = adapter
-            .request_device(
+        let (device, queue) = with_timeout(
+            adapter.request_device(
                 &DeviceDescriptor {
                     features,
                     limits: options.limits.clone(),
                     label: Some("StoryboardBackend device"),
                 },
                 trace_path, // Trace path
-            )
-            .await?;
+            ),
+            options.timeout,
+        )
+        .await??;
 
         Ok(Self {
             device,
@@ -65,6 +77,25 @@ impl StoryboardBackend {
         &self.adapter
     }
 
+    /// Graphics api backend of adapter in use
+    pub fn backend(&self) -> Backend {
+        self.adapter.get_info().backend
+    }
+
+    /// Max sampler anisotropy level supported by adapter. 1 if anisotropic filtering is not supported.
+    pub fn max_anisotropy(&self) -> u8 {
+        if self
+            .adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::ANISOTROPIC_FILTERING)
+        {
+            16
+        } else {
+            1
+        }
+    }
+
     pub const fn features(&self) -> Features {
         self.features
     }
@@ -76,14 +107,41 @@ impl StoryboardBackend {
     pub const fn queue(&self) -> &Queue {
         &self.queue
     }
+
+    /// Poll device to process mapped buffer callbacks and free resources.
+    /// Headless rendering or texture readback must poll device or mapping never completes.
+    /// This is no-op on wasm, which is polled by browser automatically.
+    /// Returns true if submission queue is empty.
+    pub fn poll(&self, maintain: Maintain) -> bool {
+        self.device.poll(maintain)
+    }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct BackendOptions {
     pub power_preference: PowerPreference,
     pub force_fallback_adapter: bool,
 
     pub limits: Limits,
+
+    /// Backends to try in order until one yields adapter
+    pub backends: Vec<Backends>,
+
+    /// Timeout of adapter and device request each.
+    /// Not applied on wasm, which has no thread to wake pending request.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self {
+            power_preference: Default::default(),
+            force_fallback_adapter: false,
+            limits: Default::default(),
+            backends: vec![Backends::PRIMARY, Backends::SECONDARY],
+            timeout: None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -92,6 +150,7 @@ pub enum BackendInitError {
     IncompatibleSurface,
     IncompatibleFeatures(Features),
     Device(RequestDeviceError),
+    Timeout,
 }
 
 impl From<RequestDeviceError> for BackendInitError {
@@ -111,8 +170,80 @@ impl Display for BackendInitError {
             Self::IncompatibleSurface => writeln!(f, "Incompatible surface"),
 
             Self::Device(err) => err.fmt(f),
+
+            Self::Timeout => writeln!(f, "Adapter or device request timed out"),
         }
     }
 }
 
 impl Error for BackendInitError {}
+
+async fn with_timeout<F: Future>(
+    future: F,
+    timeout: Option<Duration>,
+) -> Result<F::Output, BackendInitError> {
+    match timeout {
+        #[cfg(not(target_arch = "wasm32"))]
+        Some(timeout) => Timeout::new(future, timeout)
+            .await
+            .ok_or(BackendInitError::Timeout),
+
+        _ => Ok(future.await),
+    }
+}
+
+/// Future resolving to None if inner future is not completed until deadline
+#[cfg(not(target_arch = "wasm32"))]
+struct Timeout<F> {
+    future: Pin<Box<F>>,
+    timeout: Duration,
+    deadline: Option<std::time::Instant>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<F> Timeout<F> {
+    fn new(future: F, timeout: Duration) -> Self {
+        Self {
+            future: Box::pin(future),
+            timeout,
+            deadline: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<F: Future> Future for Timeout<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Poll::Ready(output) = this.future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+
+        match this.deadline {
+            Some(deadline) => {
+                if std::time::Instant::now() >= deadline {
+                    return Poll::Ready(None);
+                }
+            }
+
+            None => {
+                let deadline = std::time::Instant::now() + this.timeout;
+                this.deadline = Some(deadline);
+
+                // Wake task at deadline since pending request may never wake it
+                let waker = cx.waker().clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(
+                        deadline.saturating_duration_since(std::time::Instant::now()),
+                    );
+                    waker.wake();
+                });
+            }
+        }
+
+        Poll::Pending
+    }
+}
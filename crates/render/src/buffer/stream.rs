@@ -1,6 +1,9 @@
 use std::{borrow::Cow, num::NonZeroU64, ops::Range};
 
-use wgpu::{Buffer, BufferAddress, BufferBinding, BufferSlice, BufferUsages, Device, Queue};
+use wgpu::{
+    Buffer, BufferAddress, BufferBinding, BufferSlice, BufferUsages, Device, Queue,
+    COPY_BUFFER_ALIGNMENT,
+};
 
 use super::GrowingBuffer;
 
@@ -37,8 +40,22 @@ impl<'a> BufferStream<'a> {
         writer.finish()
     }
 
+    /// Write slice of data starting from offset aligned to given alignment and return written range
+    pub fn write_aligned_slice(&mut self, data: &[u8], alignment: usize) -> StreamRange {
+        let padding = (alignment - self.data.len() % alignment) % alignment;
+        self.data.resize(self.data.len() + padding, 0);
+
+        self.write_slice(data)
+    }
+
+    /// Size of data written since last finish
+    pub fn written_size(&self) -> BufferAddress {
+        self.data.len() as BufferAddress
+    }
+
     /// Finish streaming and upload memory buffer to gpu
     pub fn finish(&mut self, device: &Device, queue: &Queue) -> StreamBuffer {
+        self.pad_to_copy_alignment();
         let size = self.data.len() as BufferAddress;
 
         let (buffer, mapped) = self.buffer.alloc(device, size);
@@ -59,6 +76,13 @@ impl<'a> BufferStream<'a> {
 
         StreamBuffer { buffer }
     }
+
+    /// Pad end of data so upload size is multiple of [COPY_BUFFER_ALIGNMENT]
+    fn pad_to_copy_alignment(&mut self) {
+        let alignment = COPY_BUFFER_ALIGNMENT as usize;
+        let padding = (alignment - self.data.len() % alignment) % alignment;
+        self.data.resize(self.data.len() + padding, 0);
+    }
 }
 
 #[derive(Debug)]
@@ -119,3 +143,37 @@ pub fn write_test() {
 
     println!("Writing took {} microseconds", start.elapsed().as_micros());
 }
+
+#[cfg(test)]
+#[test]
+pub fn aligned_write_test() {
+    use wgpu::BufferUsages;
+
+    let mut stream = BufferStream::new(None, BufferUsages::UNIFORM);
+
+    let first = stream.write_aligned_slice(&[1; 64], 256);
+    let second = stream.write_aligned_slice(&[2; 80], 256);
+    let third = stream.write_aligned_slice(&[3; 16], 256);
+
+    assert_eq!(first, 0..64);
+    assert_eq!(second, 256..336);
+    assert_eq!(third, 512..528);
+}
+
+#[cfg(test)]
+#[test]
+pub fn copy_alignment_test() {
+    use wgpu::BufferUsages;
+
+    let mut stream = BufferStream::new(None, BufferUsages::INDEX);
+
+    // Three u16 indices
+    let range = stream.write_aligned_slice(&[1; 6], 4);
+    stream.pad_to_copy_alignment();
+
+    assert_eq!(range, 0..6);
+    assert_eq!(stream.written_size(), 8);
+
+    stream.pad_to_copy_alignment();
+    assert_eq!(stream.written_size(), 8);
+}
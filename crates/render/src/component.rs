@@ -1,15 +1,36 @@
+use storyboard_core::{euclid::Rect, unit::LogicalPixelUnit};
 use wgpu::CommandEncoder;
 
 use std::fmt::Debug;
 
 use crate::renderer::{
     context::{DrawContext, RenderContext},
-    ComponentQueue,
+    Backdrop, ComponentQueue,
 };
 
 use super::renderer::pass::StoryboardRenderPass;
 
-pub trait Drawable: Send {
+/// Sync bound of [Drawable], only required with `parallel` feature which shares drawables across threads
+#[cfg(feature = "parallel")]
+pub trait DrawableSync: Sync {}
+
+#[cfg(feature = "parallel")]
+impl<T: ?Sized + Sync> DrawableSync for T {}
+
+/// Sync bound of [Drawable], only required with `parallel` feature which shares drawables across threads
+#[cfg(not(feature = "parallel"))]
+pub trait DrawableSync {}
+
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> DrawableSync for T {}
+
+pub trait Drawable: Send + DrawableSync {
+    /// Prepare components of drawable and push them into queue.
+    ///
+    /// Renderer prepares every drawable before beginning its render pass, so commands recorded into `encoder`,
+    /// like nested renders into textures, run before components of same frame sample their results.
+    /// With parallel preparing, each chunk records into separate encoder, ordered before the render pass by
+    /// [crate::renderer::StoryboardRenderer::take_prepare_commands].
     fn prepare(
         &self,
         component_queue: &mut ComponentQueue,
@@ -37,10 +58,63 @@ pub trait Component: Send {
         ctx: &RenderContext<'rpass>,
         pass: &mut StoryboardRenderPass<'rpass>,
     );
+
+    /// Check if transparent component reads content drawn before it, see [Component::encode_backdrop]
+    fn reads_backdrop(&self) -> bool {
+        false
+    }
+
+    /// Record commands reading render target content drawn before this component, like copying region to blur.
+    ///
+    /// Renderer ends render pass before the component, calls this and draws the component in new pass loading previous content.
+    /// Only called for transparent components returning true from [Component::reads_backdrop]
+    /// if render target texture is known, see [crate::renderer::StoryboardRenderer::render_with_backdrop].
+    fn encode_backdrop(
+        &self,
+        _ctx: &RenderContext,
+        _encoder: &mut CommandEncoder,
+        _backdrop: &Backdrop,
+    ) {
+    }
+
+    /// Tag attached by drawable which created this component
+    fn tag(&self) -> Option<&ComponentTag> {
+        None
+    }
+
+    /// Name of component type, used for debug groups and grouping components using same pipeline
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Logical screen space bounds covered by component.
+    /// Transparent component without bounds is never reordered, see [crate::renderer::StoryboardRenderer::set_group_transparent].
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        None
+    }
 }
 
 impl Debug for dyn Component {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Component").finish_non_exhaustive()
+        f.debug_struct("Component")
+            .field("tag", &self.tag())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tag identifying component for debugging and picking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ComponentTag {
+    pub debug_label: Option<&'static str>,
+    /// Application defined id of semantic element
+    pub user_id: u64,
+}
+
+impl ComponentTag {
+    pub const fn new(debug_label: Option<&'static str>, user_id: u64) -> Self {
+        Self {
+            debug_label,
+            user_id,
+        }
     }
 }
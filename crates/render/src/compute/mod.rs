@@ -0,0 +1,170 @@
+//! Compute shader integration
+
+pub mod pass;
+
+use std::fmt::Debug;
+
+use trait_stack::TraitStack;
+use wgpu::{BufferUsages, CommandEncoder, ComputePassDescriptor};
+
+use crate::{
+    buffer::stream::{BufferStream, StreamBuffer, StreamRange},
+    shared::RenderScope,
+};
+
+use self::pass::StoryboardComputePass;
+
+/// Compute work submitted with drawables, like particle simulation writing vertex buffer.
+///
+/// Compute tasks run in single compute pass recorded before render pass in same encoder,
+/// so buffers written by them are visible to components of same frame.
+pub trait Dispatchable: Send + Sync {
+    fn prepare(
+        &self,
+        task_queue: &mut ComputeQueue,
+        ctx: &mut ComputeContext,
+        encoder: &mut CommandEncoder,
+    );
+}
+
+impl Debug for dyn Dispatchable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dispatchable").finish_non_exhaustive()
+    }
+}
+
+pub trait ComputeTask: Send {
+    fn dispatch<'pass>(
+        &'pass self,
+        ctx: &DispatchContext<'pass>,
+        pass: &mut StoryboardComputePass<'pass>,
+    );
+}
+
+impl Debug for dyn ComputeTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComputeTask").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub struct ComputeQueue<'a> {
+    tasks: &'a mut TraitStack<dyn ComputeTask>,
+}
+
+impl<'a> ComputeQueue<'a> {
+    pub fn new(tasks: &'a mut TraitStack<dyn ComputeTask>) -> Self {
+        Self { tasks }
+    }
+
+    /// Push task. Tasks are dispatched in push order.
+    pub fn push(&mut self, task: impl ComputeTask + 'static) {
+        self.tasks.push(task);
+    }
+}
+
+/// [ComputeContext] contains render scope and uniform stream for compute task preparing
+#[derive(Debug)]
+pub struct ComputeContext<'a> {
+    pub scope: RenderScope<'a>,
+
+    pub uniform_stream: &'a mut BufferStream<'static>,
+}
+
+impl<'a> ComputeContext<'a> {
+    /// Write uniform data aligned to device uniform offset alignment.
+    /// Returned range start can be used as dynamic offset of bind group binding [DispatchContext::uniform_stream].
+    pub fn write_uniform(&mut self, data: &[u8]) -> StreamRange {
+        let alignment = self
+            .scope
+            .backend()
+            .device()
+            .limits()
+            .min_uniform_buffer_offset_alignment;
+
+        self.uniform_stream
+            .write_aligned_slice(data, alignment as usize)
+    }
+
+    pub fn into_dispatch_context(self) -> DispatchContext<'a> {
+        let backend = self.scope.backend();
+        let uniform_stream = self
+            .uniform_stream
+            .finish(backend.device(), backend.queue());
+
+        DispatchContext {
+            scope: self.scope,
+            uniform_stream,
+        }
+    }
+}
+
+/// [DispatchContext] contains render scope and uploaded uniform stream for compute task dispatching
+#[derive(Debug)]
+pub struct DispatchContext<'a> {
+    pub scope: RenderScope<'a>,
+
+    pub uniform_stream: StreamBuffer<'a>,
+}
+
+/// Dispatch compute tasks of [Dispatchable] in single compute pass
+#[derive(Debug)]
+pub struct StoryboardComputer {
+    uniform_stream: BufferStream<'static>,
+    tasks: TraitStack<dyn ComputeTask>,
+}
+
+impl StoryboardComputer {
+    pub fn new() -> Self {
+        Self {
+            uniform_stream: BufferStream::new(
+                Some("StoryboardComputer uniform stream buffer".into()),
+                BufferUsages::UNIFORM,
+            ),
+            tasks: TraitStack::new(),
+        }
+    }
+
+    /// Prepare dispatchables and record compute pass into encoder.
+    /// Record render passes using the results after this call.
+    pub fn dispatch<'a>(
+        &mut self,
+        scope: RenderScope,
+        dispatchables: impl Iterator<Item = &'a dyn Dispatchable>,
+        encoder: &mut CommandEncoder,
+    ) {
+        let mut ctx = ComputeContext {
+            scope,
+            uniform_stream: &mut self.uniform_stream,
+        };
+
+        {
+            let mut task_queue = ComputeQueue::new(&mut self.tasks);
+
+            for dispatchable in dispatchables {
+                dispatchable.prepare(&mut task_queue, &mut ctx, encoder);
+            }
+        }
+
+        let ctx = ctx.into_dispatch_context();
+
+        if !self.tasks.is_empty() {
+            let mut pass =
+                StoryboardComputePass::new(encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("StoryboardComputer compute pass"),
+                }));
+
+            for task in self.tasks.iter() {
+                task.dispatch(&ctx, &mut pass);
+            }
+        }
+
+        self.tasks.clear();
+    }
+}
+
+impl Default for StoryboardComputer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
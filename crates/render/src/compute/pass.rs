@@ -0,0 +1,105 @@
+use std::hash::BuildHasherDefault;
+
+use rustc_hash::FxHashMap;
+use wgpu::{BindGroup, Buffer, BufferAddress, ComputePass, ComputePipeline, DynamicOffset};
+
+#[derive(Debug)]
+pub struct StoryboardComputePass<'a> {
+    pass: ComputePass<'a>,
+
+    current_bind_groups: FxHashMap<u32, (&'a BindGroup, usize)>,
+
+    current_pipeline: Option<&'a ComputePipeline>,
+}
+
+impl<'a> StoryboardComputePass<'a> {
+    pub fn new(pass: ComputePass<'a>) -> Self {
+        Self {
+            pass,
+
+            current_pipeline: None,
+
+            current_bind_groups: FxHashMap::with_capacity_and_hasher(
+                8,
+                BuildHasherDefault::default(),
+            ),
+        }
+    }
+
+    pub fn set_pipeline(&mut self, pipeline: &'a ComputePipeline) {
+        if let Some(current_pipeline) = &self.current_pipeline {
+            if std::ptr::eq(*current_pipeline, pipeline) {
+                return;
+            }
+        }
+
+        self.current_pipeline = Some(pipeline);
+
+        self.current_bind_groups.clear();
+
+        self.pass.set_pipeline(pipeline)
+    }
+
+    pub fn set_bind_group(
+        &mut self,
+        index: u32,
+        bind_group: &'a BindGroup,
+        offsets: &[DynamicOffset],
+    ) {
+        let offsets_ptr = offsets.as_ptr() as usize;
+
+        if let Some((current_group, current_offsets_ptr)) = self.current_bind_groups.get(&index) {
+            if std::ptr::eq(bind_group, *current_group) && offsets_ptr == *current_offsets_ptr {
+                return;
+            }
+        }
+
+        self.current_bind_groups
+            .insert(index, (bind_group, offsets_ptr));
+
+        self.pass.set_bind_group(index, bind_group, offsets)
+    }
+
+    #[inline(always)]
+    pub fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        self.pass.dispatch_workgroups(x, y, z)
+    }
+
+    #[inline(always)]
+    pub fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &'a Buffer,
+        indirect_offset: BufferAddress,
+    ) {
+        self.pass
+            .dispatch_workgroups_indirect(indirect_buffer, indirect_offset)
+    }
+
+    #[inline(always)]
+    pub fn set_push_constants(&mut self, offset: u32, data: &[u8]) {
+        self.pass.set_push_constants(offset, data)
+    }
+
+    #[inline(always)]
+    pub fn push_debug_group(&mut self, label: &str) {
+        self.pass.push_debug_group(label)
+    }
+
+    #[inline(always)]
+    pub fn pop_debug_group(&mut self) {
+        self.pass.pop_debug_group()
+    }
+
+    #[inline(always)]
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        self.pass.insert_debug_marker(label)
+    }
+
+    /// Raw wgpu compute pass. Cached pipeline and bind group state is discarded, so next task sets them again.
+    pub fn raw_mut(&mut self) -> &mut ComputePass<'a> {
+        self.current_pipeline = None;
+        self.current_bind_groups.clear();
+
+        &mut self.pass
+    }
+}
@@ -0,0 +1,295 @@
+//! Automatic internal resolution scaling holding target frame time
+
+use std::time::Duration;
+
+use storyboard_core::euclid::Size2D;
+
+use crate::{
+    renderer::surface::{InternalResolution, RenderResolution},
+    task::RenderTask,
+};
+
+/// Controller lowering internal render resolution when frames take longer than target and raising it back when headroom returns.
+///
+/// Frame time includes waiting for vsync and fps limit, so frames never finish faster than refresh interval with vsync enabled.
+/// Use with present mode without vsync, or target frame time above refresh interval.
+///
+/// Internal resolution configured by user is kept as base and multiplied by scale.
+#[derive(Debug, Clone)]
+pub struct DynamicResolution {
+    pub target_frame_time: Duration,
+
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Scale change of one adjustment
+    pub step: f32,
+
+    /// Relative band around target frame time, scale is kept while average frame time is in it
+    pub hysteresis: f32,
+    /// Updates skipped after adjustment, so average frame time catches up with new scale
+    pub cooldown: u32,
+
+    scale: f32,
+    average: Option<f32>,
+    remaining_cooldown: u32,
+
+    base: Option<InternalResolution>,
+    /// Internal resolution written by last apply, None before first apply
+    applied: Option<Option<InternalResolution>>,
+}
+
+impl DynamicResolution {
+    /// Weight of new sample in average frame time
+    pub const SMOOTHING: f32 = 0.1;
+
+    pub const fn new(target_frame_time: Duration) -> Self {
+        Self {
+            target_frame_time,
+
+            min_scale: 0.5,
+            max_scale: 1.0,
+            step: 0.1,
+
+            hysteresis: 0.15,
+            cooldown: 30,
+
+            scale: 1.0,
+            average: None,
+            remaining_cooldown: 0,
+
+            base: None,
+            applied: None,
+        }
+    }
+
+    /// Current internal resolution scale
+    pub const fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Internal resolution configured by user, which scale is applied to
+    pub const fn base_resolution(&self) -> Option<InternalResolution> {
+        self.base
+    }
+
+    /// Average frame time of recent samples
+    pub fn average_frame_time(&self) -> Option<Duration> {
+        self.average.map(Duration::from_secs_f32)
+    }
+
+    /// Add frame time sample. Returns new scale if it changed.
+    pub fn update(&mut self, frame_time: Duration) -> Option<f32> {
+        let frame_time = frame_time.as_secs_f32();
+        let average = match self.average {
+            Some(average) => average + (frame_time - average) * Self::SMOOTHING,
+            None => frame_time,
+        };
+        self.average = Some(average);
+
+        if self.remaining_cooldown > 0 {
+            self.remaining_cooldown -= 1;
+            return None;
+        }
+
+        let target = self.target_frame_time.as_secs_f32();
+        let scale = if average > target * (1.0 + self.hysteresis) {
+            self.scale - self.step
+        } else if average < target * (1.0 - self.hysteresis) {
+            self.scale + self.step
+        } else {
+            return None;
+        };
+
+        // Round away accumulated float error, so bounds are reached exactly
+        let scale = ((scale * 1000.0).round() / 1000.0).clamp(self.min_scale, self.max_scale);
+
+        if scale == self.scale {
+            return None;
+        }
+
+        self.scale = scale;
+        self.remaining_cooldown = self.cooldown;
+
+        Some(scale)
+    }
+
+    /// Sample last frame time of render task and apply scale to its internal resolution.
+    /// Internal resolution changed by user since last apply becomes new base. Call once every update.
+    pub fn apply(&mut self, render_task: &RenderTask) -> Option<f32> {
+        let changed = self.update(render_task.stats().last_frame);
+
+        let current = render_task.configuration().surface.internal_resolution;
+        if self.applied != Some(current) {
+            self.base = current;
+        }
+
+        let resolution = scale_resolution(self.base, self.scale);
+        if resolution != current {
+            render_task.configuration_mut().surface.internal_resolution = resolution;
+        }
+        self.applied = Some(resolution);
+
+        changed
+    }
+}
+
+/// Multiply base internal resolution by scale
+fn scale_resolution(base: Option<InternalResolution>, scale: f32) -> Option<InternalResolution> {
+    if scale == 1.0 {
+        return base;
+    }
+
+    let base = match base {
+        Some(base) => base,
+        None => return Some(InternalResolution::scaled(scale)),
+    };
+
+    Some(match base.resolution {
+        RenderResolution::Scale(base_scale) => InternalResolution {
+            resolution: RenderResolution::Scale(base_scale * scale),
+            ..base
+        },
+
+        // Box filter only downsamples integer factor
+        RenderResolution::Supersample(factor) => {
+            InternalResolution::scaled(factor.get() as f32 * scale)
+        }
+
+        RenderResolution::Fixed(size) => InternalResolution {
+            resolution: RenderResolution::Fixed(
+                (size.cast::<f32>() * scale)
+                    .round()
+                    .cast::<u32>()
+                    .max(Size2D::new(1, 1)),
+            ),
+            ..base
+        },
+    })
+}
+
+impl Default for DynamicResolution {
+    /// Target 60 frames per second
+    fn default() -> Self {
+        Self::new(Duration::from_micros(16_667))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU32, time::Duration};
+
+    use storyboard_core::euclid::Size2D;
+
+    use crate::renderer::surface::{InternalResolution, RenderResolution};
+
+    use super::{scale_resolution, DynamicResolution};
+
+    #[test]
+    fn dynamic_resolution_test() {
+        let mut resolution = DynamicResolution::new(Duration::from_millis(16));
+
+        // Slow frames lower scale step by step down to minimum
+        let mut changes = vec![];
+        for _ in 0..500 {
+            changes.extend(resolution.update(Duration::from_millis(30)));
+        }
+        assert_eq!(changes.len(), 5);
+        assert_eq!(resolution.scale(), resolution.min_scale);
+
+        // Frame time inside of hysteresis band keeps scale
+        for _ in 0..500 {
+            assert_eq!(resolution.update(Duration::from_millis(17)), None);
+        }
+
+        // Raised back up to maximum when headroom returns
+        for _ in 0..500 {
+            resolution.update(Duration::from_millis(8));
+        }
+        assert_eq!(resolution.scale(), resolution.max_scale);
+    }
+
+    #[test]
+    fn hysteresis_test() {
+        let mut resolution = DynamicResolution::new(Duration::from_millis(10));
+        resolution.cooldown = 0;
+
+        // Just inside of upper and lower band edge
+        for _ in 0..100 {
+            assert_eq!(resolution.update(Duration::from_micros(11_400)), None);
+        }
+
+        let mut resolution = DynamicResolution::new(Duration::from_millis(10));
+        resolution.cooldown = 0;
+        resolution.max_scale = 2.0;
+        for _ in 0..100 {
+            assert_eq!(resolution.update(Duration::from_micros(8_600)), None);
+        }
+
+        // Leaving band changes scale on first update without cooldown
+        assert_eq!(resolution.update(Duration::from_millis(100)), Some(0.9));
+        assert_eq!(resolution.scale(), 0.9);
+    }
+
+    #[test]
+    fn cooldown_test() {
+        let mut resolution = DynamicResolution::new(Duration::from_millis(10));
+        resolution.cooldown = 3;
+
+        assert_eq!(resolution.update(Duration::from_millis(20)), Some(0.9));
+        for _ in 0..3 {
+            assert_eq!(resolution.update(Duration::from_millis(20)), None);
+        }
+        assert_eq!(resolution.update(Duration::from_millis(20)), Some(0.8));
+    }
+
+    #[test]
+    fn clamp_test() {
+        let mut resolution = DynamicResolution::new(Duration::from_millis(10));
+        resolution.cooldown = 0;
+        resolution.step = 0.3;
+        resolution.min_scale = 0.5;
+
+        // Step past minimum stops at minimum, then stays there
+        assert_eq!(resolution.update(Duration::from_millis(20)), Some(0.7));
+        assert_eq!(resolution.update(Duration::from_millis(20)), Some(0.5));
+        assert_eq!(resolution.update(Duration::from_millis(20)), None);
+        assert_eq!(resolution.scale(), 0.5);
+
+        // Step past maximum stops at maximum
+        let mut changes = vec![];
+        for _ in 0..100 {
+            changes.extend(resolution.update(Duration::from_millis(1)));
+        }
+        assert_eq!(changes, [0.8, 1.0]);
+        assert_eq!(resolution.scale(), resolution.max_scale);
+    }
+
+    #[test]
+    fn scale_resolution_test() {
+        assert_eq!(scale_resolution(None, 1.0), None);
+        assert_eq!(
+            scale_resolution(None, 0.5),
+            Some(InternalResolution::scaled(0.5))
+        );
+
+        // User resolution is kept as base and multiplied
+        let base = InternalResolution::scaled(0.8);
+        assert_eq!(scale_resolution(Some(base), 1.0), Some(base));
+        assert_eq!(
+            scale_resolution(Some(base), 0.5).unwrap().resolution,
+            RenderResolution::Scale(0.4)
+        );
+
+        let fixed = InternalResolution::fixed(Size2D::new(320, 180));
+        assert_eq!(
+            scale_resolution(Some(fixed), 0.5),
+            Some(InternalResolution::fixed(Size2D::new(160, 90)))
+        );
+
+        let supersampled = InternalResolution::supersampled(NonZeroU32::new(2).unwrap());
+        assert_eq!(
+            scale_resolution(Some(supersampled), 0.75),
+            Some(InternalResolution::scaled(1.5))
+        );
+    }
+}
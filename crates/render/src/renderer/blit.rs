@@ -0,0 +1,295 @@
+use std::borrow::Cow;
+
+use storyboard_core::{
+    euclid::{Point2D, Rect, Size2D},
+    store::{Store, StoreResources},
+    unit::PhyiscalPixelUnit,
+};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Color,
+    ColorTargetState, ColorWrites, CommandEncoder, Device, FilterMode, FragmentState, LoadOp,
+    MultisampleState, Operations, PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderModule, ShaderModuleDescriptor,
+    ShaderSource, ShaderStages, TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{cache::shader::ShaderCache, shared::RenderScopeContext};
+
+/// Pipeline copying texture into region of render target, scaling it to fit
+#[derive(Debug)]
+pub struct BlitResources {
+    pub pipeline: RenderPipeline,
+    /// Pipeline averaging source texels covered by each target pixel. Source size must be integer multiple of viewport size.
+    pub downsample_pipeline: RenderPipeline,
+    pub bind_group_layout: BindGroupLayout,
+
+    pub nearest_sampler: Sampler,
+    pub linear_sampler: Sampler,
+}
+
+impl BlitResources {
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        source: &TextureView,
+        filter: FilterMode,
+    ) -> BindGroup {
+        let sampler = match filter {
+            FilterMode::Nearest => &self.nearest_sampler,
+            FilterMode::Linear => &self.linear_sampler,
+        };
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Blit bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(source),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Draw source bind group into viewport of target. Outside of viewport is cleared with clear color.
+    pub fn blit_pass(
+        &self,
+        encoder: &mut CommandEncoder,
+        source: &BindGroup,
+        target: &TextureView,
+        viewport: Rect<u32, PhyiscalPixelUnit>,
+        clear_color: Color,
+    ) {
+        Self::draw_pass(
+            &self.pipeline,
+            encoder,
+            source,
+            target,
+            viewport,
+            clear_color,
+        )
+    }
+
+    /// Downsample supersampled source into viewport of target using box filter.
+    /// Outside of viewport is cleared with clear color.
+    pub fn downsample_pass(
+        &self,
+        encoder: &mut CommandEncoder,
+        source: &BindGroup,
+        target: &TextureView,
+        viewport: Rect<u32, PhyiscalPixelUnit>,
+        clear_color: Color,
+    ) {
+        Self::draw_pass(
+            &self.downsample_pipeline,
+            encoder,
+            source,
+            target,
+            viewport,
+            clear_color,
+        )
+    }
+
+    fn draw_pass(
+        pipeline: &RenderPipeline,
+        encoder: &mut CommandEncoder,
+        source: &BindGroup,
+        target: &TextureView,
+        viewport: Rect<u32, PhyiscalPixelUnit>,
+        clear_color: Color,
+    ) {
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Blit render pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(clear_color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        if viewport.is_empty() {
+            return;
+        }
+
+        pass.set_viewport(
+            viewport.origin.x as f32,
+            viewport.origin.y as f32,
+            viewport.size.width as f32,
+            viewport.size.height as f32,
+            0.0,
+            1.0,
+        );
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, source, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+impl StoreResources<RenderScopeContext<'_>> for BlitResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let device = ctx.backend.device();
+
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("blit_shader", || init_blit_shader(device));
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Blit bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Blit pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label, fragment_entry_point| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: fragment_entry_point,
+                    targets: &[Some(ColorTargetState {
+                        format: ctx.pipeline.texture_format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                depth_stencil: None,
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    ..PrimitiveState::default()
+                },
+                multisample: MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        let pipeline = create_pipeline("Blit pipeline", "fs_main");
+        let downsample_pipeline = create_pipeline("Blit downsample pipeline", "fs_downsample");
+
+        let nearest_sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Blit nearest sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+
+            ..Default::default()
+        });
+
+        let linear_sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Blit linear sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            downsample_pipeline,
+            bind_group_layout,
+            nearest_sampler,
+            linear_sampler,
+        }
+    }
+}
+
+pub fn init_blit_shader(device: &Device) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Blit shader"),
+        source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("blit.wgsl"))),
+    })
+}
+
+/// Largest rect having aspect ratio of source, centered in target.
+/// Remaining area of target forms letterbox or pillarbox bars.
+pub fn letterbox(
+    source: Size2D<u32, PhyiscalPixelUnit>,
+    target: Size2D<u32, PhyiscalPixelUnit>,
+) -> Rect<u32, PhyiscalPixelUnit> {
+    if source.is_empty() || target.is_empty() {
+        return Rect::zero();
+    }
+
+    let scale = (target.width as f32 / source.width as f32)
+        .min(target.height as f32 / source.height as f32);
+
+    let size = (source.cast::<f32>() * scale)
+        .round()
+        .cast::<u32>()
+        .min(target);
+
+    Rect::new(
+        Point2D::new(
+            (target.width - size.width) / 2,
+            (target.height - size.height) / 2,
+        ),
+        size,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D};
+
+    use super::letterbox;
+
+    #[test]
+    fn letterbox_test() {
+        // Same aspect ratio fills target
+        assert_eq!(
+            letterbox(Size2D::new(320, 180), Size2D::new(1280, 720)),
+            Rect::new(Point2D::zero(), Size2D::new(1280, 720))
+        );
+
+        // Wider target, pillarbox
+        assert_eq!(
+            letterbox(Size2D::new(320, 240), Size2D::new(1280, 720)),
+            Rect::new(Point2D::new(160, 0), Size2D::new(960, 720))
+        );
+
+        // Taller target, letterbox
+        assert_eq!(
+            letterbox(Size2D::new(320, 180), Size2D::new(800, 800)),
+            Rect::new(Point2D::new(0, 175), Size2D::new(800, 450))
+        );
+    }
+}
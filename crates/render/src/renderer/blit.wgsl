@@ -0,0 +1,46 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) texture_coord: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var texture: texture_2d<f32>;
+@group(0) @binding(1)
+var texture_sampler: sampler;
+
+// Fullscreen triangle, clipped to viewport
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let coord = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.position = vec4<f32>(coord.x * 2.0 - 1.0, 1.0 - coord.y * 2.0, 0.0, 1.0);
+    out.texture_coord = coord;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(texture, texture_sampler, in.texture_coord);
+}
+
+// Box filter averaging every source texel covered by target pixel, for supersampled rendering
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dimensions = textureDimensions(texture);
+
+    // Texture coord spans whole viewport, so its derivative gives source texels per target pixel
+    let factor = max(vec2<i32>(round(vec2<f32>(dpdx(in.texture_coord.x), dpdy(in.texture_coord.y)) * vec2<f32>(dimensions))), vec2<i32>(1, 1));
+    let origin = vec2<i32>(floor(in.texture_coord * vec2<f32>(dimensions) / vec2<f32>(factor))) * factor;
+
+    var color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    for (var y = 0; y < factor.y; y = y + 1) {
+        for (var x = 0; x < factor.x; x = x + 1) {
+            color += textureLoad(texture, min(origin + vec2<i32>(x, y), dimensions - vec2<i32>(1, 1)), 0);
+        }
+    }
+
+    return color / f32(factor.x * factor.y);
+}
@@ -0,0 +1,213 @@
+use std::borrow::Cow;
+
+use bytemuck::{Pod, Zeroable};
+use storyboard_core::{
+    euclid::{Point2D, Rect},
+    store::{Store, StoreResources},
+    unit::LogicalPixelUnit,
+};
+use wgpu::{
+    vertex_attr_array, ColorTargetState, ColorWrites, CompareFunction, DepthStencilState, Device,
+    FragmentState, MultisampleState, PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource,
+    StencilFaceState, StencilOperation, StencilState, VertexBufferLayout, VertexState,
+    VertexStepMode,
+};
+
+use crate::{
+    buffer::stream::StreamRange, cache::shader::ShaderCache, component::Component,
+    shared::RenderScopeContext,
+};
+
+use super::{
+    context::{DrawContext, RenderContext},
+    pass::StoryboardRenderPass,
+    screen::ScreenResources,
+};
+
+/// Pipelines writing stencil of clip shapes.
+/// Component pipelines only draw fragments having stencil equal to nesting level of current clip.
+#[derive(Debug)]
+pub struct ClipResources {
+    /// Increment stencil inside of clip shape when entering clip
+    pub push_pipeline: RenderPipeline,
+    /// Decrement stencil inside of clip shape when leaving clip
+    pub pop_pipeline: RenderPipeline,
+}
+
+impl StoreResources<RenderScopeContext<'_>> for ClipResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let device = ctx.backend.device();
+
+        let shader = ctx
+            .backend
+            .get::<ShaderCache>()
+            .get_or_create("clip_shader", || init_clip_shader(device));
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Clip pipeline layout"),
+            bind_group_layouts: &[&ctx.backend.get::<ScreenResources>().bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let depth_stencil = ctx
+            .pipeline
+            .depth_stencil
+            .as_ref()
+            .expect("Stencil clipping requires depth stencil texture");
+
+        let create_pipeline = |label, pass_op| {
+            let face = StencilFaceState {
+                compare: CompareFunction::Equal,
+                fail_op: StencilOperation::Keep,
+                depth_fail_op: StencilOperation::Keep,
+                pass_op,
+            };
+
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[VertexBufferLayout {
+                        array_stride: std::mem::size_of::<ClipVertex>() as u64,
+                        step_mode: VertexStepMode::Vertex,
+                        attributes: &vertex_attr_array![
+                            0 => Float32x2,
+                            1 => Float32x2,
+                            2 => Float32x2,
+                            3 => Float32
+                        ],
+                    }],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(ColorTargetState {
+                        format: ctx.pipeline.texture_format,
+                        blend: None,
+                        write_mask: ColorWrites::empty(),
+                    })],
+                }),
+                depth_stencil: Some(DepthStencilState {
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Always,
+                    stencil: StencilState {
+                        front: face,
+                        back: face,
+                        read_mask: !0,
+                        write_mask: !0,
+                    },
+                    ..depth_stencil.clone()
+                }),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleStrip,
+                    ..PrimitiveState::default()
+                },
+                multisample: MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        Self {
+            push_pipeline: create_pipeline("Clip push pipeline", StencilOperation::IncrementClamp),
+            pop_pipeline: create_pipeline("Clip pop pipeline", StencilOperation::DecrementClamp),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct ClipVertex {
+    pub position: Point2D<f32, LogicalPixelUnit>,
+    /// Position relative to center of clip rect
+    pub local: [f32; 2],
+    pub half_size: [f32; 2],
+    pub radius: f32,
+}
+
+/// Component entering or leaving stencil clip, see [crate::renderer::ComponentQueue::push_rounded_clip]
+#[derive(Debug)]
+pub struct ClipComponent {
+    vertices_slice: StreamRange,
+    /// Nesting level of clip outside of this one
+    level: u32,
+    pop: bool,
+}
+
+impl ClipComponent {
+    pub(crate) const fn new(vertices_slice: StreamRange, level: u32, pop: bool) -> Self {
+        Self {
+            vertices_slice,
+            level,
+            pop,
+        }
+    }
+
+    /// Write vertices of rounded rect clip shape. Radius is limited to half of shorter side.
+    pub(crate) fn write_rounded_rect(
+        ctx: &mut DrawContext,
+        rect: Rect<f32, LogicalPixelUnit>,
+        radius: f32,
+    ) -> StreamRange {
+        let half_size = [rect.width() / 2.0, rect.height() / 2.0];
+        let radius = radius.max(0.0).min(half_size[0].min(half_size[1]));
+        let center = rect.center();
+
+        let vertices = [
+            rect.origin,
+            Point2D::new(rect.min_x(), rect.max_y()),
+            Point2D::new(rect.max_x(), rect.min_y()),
+            rect.max(),
+        ]
+        .map(|point| ClipVertex {
+            position: ctx.to_screen_point(point),
+            local: (point - center).to_array(),
+            half_size,
+            radius,
+        });
+
+        ctx.vertex_stream
+            .write_slice(bytemuck::cast_slice(&vertices))
+    }
+}
+
+impl Component for ClipComponent {
+    fn render_opaque<'rpass>(
+        &'rpass self,
+        _: &RenderContext<'rpass>,
+        _: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        // Always pushed as transparent, so it's ordered with clipped components
+    }
+
+    fn render_transparent<'rpass>(
+        &'rpass self,
+        ctx: &RenderContext<'rpass>,
+        pass: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        let resources = ctx.scope.get::<ClipResources>();
+
+        let (pipeline, reference, next) = if self.pop {
+            (&resources.pop_pipeline, self.level + 1, self.level)
+        } else {
+            (&resources.push_pipeline, self.level, self.level + 1)
+        };
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, ctx.screen_bind_group, &[]);
+        pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.vertices_slice.clone()));
+
+        pass.set_stencil_reference(reference);
+        pass.draw(0..4, 0..1);
+        pass.set_stencil_reference(next);
+    }
+}
+
+pub fn init_clip_shader(device: &Device) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Clip shader"),
+        source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("clip.wgsl"))),
+    })
+}
@@ -0,0 +1,42 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) half_size: vec2<f32>,
+    @location(2) @interpolate(flat) radius: f32,
+};
+
+struct Screen {
+    matrix: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> screen: Screen;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) local: vec2<f32>,
+    @location(2) half_size: vec2<f32>,
+    @location(3) radius: f32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.position = vec4<f32>((screen.matrix * vec4<f32>(position, 0.0, 1.0)).xy, 0.0, 1.0);
+    out.local = local;
+    out.half_size = half_size;
+    out.radius = radius;
+
+    return out;
+}
+
+// Color is never written, only stencil of covered fragments is updated
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let corner = abs(in.local) - in.half_size + vec2<f32>(in.radius);
+    let distance = length(max(corner, vec2<f32>(0.0))) + min(max(corner.x, corner.y), 0.0) - in.radius;
+
+    if (distance > 0.0) {
+        discard;
+    }
+
+    return vec4<f32>(0.0);
+}
@@ -1,10 +1,11 @@
 use storyboard_core::{
-    euclid::Transform3D,
+    euclid::{Point2D, Transform3D},
     unit::{LogicalPixelUnit, RenderUnit},
 };
+use wgpu::BindGroup;
 
 use crate::{
-    buffer::stream::{BufferStream, StreamBuffer},
+    buffer::stream::{BufferStream, StreamBuffer, StreamRange},
     ScreenRect, shared::RenderScope,
 };
 
@@ -18,10 +19,48 @@ pub struct DrawContext<'a> {
 
     pub vertex_stream: &'a mut BufferStream<'static>,
     pub index_stream: &'a mut BufferStream<'static>,
+    pub uniform_stream: &'a mut BufferStream<'static>,
 }
 
 impl<'a> DrawContext<'a> {
-    pub fn into_render_context(self) -> RenderContext<'a> {
+    /// Logical point written into vertex, transformed by screen uniform in vertex shader.
+    /// Point is snapped to physical pixel if pixel snapping is enabled on screen.
+    pub fn to_screen_point(
+        &self,
+        point: Point2D<f32, LogicalPixelUnit>,
+    ) -> Point2D<f32, LogicalPixelUnit> {
+        self.screen.snap_point(point)
+    }
+
+    /// Transform logical point into render space.
+    /// Point is snapped to physical pixel first if pixel snapping is enabled on screen.
+    ///
+    /// Use for components with pipelines not binding screen uniform.
+    /// Built-in components write [DrawContext::to_screen_point] instead and bind [RenderContext::screen_bind_group],
+    /// see [crate::renderer::screen::ScreenUniform].
+    pub fn to_render_point(
+        &self,
+        point: Point2D<f32, LogicalPixelUnit>,
+    ) -> Option<Point2D<f32, RenderUnit>> {
+        self.screen_matrix
+            .transform_point2d(self.screen.snap_point(point))
+    }
+
+    /// Write uniform data aligned to device uniform offset alignment.
+    /// Returned range start can be used as dynamic offset of bind group binding [RenderContext::uniform_stream].
+    pub fn write_uniform(&mut self, data: &[u8]) -> StreamRange {
+        let alignment = self
+            .scope
+            .backend()
+            .device()
+            .limits()
+            .min_uniform_buffer_offset_alignment;
+
+        self.uniform_stream
+            .write_aligned_slice(data, alignment as usize)
+    }
+
+    pub fn into_render_context(self, screen_bind_group: &'a BindGroup) -> RenderContext<'a> {
         let backend = self.scope.backend();
         let vertex_stream = self
             .vertex_stream
@@ -29,11 +68,17 @@ impl<'a> DrawContext<'a> {
         let index_stream = self
             .index_stream
             .finish(backend.device(), backend.queue());
+        let uniform_stream = self
+            .uniform_stream
+            .finish(backend.device(), backend.queue());
 
         RenderContext {
             scope: self.scope,
+            screen: self.screen,
+            screen_bind_group,
             vertex_stream,
             index_stream,
+            uniform_stream,
         }
     }
 }
@@ -43,6 +88,12 @@ impl<'a> DrawContext<'a> {
 pub struct RenderContext<'a> {
     pub scope: RenderScope<'a>,
 
+    pub screen: ScreenRect,
+
+    /// Bind group of [crate::renderer::screen::ScreenUniform] for current render pass
+    pub screen_bind_group: &'a BindGroup,
+
     pub vertex_stream: StreamBuffer<'a>,
     pub index_stream: StreamBuffer<'a>,
+    pub uniform_stream: StreamBuffer<'a>,
 }
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use storyboard_core::{
+    euclid::Size2D,
+    store::{Store, StoreResources},
+    unit::PhyiscalPixelUnit,
+};
+use wgpu::{Device, TextureFormat, TextureUsages};
+
+use crate::{
+    shared::BackendScopeContext,
+    texture::{SizedTexture2D, SizedTextureView2D},
+};
+
+/// Depth textures shared by renderers drawing offscreen, like buffered drawables.
+///
+/// Depth attachment must have same size as color attachment, so texture is shared between renderers with same target size and format.
+/// Every render clears depth before drawing, so renders recorded one after another can use same texture.
+///
+/// Pool keeps at most [DepthTexturePool::MAX_TEXTURES] textures. Least recently acquired texture is dropped to make room,
+/// preferring textures not acquired since last [DepthTexturePool::evict_idle].
+#[derive(Debug, Default)]
+pub struct DepthTexturePool {
+    entries: Mutex<Vec<PooledDepthTexture>>,
+}
+
+#[derive(Debug)]
+struct PooledDepthTexture {
+    view: Arc<SizedTextureView2D>,
+    format: TextureFormat,
+
+    /// Acquired since last [DepthTexturePool::evict_idle]
+    used: bool,
+}
+
+impl DepthTexturePool {
+    pub const MAX_TEXTURES: usize = 8;
+
+    /// Get depth texture of given size and format, creating new one if there is none
+    pub fn acquire(
+        &self,
+        device: &Device,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: TextureFormat,
+    ) -> Arc<SizedTextureView2D> {
+        let mut entries = self.entries.lock();
+
+        // Entries are kept in order of last acquire
+        if let Some(index) = entries
+            .iter()
+            .position(|entry| entry.format == format && entry.view.size() == size)
+        {
+            let mut entry = entries.remove(index);
+            entry.used = true;
+
+            let view = entry.view.clone();
+            entries.push(entry);
+            return view;
+        }
+
+        if entries.len() >= Self::MAX_TEXTURES {
+            let index = entries
+                .iter()
+                .position(|entry| !entry.used)
+                .unwrap_or_default();
+
+            entries.remove(index);
+        }
+
+        let view = Arc::new(
+            SizedTexture2D::init(
+                device,
+                Some("DepthTexturePool depth texture"),
+                size,
+                format,
+                TextureUsages::RENDER_ATTACHMENT,
+            )
+            .create_view_default(None),
+        );
+
+        entries.push(PooledDepthTexture {
+            view: view.clone(),
+            format,
+            used: true,
+        });
+
+        view
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+
+    /// Drop textures not acquired since last call and returns number of dropped textures.
+    /// Called once every frame by [crate::task::RenderTask].
+    pub fn evict_idle(&self) -> usize {
+        let mut entries = self.entries.lock();
+        let len = entries.len();
+
+        entries.retain(|entry| entry.used);
+        for entry in entries.iter_mut() {
+            entry.used = false;
+        }
+
+        len - entries.len()
+    }
+}
+
+impl StoreResources<BackendScopeContext<'_>> for DepthTexturePool {
+    fn initialize(_: &Store, _: &BackendScopeContext) -> Self {
+        Self::default()
+    }
+}
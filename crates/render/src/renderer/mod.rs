@@ -1,23 +1,53 @@
+pub mod blit;
+pub mod clip;
 pub mod context;
+pub mod depth;
 pub mod pass;
+#[cfg(feature = "debug-report")]
+pub mod report;
+pub mod screen;
+pub mod stack;
+pub mod stats;
 pub mod surface;
 
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+    mem,
+    sync::Arc,
+};
+
+#[cfg(feature = "parallel")]
+use std::num::NonZeroUsize;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use storyboard_core::{
-    euclid::{Rect, Transform3D},
+    euclid::{Rect, Size2D, Transform3D},
     unit::{LogicalPixelUnit, PhyiscalPixelUnit, RenderUnit},
 };
-use trait_stack::TraitStack;
 use wgpu::{
-    CompareFunction, DepthBiasState, DepthStencilState, Device, MultisampleState, StencilFaceState,
-    StencilState, TextureFormat,
+    BindGroup, Color, CommandBuffer, CompareFunction, DepthBiasState, DepthStencilState, Device,
+    FrontFace, MultisampleState, StencilFaceState, StencilOperation, StencilState, Texture,
+    TextureFormat, TextureView,
 };
 
-use self::{context::DrawContext, pass::StoryboardRenderPass};
+use self::{
+    clip::ClipComponent,
+    context::{DrawContext, RenderContext},
+    depth::DepthTexturePool,
+    pass::StoryboardRenderPass,
+    screen::{ScreenBuffer, ScreenResources},
+    stack::ComponentStack,
+    stats::RenderStats,
+};
 
 use super::{
-    buffer::stream::BufferStream,
+    buffer::stream::{BufferStream, StreamRange},
     texture::{SizedTexture2D, SizedTextureView2D},
 };
 
@@ -33,88 +63,242 @@ use crate::{
 
 #[derive(Debug)]
 pub struct StoryboardRenderer {
-    current_screen_rect: Rect<u32, PhyiscalPixelUnit>,
+    current_screen: Option<ScreenRect>,
     screen_matrix: Transform3D<f32, LogicalPixelUnit, RenderUnit>,
+    screen_buffer: Option<ScreenBuffer>,
 
-    opaque_component: TraitStack<dyn Component>,
-    transparent_component: TraitStack<dyn Component>,
+    segments: Vec<PrepareSegment>,
 
-    depth_texture: Option<SizedTextureView2D>,
+    depth_texture: Option<(Arc<SizedTextureView2D>, TextureFormat)>,
+    pooled_depth: bool,
 
-    vertex_stream: BufferStream<'static>,
-    index_stream: BufferStream<'static>,
+    group_transparent: bool,
+
+    stats: RenderStats,
+
+    #[cfg(feature = "parallel")]
+    parallel_prepare: Option<NonZeroUsize>,
+
+    /// Command buffers split from render encoder by parallel prepare, in submission order
+    prepare_commands: Vec<CommandBuffer>,
+
+    #[cfg(feature = "debug-report")]
+    report: report::RenderReport,
 }
 
 impl StoryboardRenderer {
-    pub const DEFAULT_DEPTH_TEXTURE_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+    /// Has stencil aspect used by [ComponentQueue::push_rounded_clip]
+    pub const DEFAULT_DEPTH_TEXTURE_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
 
-    pub fn new() -> Self {
-        let vertex_stream = BufferStream::new(
-            Some(Cow::from("StoryboardRenderer vertex stream buffer")),
-            BufferUsages::VERTEX,
-        );
-        let index_stream = BufferStream::new(
-            Some(Cow::from("StoryboardRenderer index stream buffer")),
-            BufferUsages::INDEX,
-        );
+    /// Stencil face of component pipelines, drawing fragments inside of current clip.
+    /// Reference is nesting level of clip, so stencil is never written by components.
+    const CLIP_STENCIL_FACE: StencilFaceState = StencilFaceState {
+        compare: CompareFunction::Equal,
+        fail_op: StencilOperation::Keep,
+        depth_fail_op: StencilOperation::Keep,
+        pass_op: StencilOperation::Keep,
+    };
 
+    pub fn new() -> Self {
         Self {
-            current_screen_rect: Rect::zero(),
+            current_screen: None,
             screen_matrix: Transform3D::identity(),
+            screen_buffer: None,
 
-            opaque_component: TraitStack::new(),
-            transparent_component: TraitStack::new(),
+            segments: vec![PrepareSegment::new()],
 
             depth_texture: None,
+            pooled_depth: false,
+
+            group_transparent: false,
+
+            stats: RenderStats::default(),
+
+            #[cfg(feature = "parallel")]
+            parallel_prepare: None,
+
+            prepare_commands: Vec::new(),
 
-            vertex_stream,
-            index_stream,
+            #[cfg(feature = "debug-report")]
+            report: Default::default(),
         }
     }
 
+    /// Create pipeline data for renderer using depth texture format of given [RenderMode]
+    pub const fn create_renderer_pipeline_data_for_mode(
+        texture_format: TextureFormat,
+        render_mode: RenderMode,
+        multi_sample: Option<MultisampleState>,
+    ) -> RenderPipelineData {
+        Self::create_renderer_pipeline_data(
+            texture_format,
+            render_mode.depth_format(),
+            multi_sample,
+        )
+    }
+
+    /// Create pipeline data for renderer.
+    /// If `depth_format` is None, renderer doesn't use depth texture and draws every components in push order.
+    /// Component pipelines test stencil if `depth_format` has stencil aspect, see [ComponentQueue::push_rounded_clip].
     pub const fn create_renderer_pipeline_data(
         texture_format: TextureFormat,
+        depth_format: Option<TextureFormat>,
         multi_sample: Option<MultisampleState>,
     ) -> RenderPipelineData {
         RenderPipelineData {
             texture_format,
-            depth_stencil: Some(DepthStencilState {
-                format: Self::DEFAULT_DEPTH_TEXTURE_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: CompareFunction::Less,
-                stencil: StencilState {
-                    front: StencilFaceState::IGNORE,
-                    back: StencilFaceState::IGNORE,
-                    read_mask: 0,
-                    write_mask: 0,
-                },
-                bias: DepthBiasState {
-                    constant: 0,
-                    slope_scale: 0.0,
-                    clamp: 0.0,
-                },
-            }),
+            depth_stencil: match depth_format {
+                Some(format) => Some(DepthStencilState {
+                    format,
+                    depth_write_enabled: true,
+                    depth_compare: CompareFunction::Less,
+                    stencil: if matches!(
+                        format,
+                        TextureFormat::Depth24PlusStencil8 | TextureFormat::Depth32FloatStencil8
+                    ) {
+                        StencilState {
+                            front: Self::CLIP_STENCIL_FACE,
+                            back: Self::CLIP_STENCIL_FACE,
+                            read_mask: !0,
+                            write_mask: 0,
+                        }
+                    } else {
+                        StencilState {
+                            front: StencilFaceState::IGNORE,
+                            back: StencilFaceState::IGNORE,
+                            read_mask: 0,
+                            write_mask: 0,
+                        }
+                    },
+                    bias: DepthBiasState {
+                        constant: 0,
+                        slope_scale: 0.0,
+                        clamp: 0.0,
+                    },
+                }),
+
+                None => None,
+            },
             multi_sample,
+
+            cull_mode: None,
+            front_face: FrontFace::Ccw,
         }
     }
 
-    fn update_screen_matrix(&mut self, screen: ScreenRect) {
+    pub const fn pooled_depth(&self) -> bool {
+        self.pooled_depth
+    }
+
+    /// Use depth texture from [DepthTexturePool] shared with other renderers instead of owning one.
+    /// Reduces memory for many renderers rendering offscreen, like cached buffered drawables.
+    pub fn set_pooled_depth(&mut self, pooled_depth: bool) {
+        self.pooled_depth = pooled_depth;
+        self.depth_texture = None;
+    }
+
+    pub const fn group_transparent(&self) -> bool {
+        self.group_transparent
+    }
+
+    /// Reorder transparent components so components of same type are drawn together, reducing pipeline and bind group changes.
+    /// Component is only moved over components not overlapping its [Component::bounds], so blending result doesn't change.
+    pub fn set_group_transparent(&mut self, group_transparent: bool) {
+        self.group_transparent = group_transparent;
+    }
+
+    #[cfg(feature = "parallel")]
+    #[inline]
+    pub const fn parallel_prepare(&self) -> Option<NonZeroUsize> {
+        self.parallel_prepare
+    }
+
+    /// Prepare drawables on rayon threads when drawables count exceeds given chunk size.
+    /// Each chunk is prepared into its own stream and command encoder, output order is same as sequential prepare.
+    ///
+    /// Commands recorded into render encoder before the render and chunk commands are split into
+    /// [StoryboardRenderer::take_prepare_commands], which must be submitted before render encoder in same submit.
+    #[cfg(feature = "parallel")]
+    pub fn set_parallel_prepare(&mut self, chunk_size: Option<NonZeroUsize>) {
+        self.parallel_prepare = chunk_size;
+    }
+
+    /// Take command buffers split from render encoder since last call, in order they must run.
+    /// Submit them followed by render encoder in single submit. Always empty without parallel prepare.
+    pub fn take_prepare_commands(&mut self) -> Vec<CommandBuffer> {
+        mem::take(&mut self.prepare_commands)
+    }
+
+    /// Statistics of last render
+    pub const fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Component slots allocated for queued components, kept across renders
+    pub fn component_capacity(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| {
+                segment.opaque_component.capacity() + segment.transparent_component.capacity()
+            })
+            .sum()
+    }
+
+    /// Report of drawables and components queued during last render
+    #[cfg(feature = "debug-report")]
+    pub const fn report(&self) -> &report::RenderReport {
+        &self.report
+    }
+
+    /// Describe drawables and components queued during last render, with depth, bounds and whether they were skipped.
+    #[cfg(feature = "debug-report")]
+    pub fn describe(&self) -> String {
+        self.report.to_string()
+    }
+
+    fn update_screen_matrix(&mut self, scope: RenderScope, screen: ScreenRect) {
         self.screen_matrix = screen.get_logical_ortho_matrix();
+        self.screen_buffer = Some(ScreenBuffer::init(
+            scope.backend().device(),
+            &scope.backend().get::<ScreenResources>().bind_group_layout,
+            &self.screen_matrix,
+        ));
     }
 
-    fn update_depth_stencil(&mut self, device: &Device, screen: ScreenRect) {
-        self.depth_texture = Some(
-            SizedTexture2D::init(
-                device,
-                Some("StoryboardRenderer depth texture"),
-                screen.rect.size,
-                Self::DEFAULT_DEPTH_TEXTURE_FORMAT,
-                TextureUsages::RENDER_ATTACHMENT,
+    fn update_depth_stencil(
+        &mut self,
+        device: &Device,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: Option<TextureFormat>,
+    ) {
+        self.depth_texture = format.map(|format| {
+            (
+                Arc::new(
+                    SizedTexture2D::init(
+                        device,
+                        Some("StoryboardRenderer depth texture"),
+                        size,
+                        format,
+                        TextureUsages::RENDER_ATTACHMENT,
+                    )
+                    .create_view_default(None),
+                ),
+                format,
             )
-            .create_view_default(None),
-        );
+        });
+    }
+
+    /// Depth of drawable at `index`, strictly decreasing in draw order so later drawable is in front.
+    ///
+    /// Depths are multiples of power of two step below 1.0, which are exact in f32 and stay distinct in 24 bit depth buffer for up to 2^23 drawables.
+    /// Transparent components only test depth against opaque ones and are drawn in push order, so they never z-fight each other.
+    #[inline]
+    fn drawable_depth(index: usize, total: usize) -> f32 {
+        (total - index - 1) as f32 / (total + 1).next_power_of_two() as f32
     }
 
+    /// Render drawables to whole render target which has same size as screen
+    /// Render target can be single layer of texture array, see [crate::texture::SizedTexture2DArray::create_layer_view]
     pub fn render<'a>(
         &mut self,
         scope: RenderScope,
@@ -123,118 +307,1035 @@ impl StoryboardRenderer {
         color_attachment: Option<RenderPassColorAttachment>,
         encoder: &mut CommandEncoder,
     ) {
-        if drawables.len() == 0 || screen.rect.area() == 0 {
-            return;
+        self.render_inner(
+            scope,
+            screen,
+            screen.rect.size,
+            None,
+            drawables,
+            color_attachment,
+            None,
+            encoder,
+        )
+    }
+
+    /// Render drawables to whole render target like [StoryboardRenderer::render],
+    /// letting components copy content drawn before them from `backdrop`, see [Component::encode_backdrop].
+    /// `backdrop` must be texture of color attachment view.
+    pub fn render_with_backdrop<'a>(
+        &mut self,
+        scope: RenderScope,
+        screen: ScreenRect,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        color_attachment: Option<RenderPassColorAttachment>,
+        backdrop: Backdrop,
+        encoder: &mut CommandEncoder,
+    ) {
+        self.render_inner(
+            scope,
+            screen,
+            screen.rect.size,
+            None,
+            drawables,
+            color_attachment,
+            Some(backdrop),
+            encoder,
+        )
+    }
+
+    /// Render drawables into texture view owned by caller, like thumbnails or texture composited by other engine.
+    ///
+    /// View must be single sampled 2D view of texture created with [TextureUsages::RENDER_ATTACHMENT] and physical `size`.
+    /// `format` must be same as texture format of render scope pipeline, since component pipelines are created for it.
+    /// Drawables are laid out in logical pixels, `size / scale_factor`.
+    ///
+    /// View is cleared with `clear_color`, or drawn over existing content if None.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_to_view<'a>(
+        &mut self,
+        scope: RenderScope,
+        view: &TextureView,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        scale_factor: f32,
+        clear_color: Option<Color>,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        encoder: &mut CommandEncoder,
+    ) -> Result<(), RenderToViewError> {
+        let pipeline = scope.pipeline();
+
+        if format != pipeline.texture_format {
+            return Err(RenderToViewError::FormatMismatch {
+                expected: pipeline.texture_format,
+                format,
+            });
+        }
+
+        if pipeline
+            .multi_sample
+            .map_or(false, |multi_sample| multi_sample.count > 1)
+        {
+            return Err(RenderToViewError::Multisampled);
         }
 
-        if self.current_screen_rect != screen.rect {
-            self.update_screen_matrix(screen);
+        if scale_factor.is_nan() || scale_factor <= 0.0 {
+            return Err(RenderToViewError::InvalidScaleFactor(scale_factor));
+        }
 
-            if self.current_screen_rect.size != screen.rect.size {
-                self.update_depth_stencil(scope.backend().device(), screen);
-            }
+        self.render(
+            scope,
+            ScreenRect::new(Rect::from_size(size), scale_factor),
+            drawables,
+            Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: match clear_color {
+                        Some(color) => LoadOp::Clear(color),
+                        None => LoadOp::Load,
+                    },
+                    store: true,
+                },
+            }),
+            encoder,
+        );
 
-            self.current_screen_rect = screen.rect;
+        Ok(())
+    }
+
+    /// Render drawables into `screen.rect` region of render target. The region must be inside of render target.
+    /// Outside of region is clipped. Use [LoadOp::Load] on color attachment to keep other regions drawn before.
+    pub fn render_viewport<'a>(
+        &mut self,
+        scope: RenderScope,
+        screen: ScreenRect,
+        target_size: Size2D<u32, PhyiscalPixelUnit>,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        color_attachment: Option<RenderPassColorAttachment>,
+        encoder: &mut CommandEncoder,
+    ) {
+        if !Rect::from_size(target_size).contains_rect(&screen.rect) {
+            return;
         }
 
-        let mut draw_context = DrawContext {
+        self.render_inner(
             scope,
             screen,
-            screen_matrix: self.screen_matrix,
-            vertex_stream: &mut self.vertex_stream,
-            index_stream: &mut self.index_stream,
-        };
+            target_size,
+            Some(screen.rect),
+            drawables,
+            color_attachment,
+            None,
+            encoder,
+        )
+    }
 
+    #[allow(clippy::too_many_arguments)]
+    fn render_inner<'a>(
+        &mut self,
+        scope: RenderScope,
+        screen: ScreenRect,
+        target_size: Size2D<u32, PhyiscalPixelUnit>,
+        viewport: Option<Rect<u32, PhyiscalPixelUnit>>,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        color_attachment: Option<RenderPassColorAttachment>,
+        backdrop: Option<Backdrop>,
+        encoder: &mut CommandEncoder,
+    ) {
+        self.stats = RenderStats::default();
+
+        #[cfg(feature = "debug-report")]
         {
-            let mut components_queue = ComponentQueue {
-                opaque: &mut self.opaque_component,
-                transparent: &mut self.transparent_component,
-            };
-
-            let total = drawables.len() as f32;
-            for (i, drawable) in drawables.enumerate() {
-                drawable.prepare(
-                    &mut components_queue,
-                    &mut draw_context,
-                    encoder,
-                    1.0_f32 - ((1.0_f32 + i as f32) / total),
-                );
+            self.report.clear();
+            self.report.screen = Some(screen);
+
+            if screen.rect.area() == 0 {
+                self.report.skipped = Some("Screen area is zero");
+            } else if drawables.len() == 0 {
+                self.report.skipped = Some("No drawables, render target is only loaded or cleared");
             }
         }
 
-        let render_opaque = !self.opaque_component.is_empty();
-        let render_transparent = !self.transparent_component.is_empty();
+        if screen.rect.area() == 0 {
+            return;
+        }
 
-        let render_context = draw_context.into_render_context();
+        if drawables.len() == 0 {
+            // Run load operation of color attachment, so cleared target doesn't keep stale content
+            if color_attachment.is_some() {
+                encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("StoryboardRenderer clear pass"),
+                    color_attachments: &[color_attachment],
+                    depth_stencil_attachment: None,
+                });
+            }
 
-        let depth_attachment = RenderPassDepthStencilAttachment {
-            view: self.depth_texture.as_ref().unwrap().inner(),
-            depth_ops: Some(Operations {
-                load: LoadOp::Clear(1.0),
-                store: true,
-            }),
-            stencil_ops: None,
-        };
+            return;
+        }
+
+        if self.current_screen != Some(screen) {
+            self.update_screen_matrix(scope, screen);
+            self.current_screen = Some(screen);
+        }
+
+        let depth_format = scope
+            .pipeline()
+            .depth_stencil
+            .as_ref()
+            .map(|depth_stencil| depth_stencil.format);
+
+        if self.pooled_depth {
+            let pool = scope.backend().get::<DepthTexturePool>();
 
+            self.depth_texture = depth_format.map(|format| {
+                (
+                    pool.acquire(scope.backend().device(), target_size, format),
+                    format,
+                )
+            });
+        } else if self
+            .depth_texture
+            .as_ref()
+            .map(|(view, format)| (view.size(), *format))
+            != depth_format.map(|format| (target_size, format))
         {
-            let mut pass =
-                StoryboardRenderPass::new(encoder.begin_render_pass(&RenderPassDescriptor {
-                    label: Some("StoryboardRenderer render pass"),
-                    color_attachments: &[color_attachment],
-                    depth_stencil_attachment: Some(depth_attachment),
-                }));
+            self.update_depth_stencil(scope.backend().device(), target_size, depth_format);
+        }
 
-            if render_opaque {
-                for component in self.opaque_component.iter().rev() {
-                    component.render_opaque(&render_context, &mut pass);
-                }
+        // Every drawable must be prepared before render pass begins, see [Drawable::prepare]
+        let used_segments = self.prepare(scope, screen, drawables, encoder);
+
+        #[cfg(feature = "debug-report")]
+        {
+            let screen_rect = screen.get_logical_rect();
+
+            for segment in &self.segments[..used_segments] {
+                self.report.components.extend(
+                    segment
+                        .opaque_component
+                        .iter()
+                        .map(|component| report::ComponentReport::new(component, true, screen_rect))
+                        .chain(segment.transparent_component.iter().map(|component| {
+                            report::ComponentReport::new(component, false, screen_rect)
+                        })),
+                );
             }
+        }
+
+        let stencil = scope.pipeline().has_stencil();
+
+        {
+            let screen_bind_group = self.screen_buffer.as_ref().unwrap().bind_group();
 
-            if render_transparent {
-                for component in self.transparent_component.iter() {
-                    component.render_transparent(&render_context, &mut pass);
+            for segment in &self.segments[..used_segments] {
+                self.stats.streamed_bytes += segment.vertex_stream.written_size()
+                    + segment.index_stream.written_size()
+                    + segment.uniform_stream.written_size();
+            }
+
+            let segments = self.segments[..used_segments]
+                .iter_mut()
+                .map(|segment| segment.finish(scope, screen, screen_bind_group))
+                .collect::<Vec<_>>();
+
+            let transparent = segments
+                .iter()
+                .flat_map(|(render_context, _, transparent)| {
+                    let transparent = if self.group_transparent {
+                        group_by_type(transparent.iter())
+                    } else {
+                        transparent.iter().collect()
+                    };
+
+                    transparent
+                        .into_iter()
+                        .map(move |component| (render_context, component))
+                })
+                .collect::<Vec<_>>();
+
+            // Render pass is split before each component reading backdrop, later passes load previous content
+            let mut first = true;
+            let mut next = 0;
+            let mut stencil_reference = 0;
+            loop {
+                let mut pass =
+                    StoryboardRenderPass::new(encoder.begin_render_pass(&RenderPassDescriptor {
+                        label: Some("StoryboardRenderer render pass"),
+                        color_attachments: &[color_attachment.clone().map(|attachment| {
+                            RenderPassColorAttachment {
+                                ops: Operations {
+                                    load: if first {
+                                        attachment.ops.load
+                                    } else {
+                                        LoadOp::Load
+                                    },
+                                    ..attachment.ops
+                                },
+                                ..attachment
+                            }
+                        })],
+                        depth_stencil_attachment: self.depth_texture.as_ref().map(|(view, _)| {
+                            RenderPassDepthStencilAttachment {
+                                view: view.inner(),
+                                depth_ops: Some(Operations {
+                                    load: if first {
+                                        LoadOp::Clear(1.0)
+                                    } else {
+                                        LoadOp::Load
+                                    },
+                                    store: true,
+                                }),
+                                // Clips can be entered before pass is split
+                                stencil_ops: stencil.then(|| Operations {
+                                    load: if first {
+                                        LoadOp::Clear(0)
+                                    } else {
+                                        LoadOp::Load
+                                    },
+                                    store: true,
+                                }),
+                            }
+                        }),
+                    }));
+
+                if let Some(viewport) = viewport {
+                    pass.set_viewport(
+                        viewport.origin.x as f32,
+                        viewport.origin.y as f32,
+                        viewport.size.width as f32,
+                        viewport.size.height as f32,
+                        0.0,
+                        1.0,
+                    );
+
+                    pass.set_scissor_rect(
+                        viewport.origin.x,
+                        viewport.origin.y,
+                        viewport.size.width,
+                        viewport.size.height,
+                    );
+                }
+
+                if stencil_reference != 0 {
+                    pass.set_stencil_reference(stencil_reference);
+                }
+
+                #[cfg(feature = "debug-markers")]
+                let mut debug_group = ComponentDebugGroup::new();
+
+                if first {
+                    #[cfg(feature = "debug-markers")]
+                    pass.push_debug_group("Opaque components");
+
+                    for (render_context, opaque, _) in segments.iter().rev() {
+                        for component in opaque.iter().rev() {
+                            #[cfg(feature = "debug-markers")]
+                            debug_group.enter(&mut pass, component);
+
+                            component.render_opaque(render_context, &mut pass);
+                        }
+                    }
+
+                    #[cfg(feature = "debug-markers")]
+                    {
+                        debug_group.end(&mut pass);
+                        pass.pop_debug_group();
+                    }
+                }
+
+                #[cfg(feature = "debug-markers")]
+                pass.push_debug_group("Transparent components");
+
+                let start = next;
+                for (render_context, component) in &transparent[start..] {
+                    // Component reading backdrop is drawn first in next pass, after encoding its backdrop
+                    if (first || next != start) && backdrop.is_some() && component.reads_backdrop()
+                    {
+                        break;
+                    }
+
+                    #[cfg(feature = "debug-markers")]
+                    debug_group.enter(&mut pass, *component);
+
+                    component.render_transparent(render_context, &mut pass);
+                    next += 1;
+                }
+
+                #[cfg(feature = "debug-markers")]
+                {
+                    debug_group.end(&mut pass);
+                    pass.pop_debug_group();
+                }
+
+                self.stats += *pass.stats();
+                stencil_reference = pass.stencil_reference();
+                drop(pass);
+
+                first = false;
+
+                match (transparent.get(next), &backdrop) {
+                    (Some((render_context, component)), Some(backdrop)) => {
+                        component.encode_backdrop(render_context, encoder, backdrop)
+                    }
+
+                    _ => break,
                 }
             }
         }
 
-        if render_opaque {
-            self.opaque_component.clear();
+        for segment in &mut self.segments[..used_segments] {
+            segment.clear();
         }
 
-        if render_transparent {
-            self.transparent_component.clear();
+        // Pool can evict texture while it's idle
+        if self.pooled_depth {
+            self.depth_texture = None;
+        }
+    }
+
+    /// Prepare drawables into segments and returns number of used segments
+    fn prepare<'a>(
+        &mut self,
+        scope: RenderScope,
+        screen: ScreenRect,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        encoder: &mut CommandEncoder,
+    ) -> usize {
+        #[cfg(feature = "parallel")]
+        if let Some(chunk_size) = self.parallel_prepare {
+            if drawables.len() > chunk_size.get() {
+                return self.prepare_parallel(
+                    scope,
+                    screen,
+                    drawables.collect(),
+                    chunk_size.get(),
+                    encoder,
+                );
+            }
+        }
+
+        let depth = scope.pipeline().depth_stencil.is_some();
+
+        let segment = &mut self.segments[0];
+
+        let mut draw_context = DrawContext {
+            scope,
+            screen,
+            screen_matrix: self.screen_matrix,
+            vertex_stream: &mut segment.vertex_stream,
+            index_stream: &mut segment.index_stream,
+            uniform_stream: &mut segment.uniform_stream,
+        };
+
+        let mut components_queue = ComponentQueue {
+            opaque: &mut segment.opaque_component,
+            transparent: &mut segment.transparent_component,
+            depth,
+            last_batch: None,
+            clips: Vec::new(),
+
+            #[cfg(feature = "debug-report")]
+            pushed: 0,
+        };
+
+        let total = drawables.len();
+        for (i, drawable) in drawables.enumerate() {
+            #[cfg(feature = "debug-report")]
+            let pushed = components_queue.pushed;
+
+            drawable.prepare(
+                &mut components_queue,
+                &mut draw_context,
+                encoder,
+                Self::drawable_depth(i, total),
+            );
+
+            #[cfg(feature = "debug-report")]
+            self.report.drawables.push(report::DrawableReport {
+                index: i,
+                depth: Self::drawable_depth(i, total),
+                components: components_queue.pushed - pushed,
+            });
+        }
+
+        1
+    }
+
+    #[cfg(feature = "parallel")]
+    fn prepare_parallel(
+        &mut self,
+        scope: RenderScope,
+        screen: ScreenRect,
+        drawables: Vec<&dyn Drawable>,
+        chunk_size: usize,
+        encoder: &mut CommandEncoder,
+    ) -> usize {
+        let chunks = (drawables.len() + chunk_size - 1) / chunk_size;
+        if self.segments.len() < chunks {
+            self.segments.resize_with(chunks, PrepareSegment::new);
+        }
+
+        let screen_matrix = self.screen_matrix;
+        let total = drawables.len();
+        let depth = scope.pipeline().depth_stencil.is_some();
+
+        let command_buffers = self.segments[..chunks]
+            .par_iter_mut()
+            .zip(drawables.par_chunks(chunk_size))
+            .enumerate()
+            .map(|(chunk_index, (segment, chunk))| {
+                let mut encoder = scope.backend().device().create_command_encoder(
+                    &crate::wgpu::CommandEncoderDescriptor {
+                        label: Some("StoryboardRenderer parallel prepare command encoder"),
+                    },
+                );
+
+                let mut draw_context = DrawContext {
+                    scope,
+                    screen,
+                    screen_matrix,
+                    vertex_stream: &mut segment.vertex_stream,
+                    index_stream: &mut segment.index_stream,
+                    uniform_stream: &mut segment.uniform_stream,
+                };
+
+                let mut components_queue = ComponentQueue {
+                    opaque: &mut segment.opaque_component,
+                    transparent: &mut segment.transparent_component,
+                    depth,
+                    last_batch: None,
+                    clips: Vec::new(),
+
+                    #[cfg(feature = "debug-report")]
+                    pushed: 0,
+                };
+
+                let offset = chunk_index * chunk_size;
+                for (i, drawable) in chunk.iter().enumerate() {
+                    drawable.prepare(
+                        &mut components_queue,
+                        &mut draw_context,
+                        &mut encoder,
+                        Self::drawable_depth(offset + i, total),
+                    );
+                }
+
+                encoder.finish()
+            })
+            .collect::<Vec<_>>();
+
+        // Commands recorded before this render run first, then chunk commands in prepare order,
+        // then the render pass recorded into fresh render encoder
+        let previous = mem::replace(
+            encoder,
+            scope.backend().device().create_command_encoder(
+                &crate::wgpu::CommandEncoderDescriptor {
+                    label: Some("StoryboardRenderer render command encoder"),
+                },
+            ),
+        );
+        self.prepare_commands.push(previous.finish());
+        self.prepare_commands.extend(command_buffers);
+
+        chunks
+    }
+}
+
+/// How renderer orders components
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Opaque components are drawn first using depth texture of given format, so hidden pixels are culled.
+    /// Transparent components are drawn after them in push order.
+    Depth(TextureFormat),
+
+    /// Every component is drawn in push order in single pass without depth texture, like classic 2D painter's algorithm.
+    /// Later pushed component is always drawn over former ones, including opaque ones.
+    PainterOrder,
+}
+
+impl RenderMode {
+    /// Depth texture format of mode. None for [RenderMode::PainterOrder]
+    pub const fn depth_format(self) -> Option<TextureFormat> {
+        match self {
+            RenderMode::Depth(format) => Some(format),
+            RenderMode::PainterOrder => None,
         }
     }
 }
 
+impl Default for RenderMode {
+    fn default() -> Self {
+        Self::Depth(StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT)
+    }
+}
+
+/// Render target texture components read content drawn before them from, see [Component::encode_backdrop]
+#[derive(Debug, Clone, Copy)]
+pub struct Backdrop<'a> {
+    /// Texture created with [wgpu::TextureUsages::COPY_SRC]
+    pub texture: &'a Texture,
+    pub size: Size2D<u32, PhyiscalPixelUnit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderToViewError {
+    /// View format is different from texture format of pipelines
+    FormatMismatch {
+        expected: TextureFormat,
+        format: TextureFormat,
+    },
+
+    /// Pipelines are multisampled, which needs resolve target
+    Multisampled,
+
+    InvalidScaleFactor(f32),
+}
+
+impl Display for RenderToViewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FormatMismatch { expected, format } => writeln!(
+                f,
+                "View format {:?} doesn't match pipeline texture format {:?}",
+                format, expected
+            ),
+
+            Self::Multisampled => writeln!(f, "Cannot render multisampled pipelines to view"),
+
+            Self::InvalidScaleFactor(scale_factor) => {
+                writeln!(f, "Invalid scale factor: {}", scale_factor)
+            }
+        }
+    }
+}
+
+impl Error for RenderToViewError {}
+
+/// Components of same type drawn together after grouping
+struct TypeBatch<'a> {
+    components: Vec<&'a dyn Component>,
+    /// Union of inflated bounds of bounded components
+    bounds: Rect<f32, LogicalPixelUnit>,
+}
+
+/// Maximum number of batches component can be moved back over
+const GROUP_LOOKBACK: usize = 16;
+
+/// Move each component to last batch of same type, if components in batches after it don't overlap it.
+/// Relative order of overlapping components is kept.
+///
+/// Last batch of each type is found in a map and only up to [GROUP_LOOKBACK] batches are checked for overlap,
+/// so grouping is linear in number of components.
+fn group_by_type<'a>(
+    components: impl Iterator<Item = &'a dyn Component>,
+) -> Vec<&'a dyn Component> {
+    let mut batches: Vec<TypeBatch> = Vec::new();
+    let mut last_batch: HashMap<&'static str, usize> = HashMap::new();
+
+    // Components cannot move before unbounded component
+    let mut barrier = 0;
+
+    for component in components {
+        let type_name = component.type_name();
+        // Inflated to cover anti aliased edges and pixel snapping
+        let bounds = component.bounds().map(|bounds| bounds.inflate(1.0, 1.0));
+
+        let index = match (bounds, last_batch.get(type_name).copied()) {
+            (_, Some(index)) if index + 1 == batches.len() => Some(index),
+
+            (Some(bounds), Some(index))
+                if index >= barrier
+                    && index + GROUP_LOOKBACK >= batches.len()
+                    && batches[index + 1..]
+                        .iter()
+                        .all(|batch| !batch.bounds.intersects(&bounds)) =>
+            {
+                Some(index)
+            }
+
+            _ => None,
+        };
+
+        let index = index.unwrap_or_else(|| {
+            batches.push(TypeBatch {
+                components: Vec::new(),
+                bounds: Rect::zero(),
+            });
+            last_batch.insert(type_name, batches.len() - 1);
+
+            batches.len() - 1
+        });
+
+        let batch = &mut batches[index];
+        batch.components.push(component);
+        match bounds {
+            Some(bounds) => batch.bounds = batch.bounds.union(&bounds),
+            None => barrier = index,
+        }
+    }
+
+    batches
+        .into_iter()
+        .flat_map(|batch| batch.components)
+        .collect()
+}
+
 impl Default for StoryboardRenderer {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Groups consecutive components of same type into debug group, visible in GPU captures
+#[cfg(feature = "debug-markers")]
+#[derive(Debug)]
+struct ComponentDebugGroup {
+    current: Option<&'static str>,
+}
+
+#[cfg(feature = "debug-markers")]
+impl ComponentDebugGroup {
+    const fn new() -> Self {
+        Self { current: None }
+    }
+
+    fn enter(&mut self, pass: &mut StoryboardRenderPass, component: &dyn Component) {
+        let type_name = component.type_name();
+
+        if self.current != Some(type_name) {
+            self.end(pass);
+
+            pass.push_debug_group(type_name);
+            self.current = Some(type_name);
+        }
+
+        if let Some(label) = component.tag().and_then(|tag| tag.debug_label) {
+            pass.insert_debug_marker(label);
+        }
+    }
+
+    fn end(&mut self, pass: &mut StoryboardRenderPass) {
+        if self.current.take().is_some() {
+            pass.pop_debug_group();
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ComponentQueue<'a> {
-    opaque: &'a mut TraitStack<dyn Component>,
-    transparent: &'a mut TraitStack<dyn Component>,
+    opaque: &'a mut ComponentStack,
+    transparent: &'a mut ComponentStack,
+
+    depth: bool,
+
+    last_batch: Option<Arc<dyn Any + Send + Sync>>,
+
+    /// Vertices of entered clips, innermost last
+    clips: Vec<StreamRange>,
+
+    /// Number of pushed components, for draw list report
+    #[cfg(feature = "debug-report")]
+    pushed: usize,
 }
 
 impl<'a> ComponentQueue<'a> {
-    pub fn new(
-        opaque: &'a mut TraitStack<dyn Component>,
-        transparent: &'a mut TraitStack<dyn Component>,
-    ) -> Self {
+    pub fn new(opaque: &'a mut ComponentStack, transparent: &'a mut ComponentStack) -> Self {
         Self {
             opaque,
             transparent,
+            depth: true,
+            last_batch: None,
+            clips: Vec::new(),
+
+            #[cfg(feature = "debug-report")]
+            pushed: 0,
         }
     }
 
+    /// Push opaque component.
+    /// Without depth test or inside of clip, opaque components are drawn with transparent ones in push order.
     pub fn push_opaque(&mut self, component: impl Component + 'static) {
-        self.opaque.push(component);
+        if self.depth && self.clips.is_empty() {
+            #[cfg(feature = "debug-report")]
+            {
+                self.pushed += 1;
+            }
+
+            self.opaque.push(component);
+        } else {
+            self.push_transparent(component);
+        }
     }
 
     pub fn push_transparent(&mut self, component: impl Component + 'static) {
+        #[cfg(feature = "debug-report")]
+        {
+            self.pushed += 1;
+        }
+
+        self.last_batch = None;
         self.transparent.push(component);
     }
+
+    /// Clip components pushed until [ComponentQueue::pop_clip] into rounded rect, intersected with current clip.
+    ///
+    /// Clip shape is written into stencil and tested by component pipelines, so pipeline depth format must have stencil aspect
+    /// like [StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT]. Returns false without clipping otherwise.
+    /// Fragments are either inside or outside of clip shape, so corners are not anti aliased.
+    pub fn push_rounded_clip(
+        &mut self,
+        ctx: &mut DrawContext,
+        rect: Rect<f32, LogicalPixelUnit>,
+        radius: f32,
+    ) -> bool {
+        if !ctx.scope.pipeline().has_stencil() {
+            return false;
+        }
+
+        let vertices_slice = ClipComponent::write_rounded_rect(ctx, rect, radius);
+        self.push_transparent(ClipComponent::new(
+            vertices_slice.clone(),
+            self.clips.len() as u32,
+            false,
+        ));
+        self.clips.push(vertices_slice);
+
+        true
+    }
+
+    /// Leave clip entered last by [ComponentQueue::push_rounded_clip]
+    pub fn pop_clip(&mut self) {
+        if let Some(vertices_slice) = self.clips.pop() {
+            self.push_transparent(ClipComponent::new(
+                vertices_slice,
+                self.clips.len() as u32,
+                true,
+            ));
+        }
+    }
+
+    /// Push transparent component with shared batch state.
+    /// Next component can be merged into the batch using [ComponentQueue::last_batch] instead of being pushed.
+    pub fn push_transparent_batch<T: Any + Send + Sync>(
+        &mut self,
+        component: impl Component + 'static,
+        batch: Arc<T>,
+    ) {
+        #[cfg(feature = "debug-report")]
+        {
+            self.pushed += 1;
+        }
+
+        self.transparent.push(component);
+        self.last_batch = Some(batch);
+    }
+
+    /// Batch state of last pushed transparent component, if it was pushed with batch of same type.
+    /// Merging into it keeps draw order since no other component is pushed after it.
+    pub fn last_batch<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.last_batch.clone()?.downcast().ok()
+    }
+}
+
+/// Components and streams prepared from contiguous range of drawables
+#[derive(Debug)]
+struct PrepareSegment {
+    opaque_component: ComponentStack,
+    transparent_component: ComponentStack,
+
+    vertex_stream: BufferStream<'static>,
+    index_stream: BufferStream<'static>,
+    uniform_stream: BufferStream<'static>,
+}
+
+impl PrepareSegment {
+    pub fn new() -> Self {
+        Self {
+            opaque_component: ComponentStack::new(),
+            transparent_component: ComponentStack::new(),
+
+            vertex_stream: BufferStream::new(
+                Some(Cow::from("StoryboardRenderer vertex stream buffer")),
+                BufferUsages::VERTEX,
+            ),
+            index_stream: BufferStream::new(
+                Some(Cow::from("StoryboardRenderer index stream buffer")),
+                BufferUsages::INDEX,
+            ),
+            uniform_stream: BufferStream::new(
+                Some(Cow::from("StoryboardRenderer uniform stream buffer")),
+                BufferUsages::UNIFORM,
+            ),
+        }
+    }
+
+    pub fn finish<'a>(
+        &'a mut self,
+        scope: RenderScope<'a>,
+        screen: ScreenRect,
+        screen_bind_group: &'a BindGroup,
+    ) -> (RenderContext<'a>, &'a ComponentStack, &'a ComponentStack) {
+        let backend = scope.backend();
+
+        (
+            RenderContext {
+                scope,
+                screen,
+                screen_bind_group,
+                vertex_stream: self.vertex_stream.finish(backend.device(), backend.queue()),
+                index_stream: self.index_stream.finish(backend.device(), backend.queue()),
+                uniform_stream: self
+                    .uniform_stream
+                    .finish(backend.device(), backend.queue()),
+            },
+            &self.opaque_component,
+            &self.transparent_component,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        self.opaque_component.clear();
+        self.transparent_component.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::{
+        euclid::{Point2D, Rect, Size2D},
+        unit::LogicalPixelUnit,
+    };
+
+    use crate::component::Component;
+
+    use super::{
+        context::RenderContext, group_by_type, pass::StoryboardRenderPass, StoryboardRenderer,
+    };
+
+    struct MockComponent {
+        type_name: &'static str,
+        bounds: Option<Rect<f32, LogicalPixelUnit>>,
+    }
+
+    impl Component for MockComponent {
+        fn render_opaque<'rpass>(
+            &'rpass self,
+            _: &RenderContext<'rpass>,
+            _: &mut StoryboardRenderPass<'rpass>,
+        ) {
+        }
+
+        fn render_transparent<'rpass>(
+            &'rpass self,
+            _: &RenderContext<'rpass>,
+            _: &mut StoryboardRenderPass<'rpass>,
+        ) {
+        }
+
+        fn type_name(&self) -> &'static str {
+            self.type_name
+        }
+
+        fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+            self.bounds
+        }
+    }
+
+    fn addr(component: &dyn Component) -> *const u8 {
+        component as *const dyn Component as *const u8
+    }
+
+    fn type_changes(components: &[&dyn Component]) -> usize {
+        components
+            .windows(2)
+            .filter(|pair| pair[0].type_name() != pair[1].type_name())
+            .count()
+    }
+
+    #[test]
+    fn group_by_type_test() {
+        // List rows of box background, label and icon
+        let mut components = Vec::new();
+        for row in 0..10 {
+            let y = row as f32 * 40.0;
+
+            components.push(MockComponent {
+                type_name: "box",
+                bounds: Some(Rect::new(Point2D::new(0.0, y), Size2D::new(200.0, 30.0))),
+            });
+            components.push(MockComponent {
+                type_name: "text",
+                bounds: Some(Rect::new(Point2D::new(10.0, y), Size2D::new(100.0, 20.0))),
+            });
+            components.push(MockComponent {
+                type_name: "primitive",
+                bounds: Some(Rect::new(Point2D::new(150.0, y), Size2D::new(20.0, 20.0))),
+            });
+        }
+
+        // Unbounded component is barrier
+        components.push(MockComponent {
+            type_name: "custom",
+            bounds: None,
+        });
+        components.push(MockComponent {
+            type_name: "box",
+            bounds: Some(Rect::new(
+                Point2D::new(0.0, 1000.0),
+                Size2D::new(10.0, 10.0),
+            )),
+        });
+
+        let unordered = components
+            .iter()
+            .map(|component| component as &dyn Component)
+            .collect::<Vec<_>>();
+        let ordered = group_by_type(unordered.iter().copied());
+
+        assert_eq!(ordered.len(), unordered.len());
+        assert!(type_changes(&ordered) < type_changes(&unordered));
+
+        // Every overlapping pair keeps relative order
+        let index_of = |component: &dyn Component| {
+            ordered
+                .iter()
+                .position(|other| std::ptr::eq(addr(*other), addr(component)))
+                .unwrap()
+        };
+        for (i, a) in unordered.iter().enumerate() {
+            for b in &unordered[i + 1..] {
+                let overlaps = match (a.bounds(), b.bounds()) {
+                    (Some(a), Some(b)) => a.inflate(1.0, 1.0).intersects(&b),
+                    _ => true,
+                };
+
+                if overlaps {
+                    assert!(index_of(*a) < index_of(*b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn drawable_depth_test() {
+        for total in [1, 2, 500, 1 << 20] {
+            let depths = (0..total)
+                .map(|i| StoryboardRenderer::drawable_depth(i, total))
+                .collect::<Vec<_>>();
+
+            assert!(depths[0] < 1.0);
+            assert_eq!(depths[total - 1], 0.0);
+
+            // Stays strictly decreasing after quantizing to 24 bit depth buffer
+            let max = ((1 << 24) - 1) as f64;
+            for pair in depths.windows(2) {
+                assert!(
+                    (pair[0] as f64 * max).round() > (pair[1] as f64 * max).round(),
+                    "total: {} depths: {:?}",
+                    total,
+                    pair
+                );
+            }
+        }
+    }
 }
@@ -1,18 +1,33 @@
 use std::{hash::BuildHasherDefault, ops::Range};
 
 use rustc_hash::FxHashMap;
+use storyboard_core::{
+    euclid::{Point2D, Rect, Size2D},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
+};
 use wgpu::{
-    BindGroup, Buffer, BufferAddress, BufferSlice, DynamicOffset,
-    IndexFormat, RenderPipeline, ShaderStages, RenderPass,
+    BindGroup, Buffer, BufferAddress, BufferSlice, DynamicOffset, IndexFormat, RenderPass,
+    RenderPipeline, ShaderStages,
 };
 
+use crate::ScreenRect;
+
+use super::stats::RenderStats;
+
 #[derive(Debug)]
 pub struct StoryboardRenderPass<'a> {
     pass: RenderPass<'a>,
 
     current_bind_groups: FxHashMap<u32, (&'a BindGroup, usize)>,
 
-    current_pipeline: Option<&'a RenderPipeline>
+    current_pipeline: Option<&'a RenderPipeline>,
+
+    /// Scissor rect set by [StoryboardRenderPass::set_scissor_rect], restored after clipped component
+    scissor_rect: Option<Rect<u32, PhyiscalPixelUnit>>,
+
+    stencil_reference: u32,
+
+    stats: RenderStats,
 }
 
 impl<'a> StoryboardRenderPass<'a> {
@@ -25,7 +40,13 @@ impl<'a> StoryboardRenderPass<'a> {
             current_bind_groups: FxHashMap::with_capacity_and_hasher(
                 16,
                 BuildHasherDefault::default(),
-            )
+            ),
+
+            scissor_rect: None,
+
+            stencil_reference: 0,
+
+            stats: RenderStats::default(),
         }
     }
 
@@ -76,16 +97,21 @@ impl<'a> StoryboardRenderPass<'a> {
 
     #[inline(always)]
     pub fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+        self.stats
+            .add_draw(vertices.len() as u32, instances.len() as u32);
         self.pass.draw(vertices, instances)
     }
 
     #[inline(always)]
     pub fn draw_indexed(&mut self, indices: Range<u32>, base_vertex: i32, instances: Range<u32>) {
+        self.stats
+            .add_draw(indices.len() as u32, instances.len() as u32);
         self.pass.draw_indexed(indices, base_vertex, instances)
     }
 
     #[inline(always)]
     pub fn draw_indirect(&mut self, indirect_buffer: &'a Buffer, indirect_offset: BufferAddress) {
+        self.stats.draw_calls += 1;
         self.pass.draw_indirect(indirect_buffer, indirect_offset)
     }
 
@@ -95,16 +121,151 @@ impl<'a> StoryboardRenderPass<'a> {
         indirect_buffer: &'a Buffer,
         indirect_offset: BufferAddress,
     ) {
+        self.stats.draw_calls += 1;
         self.pass
             .draw_indexed_indirect(indirect_buffer, indirect_offset)
     }
 
+    #[inline(always)]
+    pub fn set_viewport(&mut self, x: f32, y: f32, w: f32, h: f32, min_depth: f32, max_depth: f32) {
+        self.pass.set_viewport(x, y, w, h, min_depth, max_depth)
+    }
+
+    #[inline(always)]
+    pub fn set_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        self.scissor_rect = Some(Rect::new(Point2D::new(x, y), Size2D::new(width, height)));
+        self.pass.set_scissor_rect(x, y, width, height)
+    }
+
+    /// Clip following draws to screen space logical rect, intersected with current scissor rect.
+    /// Call [StoryboardRenderPass::reset_clip] after drawing clipped component.
+    pub fn set_clip(&mut self, screen: &ScreenRect, clip: &Rect<f32, LogicalPixelUnit>) {
+        let rect = clip_scissor_rect(screen, self.scissor_rect.unwrap_or(screen.rect), clip);
+
+        self.pass.set_scissor_rect(
+            rect.origin.x,
+            rect.origin.y,
+            rect.size.width,
+            rect.size.height,
+        )
+    }
+
+    /// Restore scissor rect changed by [StoryboardRenderPass::set_clip]
+    pub fn reset_clip(&mut self, screen: &ScreenRect) {
+        let scissor = self.scissor_rect.unwrap_or(screen.rect);
+
+        self.pass.set_scissor_rect(
+            scissor.origin.x,
+            scissor.origin.y,
+            scissor.size.width,
+            scissor.size.height,
+        )
+    }
+
+    /// Set stencil reference tested by component pipelines, see [crate::renderer::ComponentQueue::push_rounded_clip]
+    #[inline(always)]
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.stencil_reference = reference;
+        self.pass.set_stencil_reference(reference)
+    }
+
+    pub const fn stencil_reference(&self) -> u32 {
+        self.stencil_reference
+    }
+
     #[inline(always)]
     pub fn set_push_constants(&mut self, stages: ShaderStages, offset: u32, data: &[u8]) {
         self.pass.set_push_constants(stages, offset, data)
     }
 
+    #[inline(always)]
+    pub fn push_debug_group(&mut self, label: &str) {
+        self.pass.push_debug_group(label)
+    }
+
+    #[inline(always)]
+    pub fn pop_debug_group(&mut self) {
+        self.pass.pop_debug_group()
+    }
+
+    #[inline(always)]
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        self.pass.insert_debug_marker(label)
+    }
+
+    /// Statistics of draw calls issued on this pass. Vertices of indirect draws are not counted.
+    pub const fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut RenderStats {
+        &mut self.stats
+    }
+
+    /// Raw wgpu render pass for issuing custom draw calls between components.
+    ///
+    /// Cached pipeline and bind group state is discarded, so next component sets them again.
+    /// Viewport, scissor rect, vertex and index buffers changed on raw pass are not restored
+    /// and must be reset by caller before resuming component rendering.
+    /// Draw calls issued on raw pass are not counted in [StoryboardRenderPass::stats].
+    pub fn raw_mut(&mut self) -> &mut RenderPass<'a> {
+        self.current_pipeline = None;
+        self.reset_pipeline_desc();
+
+        &mut self.pass
+    }
+
     fn reset_pipeline_desc(&mut self) {
         self.current_bind_groups.clear();
     }
 }
+
+/// Physical scissor rect covering logical clip rect inside of current scissor rect. Empty if clipped out entirely.
+fn clip_scissor_rect(
+    screen: &ScreenRect,
+    scissor: Rect<u32, PhyiscalPixelUnit>,
+    clip: &Rect<f32, LogicalPixelUnit>,
+) -> Rect<u32, PhyiscalPixelUnit> {
+    clip.intersection(&screen.get_logical_rect())
+        .and_then(|clip| screen.logical_rect_to_physical(clip))
+        .and_then(|clip| clip.intersection(&scissor))
+        .unwrap_or_else(|| Rect::new(scissor.origin, Size2D::zero()))
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D};
+
+    use crate::ScreenRect;
+
+    use super::clip_scissor_rect;
+
+    #[test]
+    fn clip_scissor_rect_test() {
+        let screen = ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(200, 100)), 2.0);
+
+        // Label overflowing clip rect is cut off at physical edge of clip rect
+        let label_clip = Rect::new(Point2D::new(10.0, 10.0), Size2D::new(40.25, 20.0));
+        assert_eq!(
+            clip_scissor_rect(&screen, screen.rect, &label_clip),
+            Rect::new(Point2D::new(20, 20), Size2D::new(81, 40))
+        );
+
+        // Intersected with ambient scissor rect
+        let scissor = Rect::new(Point2D::new(0, 0), Size2D::new(50, 30));
+        assert_eq!(
+            clip_scissor_rect(&screen, scissor, &label_clip),
+            Rect::new(Point2D::new(20, 20), Size2D::new(30, 10))
+        );
+
+        // Clip partially outside of screen
+        assert_eq!(
+            clip_scissor_rect(
+                &screen,
+                screen.rect,
+                &Rect::new(Point2D::new(-10.0, 40.0), Size2D::new(30.0, 30.0))
+            ),
+            Rect::new(Point2D::new(0, 80), Size2D::new(40, 20))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Draw list report of last render, for finding out why drawables are not visible
+
+use std::fmt::Display;
+
+use storyboard_core::{euclid::Rect, unit::LogicalPixelUnit};
+
+use crate::{
+    component::{Component, ComponentTag},
+    ScreenRect,
+};
+
+/// Drawable prepared during last render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawableReport {
+    /// Index in drawable list
+    pub index: usize,
+    pub depth: f32,
+    /// Number of components pushed by drawable
+    pub components: usize,
+}
+
+/// Component queued during last render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComponentReport {
+    pub type_name: &'static str,
+    pub tag: Option<ComponentTag>,
+    pub bounds: Option<Rect<f32, LogicalPixelUnit>>,
+    pub opaque: bool,
+    /// Bounds don't intersect screen
+    pub offscreen: bool,
+}
+
+impl ComponentReport {
+    pub fn new(
+        component: &dyn Component,
+        opaque: bool,
+        screen: Rect<f32, LogicalPixelUnit>,
+    ) -> Self {
+        let bounds = component.bounds();
+
+        Self {
+            type_name: component.type_name(),
+            tag: component.tag().copied(),
+            bounds,
+            opaque,
+            offscreen: bounds.map_or(false, |bounds| !bounds.intersects(&screen)),
+        }
+    }
+}
+
+/// Report of last render. See [super::StoryboardRenderer::describe]
+#[derive(Debug, Clone, Default)]
+pub struct RenderReport {
+    pub screen: Option<ScreenRect>,
+
+    /// Reason whole render was skipped
+    pub skipped: Option<&'static str>,
+
+    /// Prepared drawables. Empty if drawables are prepared in parallel.
+    pub drawables: Vec<DrawableReport>,
+    pub components: Vec<ComponentReport>,
+}
+
+impl RenderReport {
+    pub fn clear(&mut self) {
+        self.screen = None;
+        self.skipped = None;
+        self.drawables.clear();
+        self.components.clear();
+    }
+}
+
+impl Display for RenderReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Render report")?;
+
+        if let Some(screen) = &self.screen {
+            writeln!(
+                f,
+                "Screen: {:?} scale factor: {}",
+                screen.rect, screen.scale_factor
+            )?;
+        }
+
+        if let Some(reason) = self.skipped {
+            return writeln!(f, "Skipped: {}", reason);
+        }
+
+        writeln!(f, "Drawables: {}", self.drawables.len())?;
+        for drawable in &self.drawables {
+            write!(f, "  #{} depth: {:.6} ", drawable.index, drawable.depth)?;
+
+            if drawable.components == 0 {
+                writeln!(
+                    f,
+                    "no component (zero area, failed transform, culled or merged into previous batch)"
+                )?;
+            } else {
+                writeln!(f, "components: {}", drawable.components)?;
+            }
+        }
+
+        writeln!(f, "Components: {}", self.components.len())?;
+        for component in &self.components {
+            write!(
+                f,
+                "  [{}] {}",
+                if component.opaque {
+                    "opaque"
+                } else {
+                    "transparent"
+                },
+                component.type_name
+            )?;
+
+            if let Some(label) = component.tag.and_then(|tag| tag.debug_label) {
+                write!(f, " \"{}\"", label)?;
+            }
+
+            match component.bounds {
+                Some(bounds) => write!(f, " bounds: {:?}", bounds)?,
+                None => write!(f, " bounds: unknown")?,
+            }
+
+            if component.offscreen {
+                write!(f, " (offscreen)")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
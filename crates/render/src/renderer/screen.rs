@@ -0,0 +1,137 @@
+use bytemuck::{Pod, Zeroable};
+use storyboard_core::{
+    euclid::Transform3D,
+    store::{Store, StoreResources},
+    unit::{LogicalPixelUnit, RenderUnit},
+};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, Device,
+    ShaderStages,
+};
+
+use crate::shared::BackendScopeContext;
+
+/// Screen matrix uniform bound to component pipelines.
+///
+/// Components write logical space vertex positions and vertex shader transforms them using the matrix.
+/// ```wgsl
+/// struct Screen {
+///     matrix: mat4x4<f32>,
+/// };
+///
+/// @group(1) @binding(0) var<uniform> screen: Screen;
+/// ```
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct ScreenUniform {
+    pub matrix: [[f32; 4]; 4],
+}
+
+impl ScreenUniform {
+    pub fn new(matrix: &Transform3D<f32, LogicalPixelUnit, RenderUnit>) -> Self {
+        Self {
+            matrix: matrix.to_arrays(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScreenResources {
+    pub bind_group_layout: BindGroupLayout,
+}
+
+impl StoreResources<BackendScopeContext<'_>> for ScreenResources {
+    fn initialize(_: &Store, ctx: &BackendScopeContext) -> Self {
+        let bind_group_layout = ctx
+            .device
+            .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Screen uniform bind group layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        Self { bind_group_layout }
+    }
+}
+
+/// Uniform buffer holding screen matrix of one screen.
+/// Buffer content is never modified, so renders with different screens in same submission don't overwrite each other.
+#[derive(Debug)]
+pub struct ScreenBuffer {
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl ScreenBuffer {
+    pub fn init(
+        device: &Device,
+        layout: &BindGroupLayout,
+        matrix: &Transform3D<f32, LogicalPixelUnit, RenderUnit>,
+    ) -> Self {
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Screen uniform buffer"),
+            contents: bytemuck::bytes_of(&ScreenUniform::new(matrix)),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Screen uniform bind group"),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self { buffer, bind_group }
+    }
+
+    pub const fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub const fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D};
+
+    use crate::ScreenRect;
+
+    use super::ScreenUniform;
+
+    #[test]
+    fn screen_uniform_layout_test() {
+        let screen = ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(1280, 720)), 1.5);
+        let matrix = screen.get_logical_ortho_matrix();
+        let uniform = ScreenUniform::new(&matrix);
+
+        let point = Point2D::new(200.0, 120.0);
+
+        // Column major matrix * column vector, as in wgsl
+        let columns = uniform.matrix;
+        let vector = [point.x, point.y, 0.0, 1.0];
+        let transformed = [0, 1].map(|row| {
+            (0..4)
+                .map(|column| columns[column][row] * vector[column])
+                .sum::<f32>()
+        });
+
+        let expected = matrix.transform_point2d(point).unwrap();
+        assert!((transformed[0] - expected.x).abs() < 1e-5);
+        assert!((transformed[1] - expected.y).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,192 @@
+//! Component stack keeping its storage across frames
+
+use std::fmt::{self, Debug};
+
+use crate::component::Component;
+
+/// Stack of boxed components with different types.
+///
+/// [ComponentStack::clear] drops components but keeps allocated slots,
+/// so pushing same amount of components on next frame doesn't grow the stack again.
+#[derive(Default)]
+pub struct ComponentStack {
+    components: Vec<Box<dyn Component>>,
+}
+
+impl ComponentStack {
+    pub const fn new() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    pub fn push<T: Component + 'static>(&mut self, component: T) {
+        self.components.push(Box::new(component));
+    }
+
+    /// Iterate components in push order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &dyn Component> + ExactSizeIterator {
+        self.components.iter().map(|component| &**component)
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Components which can be pushed without growing the stack
+    pub fn capacity(&self) -> usize {
+        self.components.capacity()
+    }
+
+    /// Reserve slots for at least additional components
+    pub fn reserve(&mut self, additional: usize) {
+        self.components.reserve(additional);
+    }
+
+    /// Drop every component, keeping allocated slots
+    pub fn clear(&mut self) {
+        self.components.clear();
+    }
+}
+
+impl Debug for ComponentStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentStack")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        component::Component,
+        renderer::{context::RenderContext, pass::StoryboardRenderPass},
+    };
+
+    use super::ComponentStack;
+
+    struct MockComponent {
+        id: usize,
+        _data: [u64; 5],
+        drops: Arc<AtomicUsize>,
+    }
+
+    impl Component for MockComponent {
+        fn render_opaque<'rpass>(
+            &'rpass self,
+            _: &RenderContext<'rpass>,
+            _: &mut StoryboardRenderPass<'rpass>,
+        ) {
+        }
+
+        fn render_transparent<'rpass>(
+            &'rpass self,
+            _: &RenderContext<'rpass>,
+            _: &mut StoryboardRenderPass<'rpass>,
+        ) {
+        }
+
+        fn type_name(&self) -> &'static str {
+            ["even", "odd"][self.id % 2]
+        }
+    }
+
+    impl Drop for MockComponent {
+        fn drop(&mut self) {
+            self.drops.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    struct ZeroSized;
+
+    impl Component for ZeroSized {
+        fn render_opaque<'rpass>(
+            &'rpass self,
+            _: &RenderContext<'rpass>,
+            _: &mut StoryboardRenderPass<'rpass>,
+        ) {
+        }
+
+        fn render_transparent<'rpass>(
+            &'rpass self,
+            _: &RenderContext<'rpass>,
+            _: &mut StoryboardRenderPass<'rpass>,
+        ) {
+        }
+
+        fn type_name(&self) -> &'static str {
+            "zero"
+        }
+    }
+
+    fn push_frame(stack: &mut ComponentStack, count: usize, drops: &Arc<AtomicUsize>) {
+        for id in 0..count {
+            stack.push(MockComponent {
+                id,
+                _data: [0; 5],
+                drops: drops.clone(),
+            });
+            stack.push(ZeroSized);
+        }
+    }
+
+    #[test]
+    fn component_stack_test() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut stack = ComponentStack::new();
+
+        push_frame(&mut stack, 3, &drops);
+        let names: Vec<_> = stack
+            .iter()
+            .map(|component| component.type_name())
+            .collect();
+        assert_eq!(names, ["even", "zero", "odd", "zero", "even", "zero"]);
+        assert_eq!(stack.iter().next_back().unwrap().type_name(), "zero");
+
+        stack.clear();
+        assert!(stack.is_empty());
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+
+        push_frame(&mut stack, 2, &drops);
+        drop(stack);
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn capacity_retained_test() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut stack = ComponentStack::new();
+
+        push_frame(&mut stack, 10_000, &drops);
+        let capacity = stack.capacity();
+        assert!(capacity >= 20_000);
+
+        stack.clear();
+        assert!(stack.is_empty());
+        assert_eq!(stack.capacity(), capacity);
+
+        // Same frame again fits in retained storage
+        push_frame(&mut stack, 10_000, &drops);
+        assert_eq!(stack.len(), 20_000);
+        assert_eq!(stack.capacity(), capacity);
+
+        let mut reserved = ComponentStack::new();
+        reserved.reserve(20_000);
+        let capacity = reserved.capacity();
+        assert!(capacity >= 20_000);
+
+        push_frame(&mut reserved, 10_000, &drops);
+        assert_eq!(reserved.capacity(), capacity);
+    }
+}
@@ -0,0 +1,36 @@
+//! Per frame render statistics
+
+use std::ops::AddAssign;
+
+/// Work issued by renderer during one render.
+///
+/// Draw calls issued through [super::pass::StoryboardRenderPass] are counted automatically, assuming triangle list topology.
+/// Components drawing other topologies or using raw render pass can correct counters using [super::pass::StoryboardRenderPass::stats_mut].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    /// Vertices or indices drawn, multiplied by instance count
+    pub vertices: u64,
+    pub triangles: u64,
+    /// Bytes uploaded through vertex, index and uniform streams
+    pub streamed_bytes: u64,
+}
+
+impl RenderStats {
+    /// Count draw call of triangle list
+    #[inline]
+    pub fn add_draw(&mut self, vertices: u32, instances: u32) {
+        self.draw_calls += 1;
+        self.vertices += vertices as u64 * instances as u64;
+        self.triangles += (vertices / 3) as u64 * instances as u64;
+    }
+}
+
+impl AddAssign for RenderStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.draw_calls += rhs.draw_calls;
+        self.vertices += rhs.vertices;
+        self.triangles += rhs.triangles;
+        self.streamed_bytes += rhs.streamed_bytes;
+    }
+}
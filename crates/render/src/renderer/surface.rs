@@ -1,21 +1,34 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, num::NonZeroU32};
 
-use storyboard_core::observable::Observable;
+use storyboard_core::{
+    euclid::{Point2D, Rect, Size2D},
+    unit::PhyiscalPixelUnit,
+};
 use wgpu::{
-    self, Color, CommandBuffer, CommandEncoderDescriptor, LoadOp, Operations, PresentMode,
-    RenderPassColorAttachment, Surface, SurfaceTexture, TextureUsages, TextureViewDescriptor,
+    self, BindGroup, Color, CommandBuffer, CommandEncoder, CommandEncoderDescriptor,
+    CompositeAlphaMode, FilterMode, LoadOp, Operations, PresentMode, RenderPassColorAttachment,
+    Surface, SurfaceError, SurfaceTexture, TextureFormat, TextureUsages, TextureViewDescriptor,
 };
 
-use crate::{component::Drawable, shared::RenderScope};
+use crate::{
+    component::Drawable,
+    shared::RenderScope,
+    texture::{SizedTexture2D, SizedTextureView2D},
+};
 
-use super::{ScreenRect, StoryboardRenderer};
+use super::{
+    blit::{letterbox, BlitResources},
+    Backdrop, ScreenRect, StoryboardRenderer,
+};
 
 #[derive(Debug)]
 pub struct StoryboardSurfaceRenderer {
     surface: Surface,
-    configuration: Observable<SurfaceConfiguration>,
+    configuration: SurfaceConfiguration,
+    configured: Option<ConfiguredSurface>,
 
     renderer: StoryboardRenderer,
+    internal_target: Option<InternalTarget>,
 }
 
 impl StoryboardSurfaceRenderer {
@@ -24,74 +37,198 @@ impl StoryboardSurfaceRenderer {
 
         Self {
             surface,
-            configuration: configuration.into(),
+            configuration,
+            configured: None,
             renderer,
+            internal_target: None,
         }
     }
 
     pub fn configuration(&self) -> SurfaceConfiguration {
-        *self.configuration
+        self.configuration
     }
 
+    /// Set configuration used on next render.
+    /// Surface is only reconfigured if size, format, present mode or alpha mode changed,
+    /// so setting many intermediate sizes between frames reconfigures once with latest one.
     pub fn set_configuration(&mut self, configuration: SurfaceConfiguration) {
-        if self.configuration.ne(&configuration) {
-            self.configuration = configuration.into();
-        }
+        self.configuration = configuration;
+    }
+
+    pub const fn renderer(&self) -> &StoryboardRenderer {
+        &self.renderer
     }
 
+    pub fn renderer_mut(&mut self) -> &mut StoryboardRenderer {
+        &mut self.renderer
+    }
+
+    /// Render drawables into current surface texture.
+    /// Surface is reconfigured on next call if it is lost or outdated.
     pub fn render<'a>(
         &mut self,
         scope: RenderScope,
         drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
-    ) -> Option<SurfaceRenderResult> {
+    ) -> Result<SurfaceRenderResult, SurfaceError> {
+        let backend = scope.backend();
+
+        let mut encoder = backend
+            .device()
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("StoryboardSurfaceRenderer command encoder"),
+            });
+
+        let surface_texture = self.render_with_encoder(scope, drawables, &mut encoder)?;
+
+        let mut command_buffers = self.renderer.take_prepare_commands();
+        command_buffers.push(encoder.finish());
+
+        Ok(SurfaceRenderResult {
+            surface_texture,
+            command_buffers,
+        })
+    }
+
+    /// Render drawables into current surface texture, appending commands to caller provided encoder.
+    /// Commands recorded into encoder before this call run before surface rendering.
+    /// Surface texture must be presented after the encoder is submitted.
+    ///
+    /// With parallel prepare, submit [StoryboardRenderer::take_prepare_commands] of [Self::renderer_mut] before encoder in same submit.
+    pub fn render_with_encoder<'a>(
+        &mut self,
+        scope: RenderScope,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        encoder: &mut CommandEncoder,
+    ) -> Result<SurfaceTexture, SurfaceError> {
         let backend = scope.backend();
 
-        if Observable::invalidate(&mut self.configuration)
-            && self.configuration.screen.rect.size.area() > 0
-        {
+        let configured = ConfiguredSurface {
+            size: self.configuration.screen.rect.size,
+            format: scope.pipeline().texture_format,
+            present_mode: self.configuration.present_mode,
+            alpha_mode: self.configuration.alpha_mode,
+        };
+
+        if self.configured != Some(configured) && configured.size.area() > 0 {
             self.surface.configure(
                 backend.device(),
                 &wgpu::SurfaceConfiguration {
-                    usage: TextureUsages::RENDER_ATTACHMENT,
-                    format: scope.pipeline().texture_format,
-                    width: self.configuration.screen.rect.size.width,
-                    height: self.configuration.screen.rect.size.height,
-                    present_mode: self.configuration.present_mode,
+                    // Copied by components reading backdrop
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+                    format: configured.format,
+                    width: configured.size.width,
+                    height: configured.size.height,
+                    present_mode: configured.present_mode,
+                    alpha_mode: configured.alpha_mode,
                 },
             );
+
+            self.configured = Some(configured);
         }
 
-        if let Ok(surface_texture) = self.surface.get_current_texture() {
-            let mut encoder = backend
-                .device()
-                .create_command_encoder(&CommandEncoderDescriptor {
-                    label: Some("StoryboardSurfaceRenderer command encoder"),
-                });
-
-            self.renderer.render(
-                scope,
-                self.configuration.screen,
-                drawables,
-                Some(RenderPassColorAttachment {
-                    view: &surface_texture
-                        .texture
-                        .create_view(&TextureViewDescriptor::default()),
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: true,
+        let surface_texture = match self.surface.get_current_texture() {
+            Ok(surface_texture) => surface_texture,
+
+            Err(err) => {
+                if let SurfaceError::Lost | SurfaceError::Outdated = err {
+                    self.configured = None;
+                }
+
+                return Err(err);
+            }
+        };
+
+        let surface_view = surface_texture
+            .texture
+            .create_view(&TextureViewDescriptor::default());
+
+        match self.configuration.internal_resolution {
+            Some(internal_resolution) => {
+                let screen = internal_resolution.screen(&self.configuration.screen);
+                let format = scope.pipeline().texture_format;
+
+                let target = match &mut self.internal_target {
+                    Some(target)
+                        if target.texture.size() == screen.rect.size
+                            && target.format == format
+                            && target.filter == internal_resolution.filter =>
+                    {
+                        target
+                    }
+
+                    target => target.insert(InternalTarget::init(
+                        scope,
+                        screen.rect.size,
+                        format,
+                        internal_resolution.filter,
+                    )),
+                };
+
+                self.renderer.render_with_backdrop(
+                    scope,
+                    screen,
+                    drawables,
+                    Some(RenderPassColorAttachment {
+                        view: target.view.inner(),
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Clear(self.configuration.clear_color),
+                            store: true,
+                        },
+                    }),
+                    Backdrop {
+                        texture: target.texture.inner(),
+                        size: target.texture.size(),
                     },
-                }),
-                &mut encoder,
-            );
+                    encoder,
+                );
 
-            return Some(SurfaceRenderResult {
-                surface_texture,
-                command_buffer: encoder.finish(),
-            });
+                let blit = scope.get::<BlitResources>();
+
+                match internal_resolution.resolution {
+                    RenderResolution::Supersample(_) => blit.downsample_pass(
+                        encoder,
+                        &target.bind_group,
+                        &surface_view,
+                        self.configuration.viewport(),
+                        self.configuration.clear_color,
+                    ),
+
+                    _ => blit.blit_pass(
+                        encoder,
+                        &target.bind_group,
+                        &surface_view,
+                        self.configuration.viewport(),
+                        self.configuration.clear_color,
+                    ),
+                }
+            }
+
+            None => {
+                self.internal_target = None;
+
+                self.renderer.render_with_backdrop(
+                    scope,
+                    self.configuration.screen,
+                    drawables,
+                    Some(RenderPassColorAttachment {
+                        view: &surface_view,
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Clear(self.configuration.clear_color),
+                            store: true,
+                        },
+                    }),
+                    Backdrop {
+                        texture: &surface_texture.texture,
+                        size: configured.size,
+                    },
+                    encoder,
+                );
+            }
         }
 
-        None
+        Ok(surface_texture)
     }
 
     pub fn into_inner(self) -> Surface {
@@ -103,10 +240,202 @@ impl StoryboardSurfaceRenderer {
 pub struct SurfaceConfiguration {
     pub present_mode: PresentMode,
     pub screen: ScreenRect,
+
+    /// Compositing of surface alpha with windows behind it. Must be one of [wgpu::Surface::get_supported_alpha_modes].
+    ///
+    /// Use [CompositeAlphaMode::PreMultiplied] with transparent clear color for see-through window.
+    /// Window must be created transparent too. Support varies by platform:
+    /// Metal and Vulkan on Wayland or Android support [CompositeAlphaMode::PreMultiplied],
+    /// Vulkan on X11 depends on compositor, DX12 and GL surfaces only support [CompositeAlphaMode::Opaque].
+    pub alpha_mode: CompositeAlphaMode,
+
+    /// Maximum frames submitted to gpu but not finished yet. Clamped to [SurfaceConfiguration::MAX_FRAME_LATENCY].
+    ///
+    /// Render task waits for older frame before submitting new one, so lower value reduces input lag and higher value allows more throughput.
+    /// With [PresentMode::Fifo] frames also queue on swapchain, so cpu may run ahead of display by this many refresh intervals.
+    /// With [PresentMode::Mailbox] or [PresentMode::Immediate] frames are not queued and it only limits gpu work in flight.
+    pub max_frame_latency: NonZeroU32,
+
+    /// Color surface is cleared with before drawing. Alpha is kept if [SurfaceConfiguration::alpha_mode] is not opaque.
+    pub clear_color: Color,
+
+    /// Render at different resolution than surface and upscale to present. Renders at surface resolution if None.
+    pub internal_resolution: Option<InternalResolution>,
+}
+
+impl SurfaceConfiguration {
+    /// Maximum frame latency supported. Swapchains hold at most three images on every backend.
+    pub const MAX_FRAME_LATENCY: u32 = 3;
+
+    /// Frames in flight render task allows, clamped into supported range
+    pub fn frame_latency(&self) -> u32 {
+        self.max_frame_latency.get().min(Self::MAX_FRAME_LATENCY)
+    }
+
+    /// Screen drawables are rendered into
+    pub fn render_screen(&self) -> ScreenRect {
+        match self.internal_resolution {
+            Some(internal_resolution) => internal_resolution.screen(&self.screen),
+            None => self.screen,
+        }
+    }
+
+    /// Region of surface rendered image is presented in, in physical pixel.
+    /// Use to map window coordinates into rendered image when letterboxed.
+    pub fn viewport(&self) -> Rect<u32, PhyiscalPixelUnit> {
+        match self.internal_resolution {
+            Some(InternalResolution {
+                resolution: RenderResolution::Fixed(size),
+                ..
+            }) => letterbox(size, self.screen.rect.size),
+
+            _ => Rect::from_size(self.screen.rect.size),
+        }
+    }
+}
+
+/// Surface state last passed to [Surface::configure]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ConfiguredSurface {
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    format: TextureFormat,
+    present_mode: PresentMode,
+    alpha_mode: CompositeAlphaMode,
+}
+
+/// Resolution drawables are rendered at, before upscaled to surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InternalResolution {
+    pub resolution: RenderResolution,
+    /// Filter used for scaling into surface
+    pub filter: FilterMode,
+}
+
+impl InternalResolution {
+    /// Render at surface resolution multiplied by scale, filtered linearly
+    pub const fn scaled(scale: f32) -> Self {
+        Self {
+            resolution: RenderResolution::Scale(scale),
+            filter: FilterMode::Linear,
+        }
+    }
+
+    /// Render at surface resolution multiplied by integer factor on both axis and downsample with box filter.
+    /// Smooths every edge, including ones MSAA doesn't cover like textures and shader edges, for clean screenshots.
+    /// Costs factor squared times of fill rate.
+    pub const fn supersampled(factor: NonZeroU32) -> Self {
+        Self {
+            resolution: RenderResolution::Supersample(factor),
+            filter: FilterMode::Nearest,
+        }
+    }
+
+    /// Render at fixed resolution with nearest filtering, for pixel perfect rendering
+    pub const fn fixed(size: Size2D<u32, PhyiscalPixelUnit>) -> Self {
+        Self {
+            resolution: RenderResolution::Fixed(size),
+            filter: FilterMode::Nearest,
+        }
+    }
+
+    /// Internal screen for given surface screen.
+    /// Scale factor is adjusted so logical pixels keep their size on the surface.
+    pub fn screen(&self, surface: &ScreenRect) -> ScreenRect {
+        match self.resolution {
+            RenderResolution::Scale(scale) => ScreenRect::new(
+                Rect::from_size(
+                    (surface.rect.size.cast::<f32>() * scale)
+                        .round()
+                        .cast::<u32>()
+                        .max(Size2D::new(1, 1)),
+                ),
+                surface.scale_factor * scale,
+            ),
+
+            RenderResolution::Supersample(factor) => ScreenRect::new(
+                Rect::from_size(surface.rect.size * factor.get()),
+                surface.scale_factor * factor.get() as f32,
+            ),
+
+            RenderResolution::Fixed(size) => {
+                let viewport = letterbox(size, surface.rect.size);
+
+                let scale = if viewport.is_empty() {
+                    1.0
+                } else {
+                    viewport.size.width as f32 / size.width as f32
+                };
+
+                ScreenRect::new(
+                    Rect::new(Point2D::zero(), size),
+                    surface.scale_factor / scale,
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderResolution {
+    /// Multiply surface resolution. Values below 1.0 upscale, above 1.0 supersample.
+    Scale(f32),
+
+    /// Multiply surface resolution by integer factor and downsample with box filter instead of linear filter
+    Supersample(NonZeroU32),
+
+    /// Fixed resolution. Letterboxed if aspect ratio differs from surface.
+    Fixed(Size2D<u32, PhyiscalPixelUnit>),
+}
+
+/// Offscreen texture rendered at internal resolution
+#[derive(Debug)]
+struct InternalTarget {
+    texture: SizedTexture2D,
+    view: SizedTextureView2D,
+    format: TextureFormat,
+    filter: FilterMode,
+
+    bind_group: BindGroup,
+}
+
+impl InternalTarget {
+    pub fn init(
+        scope: RenderScope,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: TextureFormat,
+        filter: FilterMode,
+    ) -> Self {
+        let device = scope.backend().device();
+
+        let texture = SizedTexture2D::init(
+            device,
+            Some("StoryboardSurfaceRenderer internal texture"),
+            size,
+            format,
+            TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+        );
+        let view = texture.create_view_default(None);
+
+        let bind_group =
+            scope
+                .get::<BlitResources>()
+                .create_bind_group(device, view.inner(), filter);
+
+        Self {
+            texture,
+            view,
+            format,
+            filter,
+            bind_group,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct SurfaceRenderResult {
     pub surface_texture: SurfaceTexture,
-    pub command_buffer: CommandBuffer,
+    /// Command buffers of the frame, to be submitted in order in single submit
+    pub command_buffers: Vec<CommandBuffer>,
 }
@@ -1,5 +1,10 @@
-use storyboard_core::store::{Store, StoreResources};
-use wgpu::{DepthStencilState, Device, MultisampleState, Queue, TextureFormat};
+use storyboard_core::store::{Store, StoreResources, StoreResourcesList};
+use wgpu::{
+    DepthStencilState, Device, Face, FrontFace, MultisampleState, PrimitiveState,
+    PrimitiveTopology, Queue, TextureFormat,
+};
+
+use crate::renderer::RenderMode;
 
 #[derive(Debug, Default)]
 /// Shared backend data container
@@ -62,6 +67,11 @@ impl<'a> BackendScope<'a> {
         self.container.store.get(&self.context)
     }
 
+    /// Initialize tuple of backend resources ahead of first use
+    pub fn precompile<T: StoreResourcesList<BackendScopeContext<'a>>>(&self) {
+        self.container.store.precompile::<T, _>(&self.context)
+    }
+
     #[inline]
     pub const fn render_scope(self, container: &'a RenderShared) -> RenderScope {
         RenderScope::new(self, container)
@@ -146,6 +156,11 @@ impl<'a> RenderScope<'a> {
     pub fn get<T: for<'ctx> StoreResources<RenderScopeContext<'ctx>>>(&self) -> &'a T {
         self.container.store.get(&self.context())
     }
+
+    /// Initialize tuple of render resources like component pipelines ahead of first use, so first frame drawing them doesn't stutter
+    pub fn precompile<T: for<'ctx> StoreResourcesList<RenderScopeContext<'ctx>>>(&self) {
+        self.container.store.precompile::<T, _>(&self.context())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -153,6 +168,11 @@ pub struct RenderPipelineData {
     pub texture_format: TextureFormat,
     pub depth_stencil: Option<DepthStencilState>,
     pub multi_sample: Option<MultisampleState>,
+
+    /// Face culled by component pipelines. Default is None, which draws both faces.
+    pub cull_mode: Option<Face>,
+    /// Winding order of front face. Components emit counter clockwise triangles on screen.
+    pub front_face: FrontFace,
 }
 
 impl RenderPipelineData {
@@ -165,9 +185,64 @@ impl RenderPipelineData {
             texture_format,
             depth_stencil,
             multi_sample,
+
+            cull_mode: None,
+            front_face: FrontFace::Ccw,
+        }
+    }
+
+    /// Create copy of pipeline data using given culling configuration
+    pub fn with_cull_mode(&self, cull_mode: Option<Face>, front_face: FrontFace) -> Self {
+        Self {
+            cull_mode,
+            front_face,
+            ..self.clone()
+        }
+    }
+
+    /// Primitive state for component pipelines using configured culling
+    pub fn primitive_state(&self, topology: PrimitiveTopology) -> PrimitiveState {
+        PrimitiveState {
+            topology,
+            front_face: self.front_face,
+            cull_mode: self.cull_mode,
+            ..PrimitiveState::default()
         }
     }
 
+    /// Create copy of pipeline data targeting other color format, like [TextureFormat::Rgba16Float] for HDR offscreen rendering
+    pub fn with_texture_format(&self, texture_format: TextureFormat) -> Self {
+        Self {
+            texture_format,
+            ..self.clone()
+        }
+    }
+
+    /// Check if blending into render target happens in linear space.
+    /// Colors are linear, so blending in non sRGB unorm target produces dark fringes on antialiased edges.
+    pub fn linear_blending(&self) -> bool {
+        self.texture_format.describe().srgb
+            || matches!(
+                self.texture_format,
+                TextureFormat::Rgba16Float | TextureFormat::Rgba32Float
+            )
+    }
+
+    /// [RenderMode] of renderer using the pipeline data, decided by depth stencil state
+    pub fn render_mode(&self) -> RenderMode {
+        match &self.depth_stencil {
+            Some(depth_stencil) => RenderMode::Depth(depth_stencil.format),
+            None => RenderMode::PainterOrder,
+        }
+    }
+
+    /// Check if component pipelines test stencil written by [crate::renderer::ComponentQueue::push_rounded_clip]
+    pub fn has_stencil(&self) -> bool {
+        self.depth_stencil
+            .as_ref()
+            .map_or(false, |depth_stencil| depth_stencil.stencil.is_enabled())
+    }
+
     pub fn depth_stencil_read_only(&self) -> Option<DepthStencilState> {
         self.depth_stencil.clone().map(|mut depth_stencil| {
             depth_stencil.depth_write_enabled = false;
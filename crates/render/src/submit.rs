@@ -0,0 +1,52 @@
+use wgpu::{CommandBuffer, CommandEncoder, Queue, SubmissionIndex};
+
+/// Command buffers collected from multiple renderers, submitted in single [Queue::submit] call.
+///
+/// Command buffers are executed in push order, so commands of earlier buffer are visible to later ones.
+/// Render main renderer after renderers producing textures it samples, or record them into same [CommandEncoder].
+/// Commands recorded into one encoder run in recording order.
+#[derive(Debug, Default)]
+pub struct SubmitBatch {
+    buffers: Vec<CommandBuffer>,
+}
+
+impl SubmitBatch {
+    pub fn new() -> Self {
+        Self {
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Finish encoder and append it to batch
+    pub fn push_encoder(&mut self, encoder: CommandEncoder) {
+        self.buffers.push(encoder.finish());
+    }
+
+    pub fn push(&mut self, buffer: CommandBuffer) {
+        self.buffers.push(buffer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Submit every command buffers in batch at once and clear batch.
+    /// Returns None if batch was empty.
+    pub fn submit(&mut self, queue: &Queue) -> Option<SubmissionIndex> {
+        if self.buffers.is_empty() {
+            return None;
+        }
+
+        Some(queue.submit(self.buffers.drain(..)))
+    }
+}
+
+impl Extend<CommandBuffer> for SubmitBatch {
+    fn extend<T: IntoIterator<Item = CommandBuffer>>(&mut self, iter: T) {
+        self.buffers.extend(iter);
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::VecDeque,
     hint, iter,
     num::NonZeroU32,
     sync::{
@@ -8,31 +9,52 @@ use std::{
     time::Duration,
 };
 
+#[cfg(feature = "parallel")]
+use std::num::NonZeroUsize;
+
 use crate::{
     backend::StoryboardBackend,
     component::Drawable,
-    renderer::surface::{StoryboardSurfaceRenderer, SurfaceConfiguration},
+    compute::{Dispatchable, StoryboardComputer},
+    renderer::{
+        depth::DepthTexturePool,
+        stats::RenderStats,
+        surface::{StoryboardSurfaceRenderer, SurfaceConfiguration},
+    },
     shared::{BackendScopeContext, BackendShared, RenderShared},
 };
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, Receiver, Sender, TryIter};
+use instant::Instant;
 use parking_lot::{Mutex, MutexGuard};
-use storyboard_core::{tick_task::IndependentTickTask, time_sampler::TimeSampler};
+use storyboard_core::{
+    tick_task::IndependentTickTask,
+    time_sampler::{FrameStats, TimeSampler},
+};
 use trait_stack::TraitStack;
 use triple_buffer::{Input, Output, TripleBuffer};
-use wgpu::{CommandBuffer, Maintain};
+use wgpu::{CommandBuffer, CommandEncoderDescriptor, Maintain, SubmissionIndex, SurfaceError};
 
 #[derive(Debug)]
 pub struct RenderTask {
     renderer_config: Arc<(Mutex<RenderConfiguration>, AtomicBool)>,
-    input: Input<(TraitStack<dyn Drawable + 'static>, Vec<CommandBuffer>)>,
+    input: Input<FrameInput>,
 
     frame_rate: Arc<AtomicU64>,
+    stats: Arc<Mutex<FrameStats>>,
+    render_stats: Arc<Mutex<RenderStats>>,
 
     signal_sender: Sender<()>,
+    event_receiver: Receiver<RenderEvent>,
     task: IndependentTickTask<RenderTaskData>,
 }
 
 impl RenderTask {
+    /// Consecutive surface lost frames before [RenderEvent::DeviceLost] is reported
+    pub const MAX_LOST_FRAMES: u32 = 3;
+
+    /// Default timeout of [RenderTask::shutdown]
+    pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
     pub fn run(
         backend: Arc<StoryboardBackend>,
         backend_shared: Arc<BackendShared>,
@@ -40,11 +62,24 @@ impl RenderTask {
         renderer: StoryboardSurfaceRenderer,
         task_config: RenderTaskConfiguration,
     ) -> Self {
+        #[cfg(feature = "parallel")]
+        let renderer = {
+            let mut renderer = renderer;
+            renderer
+                .renderer_mut()
+                .set_parallel_prepare(task_config.parallel_prepare);
+
+            renderer
+        };
+
         let (input, output) = TripleBuffer::default().split();
 
         let (signal_sender, signal_receiver) = bounded(2);
+        let (event_sender, event_receiver) = bounded(16);
 
         let frame_rate = Arc::new(AtomicU64::new(0));
+        let stats = Arc::new(Mutex::new(FrameStats::default()));
+        let render_stats = Arc::new(Mutex::new(RenderStats::default()));
 
         let renderer_config = Arc::new((
             Mutex::new(RenderConfiguration {
@@ -61,12 +96,23 @@ impl RenderTask {
 
             configuration: renderer_config.clone(),
             signal_receiver,
+            event_sender,
             output,
 
             frame_sampler: TimeSampler::new(task_config.report_rate),
             max_fps: task_config.max_fps,
+            presentation_events: task_config.presentation_events,
+            empty_frame: task_config.empty_frame,
+            acquire_retries: task_config.acquire_retries,
+            last_frame_empty: false,
+            presented_frames: 0,
             frame_rate: frame_rate.clone(),
+            stats: stats.clone(),
+            render_stats: render_stats.clone(),
+            lost_frames: 0,
+            in_flight: VecDeque::new(),
 
+            computer: StoryboardComputer::new(),
             renderer,
         };
 
@@ -82,10 +128,27 @@ impl RenderTask {
 
                     data.frame_sampler.report_rate = configuration.task.report_rate;
                     data.max_fps = configuration.task.max_fps;
+                    data.presentation_events = configuration.task.presentation_events;
+                    data.empty_frame = configuration.task.empty_frame;
+                    data.acquire_retries = configuration.task.acquire_retries;
+
+                    #[cfg(feature = "parallel")]
+                    data.renderer
+                        .renderer_mut()
+                        .set_parallel_prepare(configuration.task.parallel_prepare);
                 }
 
                 if data.output.update() {
-                    if !data.output.output_buffer().0.is_empty() {
+                    let empty = data.output.output_buffer().0.is_empty();
+
+                    let render = !empty
+                        || match data.empty_frame {
+                            EmptyFrame::Present => true,
+                            EmptyFrame::PresentOnce => !data.last_frame_empty,
+                            EmptyFrame::Skip => false,
+                        };
+
+                    if render {
                         let scope = data
                             .backend_shared
                             .scope(BackendScopeContext {
@@ -94,20 +157,94 @@ impl RenderTask {
                             })
                             .render_scope(&data.render_shared);
 
-                        if let Some(res) = data
-                            .renderer
-                            .render(scope, data.output.output_buffer().0.iter())
-                        {
-                            data.backend.device().poll(Maintain::Wait);
-                            data.backend.queue().submit(
-                                iter::once(res.command_buffer)
-                                    .chain(data.output.output_buffer().1.drain(..)),
-                            );
-
-                            res.surface_texture.present();
+                        let mut encoder = data.backend.device().create_command_encoder(
+                            &CommandEncoderDescriptor {
+                                label: Some("RenderTask command encoder"),
+                            },
+                        );
+
+                        // Compute pass runs before render pass, so drawables can use its results
+                        data.computer.dispatch(
+                            scope,
+                            data.output.output_buffer().2.iter(),
+                            &mut encoder,
+                        );
+
+                        let result = retry_transient(data.acquire_retries, || {
+                            data.renderer.render_with_encoder(
+                                scope,
+                                data.output.output_buffer().0.iter(),
+                                &mut encoder,
+                            )
+                        });
+
+                        match result {
+                            Ok(surface_texture) => {
+                                data.lost_frames = 0;
+                                data.last_frame_empty = empty;
+                                *data.render_stats.lock() = data.renderer.renderer().stats();
+
+                                // Wait for old frames until new frame fits in latency
+                                let latency = data.renderer.configuration().frame_latency();
+                                while data.in_flight.len() >= latency as usize {
+                                    if let Some(index) = data.in_flight.pop_front() {
+                                        data.backend.poll(Maintain::WaitForSubmissionIndex(index));
+                                    }
+                                }
+                                data.backend.poll(Maintain::Poll);
+
+                                // Commands split by parallel prepare run before rest of the frame
+                                let prepare_commands =
+                                    data.renderer.renderer_mut().take_prepare_commands();
+
+                                data.in_flight.push_back(
+                                    data.backend.queue().submit(
+                                        prepare_commands
+                                            .into_iter()
+                                            .chain(iter::once(encoder.finish()))
+                                            .chain(data.output.output_buffer().1.drain(..)),
+                                    ),
+                                );
+
+                                surface_texture.present();
+
+                                data.presented_frames += 1;
+                                if data.presentation_events {
+                                    data.event_sender
+                                        .try_send(RenderEvent::Presented {
+                                            frame: data.presented_frames,
+                                            time: Instant::now(),
+                                        })
+                                        .ok();
+                                }
+
+                                scope.backend().get::<DepthTexturePool>().evict_idle();
+                            }
+
+                            // Transient errors kept failing after retries. Frame is skipped and surface is reconfigured
+                            Err(err @ (SurfaceError::Timeout | SurfaceError::Outdated)) => {
+                                log::warn!(
+                                    "Dropped frame, surface texture acquisition failed after {} retries: {}",
+                                    data.acquire_retries,
+                                    err
+                                );
+                            }
+
+                            Err(SurfaceError::Lost) => {
+                                data.lost_frames += 1;
+
+                                // Reconfiguring surface doesn't recover it
+                                if data.lost_frames == RenderTask::MAX_LOST_FRAMES {
+                                    data.event_sender.try_send(RenderEvent::DeviceLost).ok();
+                                }
+                            }
+
+                            Err(err) => {
+                                data.event_sender.try_send(RenderEvent::Error(err)).ok();
+                            }
                         }
                     } else if !data.output.output_buffer().1.is_empty() {
-                        data.backend.device().poll(Maintain::Wait);
+                        data.backend.poll(Maintain::Wait);
                         data.backend
                             .queue()
                             .submit(data.output.output_buffer().1.drain(..));
@@ -120,10 +257,15 @@ impl RenderTask {
                     }
                 }
 
-                data.frame_sampler.sample_end();
+                let elapsed = data.frame_sampler.sample_end();
 
                 if let Some(rate) = data.frame_sampler.average_elapsed() {
                     data.frame_rate.store(rate.to_bits(), Ordering::Relaxed);
+
+                    *data.stats.lock() = data.frame_sampler.stats();
+                } else if let Some(mut stats) = data.stats.try_lock() {
+                    stats.last_frame = elapsed;
+                    stats.dropped_frames = data.frame_sampler.dropped_frames();
                 }
             }
         });
@@ -131,12 +273,20 @@ impl RenderTask {
         Self {
             renderer_config,
             frame_rate,
+            stats,
+            render_stats,
             input,
             signal_sender,
+            event_receiver,
             task,
         }
     }
 
+    /// Iterate render events reported since last call without blocking
+    pub fn events(&self) -> TryIter<RenderEvent> {
+        self.event_receiver.try_iter()
+    }
+
     pub fn configuration(&self) -> RenderConfiguration {
         *self.renderer_config.0.lock()
     }
@@ -157,6 +307,16 @@ impl RenderTask {
         f64::from_bits(self.frame_rate.load(Ordering::Relaxed))
     }
 
+    /// Frame time statistics. Percentiles are updated every report rate.
+    pub fn stats(&self) -> FrameStats {
+        *self.stats.lock()
+    }
+
+    /// Draw calls, vertices and streamed bytes of last presented frame
+    pub fn last_frame_stats(&self) -> RenderStats {
+        *self.render_stats.lock()
+    }
+
     pub fn interrupted(&self) -> bool {
         self.task.interrupted()
     }
@@ -187,6 +347,12 @@ impl RenderTask {
         self.input.input_buffer().1.push(buffer);
     }
 
+    /// Push compute work dispatched before drawables of current frame are rendered.
+    /// Dispatchables are skipped if frame is not rendered, see [RenderTaskConfiguration::empty_frame].
+    pub fn push_dispatch(&mut self, item: impl Dispatchable + 'static) {
+        self.input.input_buffer().2.push(item);
+    }
+
     pub fn submit(&mut self) {
         self.input.publish();
         self.signal_sender.try_send(()).ok();
@@ -194,10 +360,28 @@ impl RenderTask {
         self.task.tick();
         self.input.input_buffer().0.clear();
         self.input.input_buffer().1.clear();
+        self.input.input_buffer().2.clear();
     }
 
     pub fn join(self) -> StoryboardSurfaceRenderer {
-        self.task.join().renderer
+        self.task.join().finish()
+    }
+
+    /// Stop render thread after its current frame, then wait for gpu to finish submitted frames before releasing resources.
+    /// Returns task back if render thread doesn't finish within timeout.
+    pub fn shutdown(mut self, timeout: Duration) -> Result<StoryboardSurfaceRenderer, Self> {
+        self.interrupt();
+
+        match self.task.join_timeout(timeout) {
+            Ok(data) => Ok(data.finish()),
+
+            Err(task) => {
+                log::warn!("Render thread didn't finish within {:?}", timeout);
+
+                self.task = task;
+                Err(self)
+            }
+        }
     }
 }
 
@@ -209,15 +393,68 @@ struct RenderTaskData {
 
     configuration: Arc<(Mutex<RenderConfiguration>, AtomicBool)>,
     signal_receiver: Receiver<()>,
-    output: Output<(TraitStack<dyn Drawable + 'static>, Vec<CommandBuffer>)>,
+    event_sender: Sender<RenderEvent>,
+    output: Output<FrameInput>,
 
     frame_sampler: TimeSampler,
     max_fps: Option<NonZeroU32>,
+    presentation_events: bool,
+    empty_frame: EmptyFrame,
+    acquire_retries: u32,
+    /// Last presented frame had no drawables
+    last_frame_empty: bool,
+    presented_frames: u64,
     frame_rate: Arc<AtomicU64>,
+    stats: Arc<Mutex<FrameStats>>,
+    render_stats: Arc<Mutex<RenderStats>>,
+    lost_frames: u32,
+    /// Submissions of frames which may not be finished yet, oldest first
+    in_flight: VecDeque<SubmissionIndex>,
 
+    computer: StoryboardComputer,
     renderer: StoryboardSurfaceRenderer,
 }
 
+impl RenderTaskData {
+    /// Wait for submitted frames, so resources are not released while gpu uses them
+    fn finish(self) -> StoryboardSurfaceRenderer {
+        self.backend.poll(Maintain::Wait);
+
+        self.renderer
+    }
+}
+
+/// Drawables, command buffers and dispatchables of one frame
+type FrameInput = (
+    TraitStack<dyn Drawable + 'static>,
+    Vec<CommandBuffer>,
+    TraitStack<dyn Dispatchable + 'static>,
+);
+
+/// Event reported from render thread.
+/// Transient surface errors are recovered in render thread and not reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderEvent {
+    /// Fatal surface error like out of memory. Rendering cannot continue.
+    Error(SurfaceError),
+
+    /// Surface stays lost after reconfiguring, which usually means gpu device is lost.
+    /// Backend and render task must be initialized again.
+    DeviceLost,
+
+    /// Frame was presented. Reported if [RenderTaskConfiguration::presentation_events] is enabled.
+    ///
+    /// wgpu doesn't expose presentation timing, so `time` is taken right after `present()` returns.
+    /// Frame reaches display later by compositor latency, up to one refresh interval with [wgpu::PresentMode::Fifo].
+    /// Vulkan and DX12 may block in `present()` until swapchain image is available, so the time is closer to vblank there.
+    /// Metal and web return immediately and the time is when frame is handed to compositor.
+    Presented {
+        /// Number of frames presented by render task, starting from 1
+        frame: u64,
+        time: Instant,
+    },
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RenderConfiguration {
     pub surface: SurfaceConfiguration,
@@ -228,6 +465,21 @@ pub struct RenderConfiguration {
 pub struct RenderTaskConfiguration {
     pub report_rate: Duration,
     pub max_fps: Option<NonZeroU32>,
+
+    /// Report [RenderEvent::Presented] every frame, for syncing audio or video to rendered frames.
+    /// Events are dropped if event channel is full, so drain [RenderTask::events] every update.
+    pub presentation_events: bool,
+
+    /// Handling of frames without drawables
+    pub empty_frame: EmptyFrame,
+
+    /// Times surface texture acquisition is retried in same frame if it times out or surface is outdated, before frame is dropped.
+    /// Each acquisition waits up to timeout of wgpu backend, so render thread stays responsive if presentation queue stays full.
+    pub acquire_retries: u32,
+
+    /// Prepare drawables on multiple threads if drawables count exceeds this chunk size
+    #[cfg(feature = "parallel")]
+    pub parallel_prepare: Option<NonZeroUsize>,
 }
 
 impl Default for RenderTaskConfiguration {
@@ -235,6 +487,90 @@ impl Default for RenderTaskConfiguration {
         Self {
             report_rate: Duration::from_secs(1),
             max_fps: None,
+            presentation_events: false,
+            empty_frame: EmptyFrame::default(),
+            acquire_retries: 2,
+
+            #[cfg(feature = "parallel")]
+            parallel_prepare: None,
         }
     }
 }
+
+/// Call `acquire` again while it fails with transient surface error, up to `retries` times
+fn retry_transient<T>(
+    retries: u32,
+    mut acquire: impl FnMut() -> Result<T, SurfaceError>,
+) -> Result<T, SurfaceError> {
+    let mut result = acquire();
+
+    for _ in 0..retries {
+        match result {
+            Err(SurfaceError::Timeout | SurfaceError::Outdated) => result = acquire(),
+            _ => break,
+        }
+    }
+
+    result
+}
+
+/// Handling of submitted frames without drawables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyFrame {
+    /// Clear surface with clear color and present every empty frame
+    Present,
+
+    /// Clear and present first empty frame only.
+    /// Following empty frames are skipped to save power, since surface already shows cleared frame.
+    PresentOnce,
+
+    /// Skip empty frames. Surface keeps showing last presented frame.
+    Skip,
+}
+
+impl Default for EmptyFrame {
+    fn default() -> Self {
+        Self::PresentOnce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::SurfaceError;
+
+    use super::retry_transient;
+
+    #[test]
+    fn retry_transient_test() {
+        // Recovers when acquisition fails transiently
+        let mut attempts = 0;
+        let result = retry_transient(2, || {
+            attempts += 1;
+
+            match attempts {
+                1 => Err(SurfaceError::Timeout),
+                2 => Err(SurfaceError::Outdated),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result, Ok(3));
+
+        // Gives up after retries
+        let mut attempts = 0;
+        let result = retry_transient(2, || {
+            attempts += 1;
+            Err::<(), _>(SurfaceError::Timeout)
+        });
+        assert_eq!(result, Err(SurfaceError::Timeout));
+        assert_eq!(attempts, 3);
+
+        // Other errors are not retried
+        let mut attempts = 0;
+        let result = retry_transient(2, || {
+            attempts += 1;
+            Err::<(), _>(SurfaceError::Lost)
+        });
+        assert_eq!(result, Err(SurfaceError::Lost));
+        assert_eq!(attempts, 1);
+    }
+}
@@ -1,4 +1,4 @@
-use std::num::NonZeroU32;
+use std::{borrow::Cow, error::Error, fmt::Display, num::NonZeroU32};
 
 use storyboard_core::{
     euclid::{Point2D, Rect, Size2D},
@@ -7,7 +7,7 @@ use storyboard_core::{
 use wgpu::{
     Device, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, Texture, TextureAspect,
     TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
-    TextureViewDescriptor,
+    TextureViewDescriptor, TextureViewDimension,
 };
 
 #[derive(Debug)]
@@ -24,6 +24,18 @@ impl SizedTexture2D {
         size: Size2D<u32, PhyiscalPixelUnit>,
         format: TextureFormat,
         usage: TextureUsages,
+    ) -> Self {
+        Self::init_mipmapped(device, label, size, 1, format, usage)
+    }
+
+    /// Create texture with mip levels. Levels other than first must be written using [SizedTexture2D::write_mip].
+    pub fn init_mipmapped(
+        device: &Device,
+        label: Option<&str>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        mip_level_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
     ) -> Self {
         let texture = device.create_texture(&TextureDescriptor {
             label,
@@ -32,7 +44,7 @@ impl SizedTexture2D {
                 height: size.height,
                 depth_or_array_layers: 1,
             },
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: TextureDimension::D2,
             format,
@@ -54,6 +66,27 @@ impl SizedTexture2D {
         }
     }
 
+    /// Wrap texture created outside of storyboard, like decoded video frame, so it can be sampled by components.
+    ///
+    /// `format`, `size` and `usage` must be same as used to create the texture, since they cannot be queried from [Texture].
+    /// Texture must be single layer 2D texture and `usage` must contain [TextureUsages::TEXTURE_BINDING].
+    pub fn from_raw(
+        texture: Texture,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        usage: TextureUsages,
+    ) -> Result<Self, RawTextureError> {
+        if !usage.contains(TextureUsages::TEXTURE_BINDING) {
+            return Err(RawTextureError::NotSampleable(usage));
+        }
+
+        if size.is_empty() {
+            return Err(RawTextureError::EmptySize);
+        }
+
+        Ok(Self::from_texture(texture, format, size))
+    }
+
     pub const fn inner(&self) -> &Texture {
         &self.texture
     }
@@ -78,14 +111,25 @@ impl SizedTexture2D {
     }
 
     pub fn write(&self, queue: &Queue, rect: Option<Rect<u32, PhyiscalPixelUnit>>, data: &[u8]) {
+        self.write_mip(queue, 0, rect, data)
+    }
+
+    /// Write to mip level. Rect is in pixels of the level, whole level is written if None.
+    pub fn write_mip(
+        &self,
+        queue: &Queue,
+        mip_level: u32,
+        rect: Option<Rect<u32, PhyiscalPixelUnit>>,
+        data: &[u8],
+    ) {
         let (origin, extent) = match rect {
             Some(rect) => rect_to_origin_extent(rect),
 
             None => (
                 Origin3d::ZERO,
                 Extent3d {
-                    width: self.size.width,
-                    height: self.size.height,
+                    width: (self.size.width >> mip_level).max(1),
+                    height: (self.size.height >> mip_level).max(1),
                     depth_or_array_layers: 1,
                 },
             ),
@@ -96,7 +140,7 @@ impl SizedTexture2D {
         queue.write_texture(
             ImageCopyTexture {
                 texture: &self.texture,
-                mip_level: 0,
+                mip_level,
                 origin,
                 aspect: TextureAspect::All,
             },
@@ -113,11 +157,353 @@ impl SizedTexture2D {
         );
     }
 
+    /// Convert data from source color type to texture format and write
+    pub fn write_converted(
+        &self,
+        queue: &Queue,
+        rect: Option<Rect<u32, PhyiscalPixelUnit>>,
+        source: ColorType,
+        data: &[u8],
+    ) -> Result<(), TextureConvertError> {
+        self.write_converted_with(queue, rect, source, data, TextureDataOptions::default())
+    }
+
+    /// Convert data from source color type to texture format with preprocessing options and write
+    pub fn write_converted_with(
+        &self,
+        queue: &Queue,
+        rect: Option<Rect<u32, PhyiscalPixelUnit>>,
+        source: ColorType,
+        data: &[u8],
+        options: TextureDataOptions,
+    ) -> Result<(), TextureConvertError> {
+        let size = rect.map(|rect| rect.size).unwrap_or(self.size);
+
+        let converted = convert_texture_data_with(source, self.format, size, data, options)?;
+        self.write(queue, rect, &converted);
+
+        Ok(())
+    }
+
     pub fn into_inner(self) -> Texture {
         self.texture
     }
 }
 
+/// 2D texture with multiple array layers sharing one texture object.
+///
+/// Each layer can be used as render target using [SizedTexture2DArray::create_layer_view].
+/// To sample every layers in shader, bind view from [SizedTexture2DArray::create_array_view] as `texture_2d_array<f32>`
+/// and sample with `textureSample(texture, texture_sampler, coord, layer)`.
+/// Bind group layout entry must use [TextureViewDimension::D2Array] as `view_dimension`.
+#[derive(Debug)]
+pub struct SizedTexture2DArray {
+    texture: Texture,
+    format: TextureFormat,
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    layers: u32,
+}
+
+impl SizedTexture2DArray {
+    pub fn init(
+        device: &Device,
+        label: Option<&str>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        layers: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label,
+            size: Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+        });
+
+        Self::from_texture(texture, format, size, layers)
+    }
+
+    pub fn from_texture(
+        texture: Texture,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        layers: u32,
+    ) -> Self {
+        Self {
+            texture,
+            format,
+            size,
+            layers,
+        }
+    }
+
+    pub const fn inner(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub const fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub const fn size(&self) -> Size2D<u32, PhyiscalPixelUnit> {
+        self.size
+    }
+
+    pub const fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// Create 2D view of single layer. Returns None if layer is out of range.
+    pub fn create_layer_view(&self, layer: u32, label: Option<&str>) -> Option<SizedTextureView2D> {
+        if layer >= self.layers {
+            return None;
+        }
+
+        Some(SizedTextureView2D::from_view(
+            self.texture
+                .create_view(&layer_view_descriptor(label, layer)),
+            self.size,
+        ))
+    }
+
+    /// Create view of every layers
+    pub fn create_array_view(&self, label: Option<&str>) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor {
+            label,
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        })
+    }
+
+    pub fn write_layer(
+        &self,
+        queue: &Queue,
+        layer: u32,
+        rect: Option<Rect<u32, PhyiscalPixelUnit>>,
+        data: &[u8],
+    ) {
+        let (mut origin, extent) = match rect {
+            Some(rect) => rect_to_origin_extent(rect),
+
+            None => (
+                Origin3d::ZERO,
+                Extent3d {
+                    width: self.size.width,
+                    height: self.size.height,
+                    depth_or_array_layers: 1,
+                },
+            ),
+        };
+        origin.z = layer;
+
+        let format_info = self.format.describe();
+
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin,
+                aspect: TextureAspect::All,
+            },
+            data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(extent.width * format_info.block_size as u32),
+                rows_per_image: NonZeroU32::new(extent.height),
+            },
+            extent,
+        );
+    }
+
+    pub fn into_inner(self) -> Texture {
+        self.texture
+    }
+}
+
+fn layer_view_descriptor(label: Option<&str>, layer: u32) -> TextureViewDescriptor {
+    TextureViewDescriptor {
+        label,
+        dimension: Some(TextureViewDimension::D2),
+        base_array_layer: layer,
+        array_layer_count: NonZeroU32::new(1),
+        ..Default::default()
+    }
+}
+
+/// Pixel layout of cpu side texture data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+    L8,
+    Rgb8,
+    Rgba8,
+    Bgra8,
+}
+
+impl ColorType {
+    pub const fn bytes_per_pixel(&self) -> usize {
+        match self {
+            ColorType::L8 => 1,
+            ColorType::Rgb8 => 3,
+            ColorType::Rgba8 | ColorType::Bgra8 => 4,
+        }
+    }
+
+    fn read_rgba(&self, pixel: &[u8]) -> [u8; 4] {
+        match self {
+            ColorType::L8 => [pixel[0], pixel[0], pixel[0], 0xff],
+            ColorType::Rgb8 => [pixel[0], pixel[1], pixel[2], 0xff],
+            ColorType::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            ColorType::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        }
+    }
+}
+
+/// Preprocessing applied to cpu side texture data before upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextureDataOptions {
+    /// Pixels matching rgb color become fully transparent black.
+    /// Applied before premultiplication, comparing source color.
+    pub color_key: Option<[u8; 3]>,
+
+    /// Multiply color channels by alpha
+    pub premultiply: bool,
+}
+
+impl TextureDataOptions {
+    pub const fn new(color_key: Option<[u8; 3]>, premultiply: bool) -> Self {
+        Self {
+            color_key,
+            premultiply,
+        }
+    }
+
+    pub const fn is_identity(&self) -> bool {
+        self.color_key.is_none() && !self.premultiply
+    }
+
+    fn apply(&self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        if self.color_key == Some([r, g, b]) {
+            return [0, 0, 0, 0];
+        }
+
+        if self.premultiply {
+            let multiply = |channel: u8| ((channel as u16 * a as u16 + 127) / 255) as u8;
+
+            return [multiply(r), multiply(g), multiply(b), a];
+        }
+
+        [r, g, b, a]
+    }
+}
+
+/// Convert texture data of source color type into byte layout of target format.
+/// R8 target takes red channel only. Data is borrowed if no conversion is needed.
+pub fn convert_texture_data(
+    source: ColorType,
+    target: TextureFormat,
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    data: &[u8],
+) -> Result<Cow<[u8]>, TextureConvertError> {
+    convert_texture_data_with(source, target, size, data, TextureDataOptions::default())
+}
+
+/// Convert texture data like [convert_texture_data], applying preprocessing options on each pixel
+pub fn convert_texture_data_with(
+    source: ColorType,
+    target: TextureFormat,
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    data: &[u8],
+    options: TextureDataOptions,
+) -> Result<Cow<[u8]>, TextureConvertError> {
+    let expected = size.area() as usize * source.bytes_per_pixel();
+    if data.len() != expected {
+        return Err(TextureConvertError::InvalidLength {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let target_type = match target {
+        TextureFormat::R8Unorm => ColorType::L8,
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => ColorType::Rgba8,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => ColorType::Bgra8,
+
+        _ => return Err(TextureConvertError::UnsupportedFormat(target)),
+    };
+
+    if source == target_type && options.is_identity() {
+        return Ok(Cow::Borrowed(data));
+    }
+
+    let mut converted = Vec::with_capacity(size.area() as usize * target_type.bytes_per_pixel());
+    for pixel in data.chunks_exact(source.bytes_per_pixel()) {
+        let [r, g, b, a] = options.apply(source.read_rgba(pixel));
+
+        match target_type {
+            ColorType::L8 => converted.push(r),
+            ColorType::Rgb8 => converted.extend_from_slice(&[r, g, b]),
+            ColorType::Rgba8 => converted.extend_from_slice(&[r, g, b, a]),
+            ColorType::Bgra8 => converted.extend_from_slice(&[b, g, r, a]),
+        }
+    }
+
+    Ok(Cow::Owned(converted))
+}
+
+#[derive(Debug)]
+pub enum TextureConvertError {
+    InvalidLength { expected: usize, actual: usize },
+    UnsupportedFormat(TextureFormat),
+}
+
+impl Display for TextureConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLength { expected, actual } => writeln!(
+                f,
+                "Invalid texture data length. expected: {}, actual: {}",
+                expected, actual
+            ),
+
+            Self::UnsupportedFormat(format) => {
+                writeln!(f, "Unsupported conversion target format: {:?}", format)
+            }
+        }
+    }
+}
+
+impl Error for TextureConvertError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawTextureError {
+    /// Usage doesn't contain [TextureUsages::TEXTURE_BINDING]
+    NotSampleable(TextureUsages),
+    EmptySize,
+}
+
+impl Display for RawTextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotSampleable(usage) => writeln!(
+                f,
+                "Texture usage {:?} doesn't contain TEXTURE_BINDING",
+                usage
+            ),
+
+            Self::EmptySize => writeln!(f, "Texture size is empty"),
+        }
+    }
+}
+
+impl Error for RawTextureError {}
+
 #[derive(Debug)]
 pub struct SizedTextureView2D {
     view: TextureView,
@@ -177,7 +563,7 @@ impl TextureView2D {
         }
     }
 
-    /// Slice view into partial
+    /// Slice view into partial. Rect of partial view is relative to it, see [PartialTextureView2D::slice]
     pub fn slice(self, rect: Rect<u32, PhyiscalPixelUnit>) -> TextureView2D {
         match self {
             TextureView2D::All(view) => view.slice(rect),
@@ -247,17 +633,14 @@ impl PartialTextureView2D {
         Self { view, rect }
     }
 
-    /// Slice partial view. The offset and size must be larger than (0, 0) or it will be clamped to zero
+    /// Slice partial view. The rect is relative to this view and clamped to its bounds.
+    ///
+    /// Rect was used as absolute texture rect before, which made nested slices escape their parent.
+    /// Callers passing texture coordinates must subtract origin of this view first.
     pub fn slice(self, inner_rect: Rect<u32, PhyiscalPixelUnit>) -> PartialTextureView2D {
-        let offset = inner_rect.origin.max(Point2D::zero());
-        let size = inner_rect.size.max(Size2D::zero());
-
         Self {
+            rect: slice_rect(self.rect, inner_rect),
             view: self.view,
-            rect: Rect::new(
-                offset,
-                (inner_rect.size - offset.to_vector().to_size() - size).max(Size2D::zero()),
-            ),
         }
     }
 
@@ -289,6 +672,17 @@ impl PartialTextureView2D {
     }
 }
 
+/// Rect of `inner` relative to `outer`, clamped to `outer`. Empty rect at origin of `outer` if they don't overlap.
+fn slice_rect(
+    outer: Rect<u32, PhyiscalPixelUnit>,
+    inner: Rect<u32, PhyiscalPixelUnit>,
+) -> Rect<u32, PhyiscalPixelUnit> {
+    inner
+        .translate(outer.origin.to_vector())
+        .intersection(&outer)
+        .unwrap_or_else(|| Rect::new(outer.origin, Size2D::zero()))
+}
+
 fn rect_to_origin_extent(rect: Rect<u32, PhyiscalPixelUnit>) -> (Origin3d, Extent3d) {
     (
         Origin3d {
@@ -303,3 +697,107 @@ fn rect_to_origin_extent(rect: Rect<u32, PhyiscalPixelUnit>) -> (Origin3d, Exten
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D};
+    use wgpu::TextureFormat;
+
+    use super::{
+        convert_texture_data, convert_texture_data_with, layer_view_descriptor, slice_rect,
+        ColorType, TextureConvertError, TextureDataOptions,
+    };
+
+    #[test]
+    fn convert_test() {
+        let rgb = [0x10, 0x20, 0x30, 0x40, 0x50, 0x60];
+
+        assert_eq!(
+            &*convert_texture_data(
+                ColorType::Rgb8,
+                TextureFormat::Bgra8Unorm,
+                Size2D::new(2, 1),
+                &rgb
+            )
+            .unwrap(),
+            &[0x30, 0x20, 0x10, 0xff, 0x60, 0x50, 0x40, 0xff]
+        );
+
+        assert!(matches!(
+            convert_texture_data(
+                ColorType::Rgb8,
+                TextureFormat::Bgra8Unorm,
+                Size2D::new(2, 2),
+                &rgb
+            ),
+            Err(TextureConvertError::InvalidLength {
+                expected: 12,
+                actual: 6
+            })
+        ));
+    }
+
+    #[test]
+    fn color_key_premultiply_test() {
+        let rgba = [
+            0xff, 0x00, 0xff, 0xff, // Key color
+            0xff, 0x00, 0xff, 0x80, // Matches key only after premultiply
+            0x80, 0x00, 0x80, 0xff,
+        ];
+
+        assert_eq!(
+            &*convert_texture_data_with(
+                ColorType::Rgba8,
+                TextureFormat::Rgba8Unorm,
+                Size2D::new(3, 1),
+                &rgba,
+                TextureDataOptions::new(Some([0xff, 0x00, 0xff]), true),
+            )
+            .unwrap(),
+            &[0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x80, 0x80, 0x80, 0x00, 0x80, 0xff]
+        );
+
+        assert_eq!(
+            &*convert_texture_data_with(
+                ColorType::Rgba8,
+                TextureFormat::Rgba8Unorm,
+                Size2D::new(3, 1),
+                &rgba,
+                TextureDataOptions::new(Some([0x80, 0x00, 0x80]), true),
+            )
+            .unwrap(),
+            &[0xff, 0x00, 0xff, 0xff, 0x80, 0x00, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn layer_view_test() {
+        let first = layer_view_descriptor(None, 0);
+        let second = layer_view_descriptor(None, 1);
+
+        assert_eq!(first.base_array_layer, 0);
+        assert_eq!(second.base_array_layer, 1);
+        assert_eq!(second.array_layer_count.map(|count| count.get()), Some(1));
+    }
+
+    #[test]
+    fn nested_slice_test() {
+        let outer = Rect::new(Point2D::new(16, 8), Size2D::new(32, 32));
+
+        // Inner rect is relative to outer origin
+        let slice = slice_rect(outer, Rect::new(Point2D::new(4, 4), Size2D::new(8, 8)));
+        assert_eq!(slice, Rect::new(Point2D::new(20, 12), Size2D::new(8, 8)));
+
+        // Slicing again offsets by every parent
+        let nested = slice_rect(slice, Rect::new(Point2D::new(2, 1), Size2D::new(4, 4)));
+        assert_eq!(nested, Rect::new(Point2D::new(22, 13), Size2D::new(4, 4)));
+
+        // Overflowing part is clamped to parent
+        let clamped = slice_rect(slice, Rect::new(Point2D::new(6, 6), Size2D::new(8, 8)));
+        assert_eq!(clamped, Rect::new(Point2D::new(26, 18), Size2D::new(2, 2)));
+
+        // Slice outside of parent is empty at its origin
+        let outside = slice_rect(slice, Rect::new(Point2D::new(16, 0), Size2D::new(4, 4)));
+        assert_eq!(outside, Rect::new(slice.origin, Size2D::zero()));
+    }
+}
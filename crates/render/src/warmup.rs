@@ -0,0 +1,178 @@
+//! Pipeline warm up before first frame
+
+use std::{any::type_name, fmt::Debug};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use storyboard_core::store::StoreResources;
+
+use crate::shared::{RenderScope, RenderScopeContext};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    backend::StoryboardBackend,
+    shared::{BackendScopeContext, BackendShared, RenderShared},
+};
+
+/// List of render resources initialized ahead of first use.
+///
+/// Component pipelines are compiled when the component is drawn first time, blocking render thread on that frame.
+/// Warm up them on app load so first frame drawing them doesn't stutter.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineWarmup {
+    entries: Vec<WarmupEntry>,
+}
+
+#[derive(Clone, Copy)]
+struct WarmupEntry {
+    name: &'static str,
+    init: fn(RenderScope),
+}
+
+impl Debug for WarmupEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WarmupEntry")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PipelineWarmup {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add render resource to warm up, like `Box2DResources`
+    pub fn with<T: for<'ctx> StoreResources<RenderScopeContext<'ctx>>>(mut self) -> Self {
+        self.push::<T>();
+        self
+    }
+
+    pub fn push<T: for<'ctx> StoreResources<RenderScopeContext<'ctx>>>(&mut self) {
+        fn init<T: for<'ctx> StoreResources<RenderScopeContext<'ctx>>>(scope: RenderScope) {
+            scope.get::<T>();
+        }
+
+        self.entries.push(WarmupEntry {
+            name: type_name::<T>(),
+            init: init::<T>,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Initialize resources on current thread, reporting progress after each one
+    pub fn run(&self, scope: RenderScope, mut progress: impl FnMut(WarmupProgress)) {
+        for (index, entry) in self.entries.iter().enumerate() {
+            (entry.init)(scope);
+
+            progress(WarmupProgress {
+                completed: index + 1,
+                total: self.entries.len(),
+                name: entry.name,
+            });
+        }
+    }
+
+    /// Initialize resources on separate thread so app can keep drawing loading screen.
+    /// Pipeline creation doesn't depend on queue, so it can overlap with rendering.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn(
+        self,
+        backend: Arc<StoryboardBackend>,
+        backend_shared: Arc<BackendShared>,
+        render_shared: Arc<RenderShared>,
+    ) -> WarmupHandle {
+        let completed = Arc::new(AtomicUsize::new(0));
+        let total = self.entries.len();
+
+        let thread = thread::spawn({
+            let completed = completed.clone();
+
+            move || {
+                let scope = backend_shared
+                    .scope(BackendScopeContext {
+                        device: backend.device(),
+                        queue: backend.queue(),
+                    })
+                    .render_scope(&render_shared);
+
+                self.run(scope, |progress| {
+                    completed.store(progress.completed, Ordering::Release);
+                });
+            }
+        });
+
+        WarmupHandle {
+            completed,
+            total,
+            thread,
+        }
+    }
+}
+
+/// Warm up progress reported after each resource
+#[derive(Debug, Clone, Copy)]
+pub struct WarmupProgress {
+    pub completed: usize,
+    pub total: usize,
+
+    /// Type name of initialized resource
+    pub name: &'static str,
+}
+
+impl WarmupProgress {
+    /// Completed ratio between 0.0 and 1.0
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f32 / self.total as f32
+        }
+    }
+}
+
+/// Warm up running on separate thread
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct WarmupHandle {
+    completed: Arc<AtomicUsize>,
+    total: usize,
+
+    thread: JoinHandle<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WarmupHandle {
+    pub fn completed(&self) -> usize {
+        self.completed.load(Ordering::Acquire)
+    }
+
+    pub const fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn finished(&self) -> bool {
+        self.completed() >= self.total
+    }
+
+    /// Block until every resource is initialized
+    pub fn join(self) {
+        self.thread.join().ok();
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    iter,
+    num::{NonZeroU32, NonZeroUsize},
+    sync::Arc,
+};
+
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Point2D, Rect, Size2D, Transform3D, Vector2D},
+    palette::LinSrgba,
+};
+use storyboard_primitive::Polygon;
+use storyboard_render::{
+    component::Drawable,
+    renderer::{
+        blit::BlitResources, depth::DepthTexturePool, surface::InternalResolution, RenderMode,
+        StoryboardRenderer,
+    },
+    texture::SizedTexture2D,
+    wgpu::{
+        Color, FilterMode, LoadOp, Operations, RenderPassColorAttachment, TextureFormat,
+        TextureUsages,
+    },
+    ScreenRect,
+};
+use storyboard_test_util::{
+    full_rect, pixel, read_pixels, read_screen_pixels, read_target, rectangle, TestBackend, FORMAT,
+    GREEN, RED, SIZE,
+};
+
+#[test]
+fn side_by_side_viewport_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    // Each viewport has its own renderer, as prepared data of both are used in same submit
+    let mut left_renderer = StoryboardRenderer::new();
+    let mut right_renderer = StoryboardRenderer::new();
+
+    let half = Size2D::new(SIZE / 2, SIZE);
+    let left = ScreenRect::new(Rect::new(Point2D::zero(), half), 1.0);
+    let right = ScreenRect::new(Rect::new(Point2D::new(SIZE / 2, 0), half), 1.0);
+
+    // Red top and green bottom spanning whole target, clipped to left viewport
+    let full_half = Size2D::new(SIZE as f32, SIZE as f32 / 2.0);
+    let left_top = rectangle(Rect::new(Point2D::zero(), full_half), ShapeColor::RED, None);
+    let left_bottom = rectangle(
+        Rect::new(Point2D::new(0.0, full_half.height), full_half),
+        ShapeColor::GREEN,
+        None,
+    );
+
+    // Blue background spanning whole target would cover left viewport if right one is not clipped.
+    // Green top and red bottom placed in logical rect of right viewport.
+    let background = rectangle(full_rect(), ShapeColor::BLUE, None);
+    let logical = right.get_logical_rect();
+    let quarter = Size2D::new(logical.width(), logical.height() / 2.0);
+    let right_top = rectangle(Rect::new(logical.origin, quarter), ShapeColor::GREEN, None);
+    let right_bottom = rectangle(
+        Rect::new(logical.origin + Vector2D::new(0.0, quarter.height), quarter),
+        ShapeColor::RED,
+        None,
+    );
+
+    let data = read_target(backend, |view, encoder| {
+        left_renderer.render_viewport(
+            scope,
+            left,
+            Size2D::new(SIZE, SIZE),
+            [&left_top as &dyn Drawable, &left_bottom].into_iter(),
+            Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }),
+            encoder,
+        );
+
+        // Keep left viewport drawn before
+        right_renderer.render_viewport(
+            scope,
+            right,
+            Size2D::new(SIZE, SIZE),
+            [&background as &dyn Drawable, &right_top, &right_bottom].into_iter(),
+            Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                },
+            }),
+            encoder,
+        );
+
+        let mut commands = left_renderer.take_prepare_commands();
+        commands.extend(right_renderer.take_prepare_commands());
+        commands
+    });
+
+    for (name, xs, top, bottom) in [
+        ("left", [4, SIZE / 4, SIZE / 2 - 5], RED, GREEN),
+        ("right", [SIZE / 2 + 4, SIZE * 3 / 4, SIZE - 5], GREEN, RED),
+    ] {
+        for x in xs {
+            for y in [4, SIZE / 2 - 4] {
+                assert_eq!(pixel(&data, x, y), top, "{} pixel at {}, {}", name, x, y);
+            }
+
+            for y in [SIZE / 2 + 4, SIZE - 5] {
+                assert_eq!(pixel(&data, x, y), bottom, "{} pixel at {}, {}", name, x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn parallel_prepare_test() {
+    const DRAWABLES: usize = 10_000;
+
+    let test = TestBackend::init(Some(StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT));
+    let (backend, scope) = (&test.backend, test.scope());
+
+    // Overlapping opaque and transparent rects, so any reordering changes output
+    let colors = [
+        ShapeColor::RED,
+        ShapeColor::GREEN,
+        ShapeColor::from(LinSrgba::new(0.0, 0.0, 1.0, 0.5)),
+    ];
+    let rects = (0..DRAWABLES)
+        .map(|i| {
+            rectangle(
+                Rect::new(
+                    Point2D::new((i * 7 % 56) as f32, (i * 13 % 56) as f32),
+                    Size2D::new(8.0, 8.0),
+                ),
+                colors[i % colors.len()].clone(),
+                None,
+            )
+        })
+        .collect::<Vec<_>>();
+    let drawables = rects
+        .iter()
+        .map(|rect| rect as &dyn Drawable)
+        .collect::<Vec<_>>();
+
+    let mut sequential_renderer = StoryboardRenderer::new();
+    let mut parallel_renderer = StoryboardRenderer::new();
+    parallel_renderer.set_parallel_prepare(NonZeroUsize::new(DRAWABLES / 8));
+
+    // Second frame reuses pipelines and buffers created on first one
+    for _ in 0..2 {
+        let sequential = read_pixels(backend, scope, &mut sequential_renderer, &drawables);
+        let parallel = read_pixels(backend, scope, &mut parallel_renderer, &drawables);
+
+        assert!(sequential == parallel);
+    }
+
+    assert!(sequential_renderer.take_prepare_commands().is_empty());
+}
+
+#[test]
+fn stacked_transparent_test() {
+    let test = TestBackend::init(Some(TextureFormat::Depth24Plus));
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Translucent boxes alternating red and green, on opaque background and below opaque center rect
+    let background = rectangle(full_rect(), ShapeColor::BLACK, None);
+    let boxes = (0..500)
+        .map(|i| {
+            let color = if i % 2 == 0 {
+                LinSrgba::new(1.0, 0.0, 0.0, 0.5)
+            } else {
+                LinSrgba::new(0.0, 1.0, 0.0, 0.5)
+            };
+
+            rectangle(full_rect(), color.into(), None)
+        })
+        .collect::<Vec<_>>();
+    let center = rectangle(
+        Rect::new(
+            Point2D::new(SIZE as f32 / 4.0, SIZE as f32 / 4.0),
+            Size2D::new(SIZE as f32 / 2.0, SIZE as f32 / 2.0),
+        ),
+        ShapeColor::BLUE,
+        None,
+    );
+
+    let drawables = [&background as &dyn Drawable]
+        .into_iter()
+        .chain(boxes.iter().map(|rect| rect as &dyn Drawable))
+        .chain(iter::once(&center as &dyn Drawable))
+        .collect::<Vec<_>>();
+
+    let first = read_pixels(backend, scope, &mut renderer, &drawables);
+
+    // Last box is green, so it dominates blended color
+    let [red, green, blue, _] = pixel(&first, 0, 0);
+    assert!(
+        green > red && red > 0 && blue == 0,
+        "{:?}",
+        pixel(&first, 0, 0)
+    );
+
+    // Opaque rect in front hides every box behind it
+    assert_eq!(pixel(&first, SIZE / 2, SIZE / 2)[..3], [0, 0, 255]);
+
+    // Same result every frame
+    for _ in 0..3 {
+        assert_eq!(
+            read_pixels(backend, scope, &mut renderer, &drawables),
+            first
+        );
+    }
+}
+
+#[test]
+fn painter_order_test() {
+    let quarter = Size2D::new(SIZE as f32 / 2.0, SIZE as f32 / 2.0);
+
+    // Opaque rects overlapping at center, later one is on top
+    let back = rectangle(full_rect(), ShapeColor::RED, None);
+    let middle = rectangle(
+        Rect::new(Point2D::zero(), quarter * 1.5),
+        ShapeColor::GREEN,
+        None,
+    );
+    let front = rectangle(
+        Rect::new(Point2D::new(SIZE as f32 / 4.0, SIZE as f32 / 4.0), quarter),
+        ShapeColor::BLUE,
+        None,
+    );
+
+    for mode in [
+        RenderMode::PainterOrder,
+        RenderMode::Depth(StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT),
+    ] {
+        let test = TestBackend::init(mode.depth_format());
+        assert_eq!(test.render_shared.pipeline().render_mode(), mode);
+
+        let data = read_pixels(
+            &test.backend,
+            test.scope(),
+            &mut StoryboardRenderer::new(),
+            &[&back, &middle, &front],
+        );
+
+        assert_eq!(
+            pixel(&data, SIZE - 1, SIZE - 1)[..3],
+            RED[..3],
+            "{:?}",
+            mode
+        );
+        assert_eq!(pixel(&data, 0, 0)[..3], GREEN[..3], "{:?}", mode);
+        assert_eq!(
+            pixel(&data, SIZE / 2, SIZE / 2)[..3],
+            [0, 0, 255],
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn depth_texture_pool_test() {
+    let test = TestBackend::init(None);
+    let device = test.backend.device();
+    let pool = DepthTexturePool::default();
+
+    let format = TextureFormat::Depth32Float;
+    let size = |i: u32| Size2D::new(SIZE + i, SIZE);
+
+    // Same size and format is reused
+    let first = pool.acquire(device, size(0), format);
+    assert!(Arc::ptr_eq(&first, &pool.acquire(device, size(0), format)));
+    assert!(!Arc::ptr_eq(
+        &first,
+        &pool.acquire(device, size(0), TextureFormat::Depth24Plus)
+    ));
+    assert_eq!(pool.len(), 2);
+    drop(first);
+
+    // Acquired textures are kept, others are evicted on next frame
+    assert_eq!(pool.evict_idle(), 0);
+    pool.acquire(device, size(0), format);
+    assert_eq!(pool.evict_idle(), 1);
+    assert_eq!(pool.len(), 1);
+
+    // Pool stays bounded while size changes every render, like resized region
+    let last = (1..=DepthTexturePool::MAX_TEXTURES as u32 * 2)
+        .map(|i| pool.acquire(device, size(i), format))
+        .last()
+        .unwrap();
+    assert_eq!(pool.len(), DepthTexturePool::MAX_TEXTURES);
+
+    // Most recently acquired textures are kept
+    assert!(Arc::ptr_eq(
+        &last,
+        &pool.acquire(
+            device,
+            size(DepthTexturePool::MAX_TEXTURES as u32 * 2),
+            format
+        )
+    ));
+    assert_eq!(pool.len(), DepthTexturePool::MAX_TEXTURES);
+}
+
+#[test]
+fn supersample_downsample_test() {
+    let test = TestBackend::init(None);
+    let (backend, scope) = (&test.backend, test.scope());
+
+    let mut renderer = StoryboardRenderer::new();
+
+    // Long diagonal edge, not aligned to pixels
+    let triangle = Polygon {
+        points: Arc::new(vec![
+            Point2D::zero(),
+            Point2D::new(SIZE as f32, 0.0),
+            Point2D::new(0.0, SIZE as f32 * 0.8),
+        ]),
+        indices: Arc::new(vec![0, 1, 2]),
+        color: ShapeColor::WHITE,
+        transform: Transform3D::identity(),
+    };
+
+    let surface = ScreenRect::new(Rect::from_size(Size2D::new(SIZE, SIZE)), 1.0);
+    let direct = read_screen_pixels(backend, scope, &mut renderer, surface, &[&triangle]);
+
+    // Render at 2x internal resolution and downsample like surface renderer does
+    let internal = InternalResolution::supersampled(NonZeroU32::new(2).unwrap()).screen(&surface);
+    assert_eq!(internal.rect.size, Size2D::new(SIZE * 2, SIZE * 2));
+
+    let texture = SizedTexture2D::init(
+        backend.device(),
+        None,
+        internal.rect.size,
+        FORMAT,
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+    );
+    let view = texture.create_view_default(None);
+
+    let blit = scope.get::<BlitResources>();
+    let bind_group = blit.create_bind_group(backend.device(), view.inner(), FilterMode::Nearest);
+
+    let supersampled = read_target(backend, |target, encoder| {
+        renderer.render(
+            scope,
+            internal,
+            iter::once(&triangle as _),
+            Some(RenderPassColorAttachment {
+                view: view.inner(),
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }),
+            encoder,
+        );
+
+        blit.downsample_pass(
+            encoder,
+            &bind_group,
+            target,
+            Rect::from_size(Size2D::new(SIZE, SIZE)),
+            Color::TRANSPARENT,
+        );
+
+        renderer.take_prepare_commands()
+    });
+
+    let partial = |data: &[u8]| {
+        (0..SIZE)
+            .flat_map(|y| (0..SIZE).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let alpha = pixel(data, x, y)[3];
+                alpha > 0 && alpha < 255
+            })
+            .count()
+    };
+
+    // Without supersampling, pixels along edge are either fully covered or not
+    assert_eq!(partial(&direct), 0);
+
+    // Downsampled edge pixels are blended, about one on each row along the diagonal
+    assert!(partial(&supersampled) >= SIZE as usize / 2);
+
+    // Away from edge, result is same
+    for (x, y) in [(4, 4), (SIZE - 4, SIZE - 4)] {
+        assert_eq!(
+            pixel(&supersampled, x, y),
+            pixel(&direct, x, y),
+            "pixel at {}, {}",
+            x,
+            y
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Shared helpers for gpu tests rendering into [SIZE] sized offscreen target
+
+use std::{iter, num::NonZeroU32};
+
+use storyboard_core::{
+    color::ShapeColor,
+    euclid::{Angle, Point2D, Rect, Size2D, Transform3D},
+    unit::{LogicalPixelUnit, PhyiscalPixelUnit},
+};
+use storyboard_primitive::Rectangle;
+use storyboard_render::{
+    backend::{BackendOptions, StoryboardBackend},
+    component::Drawable,
+    renderer::{Backdrop, StoryboardRenderer},
+    shared::{BackendScopeContext, BackendShared, RenderScope, RenderShared},
+    texture::SizedTexture2D,
+    wgpu::{
+        Backends, BufferDescriptor, BufferUsages, Color, CommandBuffer, CommandEncoder,
+        CommandEncoderDescriptor, Extent3d, Features, ImageCopyBuffer, ImageCopyTexture,
+        ImageDataLayout, Instance, LoadOp, Maintain, MapMode, Operations, Origin3d,
+        RenderPassColorAttachment, TextureAspect, TextureFormat, TextureUsages, TextureView,
+        COPY_BYTES_PER_ROW_ALIGNMENT,
+    },
+    ScreenRect,
+};
+use storyboard_texture::ComponentTexture;
+
+pub const SIZE: u32 = 64;
+pub const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+pub const RED: [u8; 4] = [255, 0, 0, 255];
+pub const GREEN: [u8; 4] = [0, 255, 0, 255];
+
+/// Backend and shared containers used by single test
+#[derive(Debug)]
+pub struct TestBackend {
+    pub backend: StoryboardBackend,
+    pub backend_shared: BackendShared,
+    pub render_shared: RenderShared,
+}
+
+impl TestBackend {
+    /// Initialize backend with renderer pipeline rendering to [FORMAT]
+    pub fn init(depth_format: Option<TextureFormat>) -> Self {
+        let backend = pollster::block_on(StoryboardBackend::init(
+            &Instance::new(Backends::all()),
+            None,
+            Features::empty(),
+            &BackendOptions::default(),
+            None,
+        ))
+        .unwrap();
+
+        let render_shared = RenderShared::new(StoryboardRenderer::create_renderer_pipeline_data(
+            FORMAT,
+            depth_format,
+            None,
+        ));
+
+        Self {
+            backend,
+            backend_shared: BackendShared::new(),
+            render_shared,
+        }
+    }
+
+    pub fn scope(&self) -> RenderScope {
+        self.backend_shared
+            .scope(BackendScopeContext {
+                device: self.backend.device(),
+                queue: self.backend.queue(),
+            })
+            .render_scope(&self.render_shared)
+    }
+}
+
+pub fn full_rect() -> Rect<f32, LogicalPixelUnit> {
+    Rect::new(Point2D::zero(), Size2D::new(SIZE as f32, SIZE as f32))
+}
+
+pub fn rectangle(
+    bounds: Rect<f32, LogicalPixelUnit>,
+    color: ShapeColor<4>,
+    texture: Option<ComponentTexture>,
+) -> Rectangle {
+    Rectangle {
+        bounds,
+        color,
+        texture,
+        texture_coord: None,
+        transform: Transform3D::identity(),
+        rotation: Angle::zero(),
+        rotation_anchor: Point2D::zero(),
+        clip: None,
+        force_opaque: false,
+        alpha_cutoff: None,
+    }
+}
+
+/// Render drawables and read back rendered pixels
+pub fn read_pixels(
+    backend: &StoryboardBackend,
+    scope: RenderScope,
+    renderer: &mut StoryboardRenderer,
+    drawables: &[&dyn Drawable],
+) -> Vec<u8> {
+    read_target(backend, |view, encoder| {
+        renderer
+            .render_to_view(
+                scope,
+                view,
+                FORMAT,
+                Size2D::new(SIZE, SIZE),
+                1.0,
+                Some(Color::TRANSPARENT),
+                drawables.iter().copied(),
+                encoder,
+            )
+            .unwrap();
+
+        renderer.take_prepare_commands()
+    })
+}
+
+/// Render drawables on screen having [SIZE] sized rect and read back rendered pixels
+pub fn read_screen_pixels(
+    backend: &StoryboardBackend,
+    scope: RenderScope,
+    renderer: &mut StoryboardRenderer,
+    screen: ScreenRect,
+    drawables: &[&dyn Drawable],
+) -> Vec<u8> {
+    assert_eq!(screen.rect.size, Size2D::new(SIZE, SIZE));
+
+    read_target(backend, |view, encoder| {
+        renderer.render(
+            scope,
+            screen,
+            drawables.iter().copied(),
+            Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }),
+            encoder,
+        );
+
+        renderer.take_prepare_commands()
+    })
+}
+
+/// Render drawables with target texture as backdrop, see [StoryboardRenderer::render_with_backdrop],
+/// and read back rendered pixels
+pub fn read_backdrop_pixels(
+    backend: &StoryboardBackend,
+    scope: RenderScope,
+    renderer: &mut StoryboardRenderer,
+    drawables: &[&dyn Drawable],
+) -> Vec<u8> {
+    read_texture_target(
+        backend,
+        Size2D::new(SIZE, SIZE),
+        |texture, view, encoder| {
+            renderer.render_with_backdrop(
+                scope,
+                ScreenRect::new(Rect::from_size(texture.size()), 1.0),
+                drawables.iter().copied(),
+                Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: true,
+                    },
+                }),
+                Backdrop {
+                    texture: texture.inner(),
+                    size: texture.size(),
+                },
+                encoder,
+            );
+
+            renderer.take_prepare_commands()
+        },
+    )
+}
+
+/// Render into [SIZE] sized target and read back its pixels.
+/// Command buffers returned from `render` are submitted before the encoder.
+pub fn read_target(
+    backend: &StoryboardBackend,
+    render: impl FnOnce(&TextureView, &mut CommandEncoder) -> Vec<CommandBuffer>,
+) -> Vec<u8> {
+    read_sized_target(backend, Size2D::new(SIZE, SIZE), render)
+}
+
+/// Render into target with given size and read back its pixels, tightly packed
+pub fn read_sized_target(
+    backend: &StoryboardBackend,
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    render: impl FnOnce(&TextureView, &mut CommandEncoder) -> Vec<CommandBuffer>,
+) -> Vec<u8> {
+    read_texture_target(backend, size, |_, view, encoder| render(view, encoder))
+}
+
+fn read_texture_target(
+    backend: &StoryboardBackend,
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    render: impl FnOnce(&SizedTexture2D, &TextureView, &mut CommandEncoder) -> Vec<CommandBuffer>,
+) -> Vec<u8> {
+    let mut encoder = create_encoder(backend);
+
+    let target = SizedTexture2D::init(
+        backend.device(),
+        Some("read_target target"),
+        size,
+        FORMAT,
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+    );
+    let view = target.create_view_default(None);
+
+    let row = size.width * 4;
+    let bytes_per_row = (row + COPY_BYTES_PER_ROW_ALIGNMENT - 1) / COPY_BYTES_PER_ROW_ALIGNMENT
+        * COPY_BYTES_PER_ROW_ALIGNMENT;
+    let readback = backend.device().create_buffer(&BufferDescriptor {
+        label: Some("read_target readback buffer"),
+        size: (bytes_per_row * size.height) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let commands = render(&target, view.inner(), &mut encoder);
+
+    encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+            texture: target.inner(),
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        ImageCopyBuffer {
+            buffer: &readback,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+    );
+
+    backend
+        .queue()
+        .submit(commands.into_iter().chain(iter::once(encoder.finish())));
+
+    let slice = readback.slice(..);
+    slice.map_async(MapMode::Read, |result| result.unwrap());
+    backend.poll(Maintain::Wait);
+
+    let data = slice
+        .get_mapped_range()
+        .chunks(bytes_per_row as usize)
+        .flat_map(|padded| &padded[..row as usize])
+        .copied()
+        .collect();
+    readback.unmap();
+
+    data
+}
+
+/// Pixel of [SIZE] sized target
+pub fn pixel(data: &[u8], x: u32, y: u32) -> [u8; 4] {
+    sized_pixel(data, SIZE, x, y)
+}
+
+/// Pixel of target with given width
+pub fn sized_pixel(data: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * width + x) * 4) as usize;
+
+    data[offset..offset + 4].try_into().unwrap()
+}
+
+pub fn create_encoder(backend: &StoryboardBackend) -> CommandEncoder {
+    backend
+        .device()
+        .create_command_encoder(&CommandEncoderDescriptor { label: None })
+}
@@ -2,10 +2,12 @@ pub mod render;
 
 use std::sync::Arc;
 use storyboard_core::{
-    euclid::{Point2D, Rect, Size2D},
+    euclid::{Point2D, Rect, Size2D, Transform2D},
     unit::{LogicalPixelUnit, TextureUnit, PhyiscalPixelUnit},
 };
 
+use storyboard_render::{texture::TextureView2D, wgpu::AddressMode};
+
 use crate::render::RenderTexture2D;
 
 #[derive(Debug, Clone)]
@@ -13,11 +15,31 @@ pub struct ComponentTexture {
     pub inner: Arc<RenderTexture2D>,
     pub layout: TextureLayout,
     pub wrapping_mode: (TextureWrap, TextureWrap),
+    /// Transform applied to texture coordinates relative to view, before wrapping and mapping into view texture rect.
+    /// Translate to scroll texture. Rotation and scaling pivot around top left of view.
+    pub uv_transform: Option<Transform2D<f32, TextureUnit, TextureUnit>>,
+    /// Sample texture upside down, for textures with bottom left origin like ones rendered by other graphics APIs.
+    /// Textures rendered by storyboard have top left origin and are sampled upright without flipping.
+    pub flip_y: bool,
 }
 
 impl ComponentTexture {
     pub const fn new(inner: Arc<RenderTexture2D>, layout: TextureLayout, wrapping_mode: (TextureWrap, TextureWrap)) -> Self {
-        Self { inner, layout, wrapping_mode }
+        Self { inner, layout, wrapping_mode, uv_transform: None, flip_y: false }
+    }
+
+    /// Apply uv transform and vertical flip to texture coordinate relative to view
+    pub fn transform_coord(&self, coord: Point2D<f32, TextureUnit>) -> Point2D<f32, TextureUnit> {
+        let coord = match &self.uv_transform {
+            Some(transform) => transform.transform_point(coord),
+            None => coord,
+        };
+
+        if self.flip_y {
+            Point2D::new(coord.x, 1.0 - coord.y)
+        } else {
+            coord
+        }
     }
 
     pub fn get_texture_bounds(
@@ -35,6 +57,13 @@ impl ComponentTexture {
         }
     }
 
+    pub fn option_transform_coords<const N: usize>(this: Option<&Self>, coords: [Point2D<f32, TextureUnit>; N]) -> [Point2D<f32, TextureUnit>; N] {
+        match this {
+            Some(this) => coords.map(|coord| this.transform_coord(coord)),
+            None => coords,
+        }
+    }
+
     pub fn option_view_texture_rect(this: Option<&Self>) -> Rect<f32, TextureUnit> {
         match this {
             Some(this) => this.inner.view().texture_rect(),
@@ -48,6 +77,31 @@ impl ComponentTexture {
             None => Default::default(),
         }
     }
+
+    /// Wrapping mode passed to shaders.
+    /// Whole texture view bound with sampler addressing matching its wrapping mode gets [SAMPLER_WRAP_MODE], so shader leaves wrapping to sampler.
+    /// Other views are wrapped in shader within their texture rect.
+    pub fn shader_wrap_mode(&self) -> [u32; 2] {
+        let (u, v) = self.wrapping_mode;
+
+        let sampler_addressed = matches!(self.inner.view(), TextureView2D::All(_))
+            && u != TextureWrap::None
+            && v != TextureWrap::None
+            && self.inner.address_mode() == (u.address_mode(), v.address_mode());
+
+        if sampler_addressed {
+            [SAMPLER_WRAP_MODE; 2]
+        } else {
+            [u as _, v as _]
+        }
+    }
+
+    pub fn option_shader_wrap_mode(this: Option<&Self>) -> [u32; 2] {
+        match this {
+            Some(this) => this.shader_wrap_mode(),
+            None => [TextureWrap::None as _; 2],
+        }
+    }
 }
 
 impl AsRef<Arc<RenderTexture2D>> for ComponentTexture {
@@ -175,14 +229,31 @@ impl Default for TextureLayoutStyle {
     }
 }
 
+/// Texture coordinate wrapping mode.
+/// Done by sampler addressing if the view covers whole texture, otherwise applied in shader within texture rect of view.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
 pub enum TextureWrap {
     None = 0,
     Clamp = 1,
-    Repeat = 2
+    Repeat = 2,
+    MirrorRepeat = 3,
 }
 
+impl TextureWrap {
+    /// Sampler address mode matching wrapping mode
+    pub const fn address_mode(&self) -> AddressMode {
+        match self {
+            TextureWrap::None | TextureWrap::Clamp => AddressMode::ClampToEdge,
+            TextureWrap::Repeat => AddressMode::Repeat,
+            TextureWrap::MirrorRepeat => AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+/// Shader wrap mode of view wrapped by sampler addressing, see [ComponentTexture::shader_wrap_mode]
+pub const SAMPLER_WRAP_MODE: u32 = 4;
+
 impl Default for TextureWrap {
     fn default() -> Self {
         Self::None
@@ -0,0 +1,109 @@
+use std::{hash::Hash, sync::Arc};
+
+use rustc_hash::FxHashMap;
+
+use super::{data::TextureData, RenderTexture2D};
+
+/// Render texture cache evicting least recently used entries when texture memory is over budget.
+///
+/// Entries still referenced outside of cache are never evicted, as dropping them wouldn't free memory.
+#[derive(Debug)]
+pub struct RenderTextureCache<K> {
+    entries: FxHashMap<K, CacheEntry>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    texture: Arc<RenderTexture2D>,
+    last_used: u64,
+}
+
+impl<K: Hash + Eq + Clone> RenderTextureCache<K> {
+    pub fn new() -> Self {
+        Self {
+            entries: FxHashMap::default(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<Arc<RenderTexture2D>> {
+        self.tick += 1;
+
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.tick;
+
+        Some(entry.texture.clone())
+    }
+
+    pub fn insert(&mut self, key: K, texture: Arc<RenderTexture2D>) {
+        self.tick += 1;
+
+        self.entries.insert(
+            key,
+            CacheEntry {
+                texture,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    pub fn get_or_insert_with(
+        &mut self,
+        key: &K,
+        func: impl FnOnce() -> Arc<RenderTexture2D>,
+    ) -> Arc<RenderTexture2D> {
+        if let Some(texture) = self.get(key) {
+            return texture;
+        }
+
+        let texture = func();
+        self.insert(key.clone(), texture.clone());
+
+        texture
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<Arc<RenderTexture2D>> {
+        self.entries.remove(key).map(|entry| entry.texture)
+    }
+
+    /// Evict least recently used unreferenced entries until texture memory usage is in budget.
+    /// Returns number of evicted entries.
+    pub fn evict(&mut self, textures: &TextureData) -> usize {
+        let mut evicted = 0;
+
+        while textures.over_budget() {
+            let key = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| Arc::strong_count(&entry.texture) == 1)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+
+            match key {
+                Some(key) => {
+                    self.entries.remove(&key);
+                    evicted += 1;
+                }
+
+                None => break,
+            }
+        }
+
+        evicted
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for RenderTextureCache<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
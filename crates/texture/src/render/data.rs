@@ -1,29 +1,58 @@
+use std::{
+    num::NonZeroU8,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
 use storyboard_core::{
     euclid::Size2D,
     store::{Store, StoreResources},
+    unit::PhyiscalPixelUnit,
 };
 
 use storyboard_render::{
-    texture::{SizedTexture2D, TextureView2D},
+    texture::{RawTextureError, SizedTexture2D, SizedTexture2DArray, TextureView2D},
     wgpu::{
-        AddressMode, BindGroupLayout, Device, Sampler, SamplerDescriptor, TextureFormat,
-        TextureUsages, FilterMode,
+        AddressMode, BindGroupLayout, Device, Queue, Sampler, SamplerDescriptor, Texture, TextureFormat,
+        TextureSampleType, TextureUsages, FilterMode,
     }, shared::BackendScopeContext,
 };
 
-use super::{create_texture2d_bind_group_layout, RenderTexture2D};
+use crate::{ComponentTexture, TextureLayout, TextureWrap};
+
+use super::{
+    cache::RenderTextureCache,
+    create_texture2d_array_bind_group_layout, create_texture2d_bind_group_layout,
+    memory::{texture_memory_size, TextureAllocation, TextureMemory},
+    usage::{RenderTextureError, RenderTextureUsage},
+    RenderTexture2D, RenderTexture2DArray,
+};
 
 /// Common texture datas.
 #[derive(Debug)]
 pub struct TextureData {
     bind_group_layout: BindGroupLayout,
+    array_bind_group_layout: BindGroupLayout,
     nearest_sampler: Sampler,
     linear_sampler: Sampler,
+
+    samplers: Mutex<FxHashMap<SamplerKey, Arc<Sampler>>>,
+
+    max_anisotropy: AtomicU8,
+    anisotropy_warned: AtomicBool,
+
+    memory: Arc<TextureMemory>,
+    memory_budget: AtomicU64,
 }
 
 impl TextureData {
     pub fn init(device: &Device) -> Self {
         let bind_group_layout = create_texture2d_bind_group_layout(device);
+        let array_bind_group_layout = create_texture2d_array_bind_group_layout(device);
 
         let nearest_sampler = device.create_sampler(&SamplerDescriptor {
             label: Some("Texture2D nearest sampler"),
@@ -46,8 +75,17 @@ impl TextureData {
 
         Self {
             bind_group_layout,
+            array_bind_group_layout,
             nearest_sampler,
             linear_sampler,
+
+            samplers: Mutex::new(FxHashMap::default()),
+
+            max_anisotropy: AtomicU8::new(1),
+            anisotropy_warned: AtomicBool::new(false),
+
+            memory: Arc::new(TextureMemory::new()),
+            memory_budget: AtomicU64::new(u64::MAX),
         }
     }
 
@@ -55,6 +93,11 @@ impl TextureData {
         &self.bind_group_layout
     }
 
+    /// Layout of [RenderTexture2DArray] bind groups
+    pub const fn array_bind_group_layout(&self) -> &BindGroupLayout {
+        &self.array_bind_group_layout
+    }
+
     pub const fn nearest_sampler(&self) -> &Sampler {
         &self.nearest_sampler
     }
@@ -63,6 +106,206 @@ impl TextureData {
         &self.linear_sampler
     }
 
+    pub fn max_anisotropy(&self) -> u8 {
+        self.max_anisotropy.load(Ordering::Relaxed)
+    }
+
+    /// Set max anisotropy level supported by device. See [storyboard_render::backend::StoryboardBackend::max_anisotropy].
+    /// Defaults to 1, which disables anisotropic filtering.
+    pub fn set_max_anisotropy(&self, max_anisotropy: u8) {
+        self.max_anisotropy
+            .store(max_anisotropy.max(1), Ordering::Relaxed);
+    }
+
+    /// Allocated bytes of tracked textures alive.
+    /// Only textures tracked using [TextureData::memory] are counted.
+    pub fn memory_usage(&self) -> u64 {
+        self.memory.usage()
+    }
+
+    /// Memory tracker of textures, for textures not covered by [TextureData::track_texture] like mipmapped ones
+    pub const fn memory(&self) -> &Arc<TextureMemory> {
+        &self.memory
+    }
+
+    pub fn memory_budget(&self) -> Option<u64> {
+        match self.memory_budget.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            budget => Some(budget),
+        }
+    }
+
+    /// Set texture memory budget used by [super::cache::RenderTextureCache] eviction
+    pub fn set_memory_budget(&self, budget: Option<u64>) {
+        self.memory_budget
+            .store(budget.unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    pub fn over_budget(&self) -> bool {
+        self.memory_usage() > self.memory_budget.load(Ordering::Relaxed)
+    }
+
+    /// Track memory of texture. Usage is released when returned allocation is dropped.
+    pub fn track_texture(&self, texture: &SizedTexture2D) -> TextureAllocation {
+        self.memory
+            .track(texture_memory_size(texture.size(), texture.format()))
+    }
+
+    /// Create render texture of whole texture with tracked memory
+    pub fn create_tracked_render_texture(
+        &self,
+        device: &Device,
+        texture: &SizedTexture2D,
+        sampler: Option<&Sampler>,
+    ) -> RenderTexture2D {
+        self.create_render_texture(device, texture.create_view_default(None).into(), sampler)
+            .with_allocation(self.track_texture(texture))
+    }
+
+    /// Create render texture array of every layers with tracked memory
+    pub fn create_tracked_render_texture_array(
+        &self,
+        device: &Device,
+        texture: SizedTexture2DArray,
+        sampler: Option<&Sampler>,
+    ) -> RenderTexture2DArray {
+        let allocation = self
+            .memory
+            .track(texture_memory_size(texture.size(), texture.format()) * texture.layers() as u64);
+
+        RenderTexture2DArray::init(
+            device,
+            texture,
+            &self.array_bind_group_layout,
+            sampler.unwrap_or(&self.nearest_sampler),
+        )
+        .with_allocation(allocation)
+    }
+
+    /// Create texture with given usage and tracked render texture of it.
+    /// Returned texture can be used to create render attachment or storage views.
+    /// Fails early if format doesn't support sampling or the usage.
+    pub fn create_render_texture_with_usage(
+        &self,
+        device: &Device,
+        label: Option<&str>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: TextureFormat,
+        usage: RenderTextureUsage,
+        sampler: Option<&Sampler>,
+    ) -> Result<(SizedTexture2D, RenderTexture2D), RenderTextureError> {
+        let texture = SizedTexture2D::init(device, label, size, format, usage.validate(format)?);
+        let render_texture = self.create_tracked_render_texture(device, &texture, sampler);
+
+        Ok((texture, render_texture))
+    }
+
+    /// Wrap texture created outside of storyboard and create render texture of it using standard bind group layout.
+    /// See [SizedTexture2D::from_raw] for requirements. Memory of external texture is not tracked.
+    pub fn create_raw_render_texture(
+        &self,
+        device: &Device,
+        texture: Texture,
+        format: TextureFormat,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        usage: TextureUsages,
+        sampler: Option<&Sampler>,
+    ) -> Result<(SizedTexture2D, RenderTexture2D), RawTextureError> {
+        let texture = SizedTexture2D::from_raw(texture, format, size, usage)?;
+        let render_texture =
+            self.create_render_texture(device, texture.create_view_default(None).into(), sampler);
+
+        Ok((texture, render_texture))
+    }
+
+    /// Clamp anisotropy level to supported power of two level
+    fn clamp_anisotropy(&self, anisotropy: u8) -> u8 {
+        let max_anisotropy = self.max_anisotropy();
+
+        if anisotropy > 1 && max_anisotropy <= 1 {
+            if !self.anisotropy_warned.swap(true, Ordering::Relaxed) {
+                log::warn!("Anisotropic filtering is not supported, ignoring anisotropy level");
+            }
+
+            return 1;
+        }
+
+        1 << (u8::BITS - 1 - anisotropy.clamp(1, max_anisotropy).leading_zeros())
+    }
+
+    /// Get or create cached sampler using given filter, address mode and anisotropy level.
+    /// Anisotropy only applies to linear filter.
+    pub fn sampler(&self, device: &Device, key: SamplerKey) -> Arc<Sampler> {
+        let key = SamplerKey {
+            anisotropy: match key.filter {
+                FilterMode::Linear => self.clamp_anisotropy(key.anisotropy),
+                FilterMode::Nearest => 1,
+            },
+            ..key
+        };
+
+        self.samplers
+            .lock()
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(device.create_sampler(&SamplerDescriptor {
+                    label: Some("Texture2D cached sampler"),
+                    address_mode_u: key.address_mode_u,
+                    address_mode_v: key.address_mode_v,
+
+                    mag_filter: key.filter,
+                    min_filter: key.filter,
+                    mipmap_filter: key.filter,
+
+                    anisotropy_clamp:
+                        NonZeroU8::new(key.anisotropy).filter(|level| level.get() > 1),
+
+                    ..Default::default()
+                }))
+            })
+            .clone()
+    }
+
+    /// Create [ComponentTexture] with sampler matching its wrapping mode and anisotropy level.
+    /// View covering whole texture is wrapped by sampler addressing, leaving shader coordinates untouched.
+    /// Partial views use clamping sampler and wrap in shader within their texture rect, see [ComponentTexture::shader_wrap_mode].
+    pub fn create_component_texture(
+        &self,
+        device: &Device,
+        view: TextureView2D,
+        filter: FilterMode,
+        anisotropy: u8,
+        layout: TextureLayout,
+        wrapping_mode: (TextureWrap, TextureWrap),
+    ) -> ComponentTexture {
+        let key = match view {
+            TextureView2D::All(_) => SamplerKey {
+                filter,
+                address_mode_u: wrapping_mode.0.address_mode(),
+                address_mode_v: wrapping_mode.1.address_mode(),
+                anisotropy,
+            },
+
+            TextureView2D::Partial(_) => SamplerKey {
+                filter,
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                anisotropy,
+            },
+        };
+
+        let sampler = self.sampler(device, key);
+
+        ComponentTexture::new(
+            Arc::new(
+                RenderTexture2D::init(device, view, &self.bind_group_layout, &sampler)
+                    .with_address_mode((key.address_mode_u, key.address_mode_v)),
+            ),
+            layout,
+            wrapping_mode,
+        )
+    }
+
     pub fn create_render_texture(
         &self,
         device: &Device,
@@ -78,16 +321,101 @@ impl TextureData {
     }
 }
 
+/// Key of sampler cached by [TextureData]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerKey {
+    pub filter: FilterMode,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    /// Anisotropic filtering level. 1 disables it.
+    pub anisotropy: u8,
+}
+
 impl StoreResources<BackendScopeContext<'_>> for TextureData {
     fn initialize(_: &Store, ctx: &BackendScopeContext) -> Self {
         Self::init(ctx.device)
     }
 }
 
+/// Resources containing white empty textures, bound as placeholder for untextured draws
 #[derive(Debug)]
-/// Resources containing white empty texture
 pub struct EmptyTextureResources {
+    /// Placeholder for filterable float texture binding of [TextureData::bind_group_layout]
     pub empty_texture: RenderTexture2D,
+
+    sampler: Sampler,
+    textures: Mutex<RenderTextureCache<TextureSampleType>>,
+    memory: Arc<TextureMemory>,
+}
+
+impl EmptyTextureResources {
+    /// Format of placeholder texture for texture binding with given sample type
+    pub const fn placeholder_format(sample_type: TextureSampleType) -> TextureFormat {
+        match sample_type {
+            TextureSampleType::Float { filterable: true } => TextureFormat::Rgba8Unorm,
+            TextureSampleType::Float { filterable: false } => TextureFormat::R32Float,
+            TextureSampleType::Uint => TextureFormat::Rgba8Uint,
+            TextureSampleType::Sint => TextureFormat::Rgba8Sint,
+            TextureSampleType::Depth => TextureFormat::Depth32Float,
+        }
+    }
+
+    /// White texel of placeholder format. None if texture cannot be written, leaving it zeroed.
+    fn placeholder_texel(sample_type: TextureSampleType) -> Option<[u8; 4]> {
+        match sample_type {
+            TextureSampleType::Float { filterable: true } | TextureSampleType::Uint => {
+                Some([0xff; 4])
+            }
+            TextureSampleType::Float { filterable: false } => Some(1.0_f32.to_ne_bytes()),
+            TextureSampleType::Sint => Some([0x7f; 4]),
+            TextureSampleType::Depth => None,
+        }
+    }
+
+    /// Get or create cached placeholder texture for texture binding with given sample type.
+    ///
+    /// Bind group is created using layout given on first request for the sample type.
+    /// Layout must have texture at binding 0 and non comparison sampler at binding 1 like [TextureData::bind_group_layout].
+    pub fn get(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        sample_type: TextureSampleType,
+        layout: &BindGroupLayout,
+    ) -> Arc<RenderTexture2D> {
+        self.textures.lock().get_or_insert_with(&sample_type, || {
+            let texel = Self::placeholder_texel(sample_type);
+
+            let sized = SizedTexture2D::init(
+                device,
+                Some("EmptyTextureResources placeholder texture"),
+                Size2D::new(1, 1),
+                Self::placeholder_format(sample_type),
+                if texel.is_some() {
+                    TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST
+                } else {
+                    TextureUsages::TEXTURE_BINDING
+                },
+            );
+
+            if let Some(texel) = texel {
+                sized.write(queue, None, &texel);
+            }
+
+            Arc::new(
+                RenderTexture2D::init(
+                    device,
+                    TextureView2D::from(sized.create_view_default(None)),
+                    layout,
+                    &self.sampler,
+                )
+                .with_allocation(
+                    self.memory
+                        .track(texture_memory_size(sized.size(), sized.format())),
+                ),
+            )
+        })
+    }
 }
 
 impl StoreResources<BackendScopeContext<'_>> for EmptyTextureResources {
@@ -111,8 +439,50 @@ impl StoreResources<BackendScopeContext<'_>> for EmptyTextureResources {
                 textures.bind_group_layout(),
                 textures.nearest_sampler(),
             )
+            .with_allocation(textures.track_texture(&sized))
         };
 
-        Self { empty_texture }
+        let sampler = ctx.device.create_sampler(&SamplerDescriptor {
+            label: Some("EmptyTextureResources sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+
+            ..Default::default()
+        });
+
+        Self {
+            empty_texture,
+
+            sampler,
+            textures: Mutex::new(RenderTextureCache::new()),
+            memory: textures.memory().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_render::wgpu::TextureSampleType;
+
+    use super::EmptyTextureResources;
+
+    #[test]
+    fn placeholder_format_test() {
+        for sample_type in [
+            TextureSampleType::Float { filterable: true },
+            TextureSampleType::Float { filterable: false },
+            TextureSampleType::Uint,
+            TextureSampleType::Sint,
+            TextureSampleType::Depth,
+        ] {
+            let format = EmptyTextureResources::placeholder_format(sample_type);
+            let info = format.describe();
+
+            assert_eq!(info.sample_type, sample_type);
+
+            if let Some(texel) = EmptyTextureResources::placeholder_texel(sample_type) {
+                assert_eq!(texel.len(), info.block_size as usize);
+            }
+        }
     }
 }
@@ -0,0 +1,120 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use storyboard_core::{euclid::Size2D, unit::PhyiscalPixelUnit};
+use storyboard_render::wgpu::TextureFormat;
+
+/// Sum of tracked texture memory
+#[derive(Debug, Default)]
+pub struct TextureMemory {
+    usage: AtomicU64,
+}
+
+impl TextureMemory {
+    pub fn new() -> Self {
+        Self {
+            usage: AtomicU64::new(0),
+        }
+    }
+
+    /// Allocated bytes of tracked textures alive
+    pub fn usage(&self) -> u64 {
+        self.usage.load(Ordering::Relaxed)
+    }
+
+    /// Track allocation of given bytes. Usage is released when returned allocation is dropped.
+    pub fn track(self: &Arc<Self>, bytes: u64) -> TextureAllocation {
+        self.usage.fetch_add(bytes, Ordering::Relaxed);
+
+        TextureAllocation {
+            memory: self.clone(),
+            bytes,
+        }
+    }
+}
+
+/// Tracked texture memory allocation
+#[derive(Debug)]
+pub struct TextureAllocation {
+    memory: Arc<TextureMemory>,
+    bytes: u64,
+}
+
+impl TextureAllocation {
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Drop for TextureAllocation {
+    fn drop(&mut self) {
+        self.memory.usage.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// Calculate memory size of single mip level texture
+pub fn texture_memory_size(size: Size2D<u32, PhyiscalPixelUnit>, format: TextureFormat) -> u64 {
+    let info = format.describe();
+    let (block_width, block_height) = info.block_dimensions;
+
+    let blocks_x = (size.width + block_width as u32 - 1) / block_width as u32;
+    let blocks_y = (size.height + block_height as u32 - 1) / block_height as u32;
+
+    blocks_x as u64 * blocks_y as u64 * info.block_size as u64
+}
+
+/// Calculate memory size of texture with given mip levels
+pub fn mipmapped_texture_memory_size(
+    size: Size2D<u32, PhyiscalPixelUnit>,
+    format: TextureFormat,
+    mip_level_count: u32,
+) -> u64 {
+    (0..mip_level_count)
+        .map(|level| {
+            texture_memory_size(
+                Size2D::new((size.width >> level).max(1), (size.height >> level).max(1)),
+                format,
+            )
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use storyboard_core::euclid::Size2D;
+    use storyboard_render::wgpu::TextureFormat;
+
+    use super::{mipmapped_texture_memory_size, texture_memory_size, TextureMemory};
+
+    #[test]
+    fn memory_tracking_test() {
+        assert_eq!(
+            texture_memory_size(Size2D::new(256, 128), TextureFormat::Rgba8Unorm),
+            256 * 128 * 4
+        );
+        assert_eq!(
+            texture_memory_size(Size2D::new(10, 10), TextureFormat::Bc1RgbaUnorm),
+            3 * 3 * 8
+        );
+        assert_eq!(
+            mipmapped_texture_memory_size(Size2D::new(8, 4), TextureFormat::R8Unorm, 4),
+            8 * 4 + 4 * 2 + 2 + 1
+        );
+
+        let memory = Arc::new(TextureMemory::new());
+
+        let first = memory.track(100);
+        let second = memory.track(50);
+        assert_eq!(memory.usage(), 150);
+
+        drop(first);
+        assert_eq!(memory.usage(), 50);
+
+        drop(second);
+        assert_eq!(memory.usage(), 0);
+    }
+}
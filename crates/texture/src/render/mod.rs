@@ -1,18 +1,30 @@
+pub mod cache;
 pub mod data;
+pub mod memory;
+pub mod renderer;
+pub mod usage;
 
 use storyboard_render::{
-    texture::TextureView2D,
+    texture::{SizedTexture2DArray, TextureView2D},
     wgpu::{
-        BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
-        BindGroupLayoutEntry, BindingResource, BindingType, Device, Sampler, SamplerBindingType,
-        ShaderStages, TextureSampleType, TextureView, TextureViewDimension,
+        AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+        BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Device,
+        Sampler, SamplerBindingType, ShaderStages, TextureSampleType, TextureView,
+        TextureViewDimension,
     },
 };
 
+use self::memory::TextureAllocation;
+
 #[derive(Debug)]
 pub struct RenderTexture2D {
     view: TextureView2D,
     bind_group: BindGroup,
+
+    /// Address mode of bound sampler in u and v direction
+    address_mode: (AddressMode, AddressMode),
+
+    allocation: Option<TextureAllocation>,
 }
 
 impl RenderTexture2D {
@@ -28,7 +40,32 @@ impl RenderTexture2D {
     }
 
     pub const fn new_from_bind_group(view: TextureView2D, bind_group: BindGroup) -> Self {
-        Self { view, bind_group }
+        Self {
+            view,
+            bind_group,
+            address_mode: (AddressMode::ClampToEdge, AddressMode::ClampToEdge),
+            allocation: None,
+        }
+    }
+
+    /// Set address mode of sampler the bind group was created with. Defaults to [AddressMode::ClampToEdge].
+    pub fn with_address_mode(mut self, address_mode: (AddressMode, AddressMode)) -> Self {
+        self.address_mode = address_mode;
+        self
+    }
+
+    pub const fn address_mode(&self) -> (AddressMode, AddressMode) {
+        self.address_mode
+    }
+
+    /// Attach tracked memory allocation of texture, released when this render texture is dropped
+    pub fn with_allocation(mut self, allocation: TextureAllocation) -> Self {
+        self.allocation = Some(allocation);
+        self
+    }
+
+    pub const fn allocation(&self) -> Option<&TextureAllocation> {
+        self.allocation.as_ref()
     }
 
     pub const fn view(&self) -> &TextureView2D {
@@ -40,6 +77,60 @@ impl RenderTexture2D {
     }
 }
 
+/// Texture array sampled as `texture_2d_array<f32>`, for drawing differently textured quads using one bind group
+#[derive(Debug)]
+pub struct RenderTexture2DArray {
+    texture: SizedTexture2DArray,
+    bind_group: BindGroup,
+
+    allocation: Option<TextureAllocation>,
+}
+
+impl RenderTexture2DArray {
+    /// Create render texture array. Layout must be created from [TEXTURE_2D_ARRAY_BIND_GROUP_LAYOUT_DESCRIPTOR].
+    pub fn init(
+        device: &Device,
+        texture: SizedTexture2DArray,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+    ) -> Self {
+        let bind_group = create_texture_bind_group(
+            device,
+            layout,
+            &texture.create_array_view(Some("RenderTexture2DArray view")),
+            sampler,
+        );
+
+        Self {
+            texture,
+            bind_group,
+            allocation: None,
+        }
+    }
+
+    /// Attach tracked memory allocation of texture, released when this render texture is dropped
+    pub fn with_allocation(mut self, allocation: TextureAllocation) -> Self {
+        self.allocation = Some(allocation);
+        self
+    }
+
+    pub const fn allocation(&self) -> Option<&TextureAllocation> {
+        self.allocation.as_ref()
+    }
+
+    pub const fn texture(&self) -> &SizedTexture2DArray {
+        &self.texture
+    }
+
+    pub const fn layers(&self) -> u32 {
+        self.texture.layers()
+    }
+
+    pub const fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
+
 pub const TEXTURE_2D_BIND_GROUP_LAYOUT_DESCRIPTOR: BindGroupLayoutDescriptor =
     BindGroupLayoutDescriptor {
         label: Some("Texture2D bind group layout"),
@@ -63,6 +154,34 @@ pub const TEXTURE_2D_BIND_GROUP_LAYOUT_DESCRIPTOR: BindGroupLayoutDescriptor =
         ],
     };
 
+pub const TEXTURE_2D_ARRAY_BIND_GROUP_LAYOUT_DESCRIPTOR: BindGroupLayoutDescriptor =
+    BindGroupLayoutDescriptor {
+        label: Some("Texture2DArray bind group layout"),
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2Array,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    };
+
+#[inline]
+pub fn create_texture2d_array_bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&TEXTURE_2D_ARRAY_BIND_GROUP_LAYOUT_DESCRIPTOR)
+}
+
 #[inline]
 pub fn create_texture2d_bind_group_layout(device: &Device) -> BindGroupLayout {
     device.create_bind_group_layout(&TEXTURE_2D_BIND_GROUP_LAYOUT_DESCRIPTOR)
@@ -0,0 +1,181 @@
+use std::{fmt::Debug, sync::Arc};
+
+use storyboard_core::{
+    euclid::{Point2D, Rect, Size2D},
+    unit::PhyiscalPixelUnit,
+};
+use storyboard_render::{
+    component::Drawable,
+    renderer::{context::DrawContext, Backdrop, StoryboardRenderer},
+    shared::RenderScope,
+    texture::{SizedTexture2D, SizedTextureView2D},
+    wgpu::{
+        Color, CommandEncoder, Device, LoadOp, Operations, RenderPassColorAttachment,
+        TextureFormat, TextureUsages,
+    },
+    ScreenRect,
+};
+
+use super::{data::TextureData, RenderTexture2D};
+
+/// Renderer rendering drawables into its own texture
+#[derive(Debug)]
+pub struct StoryboardTextureRenderer {
+    current_screen_size: Size2D<u32, PhyiscalPixelUnit>,
+    current_texture_format: TextureFormat,
+
+    texture: SizedTexture2D,
+    view: SizedTextureView2D,
+    render_texture: Arc<RenderTexture2D>,
+
+    renderer: StoryboardRenderer,
+}
+
+impl StoryboardTextureRenderer {
+    pub fn init(
+        device: &Device,
+        textures: &TextureData,
+        texture_format: TextureFormat,
+        screen_size: Size2D<u32, PhyiscalPixelUnit>,
+    ) -> Self {
+        let renderer = StoryboardRenderer::new();
+
+        let texture = SizedTexture2D::init(
+            device,
+            Some("StoryboardTextureRenderer frame texture"),
+            screen_size,
+            texture_format,
+            TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+        );
+
+        let render_texture =
+            Arc::new(textures.create_tracked_render_texture(device, &texture, None));
+        let view = texture.create_view_default(None);
+
+        Self {
+            current_screen_size: screen_size,
+            current_texture_format: texture_format,
+
+            texture,
+            view,
+            render_texture,
+
+            renderer,
+        }
+    }
+
+    pub const fn current_texture_format(&self) -> TextureFormat {
+        self.current_texture_format
+    }
+
+    pub fn render_texture(&self) -> &Arc<RenderTexture2D> {
+        &self.render_texture
+    }
+
+    pub const fn renderer(&self) -> &StoryboardRenderer {
+        &self.renderer
+    }
+
+    pub fn renderer_mut(&mut self) -> &mut StoryboardRenderer {
+        &mut self.renderer
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        scope: RenderScope,
+        screen: ScreenRect,
+        textures: &TextureData,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        encoder: &mut CommandEncoder,
+    ) {
+        if self.current_screen_size != screen.rect.size
+            || !scope.is_valid_for(self.current_texture_format)
+        {
+            let texture = SizedTexture2D::init(
+                scope.backend().device(),
+                Some("StoryboardTextureRenderer frame texture"),
+                screen.rect.size,
+                scope.pipeline().texture_format,
+                TextureUsages::RENDER_ATTACHMENT
+                    | TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_SRC,
+            );
+
+            self.render_texture = Arc::new(textures.create_tracked_render_texture(
+                scope.backend().device(),
+                &texture,
+                None,
+            ));
+            self.view = texture.create_view_default(None);
+            self.texture = texture;
+
+            self.current_screen_size = screen.rect.size;
+            self.current_texture_format = scope.pipeline().texture_format;
+        }
+
+        self.renderer.render_with_backdrop(
+            scope,
+            screen,
+            drawables,
+            Some(RenderPassColorAttachment {
+                view: self.view.inner(),
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }),
+            Backdrop {
+                texture: self.texture.inner(),
+                size: self.texture.size(),
+            },
+            encoder,
+        );
+    }
+}
+
+/// Nested rendering extension for [DrawContext]
+pub trait NestedRenderExt {
+    /// Render drawables into new scratch texture of given physical size and return it for compositing.
+    ///
+    /// Usable in [Drawable::prepare] for effects like masking or blur-behind.
+    /// Commands are recorded into given encoder, which runs before outer render pass so the texture is ready when referenced.
+    /// Creates new texture every call, cache [StoryboardTextureRenderer] instead for rendering every frame.
+    fn render_nested<'a>(
+        &self,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        encoder: &mut CommandEncoder,
+    ) -> Arc<RenderTexture2D>;
+}
+
+impl NestedRenderExt for DrawContext<'_> {
+    fn render_nested<'a>(
+        &self,
+        drawables: impl ExactSizeIterator<Item = &'a dyn Drawable>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        encoder: &mut CommandEncoder,
+    ) -> Arc<RenderTexture2D> {
+        let textures = self.scope.backend().get::<TextureData>();
+
+        let mut renderer = StoryboardTextureRenderer::init(
+            self.scope.backend().device(),
+            textures,
+            self.scope.pipeline().texture_format,
+            size,
+        );
+        renderer.renderer_mut().set_pooled_depth(true);
+
+        renderer.render(
+            self.scope,
+            ScreenRect::new(Rect::new(Point2D::zero(), size), self.screen.scale_factor),
+            textures,
+            drawables,
+            encoder,
+        );
+
+        renderer.render_texture().clone()
+    }
+}
@@ -0,0 +1,152 @@
+use std::{error::Error, fmt::Display};
+
+use storyboard_render::wgpu::{TextureFormat, TextureSampleType, TextureUsages};
+
+/// Usage of texture created for [super::RenderTexture2D]. Every preset can be sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderTextureUsage {
+    /// Sampled and written using queue
+    Sampled,
+
+    /// Sampled and drawn into, for render to texture
+    RenderTarget,
+
+    /// Sampled and written by compute shaders
+    Storage,
+
+    /// Custom usage flags. Must contain [TextureUsages::TEXTURE_BINDING].
+    Custom(TextureUsages),
+}
+
+impl RenderTextureUsage {
+    pub const fn usages(&self) -> TextureUsages {
+        match self {
+            RenderTextureUsage::Sampled => {
+                TextureUsages::TEXTURE_BINDING.union(TextureUsages::COPY_DST)
+            }
+
+            RenderTextureUsage::RenderTarget => {
+                TextureUsages::TEXTURE_BINDING.union(TextureUsages::RENDER_ATTACHMENT)
+            }
+
+            RenderTextureUsage::Storage => {
+                TextureUsages::TEXTURE_BINDING.union(TextureUsages::STORAGE_BINDING)
+            }
+
+            RenderTextureUsage::Custom(usages) => *usages,
+        }
+    }
+
+    /// Check format can be sampled by render texture bind group and supports every usage flags.
+    /// Only usages guaranteed on every adapter are accepted.
+    pub fn validate(&self, format: TextureFormat) -> Result<TextureUsages, RenderTextureError> {
+        let usages = self.usages();
+
+        if !usages.contains(TextureUsages::TEXTURE_BINDING) {
+            return Err(RenderTextureError::MissingUsage(
+                TextureUsages::TEXTURE_BINDING,
+            ));
+        }
+
+        let info = format.describe();
+
+        if info.sample_type != (TextureSampleType::Float { filterable: true }) {
+            return Err(RenderTextureError::UnsupportedFormat(format));
+        }
+
+        let unsupported = usages - info.guaranteed_format_features.allowed_usages;
+        if !unsupported.is_empty() {
+            return Err(RenderTextureError::UnsupportedUsage {
+                format,
+                usages: unsupported,
+            });
+        }
+
+        Ok(usages)
+    }
+}
+
+impl Default for RenderTextureUsage {
+    fn default() -> Self {
+        Self::Sampled
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderTextureError {
+    /// Usage flags required for render texture are missing
+    MissingUsage(TextureUsages),
+
+    /// Format cannot be sampled with filtering
+    UnsupportedFormat(TextureFormat),
+
+    /// Format doesn't support usage flags
+    UnsupportedUsage {
+        format: TextureFormat,
+        usages: TextureUsages,
+    },
+}
+
+impl Display for RenderTextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingUsage(usages) => {
+                writeln!(f, "Render texture usage is missing: {:?}", usages)
+            }
+
+            Self::UnsupportedFormat(format) => {
+                writeln!(f, "Render texture format is not filterable: {:?}", format)
+            }
+
+            Self::UnsupportedUsage { format, usages } => writeln!(
+                f,
+                "Texture format {:?} doesn't support usage: {:?}",
+                format, usages
+            ),
+        }
+    }
+}
+
+impl Error for RenderTextureError {}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_render::wgpu::{TextureFormat, TextureUsages};
+
+    use super::{RenderTextureError, RenderTextureUsage};
+
+    #[test]
+    fn usage_validate_test() {
+        assert!(RenderTextureUsage::RenderTarget
+            .validate(TextureFormat::Bgra8Unorm)
+            .unwrap()
+            .contains(TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING));
+
+        assert!(RenderTextureUsage::Storage
+            .validate(TextureFormat::Rgba8Unorm)
+            .is_ok());
+
+        assert_eq!(
+            RenderTextureUsage::Storage.validate(TextureFormat::Bgra8Unorm),
+            Err(RenderTextureError::UnsupportedUsage {
+                format: TextureFormat::Bgra8Unorm,
+                usages: TextureUsages::STORAGE_BINDING
+            })
+        );
+
+        assert_eq!(
+            RenderTextureUsage::Custom(TextureUsages::RENDER_ATTACHMENT)
+                .validate(TextureFormat::Rgba8Unorm),
+            Err(RenderTextureError::MissingUsage(
+                TextureUsages::TEXTURE_BINDING
+            ))
+        );
+
+        assert_eq!(
+            RenderTextureUsage::Sampled.validate(TextureFormat::Depth32Float),
+            Err(RenderTextureError::UnsupportedFormat(
+                TextureFormat::Depth32Float
+            ))
+        );
+    }
+}
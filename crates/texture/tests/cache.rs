@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use storyboard_core::euclid::Size2D;
+use storyboard_render::{
+    backend::{BackendOptions, StoryboardBackend},
+    texture::SizedTexture2D,
+    wgpu::{Backends, Features, Instance, TextureFormat, TextureUsages},
+};
+use storyboard_texture::render::{cache::RenderTextureCache, data::TextureData, RenderTexture2D};
+
+/// Memory of one 32x32 Rgba8Unorm texture
+const TEXTURE_BYTES: u64 = 32 * 32 * 4;
+
+fn create_texture(backend: &StoryboardBackend, textures: &TextureData) -> Arc<RenderTexture2D> {
+    let texture = SizedTexture2D::init(
+        backend.device(),
+        None,
+        Size2D::new(32, 32),
+        TextureFormat::Rgba8Unorm,
+        TextureUsages::TEXTURE_BINDING,
+    );
+
+    Arc::new(textures.create_tracked_render_texture(backend.device(), &texture, None))
+}
+
+#[test]
+fn eviction_test() {
+    let backend = pollster::block_on(StoryboardBackend::init(
+        &Instance::new(Backends::all()),
+        None,
+        Features::empty(),
+        &BackendOptions::default(),
+        None,
+    ))
+    .unwrap();
+
+    let textures = TextureData::init(backend.device());
+    textures.set_memory_budget(Some(TEXTURE_BYTES * 3));
+
+    let mut cache = RenderTextureCache::new();
+    for key in 0..5 {
+        cache.insert(key, create_texture(&backend, &textures));
+    }
+    assert_eq!(textures.memory_usage(), TEXTURE_BYTES * 5);
+    assert!(textures.over_budget());
+
+    // Oldest entry is referenced outside, so it is skipped for next least recently used ones
+    let referenced = cache.get(&0).unwrap();
+    let _ = cache.get(&3);
+
+    assert_eq!(cache.evict(&textures), 2);
+    assert_eq!(textures.memory_usage(), TEXTURE_BYTES * 3);
+    assert!(!textures.over_budget());
+
+    assert!(cache.get(&1).is_none());
+    assert!(cache.get(&2).is_none());
+    for key in [0, 3, 4] {
+        assert!(cache.get(&key).is_some());
+    }
+
+    // Nothing evictable while every entry is referenced
+    textures.set_memory_budget(Some(TEXTURE_BYTES));
+    let held = [cache.get(&3).unwrap(), cache.get(&4).unwrap()];
+    assert_eq!(cache.evict(&textures), 0);
+    assert_eq!(cache.len(), 3);
+
+    drop(referenced);
+    drop(held);
+    assert_eq!(cache.evict(&textures), 2);
+    assert_eq!(textures.memory_usage(), TEXTURE_BYTES);
+}
@@ -0,0 +1,460 @@
+use std::{borrow::Cow, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use rand::Rng;
+use storyboard::{
+    app::{StoryboardApp, StoryboardAppProp, StoryboardAppState},
+    core::{
+        euclid::{Rect, Size2D},
+        store::{Store, StoreResources},
+        unit::LogicalPixelUnit,
+    },
+    render::{
+        backend::BackendOptions,
+        buffer::stream::StreamRange,
+        cache::shader::ShaderCache,
+        component::{Component, Drawable},
+        compute::{
+            pass::StoryboardComputePass, ComputeContext, ComputeQueue, ComputeTask,
+            DispatchContext, Dispatchable,
+        },
+        renderer::{
+            context::{DrawContext, RenderContext},
+            pass::StoryboardRenderPass,
+            screen::ScreenResources,
+            ComponentQueue,
+        },
+        shared::RenderScopeContext,
+        wgpu::{
+            util::{BufferInitDescriptor, DeviceExt},
+            vertex_attr_array, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendState, Buffer,
+            BufferBindingType, BufferUsages, ColorTargetState, ColorWrites, CommandEncoder,
+            ComputePipeline, ComputePipelineDescriptor, Device, FragmentState, Limits,
+            MultisampleState, PipelineLayoutDescriptor, PowerPreference, PresentMode,
+            PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+            ShaderModuleDescriptor, ShaderSource, ShaderStages, VertexBufferLayout, VertexState,
+            VertexStepMode,
+        },
+        ScreenRect,
+    },
+    winit::{
+        event::Event,
+        event_loop::{ControlFlow, EventLoop},
+        window::{Window, WindowBuilder},
+    },
+    Storyboard,
+};
+
+const PARTICLE_COUNT: u32 = 10000;
+const PARTICLE_SIZE: f32 = 3.0;
+
+fn main() {
+    use futures::executor::block_on;
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("Storyboard compute particles")
+        .build(&event_loop)
+        .unwrap();
+
+    block_on(main_async(event_loop, window));
+}
+
+async fn main_async(event_loop: EventLoop<()>, window: Window) {
+    let storyboard = Storyboard::init(
+        window,
+        &BackendOptions {
+            power_preference: PowerPreference::HighPerformance,
+            // Compute shaders and storage buffers are not available on webgl2 limits
+            limits: Limits::default(),
+            ..Default::default()
+        },
+        PresentMode::AutoNoVsync,
+        None,
+    )
+    .await
+    .unwrap();
+
+    storyboard.run(event_loop, App { particles: None });
+}
+
+#[derive(Debug)]
+pub struct App {
+    particles: Option<Arc<Particles>>,
+}
+
+impl StoryboardApp for App {
+    fn load(&mut self, prop: &StoryboardAppProp) {
+        let size = prop.window.inner_size();
+        let screen = ScreenRect::new(
+            Rect::from_size(Size2D::new(size.width, size.height)),
+            prop.window.scale_factor() as f32,
+        );
+
+        self.particles = Some(Arc::new(Particles::init(
+            prop.backend.device(),
+            prop.render_get::<ParticleResources>(),
+            screen.physical_rect_to_logical(screen.rect).size,
+        )));
+    }
+
+    fn unload(&mut self, _: &StoryboardAppProp) {}
+
+    fn update(&mut self, prop: &StoryboardAppProp, state: &mut StoryboardAppState) {
+        let particles = match &self.particles {
+            Some(particles) => particles,
+            None => return,
+        };
+
+        match state.event {
+            Event::RedrawRequested(_) => {
+                let bounds = state
+                    .render_task
+                    .configuration()
+                    .surface
+                    .render_screen()
+                    .get_logical_size();
+
+                // Update positions on gpu, then draw them using updated buffer in same frame
+                state.dispatch(ParticleSimulation {
+                    particles: particles.clone(),
+                    bounds,
+                    delta: prop.elapsed.as_secs_f32(),
+                });
+
+                state.draw(ParticleDrawable {
+                    particles: particles.clone(),
+                    size: PARTICLE_SIZE,
+                });
+
+                state.render();
+            }
+
+            Event::MainEventsCleared => {
+                *state.control_flow = ControlFlow::Poll;
+                prop.request_redraw();
+            }
+
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+}
+
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct SimulationUniform {
+    bounds: [f32; 2],
+    delta: f32,
+    _padding: f32,
+}
+
+/// Particle storage buffer, simulated by compute shader and drawn as instance buffer
+#[derive(Debug)]
+pub struct Particles {
+    count: u32,
+
+    particle_buffer: Buffer,
+    simulation_buffer: Buffer,
+
+    bind_group: BindGroup,
+}
+
+impl Particles {
+    pub fn init(
+        device: &Device,
+        resources: &ParticleResources,
+        bounds: Size2D<f32, LogicalPixelUnit>,
+    ) -> Self {
+        let mut rng = rand::thread_rng();
+
+        let particles = (0..PARTICLE_COUNT)
+            .map(|_| Particle {
+                position: [
+                    rng.gen::<f32>() * bounds.width,
+                    rng.gen::<f32>() * bounds.height,
+                ],
+                velocity: [
+                    (rng.gen::<f32>() - 0.5) * 800.0,
+                    (rng.gen::<f32>() - 0.5) * 800.0,
+                ],
+            })
+            .collect::<Vec<_>>();
+
+        let particle_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Particle buffer"),
+            contents: bytemuck::cast_slice(&particles),
+            usage: BufferUsages::STORAGE | BufferUsages::VERTEX,
+        });
+
+        let simulation_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Particle simulation uniform buffer"),
+            contents: bytemuck::bytes_of(&SimulationUniform::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Particle simulation bind group"),
+            layout: &resources.simulation_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: particle_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: simulation_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            count: PARTICLE_COUNT,
+            particle_buffer,
+            simulation_buffer,
+            bind_group,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParticleSimulation {
+    particles: Arc<Particles>,
+    bounds: Size2D<f32, LogicalPixelUnit>,
+    delta: f32,
+}
+
+impl Dispatchable for ParticleSimulation {
+    fn prepare(
+        &self,
+        task_queue: &mut ComputeQueue,
+        ctx: &mut ComputeContext,
+        _: &mut CommandEncoder,
+    ) {
+        ctx.scope.backend().queue().write_buffer(
+            &self.particles.simulation_buffer,
+            0,
+            bytemuck::bytes_of(&SimulationUniform {
+                bounds: self.bounds.to_array(),
+                delta: self.delta,
+                _padding: 0.0,
+            }),
+        );
+
+        task_queue.push(SimulationTask {
+            particles: self.particles.clone(),
+        });
+    }
+}
+
+#[derive(Debug)]
+struct SimulationTask {
+    particles: Arc<Particles>,
+}
+
+impl ComputeTask for SimulationTask {
+    fn dispatch<'pass>(
+        &'pass self,
+        ctx: &DispatchContext<'pass>,
+        pass: &mut StoryboardComputePass<'pass>,
+    ) {
+        let resources = ctx.scope.get::<ParticleResources>();
+
+        pass.set_pipeline(&resources.simulation_pipeline);
+        pass.set_bind_group(0, &self.particles.bind_group, &[]);
+        pass.dispatch_workgroups((self.particles.count + 63) / 64, 1, 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct ParticleDrawable {
+    particles: Arc<Particles>,
+    size: f32,
+}
+
+impl Drawable for ParticleDrawable {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        _: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        let size = self.size;
+
+        // Counter clockwise quad on screen, shared by every particle instances
+        let corners: [[f32; 3]; 6] = [
+            [-size, -size, depth],
+            [-size, size, depth],
+            [size, size, depth],
+            [-size, -size, depth],
+            [size, size, depth],
+            [size, -size, depth],
+        ];
+
+        let quad = ctx
+            .vertex_stream
+            .write_slice(bytemuck::cast_slice(&corners));
+
+        component_queue.push_transparent(ParticleComponent {
+            particles: self.particles.clone(),
+            quad,
+        });
+    }
+}
+
+#[derive(Debug)]
+struct ParticleComponent {
+    particles: Arc<Particles>,
+    quad: StreamRange,
+}
+
+impl Component for ParticleComponent {
+    fn render_opaque<'rpass>(
+        &'rpass self,
+        _: &RenderContext<'rpass>,
+        _: &mut StoryboardRenderPass<'rpass>,
+    ) {
+    }
+
+    fn render_transparent<'rpass>(
+        &'rpass self,
+        ctx: &RenderContext<'rpass>,
+        pass: &mut StoryboardRenderPass<'rpass>,
+    ) {
+        let resources = ctx.scope.get::<ParticleResources>();
+
+        pass.set_pipeline(&resources.render_pipeline);
+        pass.set_bind_group(0, ctx.screen_bind_group, &[]);
+        pass.set_vertex_buffer(0, ctx.vertex_stream.slice(self.quad.clone()));
+        pass.set_vertex_buffer(1, self.particles.particle_buffer.slice(..));
+        pass.draw(0..6, 0..self.particles.count);
+    }
+}
+
+#[derive(Debug)]
+pub struct ParticleResources {
+    pub simulation_bind_group_layout: BindGroupLayout,
+    pub simulation_pipeline: ComputePipeline,
+
+    pub render_pipeline: RenderPipeline,
+}
+
+impl StoreResources<RenderScopeContext<'_>> for ParticleResources {
+    fn initialize(_: &Store, ctx: &RenderScopeContext) -> Self {
+        let device = ctx.backend.device();
+        let shaders = ctx.backend.get::<ShaderCache>();
+
+        let simulation_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Particle simulation bind group layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        // Shader cache is locked while shader is borrowed, so get next shader after using it
+        let simulate_shader = shaders.get_or_create("particle_simulate_shader", || {
+            init_shader(
+                device,
+                "Particle simulate shader",
+                include_str!("simulate.wgsl"),
+            )
+        });
+
+        let simulation_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Particle simulation pipeline"),
+            layout: Some(&device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Particle simulation pipeline layout"),
+                bind_group_layouts: &[&simulation_bind_group_layout],
+                push_constant_ranges: &[],
+            })),
+            module: &simulate_shader,
+            entry_point: "cs_main",
+        });
+        drop(simulate_shader);
+
+        let particle_shader = shaders.get_or_create("particle_shader", || {
+            init_shader(device, "Particle shader", include_str!("particle.wgsl"))
+        });
+
+        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Particle render pipeline"),
+            layout: Some(&device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Particle render pipeline layout"),
+                bind_group_layouts: &[&ctx.backend.get::<ScreenResources>().bind_group_layout],
+                push_constant_ranges: &[],
+            })),
+            vertex: VertexState {
+                module: &particle_shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    VertexBufferLayout {
+                        array_stride: std::mem::size_of::<[f32; 3]>() as u64,
+                        step_mode: VertexStepMode::Vertex,
+                        attributes: &vertex_attr_array![0 => Float32x3],
+                    },
+                    VertexBufferLayout {
+                        array_stride: std::mem::size_of::<Particle>() as u64,
+                        step_mode: VertexStepMode::Instance,
+                        attributes: &vertex_attr_array![1 => Float32x2, 2 => Float32x2],
+                    },
+                ],
+            },
+            primitive: ctx
+                .pipeline
+                .primitive_state(PrimitiveTopology::TriangleList),
+            depth_stencil: ctx.pipeline.depth_stencil_read_only(),
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &particle_shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: ctx.pipeline.texture_format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            simulation_bind_group_layout,
+            simulation_pipeline,
+            render_pipeline,
+        }
+    }
+}
+
+fn init_shader(device: &Device, label: &str, source: &'static str) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some(label),
+        source: ShaderSource::Wgsl(Cow::Borrowed(source)),
+    })
+}
@@ -0,0 +1,35 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct Screen {
+    matrix: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> screen: Screen;
+
+@vertex
+fn vs_main(
+    @location(0) corner: vec3<f32>,
+    @location(1) position: vec2<f32>,
+    @location(2) velocity: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.position = vec4<f32>((screen.matrix * vec4<f32>(position + corner.xy, 0.0, 1.0)).xy, corner.z, 1.0);
+    out.corner = normalize(corner.xy) * sqrt(2.0);
+
+    let speed = clamp(length(velocity) / 400.0, 0.0, 1.0);
+    out.color = vec4<f32>(speed, 0.4, 1.0 - speed, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let alpha = 1.0 - smoothstep(0.8, 1.0, length(in.corner));
+
+    return vec4<f32>(in.color.rgb, in.color.a * alpha);
+}
@@ -0,0 +1,37 @@
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+};
+
+struct Simulation {
+    bounds: vec2<f32>,
+    delta: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<uniform> simulation: Simulation;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= arrayLength(&particles)) {
+        return;
+    }
+
+    var particle = particles[index];
+    particle.position = particle.position + particle.velocity * simulation.delta;
+
+    // Bounce on screen edges
+    if (particle.position.x < 0.0 || particle.position.x > simulation.bounds.x) {
+        particle.velocity.x = -particle.velocity.x;
+    }
+
+    if (particle.position.y < 0.0 || particle.position.y > simulation.bounds.y) {
+        particle.velocity.y = -particle.velocity.y;
+    }
+
+    particle.position = clamp(particle.position, vec2<f32>(0.0, 0.0), simulation.bounds);
+
+    particles[index] = particle;
+}
@@ -8,9 +8,11 @@ use storyboard::{
     app::{StoryboardApp, StoryboardAppProp, StoryboardAppState},
     render::{
         backend::BackendOptions,
+        dynamic_resolution::DynamicResolution,
         wgpu::{Limits, PowerPreference, PresentMode},
     },
     winit::{
+        event::Event,
         event_loop::{ControlFlow, EventLoop},
         window::{Window, WindowBuilder},
     },
@@ -84,12 +86,15 @@ impl StateData for StoryboardStateData {
 #[derive(Debug, Default)]
 pub struct App {
     system: Option<StateSystem<StoryboardStateData>>,
+    /// Lower render resolution on weak GPUs to keep frame rate
+    resolution: DynamicResolution,
 }
 
 impl App {
     pub fn new() -> Self {
         Self {
-            system: None
+            system: None,
+            resolution: DynamicResolution::default(),
         }
     }
 }
@@ -104,6 +109,11 @@ impl StoryboardApp for App {
     }
 
     fn update(&mut self, prop: &StoryboardAppProp, state: &mut StoryboardAppState) {
+        // Sample frame time once per frame
+        if let Event::MainEventsCleared = state.event {
+            self.resolution.apply(state.render_task);
+        }
+
         let system = self.system.as_mut().unwrap();
 
         let status = system.run(prop, state);
@@ -11,7 +11,7 @@ use storyboard::{
     app::{StoryboardAppProp, StoryboardAppState},
     core::{
         color::ShapeColor,
-        euclid::{Point2D, Rect, Size2D, Transform3D},
+        euclid::{Angle, Point2D, Rect, Size2D, Transform3D},
     },
     winit::event::{Event, WindowEvent},
 };
@@ -114,6 +114,11 @@ impl State<StoryboardStateData> for Player {
                             ..Default::default()
                         },
                         transform: Transform3D::identity(),
+                        rotation: Angle::zero(),
+                        rotation_anchor: Point2D::zero(),
+                        clip: None,
+                        force_opaque: false,
+                        tag: None,
                     });
                 }
 
@@ -1,19 +1,25 @@
 #![feature(generic_associated_types)]
 
-use std::time::{Duration, Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use rand::Rng;
 use storyboard::{
     app::{StoryboardApp, StoryboardAppProp, StoryboardAppState},
     core::{
         color::{Color, ShapeColor},
-        euclid::{rect, Angle, Rect, Transform3D},
+        euclid::{rect, Angle, Point2D, Rect, Transform3D},
+        palette::LinSrgba,
         unit::LogicalPixelUnit,
     },
     render::{
         backend::BackendOptions,
+        component::Drawable,
+        renderer::{context::DrawContext, ComponentQueue},
         task::RenderTask,
-        wgpu::{Limits, PowerPreference, PresentMode},
+        wgpu::{CommandEncoder, Limits, PowerPreference, PresentMode},
     },
     winit::{
         event::Event,
@@ -23,7 +29,8 @@ use storyboard::{
     Storyboard,
 };
 use storyboard_box2d::{Box2D, Box2DStyle};
-use storyboard_frame::{FrameComponent, FrameContainer};
+use storyboard_buffered::bloom::{Bloom, BloomConfig, CachedBloomData};
+use storyboard_frame::{FrameComponent, FrameComponentKey, FrameContainer};
 use storyboard_state::{State, StateData, StateStatus, StateSystem, SystemStatus};
 
 fn main() {
@@ -39,18 +46,16 @@ fn main() {
 }
 
 async fn main_async(event_loop: EventLoop<()>, window: Window) {
-    let storyboard = Storyboard::init(
-        window,
-        &BackendOptions {
+    let storyboard = Storyboard::builder()
+        .backend_options(BackendOptions {
             power_preference: PowerPreference::HighPerformance,
             limits: Limits::downlevel_webgl2_defaults(),
             ..Default::default()
-        },
-        PresentMode::AutoNoVsync,
-        None,
-    )
-    .await
-    .unwrap();
+        })
+        .present_mode(PresentMode::AutoNoVsync)
+        .build(window)
+        .await
+        .unwrap();
 
     storyboard.run(
         event_loop,
@@ -88,9 +93,14 @@ impl StoryboardApp for App {
         match app_state.event {
             Event::RedrawRequested(_) => {
                 self.container.update();
-                for components in self.container.values() {
-                    components.draw(app_state.render_task);
-                }
+
+                let screen = app_state
+                    .render_task
+                    .configuration()
+                    .surface
+                    .render_screen();
+                self.container.draw_all(app_state.render_task, &screen);
+
                 app_state.render();
             }
 
@@ -123,6 +133,8 @@ impl StateData for AppStateData {
 pub struct SimpleAnimApp {
     elapsed: Duration,
     interval: Duration,
+
+    layer: Option<FrameComponentKey<StarLayer>>,
 }
 
 impl SimpleAnimApp {
@@ -130,6 +142,8 @@ impl SimpleAnimApp {
         Self {
             elapsed: interval,
             interval,
+
+            layer: None,
         }
     }
 }
@@ -149,14 +163,23 @@ impl State<AppStateData> for SimpleAnimApp {
         if self.elapsed >= self.interval {
             self.elapsed = Duration::ZERO;
 
+            let layer = self
+                .layer
+                .get_or_insert_with(|| system_state.add_component(StarLayer::new()))
+                .clone();
+            let layer = system_state.get_mut(&layer).unwrap();
+
+            let size = system_prop.window.inner_size();
+            layer.bounds = rect(0.0, 0.0, size.width as f32, size.height as f32);
+
             let mut rng = rand::thread_rng();
 
-            system_state.add_component(FadingStar::new(
+            layer.stars.push(FadingStar::new(
                 Instant::now(),
                 Duration::from_millis(300 + (rng.gen::<f32>() * 2000.0) as u64),
                 rect(
-                    rng.gen::<f32>() * system_prop.window.inner_size().width as f32,
-                    rng.gen::<f32>() * system_prop.window.inner_size().height as f32,
+                    rng.gen::<f32>() * size.width as f32,
+                    rng.gen::<f32>() * size.height as f32,
                     48.0,
                     48.0,
                 ),
@@ -168,6 +191,78 @@ impl State<AppStateData> for SimpleAnimApp {
     }
 }
 
+/// Every star drawn under one bloom, so bright pass and blur run once per frame
+#[derive(Debug)]
+pub struct StarLayer {
+    stars: Vec<FadingStar>,
+    /// Bounds of bloom. Kept same as window, so bloom textures are reused.
+    bounds: Rect<f32, LogicalPixelUnit>,
+
+    bloom_data: Arc<CachedBloomData>,
+}
+
+impl StarLayer {
+    const BLOOM_RADIUS: f32 = 16.0;
+
+    fn new() -> Self {
+        Self {
+            stars: Vec::new(),
+            bounds: Rect::zero(),
+
+            bloom_data: Arc::new(CachedBloomData::new()),
+        }
+    }
+}
+
+impl FrameComponent for StarLayer {
+    fn expired(&self) -> bool {
+        false
+    }
+
+    fn update(&mut self) -> bool {
+        self.stars.retain(|star| !star.expired());
+
+        for star in &mut self.stars {
+            star.update();
+        }
+
+        !self.stars.is_empty()
+    }
+
+    fn draw(&self, task: &mut RenderTask) {
+        task.push(Bloom {
+            drawable: Stars(self.stars.iter().map(FadingStar::box2d).collect()),
+            bounds: self.bounds,
+            config: BloomConfig {
+                radius: Self::BLOOM_RADIUS,
+                ..Default::default()
+            },
+            cached_data: self.bloom_data.clone(),
+        })
+    }
+
+    fn bounds(&self) -> Option<Rect<f32, LogicalPixelUnit>> {
+        Some(self.bounds)
+    }
+}
+
+#[derive(Debug)]
+struct Stars(Vec<Box2D>);
+
+impl Drawable for Stars {
+    fn prepare(
+        &self,
+        component_queue: &mut ComponentQueue,
+        ctx: &mut DrawContext,
+        encoder: &mut CommandEncoder,
+        depth: f32,
+    ) {
+        for star in &self.0 {
+            star.prepare(component_queue, ctx, encoder, depth);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct FadingStar {
     start: Instant,
@@ -180,6 +275,8 @@ pub struct FadingStar {
 }
 
 impl FadingStar {
+    const GLOW_RADIUS: f32 = 12.0;
+
     pub fn new(
         start: Instant,
         duration: Duration,
@@ -196,21 +293,22 @@ impl FadingStar {
             alpha: 1.0,
         }
     }
-}
 
-impl FrameComponent for FadingStar {
+    fn rotation(&self) -> Angle<f32> {
+        Angle::degrees(self.alpha * 360.0)
+    }
+
     fn expired(&self) -> bool {
         self.start.elapsed() > self.duration
     }
 
-    fn update(&mut self) -> bool {
+    fn update(&mut self) {
         self.alpha =
             1.0 - self.start.elapsed().as_millis() as f32 / self.duration.as_millis() as f32;
-        true
     }
 
-    fn draw(&self, task: &mut RenderTask) {
-        task.push(Box2D {
+    fn box2d(&self) -> Box2D {
+        Box2D {
             bounds: self.bounds,
             texture: None,
             fill_color: ShapeColor::Single(
@@ -223,21 +321,18 @@ impl FrameComponent for FadingStar {
                     .into(),
             ),
             border_color: ShapeColor::TRANSPARENT,
-            style: Box2DStyle::default(),
-            transform: Transform3D::translation(
-                -self.bounds.origin.x - self.bounds.size.width / 2.0,
-                -self.bounds.origin.y - self.bounds.size.height / 2.0,
-                0.0,
-            )
-            .then_rotate(0.0, 0.0, 1.0, Angle::degrees(self.alpha * 360.0))
-            .then_translate(
-                (
-                    self.bounds.origin.x + self.bounds.size.width / 2.0,
-                    self.bounds.origin.y + self.bounds.size.height / 2.0,
-                    0.0,
-                )
-                    .into(),
-            ),
-        })
+            style: Box2DStyle {
+                glow_radius: Self::GLOW_RADIUS,
+                // Brighter than 1.0, so glow passes bright pass and bleeds
+                glow_color: LinSrgba::new(2.0, 1.8, 1.2, self.alpha),
+                ..Default::default()
+            },
+            transform: Transform3D::identity(),
+            rotation: self.rotation(),
+            rotation_anchor: Point2D::new(0.5, 0.5),
+            clip: None,
+            force_opaque: false,
+            tag: None,
+        }
     }
 }
@@ -3,13 +3,13 @@ use std::{borrow::Cow, sync::Arc};
 use storyboard::{
     app::{StoryboardApp, StoryboardAppProp, StoryboardAppState},
     core::{
-        color::ShapeColor,
-        euclid::{Point2D, Rect, Size2D, Transform3D, Vector2D},
+        color::{Color, ShapeColor},
+        euclid::{Angle, Point2D, Rect, Size2D, Transform3D, Vector2D},
         unit::LogicalPixelUnit,
     },
     render::{
         backend::BackendOptions,
-        wgpu::{Limits, PowerPreference, PresentMode, TextureFormat, TextureUsages},
+        wgpu::{FilterMode, Limits, PowerPreference, PresentMode, TextureFormat, TextureUsages},
     },
     texture::{ComponentTexture, TextureLayout, TextureLayoutStyle, TextureWrap},
     winit::{
@@ -21,7 +21,7 @@ use storyboard::{
 };
 use storyboard_box2d::{Box2D, Box2DStyle};
 use storyboard_primitive::Triangle;
-use storyboard_text::{cache::GlyphCache, font::Font, Text};
+use storyboard_text::{cache::GlyphCache, component::TextSpan, font::Font, Text};
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
@@ -85,24 +85,42 @@ pub static FONT: &[u8] = include_bytes!("./NotoSansCJKkr-Regular.otf");
 #[derive(Debug)]
 pub struct SampleApp {
     texture: Option<ComponentTexture>,
+    tiled_texture: Option<ComponentTexture>,
     cursor: Point2D<f32, LogicalPixelUnit>,
     cache: GlyphCache,
     text: Text,
+    gradient_text: Text,
 }
 
 impl SampleApp {
     pub fn new(font: Font) -> Self {
+        let mut text = Text::new(
+            Point2D::new(100.0, 100.0),
+            32,
+            Transform3D::identity(),
+            font.clone(),
+            Cow::Borrowed(""),
+        );
+
+        // Highlight "테스트"
+        text.set_spans(vec![TextSpan::new(10..19, ShapeColor::RED)]);
+
+        // Text edges over gradient show dark fringes if blending is not done in linear space
+        let gradient_text = Text::new(
+            Point2D::new(30.0, 420.0),
+            32,
+            Transform3D::identity(),
+            font,
+            Cow::Borrowed("Gamma correct text"),
+        );
+
         Self {
             texture: None,
+            tiled_texture: None,
             cursor: Default::default(),
             cache: GlyphCache::new(),
-            text: Text::new(
-                Point2D::new(100.0, 100.0),
-                32,
-                Transform3D::identity(),
-                font,
-                Cow::Borrowed(""),
-            ),
+            text,
+            gradient_text,
         }
     }
 }
@@ -133,6 +151,35 @@ impl StoryboardApp for SampleApp {
             (TextureWrap::None, TextureWrap::None),
         ));
 
+        // 2x2 checker surrounded by magenta pixels, which must not appear when tiled
+        let atlas = system_prop.create_texture_with_data(
+            Some("App tiled atlas texture"),
+            Size2D::new(4, 4),
+            TextureFormat::Bgra8Unorm,
+            TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            &[
+                0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0x00,
+                0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0xff,
+                0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0xff,
+                0xff, 0x00, 0xff, 0xff, 0xff, 0x00, 0xff, 0xff,
+            ],
+        );
+
+        self.tiled_texture = Some(system_prop.texture_data().create_component_texture(
+            system_prop.backend.device(),
+            atlas
+                .create_view_default(None)
+                .slice(Rect::new(Point2D::new(1, 1), Size2D::new(2, 2))),
+            FilterMode::Linear,
+            1,
+            TextureLayout::Relative(TextureLayoutStyle::Custom(Rect::new(
+                Point2D::zero(),
+                Size2D::new(50.0, 50.0),
+            ))),
+            (TextureWrap::Repeat, TextureWrap::Repeat),
+        ));
+
         println!("App loaded");
     }
 
@@ -145,16 +192,17 @@ impl StoryboardApp for SampleApp {
             state.draw(Triangle {
                 bounds: Rect::new(Point2D::new(30.0, 30.0), Size2D::new(50.0, 50.0)),
                 color: ShapeColor::WHITE,
-                texture: self
-                    .texture
-                    .as_ref()
-                    .map(|component| component.inner.clone()),
-                texture_coord: [
+                texture: self.texture.clone(),
+                texture_coord: Some([
                     Point2D::new(0.5, 0.0),
                     Point2D::new(0.0, 1.0),
                     Point2D::new(1.0, 1.0),
-                ],
+                ]),
                 transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                force_opaque: false,
+                alpha_cutoff: None,
             });
 
             state.draw(Box2D {
@@ -170,11 +218,84 @@ impl StoryboardApp for SampleApp {
                     ..Default::default()
                 },
                 transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                tag: None,
+            });
+
+            // Sliced texture tiled 3x3
+            state.draw(Box2D {
+                bounds: Rect::new(Point2D::new(300.0, 30.0), Size2D::new(150.0, 150.0)),
+                fill_color: ShapeColor::WHITE,
+                border_color: ShapeColor::TRANSPARENT,
+                texture: self.tiled_texture.clone(),
+                style: Box2DStyle::default(),
+                transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                tag: None,
+            });
+
+            // Black to white gradient behind white text
+            state.draw(Box2D {
+                bounds: Rect::new(Point2D::new(20.0, 400.0), Size2D::new(320.0, 80.0)),
+                fill_color: ShapeColor::Gradient([
+                    Color::new(0.0, 0.0, 0.0, 1.0),
+                    Color::new(0.0, 0.0, 0.0, 1.0),
+                    Color::new(1.0, 1.0, 1.0, 1.0),
+                    Color::new(1.0, 1.0, 1.0, 1.0),
+                ]),
+                border_color: ShapeColor::TRANSPARENT,
+                texture: None,
+                style: Box2DStyle::default(),
+                transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                tag: None,
+            });
+
+            // 45 degrees gradient, top left and bottom right corners are black and white, other corners are gray
+            state.draw(Box2D {
+                bounds: Rect::new(Point2D::new(500.0, 30.0), Size2D::new(150.0, 150.0)),
+                fill_color: ShapeColor::LinearGradient {
+                    start: Color::new(0.0, 0.0, 0.0, 1.0),
+                    end: Color::new(1.0, 1.0, 1.0, 1.0),
+                    angle: Angle::degrees(45.0),
+                },
+                border_color: ShapeColor::TRANSPARENT,
+                texture: None,
+                style: Box2DStyle::default(),
+                transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                tag: None,
             });
 
+            self.gradient_text.update(
+                prop.backend.device(),
+                prop.backend.queue(),
+                prop.window.scale_factor() as _,
+                prop.texture_data(),
+                &mut self.cache,
+            );
+            state.draw(self.gradient_text.draw(&ShapeColor::WHITE));
+
             self.text.set_text(Cow::Owned(format!(
                 "렌더링 테스트\n{:?}\nElapsed: {} ms\nFps: {}",
-                self.cursor * prop.window.scale_factor() as f32,
+                state
+                    .render_task
+                    .configuration()
+                    .surface
+                    .screen
+                    .logical_to_physical(self.cursor),
                 prop.elapsed.as_nanos() as f64 / 1_000_000.0,
                 state.render_task.frame_rate()
             )));
@@ -199,6 +320,11 @@ impl StoryboardApp for SampleApp {
                     ..Default::default()
                 },
                 transform: Transform3D::identity(),
+                rotation: Angle::zero(),
+                rotation_anchor: Point2D::zero(),
+                clip: None,
+                force_opaque: false,
+                tag: None,
             });
 
             state.render();
@@ -214,8 +340,12 @@ impl StoryboardApp for SampleApp {
             ..
         } = state.event
         {
-            self.cursor = Point2D::new(position.x as f32, position.y as f32)
-                / prop.window.scale_factor() as f32;
+            self.cursor = state
+                .render_task
+                .configuration()
+                .surface
+                .screen
+                .physical_to_logical(Point2D::new(position.x, position.y));
 
             self.text.position = self.cursor;
         }
@@ -5,17 +5,22 @@ use storyboard_core::{euclid::Size2D, store::StoreResources, unit::PhyiscalPixel
 use storyboard_render::{
     backend::StoryboardBackend,
     component::Drawable,
+    compute::Dispatchable,
+    renderer::stats::RenderStats,
     shared::{
         BackendScope, BackendScopeContext, BackendShared, RenderScope, RenderScopeContext,
         RenderShared,
     },
-    task::RenderTask,
-    texture::{SizedTexture2D, TextureView2D},
+    task::{RenderEvent, RenderTask},
+    texture::{ColorType, SizedTexture2D, TextureConvertError, TextureView2D},
+    warmup::{PipelineWarmup, WarmupProgress},
     wgpu::{Sampler, TextureFormat, TextureUsages},
 };
 use storyboard_texture::render::{data::TextureData, RenderTexture2D};
 use winit::{event::Event, event_loop::ControlFlow, window::Window};
 
+use crate::input::InputState;
+
 pub trait StoryboardApp {
     fn load(&mut self, prop: &StoryboardAppProp);
     fn unload(&mut self, prop: &StoryboardAppProp);
@@ -71,6 +76,21 @@ impl StoryboardAppProp {
         self.render_shared.get(self.backend_scope())
     }
 
+    /// Compile pipelines in warm up list, blocking until done. Call in [StoryboardApp::load] to avoid first use hitches.
+    pub fn warmup(&self, warmup: &PipelineWarmup, progress: impl FnMut(WarmupProgress)) {
+        warmup.run(self.render_scope(), progress);
+    }
+
+    /// Compile pipelines in warm up list on separate thread, so app can show loading progress meanwhile
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn_warmup(&self, warmup: PipelineWarmup) -> storyboard_render::warmup::WarmupHandle {
+        warmup.spawn(
+            self.backend.clone(),
+            self.backend_shared.clone(),
+            self.render_shared.clone(),
+        )
+    }
+
     pub fn texture_data(&self) -> &TextureData {
         self.backend_get()
     }
@@ -101,6 +121,22 @@ impl StoryboardAppProp {
         tex
     }
 
+    /// Create [SizedTexture2D] from descriptor and upload entire data, converting from source color type to format
+    pub fn create_texture_with_converted_data(
+        &self,
+        label: Option<&str>,
+        size: Size2D<u32, PhyiscalPixelUnit>,
+        format: TextureFormat,
+        usage: TextureUsages,
+        source: ColorType,
+        data: &[u8],
+    ) -> Result<SizedTexture2D, TextureConvertError> {
+        let tex = SizedTexture2D::init(self.backend.device(), label, size, format, usage);
+        tex.write_converted(self.backend.queue(), None, source, data)?;
+
+        Ok(tex)
+    }
+
     /// Create Framebuffer capable texture, having same texture format as surface
     pub fn create_frame_buffer_texture(
         &self,
@@ -141,10 +177,12 @@ impl StoryboardAppProp {
 
 /// Mutable system state.
 ///
-/// Contains event.
+/// Contains event and input state.
 #[derive(Debug)]
 pub struct StoryboardAppState<'a> {
     pub event: Event<'a, ()>,
+    /// Input state including current event
+    pub input: &'a InputState,
 
     pub control_flow: &'a mut ControlFlow,
     pub render_task: &'a mut RenderTask,
@@ -156,8 +194,26 @@ impl<'a> StoryboardAppState<'a> {
         self.render_task.push(drawable);
     }
 
+    /// Push compute work dispatched before drawables of this frame
+    #[inline]
+    pub fn dispatch(&mut self, dispatchable: impl Dispatchable + 'static) {
+        self.render_task.push_dispatch(dispatchable);
+    }
+
     #[inline]
     pub fn render(&mut self) {
         self.render_task.submit();
     }
+
+    /// Draw calls, vertices and streamed bytes of last presented frame
+    #[inline]
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_task.last_frame_stats()
+    }
+
+    /// Render events reported since last call. See [RenderEvent]
+    #[inline]
+    pub fn render_events(&self) -> impl Iterator<Item = RenderEvent> + '_ {
+        self.render_task.events()
+    }
 }
@@ -0,0 +1,194 @@
+//! Builder for [Storyboard] initialization
+
+use std::{num::NonZeroU32, path::PathBuf};
+
+use storyboard_render::{
+    backend::{BackendInitError, BackendOptions, StoryboardBackend},
+    renderer::{surface::InternalResolution, RenderMode, StoryboardRenderer},
+    task::RenderTaskConfiguration,
+    wgpu::{Backends, Color, CompositeAlphaMode, Features, Instance, PresentMode, TextureFormat},
+};
+use winit::window::Window;
+
+use crate::{input::EventDelivery, Storyboard};
+
+/// Options of [Storyboard] set before initialization.
+/// Every option can be changed later using public fields of [Storyboard] before start.
+///
+/// Multisampling is not configurable, as surface renderer and components create single sampled pipelines only.
+#[derive(Debug, Clone)]
+pub struct StoryboardBuilder {
+    backend_options: BackendOptions,
+    present_mode: PresentMode,
+    trace_path: Option<PathBuf>,
+
+    linear_blending: bool,
+    alpha_mode: CompositeAlphaMode,
+    clear_color: Color,
+    max_frame_latency: NonZeroU32,
+    depth_format: Option<TextureFormat>,
+    render_task_config: RenderTaskConfiguration,
+    internal_resolution: Option<InternalResolution>,
+    event_delivery: EventDelivery,
+}
+
+impl StoryboardBuilder {
+    pub fn new() -> Self {
+        Self {
+            backend_options: BackendOptions::default(),
+            present_mode: PresentMode::AutoVsync,
+            trace_path: None,
+
+            linear_blending: true,
+            alpha_mode: CompositeAlphaMode::Opaque,
+            clear_color: Color::BLACK,
+            max_frame_latency: NonZeroU32::new(1).unwrap(),
+            depth_format: Some(StoryboardRenderer::DEFAULT_DEPTH_TEXTURE_FORMAT),
+            render_task_config: RenderTaskConfiguration::default(),
+            internal_resolution: None,
+            event_delivery: EventDelivery::default(),
+        }
+    }
+
+    pub fn backend_options(mut self, backend_options: BackendOptions) -> Self {
+        self.backend_options = backend_options;
+        self
+    }
+
+    /// Defaults to [PresentMode::AutoVsync]
+    pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    /// Directory to write api call trace into
+    pub fn trace_path(mut self, trace_path: impl Into<PathBuf>) -> Self {
+        self.trace_path = Some(trace_path.into());
+        self
+    }
+
+    /// See [Storyboard::linear_blending]
+    pub fn linear_blending(mut self, linear_blending: bool) -> Self {
+        self.linear_blending = linear_blending;
+        self
+    }
+
+    /// See [Storyboard::alpha_mode]
+    pub fn alpha_mode(mut self, alpha_mode: CompositeAlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// See [Storyboard::clear_color]
+    pub fn clear_color(mut self, clear_color: Color) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    /// See [Storyboard::max_frame_latency]
+    pub fn max_frame_latency(mut self, max_frame_latency: NonZeroU32) -> Self {
+        self.max_frame_latency = max_frame_latency;
+        self
+    }
+
+    /// See [Storyboard::depth_format]
+    pub fn depth_format(mut self, depth_format: Option<TextureFormat>) -> Self {
+        self.depth_format = depth_format;
+        self
+    }
+
+    pub fn render_task_config(mut self, render_task_config: RenderTaskConfiguration) -> Self {
+        self.render_task_config = render_task_config;
+        self
+    }
+
+    /// Set depth format used by given render mode.
+    /// [RenderMode::PainterOrder] draws everything in push order without depth texture.
+    pub fn render_mode(mut self, render_mode: RenderMode) -> Self {
+        self.depth_format = render_mode.depth_format();
+        self
+    }
+
+    /// See [Storyboard::internal_resolution]
+    pub fn internal_resolution(mut self, internal_resolution: Option<InternalResolution>) -> Self {
+        self.internal_resolution = internal_resolution;
+        self
+    }
+
+    /// See [Storyboard::event_delivery]
+    pub fn event_delivery(mut self, event_delivery: EventDelivery) -> Self {
+        self.event_delivery = event_delivery;
+        self
+    }
+
+    /// Initalize resources for storyboard app.
+    /// Backends in [BackendOptions::backends] are tried in order, falling back to next one if no adapter is found or request times out.
+    pub async fn build(self, window: Window) -> Result<Storyboard, BackendInitError> {
+        let options = &self.backend_options;
+        let mut result = Err(BackendInitError::NoSuitableAdapter);
+
+        // Try backends in order until one yields adapter
+        for backends in options
+            .backends
+            .iter()
+            .copied()
+            .chain(options.backends.is_empty().then_some(Backends::all()))
+        {
+            let instance = Instance::new(backends);
+
+            // Safety: window is valid object to create a surface
+            let surface = unsafe { instance.create_surface(&window) };
+
+            result = StoryboardBackend::init(
+                &instance,
+                Some(&surface),
+                Features::empty(),
+                options,
+                self.trace_path.as_deref(),
+            )
+            .await
+            .map(|backend| (backend, surface));
+
+            match result {
+                Err(BackendInitError::NoSuitableAdapter) | Err(BackendInitError::Timeout) => {}
+
+                _ => break,
+            }
+        }
+
+        let (backend, surface) = result?;
+
+        let supported_formats = surface.get_supported_formats(backend.adapter());
+        if supported_formats.is_empty() {
+            return Err(BackendInitError::NoSuitableAdapter);
+        }
+
+        let supported_alpha_modes = surface.get_supported_alpha_modes(backend.adapter());
+
+        Ok(Storyboard {
+            backend,
+            supported_formats,
+            supported_alpha_modes,
+
+            linear_blending: self.linear_blending,
+
+            present_mode: self.present_mode,
+            alpha_mode: self.alpha_mode,
+            clear_color: self.clear_color,
+            max_frame_latency: self.max_frame_latency,
+            depth_format: self.depth_format,
+            render_task_config: self.render_task_config,
+            internal_resolution: self.internal_resolution,
+            event_delivery: self.event_delivery,
+
+            window,
+            surface,
+        })
+    }
+}
+
+impl Default for StoryboardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,359 @@
+//! Input state snapshot and event delivery mode for storyboard app.
+use rustc_hash::FxHashSet;
+use storyboard_core::{
+    euclid::{default::Vector2D as UnknownVector2D, Point2D, Vector2D},
+    unit::LogicalPixelUnit,
+};
+use storyboard_render::ScreenRect;
+use winit::event::{
+    DeviceEvent, ElementState, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode,
+    WindowEvent,
+};
+
+/// How winit events are delivered to [crate::app::StoryboardApp::update]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventDelivery {
+    /// Call update for every event
+    PerEvent,
+
+    /// Fold input and device events into [InputState] instead of calling update for each of them.
+    /// App reads input once per `MainEventsCleared`, so update rate doesn't follow input rate. Other events are delivered as usual.
+    ///
+    /// Input is seen up to one event loop iteration later and ordering between key presses and typed text in same iteration is lost.
+    /// Use [EventDelivery::PerEvent] for latency sensitive text input.
+    Coalesced,
+}
+
+impl Default for EventDelivery {
+    fn default() -> Self {
+        Self::PerEvent
+    }
+}
+
+/// Input snapshot updated from window events.
+/// Per frame values are reset after `MainEventsCleared` update.
+#[derive(Debug, Default, Clone)]
+pub struct InputState {
+    cursor: Option<Point2D<f32, LogicalPixelUnit>>,
+    modifiers: ModifiersState,
+
+    pressed_keys: FxHashSet<VirtualKeyCode>,
+    just_pressed_keys: FxHashSet<VirtualKeyCode>,
+
+    pressed_buttons: FxHashSet<MouseButton>,
+    just_pressed_buttons: FxHashSet<MouseButton>,
+
+    scroll_lines: UnknownVector2D<f32>,
+    scroll_pixels: Vector2D<f32, LogicalPixelUnit>,
+    mouse_motion: UnknownVector2D<f64>,
+
+    text: String,
+}
+
+impl InputState {
+    /// Cursor position. None if cursor is outside of window.
+    pub const fn cursor(&self) -> Option<Point2D<f32, LogicalPixelUnit>> {
+        self.cursor
+    }
+
+    pub const fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    pub fn key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    /// Check if key is pressed during this frame, even if it is already released
+    pub fn key_just_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+
+    pub fn button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    /// Check if mouse button is pressed during this frame, even if it is already released
+    pub fn button_just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_buttons.contains(&button)
+    }
+
+    /// Scroll amount in lines during this frame
+    pub const fn scroll_lines(&self) -> UnknownVector2D<f32> {
+        self.scroll_lines
+    }
+
+    /// Scroll amount in pixels during this frame, reported by touchpads
+    pub const fn scroll_pixels(&self) -> Vector2D<f32, LogicalPixelUnit> {
+        self.scroll_pixels
+    }
+
+    /// Raw mouse motion during this frame
+    pub const fn mouse_motion(&self) -> UnknownVector2D<f64> {
+        self.mouse_motion
+    }
+
+    /// Text typed during this frame
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Update state from window event. Returns true if event is input event which can be coalesced.
+    pub(crate) fn update_window(&mut self, event: &WindowEvent, screen: &ScreenRect) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor =
+                    Some(screen.physical_to_logical(Point2D::new(position.x, position.y)));
+            }
+
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+            }
+
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+            }
+
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    match input.state {
+                        ElementState::Pressed => {
+                            self.pressed_keys.insert(key);
+                            self.just_pressed_keys.insert(key);
+                        }
+
+                        ElementState::Released => {
+                            self.pressed_keys.remove(&key);
+                        }
+                    }
+                }
+            }
+
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    self.pressed_buttons.insert(*button);
+                    self.just_pressed_buttons.insert(*button);
+                }
+
+                ElementState::Released => {
+                    self.pressed_buttons.remove(button);
+                }
+            },
+
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => {
+                    self.scroll_lines += UnknownVector2D::new(*x, *y);
+                }
+
+                MouseScrollDelta::PixelDelta(position) => {
+                    self.scroll_pixels += screen
+                        .physical_to_logical(Point2D::new(position.x, position.y))
+                        .to_vector();
+                }
+            },
+
+            WindowEvent::ReceivedCharacter(character) => {
+                self.text.push(*character);
+            }
+
+            WindowEvent::Focused(false) => {
+                self.pressed_keys.clear();
+                self.pressed_buttons.clear();
+
+                return false;
+            }
+
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Update state from device event. Every device event can be coalesced.
+    pub(crate) fn update_device(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            self.mouse_motion += UnknownVector2D::new(*x, *y);
+        }
+    }
+
+    /// Reset per frame values
+    pub(crate) fn end_frame(&mut self) {
+        self.just_pressed_keys.clear();
+        self.just_pressed_buttons.clear();
+
+        self.scroll_lines = UnknownVector2D::zero();
+        self.scroll_pixels = Vector2D::zero();
+        self.mouse_motion = UnknownVector2D::zero();
+
+        self.text.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use storyboard_core::euclid::{Point2D, Rect, Size2D, Vector2D};
+    use storyboard_render::ScreenRect;
+    use winit::{
+        dpi::PhysicalPosition,
+        event::{
+            DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+            TouchPhase, VirtualKeyCode, WindowEvent,
+        },
+    };
+
+    use super::InputState;
+
+    fn screen() -> ScreenRect {
+        ScreenRect::new(Rect::new(Point2D::zero(), Size2D::new(100, 100)), 2.0)
+    }
+
+    #[allow(deprecated)]
+    fn key_event(key: VirtualKeyCode, state: ElementState) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            // SAFETY: Dummy device id is only compared, never used to query device
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::empty(),
+            },
+            is_synthetic: false,
+        }
+    }
+
+    #[allow(deprecated)]
+    fn button_event(button: MouseButton, state: ElementState) -> WindowEvent<'static> {
+        WindowEvent::MouseInput {
+            // SAFETY: Dummy device id is only compared, never used to query device
+            device_id: unsafe { DeviceId::dummy() },
+            state,
+            button,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    #[test]
+    fn key_press_release_test() {
+        let mut input = InputState::default();
+
+        assert!(input.update_window(
+            &key_event(VirtualKeyCode::A, ElementState::Pressed),
+            &screen()
+        ));
+        assert!(input.key_pressed(VirtualKeyCode::A));
+        assert!(input.key_just_pressed(VirtualKeyCode::A));
+
+        // Held key stays pressed on next frame
+        input.end_frame();
+        assert!(input.key_pressed(VirtualKeyCode::A));
+        assert!(!input.key_just_pressed(VirtualKeyCode::A));
+
+        input.update_window(
+            &key_event(VirtualKeyCode::A, ElementState::Released),
+            &screen(),
+        );
+        assert!(!input.key_pressed(VirtualKeyCode::A));
+
+        // Key pressed and released in same frame is still seen as just pressed
+        input.update_window(
+            &key_event(VirtualKeyCode::B, ElementState::Pressed),
+            &screen(),
+        );
+        input.update_window(
+            &key_event(VirtualKeyCode::B, ElementState::Released),
+            &screen(),
+        );
+        assert!(!input.key_pressed(VirtualKeyCode::B));
+        assert!(input.key_just_pressed(VirtualKeyCode::B));
+
+        input.end_frame();
+        assert!(!input.key_just_pressed(VirtualKeyCode::B));
+    }
+
+    #[test]
+    fn button_press_release_test() {
+        let mut input = InputState::default();
+
+        input.update_window(
+            &button_event(MouseButton::Left, ElementState::Pressed),
+            &screen(),
+        );
+        assert!(input.button_pressed(MouseButton::Left));
+        assert!(input.button_just_pressed(MouseButton::Left));
+        assert!(!input.button_pressed(MouseButton::Right));
+
+        input.end_frame();
+        assert!(input.button_pressed(MouseButton::Left));
+        assert!(!input.button_just_pressed(MouseButton::Left));
+
+        input.update_window(
+            &button_event(MouseButton::Left, ElementState::Released),
+            &screen(),
+        );
+        assert!(!input.button_pressed(MouseButton::Left));
+
+        // Losing focus releases every held key and button
+        input.update_window(
+            &button_event(MouseButton::Right, ElementState::Pressed),
+            &screen(),
+        );
+        input.update_window(
+            &key_event(VirtualKeyCode::A, ElementState::Pressed),
+            &screen(),
+        );
+        assert!(!input.update_window(&WindowEvent::Focused(false), &screen()));
+        assert!(!input.button_pressed(MouseButton::Right));
+        assert!(!input.key_pressed(VirtualKeyCode::A));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn end_frame_test() {
+        let mut input = InputState::default();
+
+        // SAFETY: Dummy device id is only compared, never used to query device
+        let device_id = unsafe { DeviceId::dummy() };
+
+        input.update_window(
+            &WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(50.0, 30.0),
+                modifiers: ModifiersState::empty(),
+            },
+            &screen(),
+        );
+        input.update_window(
+            &WindowEvent::MouseWheel {
+                device_id,
+                delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 20.0)),
+                phase: TouchPhase::Moved,
+                modifiers: ModifiersState::empty(),
+            },
+            &screen(),
+        );
+        input.update_window(
+            &WindowEvent::MouseWheel {
+                device_id,
+                delta: MouseScrollDelta::LineDelta(0.0, 1.0),
+                phase: TouchPhase::Moved,
+                modifiers: ModifiersState::empty(),
+            },
+            &screen(),
+        );
+        input.update_window(&WindowEvent::ReceivedCharacter('a'), &screen());
+
+        // Physical coordinates are converted to logical on 2x scale
+        assert_eq!(input.cursor(), Some(Point2D::new(25.0, 15.0)));
+        assert_eq!(input.scroll_pixels(), Vector2D::new(0.0, 10.0));
+        assert_eq!(input.scroll_lines(), Vector2D::new(0.0, 1.0));
+        assert_eq!(input.text(), "a");
+
+        // Per frame values are reset, cursor is kept
+        input.end_frame();
+        assert_eq!(input.cursor(), Some(Point2D::new(25.0, 15.0)));
+        assert_eq!(input.scroll_pixels(), Vector2D::zero());
+        assert_eq!(input.scroll_lines(), Vector2D::zero());
+        assert_eq!(input.text(), "");
+    }
+}
@@ -1,4 +1,6 @@
 pub mod app;
+pub mod builder;
+pub mod input;
 
 // Reexports
 pub use storyboard_core as core;
@@ -9,21 +11,24 @@ pub use winit;
 use instant::Instant;
 
 use app::{StoryboardApp, StoryboardAppProp, StoryboardAppState};
+use builder::StoryboardBuilder;
+use input::{EventDelivery, InputState};
 use render::{
     renderer::StoryboardRenderer,
-    shared::{BackendShared, RenderShared},
+    shared::{BackendScopeContext, BackendShared, RenderShared},
     task::RenderTaskConfiguration,
     ScreenRect,
 };
-use std::{path::Path, sync::Arc, time::Duration};
+use std::{num::NonZeroU32, path::Path, sync::Arc, time::Duration};
 use storyboard_core::euclid::{Point2D, Rect, Size2D};
 use storyboard_render::{
     backend::{BackendInitError, BackendOptions, StoryboardBackend},
-    renderer::surface::{StoryboardSurfaceRenderer, SurfaceConfiguration},
+    renderer::surface::{InternalResolution, StoryboardSurfaceRenderer, SurfaceConfiguration},
     task::RenderTask,
     wgpu::TextureFormat,
-    wgpu::{Backends, Features, Instance, PresentMode, Surface},
+    wgpu::{Color, CompositeAlphaMode, PresentMode, Surface},
 };
+use storyboard_texture::render::data::TextureData;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::EventLoop,
@@ -35,60 +40,83 @@ use winit::{
 #[derive(Debug)]
 pub struct Storyboard {
     backend: StoryboardBackend,
-    screen_format: TextureFormat,
+    supported_formats: Vec<TextureFormat>,
+    supported_alpha_modes: Vec<CompositeAlphaMode>,
+
+    /// Render into sRGB surface format so colors are blended in linear space, giving clean antialiased edges on any background.
+    /// Falls back to first supported format if surface doesn't support sRGB format. Defaults to true.
+    pub linear_blending: bool,
 
     pub present_mode: PresentMode,
+    /// Surface alpha compositing mode. Falls back to first supported mode on start if the surface doesn't support it.
+    pub alpha_mode: CompositeAlphaMode,
+    /// Color window is cleared with every frame. Use transparent color with non opaque [Storyboard::alpha_mode] for transparent window.
+    pub clear_color: Color,
+    /// Maximum frames in flight. See [SurfaceConfiguration::max_frame_latency].
+    pub max_frame_latency: NonZeroU32,
+    /// Depth texture format. Set to None for app which doesn't need depth testing, drawing in [storyboard_render::renderer::RenderMode::PainterOrder]
+    pub depth_format: Option<TextureFormat>,
     pub render_task_config: RenderTaskConfiguration,
+    /// Resolution app is rendered at before scaled to window. Renders at window resolution if None.
+    pub internal_resolution: Option<InternalResolution>,
+    /// How events are delivered to app. See [EventDelivery]
+    pub event_delivery: EventDelivery,
 
     window: Window,
     surface: Surface,
 }
 
 impl Storyboard {
-    /// Initalize resources for storyboard app
+    /// Create [StoryboardBuilder] with default options
+    pub fn builder() -> StoryboardBuilder {
+        StoryboardBuilder::new()
+    }
+
+    /// Initalize resources for storyboard app with given backend options and present mode.
+    /// See [StoryboardBuilder::build]
     pub async fn init(
         window: Window,
         options: &BackendOptions,
         present_mode: PresentMode,
         trace_path: Option<&Path>,
     ) -> Result<Self, BackendInitError> {
-        let instance = Instance::new(Backends::all());
-
-        // Safety: window is valid object to create a surface
-        let surface = unsafe { instance.create_surface(&window) };
-
-        let backend = StoryboardBackend::init(
-            &instance,
-            Some(&surface),
-            Features::empty(),
-            options,
-            trace_path,
-        )
-        .await?;
-
-        let screen_format = *surface
-            .get_supported_formats(backend.adapter())
-            .get(0)
-            .ok_or(BackendInitError::NoSuitableAdapter)?;
-
-        Ok(Self {
-            backend,
-            screen_format,
+        let mut builder = StoryboardBuilder::new()
+            .backend_options(options.clone())
+            .present_mode(present_mode);
 
-            present_mode,
-            render_task_config: RenderTaskConfiguration::default(),
+        if let Some(trace_path) = trace_path {
+            builder = builder.trace_path(trace_path);
+        }
 
-            window,
-            surface,
-        })
+        builder.build(window).await
     }
 
+    /// Backend initialized. Use [StoryboardBackend::backend] to find which graphics api is chosen.
     pub const fn backend(&self) -> &StoryboardBackend {
         &self.backend
     }
 
-    pub const fn screen_format(&self) -> TextureFormat {
-        self.screen_format
+    /// Surface format app renders into, chosen using [Storyboard::linear_blending]
+    pub fn screen_format(&self) -> TextureFormat {
+        self.linear_blending
+            .then(|| {
+                self.supported_formats
+                    .iter()
+                    .copied()
+                    .find(|format| format.describe().srgb)
+            })
+            .flatten()
+            .unwrap_or(self.supported_formats[0])
+    }
+
+    /// Texture formats supported by window surface
+    pub fn supported_formats(&self) -> &[TextureFormat] {
+        &self.supported_formats
+    }
+
+    /// Alpha compositing modes supported by window surface
+    pub fn supported_alpha_modes(&self) -> &[CompositeAlphaMode] {
+        &self.supported_alpha_modes
     }
 
     pub const fn window(&self) -> &Window {
@@ -99,6 +127,7 @@ impl Storyboard {
     ///
     /// Start render thread and run given inital [StoryboardApp].
     pub fn run(self, event_loop: EventLoop<()>, mut app: impl StoryboardApp + 'static) -> ! {
+        let screen_format = self.screen_format();
         let backend = Arc::new(self.backend);
 
         let win_size = {
@@ -107,29 +136,56 @@ impl Storyboard {
             Size2D::new(width, height)
         };
 
+        // Kept in sync with surface screen for converting input coordinates
+        let mut screen = ScreenRect::new(
+            Rect::new(Point2D::zero(), win_size),
+            self.window.scale_factor() as _,
+        );
+
+        let alpha_mode = if self.supported_alpha_modes.contains(&self.alpha_mode) {
+            self.alpha_mode
+        } else {
+            self.supported_alpha_modes
+                .first()
+                .copied()
+                .unwrap_or(CompositeAlphaMode::Opaque)
+        };
+
         let surface_renderer = StoryboardSurfaceRenderer::new(
             self.surface,
             SurfaceConfiguration {
                 present_mode: self.present_mode,
-                screen: ScreenRect::new(
-                    Rect::new(Point2D::zero(), win_size),
-                    self.window.scale_factor() as _,
-                ),
+                screen,
+                alpha_mode,
+                max_frame_latency: self.max_frame_latency,
+                clear_color: self.clear_color,
+                internal_resolution: self.internal_resolution,
             },
         );
 
         let backend_shared = Arc::new(BackendShared::new());
+        backend_shared
+            .get::<TextureData>(BackendScopeContext {
+                device: backend.device(),
+                queue: backend.queue(),
+            })
+            .set_max_anisotropy(backend.max_anisotropy());
         let render_shared = Arc::new(RenderShared::new(
-            StoryboardRenderer::create_renderer_pipeline_data(self.screen_format, None),
+            StoryboardRenderer::create_renderer_pipeline_data(
+                screen_format,
+                self.depth_format,
+                None,
+            ),
         ));
 
-        let mut render_task = RenderTask::run(
+        // Taken out on exit to shut it down
+        let mut render_task = Some(RenderTask::run(
             backend.clone(),
             backend_shared.clone(),
             render_shared.clone(),
             surface_renderer,
             self.render_task_config,
-        );
+        ));
 
         let mut app_prop = StoryboardAppProp {
             backend,
@@ -140,13 +196,36 @@ impl Storyboard {
         };
         app.load(&app_prop);
 
+        let event_delivery = self.event_delivery;
+        let mut input = InputState::default();
+
         let mut instant = Instant::now();
         event_loop.run(move |event, _, control_flow| {
+            let input_event = match &event {
+                Event::WindowEvent { event, .. } => input.update_window(event, &screen),
+
+                Event::DeviceEvent { event, .. } => {
+                    input.update_device(event);
+                    true
+                }
+
+                _ => false,
+            };
+
+            if input_event && event_delivery == EventDelivery::Coalesced {
+                return;
+            }
+
+            let render_task_ref = match &mut render_task {
+                Some(render_task) => render_task,
+                None => return,
+            };
 
             let mut app_state = StoryboardAppState {
-                render_task: &mut render_task,
+                render_task: render_task_ref,
                 control_flow,
                 event,
+                input: &input,
             };
 
             match &app_state.event {
@@ -159,6 +238,7 @@ impl Storyboard {
 
                         Size2D::new(width, height)
                     };
+                    screen.rect.size = win_size;
 
                     app_state
                         .render_task
@@ -183,6 +263,9 @@ impl Storyboard {
                         Size2D::new(width, height)
                     };
 
+                    screen.rect.size = win_size;
+                    screen.scale_factor = *scale_factor as _;
+
                     let mut configuration = app_state.render_task.configuration_mut();
                     configuration.surface.screen.rect.size = win_size;
                     configuration.surface.screen.scale_factor = *scale_factor as _;
@@ -193,15 +276,24 @@ impl Storyboard {
 
             app.update(&app_prop, &mut app_state);
 
-            match &app_state.event {
+            // Take event out, releasing borrows of input and render task held by app state
+            let event = app_state.event;
+
+            match event {
                 Event::MainEventsCleared => {
                     app_prop.elapsed = instant.elapsed();
                     instant = Instant::now();
+
+                    input.end_frame();
                 }
 
                 Event::LoopDestroyed => {
                     app.unload(&app_prop);
-                    render_task.interrupt();
+
+                    // Release render resources before window and device are dropped
+                    if let Some(render_task) = render_task.take() {
+                        render_task.shutdown(RenderTask::SHUTDOWN_TIMEOUT).ok();
+                    }
                 }
 
                 _ => {}